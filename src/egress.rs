@@ -1,37 +1,82 @@
-use std::collections::HashMap;
-use std::sync::mpsc::{Receiver, Sender};
-use std::io::Write;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
 use crate::wish::grant::Decree;
+use crate::wish::{Response, Sin};
 
 use mio::Token;
 
-mod send;
+pub mod outbox;
+pub mod send;
+
+use outbox::{Class, Outbox, OutputLimits};
+
+// How long to wait for the next decree while a client is behind, before
+// trying its socket again.
+const RETRY: Duration = Duration::from_millis(10);
 
 pub fn egress(pilgrim_rx: Receiver<Decree>, egress_tx: Sender<Token>) {
-    let mut egress_map: HashMap<Token, mio::net::TcpStream> = HashMap::new();
+    let mut outboxes = Outboxes {
+        map: HashMap::new(),
+        pending: HashSet::new(),
+        limits: OutputLimits::default(),
+        egress_tx,
+    };
     let mut buffer = Vec::with_capacity(2100);
     let mut itoa_buf = itoa::Buffer::new();
 
     loop {
-        match pilgrim_rx.recv() {
-            Ok(Decree::Welcome(token, stream)) => {
-                egress_map.insert(token, stream);
+        let decree = if outboxes.pending.is_empty() {
+            match pilgrim_rx.recv() {
+                Ok(decree) => Some(decree),
+                Err(_) => break,
             }
-            Ok(Decree::Deliver(gift)) => {
-                if let Some(stream) = egress_map.get_mut(&gift.token) {
-                    let token = gift.token;
-
-                    if send::send(stream, gift, &mut buffer).is_err()
-                        && egress_tx.send(token).is_err()
-                    {
-                        eprintln!("angel panicked");
-                    };
-                }
+        } else {
+            match pilgrim_rx.recv_timeout(RETRY) {
+                Ok(decree) => Some(decree),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        };
+
+        match decree {
+            Some(Decree::Welcome(token, stream)) => {
+                outboxes.map.insert(token, Outbox::new(stream));
             }
-            Ok(Decree::Broadcast(token, event, message, clients)) => {
+            Some(Decree::Farewell(token)) => {
+                outboxes.map.remove(&token);
+                outboxes.pending.remove(&token);
+            }
+            Some(Decree::Limits(limits)) => {
+                outboxes.limits = limits;
+            }
+            Some(Decree::Deliver(gift)) => {
+                outboxes.post(gift.token, |outbox| {
+                    // A client is held to the pubsub limits for as long as
+                    // it's in subscriber mode.
+                    match &gift.response {
+                        Response::Subscribed(..) => outbox.class = Class::Pubsub,
+                        Response::Unsubscribed(_, left)
+                            if left.last().is_some_and(|(_, count)| *count == 0) =>
+                        {
+                            outbox.class = Class::Normal
+                        }
+                        _ => {}
+                    }
+
+                    send::send(outbox, gift, &mut buffer)
+                });
+            }
+            Some(Decree::Broadcast(token, event, message, clients)) => {
                 let clients_len = clients.len();
 
-                let mut response = b"*3\r\n$7\r\nmessage\r\n$".to_vec();
+                let mut response = if clients.shard {
+                    b"*3\r\n$8\r\nsmessage\r\n$".to_vec()
+                } else {
+                    b"*3\r\n$7\r\nmessage\r\n$".to_vec()
+                };
                 response.extend_from_slice(itoa_buf.format(event.len()).as_bytes());
                 response.extend_from_slice(b"\r\n");
                 response.extend_from_slice(&event);
@@ -41,25 +86,112 @@ pub fn egress(pilgrim_rx: Receiver<Decree>, egress_tx: Sender<Token>) {
                 response.extend_from_slice(&message);
                 response.extend_from_slice(b"\r\n");
 
-                for client in clients {
-                    if let Some(stream) = egress_map.get_mut(&client)
-                        && stream.write_all(&response).is_err()
-                    {
-                        eprintln!("writing to stream failed for client");
-                    }
+                // Readers also get the sequence ID, to resume from later.
+                if let Some(sequence) = clients.sequence {
+                    let mut response = [b"*4".as_slice(), &response[2..]].concat();
+                    response.push(b':');
+                    response.extend_from_slice(itoa_buf.format(sequence).as_bytes());
+                    response.extend_from_slice(b"\r\n");
+
+                    outboxes.broadcast(clients.readers, response.into());
                 }
 
-                if let Some(publisher_stream) = egress_map.get_mut(&token) {
+                outboxes.broadcast(clients.clients, response.into());
+
+                // Pattern subscribers also get the pattern that matched.
+                for (pattern, clients) in clients.patterns {
+                    let mut response = b"*4\r\n$8\r\npmessage\r\n$".to_vec();
+                    response.extend_from_slice(itoa_buf.format(pattern.len()).as_bytes());
+                    response.extend_from_slice(b"\r\n");
+                    response.extend_from_slice(&pattern);
+                    response.extend_from_slice(b"\r\n$");
+                    response.extend_from_slice(itoa_buf.format(event.len()).as_bytes());
+                    response.extend_from_slice(b"\r\n");
+                    response.extend_from_slice(&event);
+                    response.extend_from_slice(b"\r\n$");
+                    response.extend_from_slice(itoa_buf.format(message.len()).as_bytes());
+                    response.extend_from_slice(b"\r\n");
+                    response.extend_from_slice(&message);
+                    response.extend_from_slice(b"\r\n");
+
+                    outboxes.broadcast(clients, response.into());
+                }
+
+                if let Some(token) = token {
                     let mut response = b":".to_vec();
                     response.extend_from_slice(itoa_buf.format(clients_len).as_bytes());
                     response.extend_from_slice(b"\r\n");
 
-                    if publisher_stream.write_all(&response).is_err() {
-                        eprintln!("writing to stream failed for publisher");
-                    }
+                    outboxes.post(token, |outbox| outbox.send(&response));
                 }
             }
-            Err(_) => break,
+            None => {}
+        }
+
+        outboxes.flush();
+    }
+}
+
+// Every client's outbox, which of them have something queued, and the limits
+// on how much that may be.
+struct Outboxes {
+    map: HashMap<Token, Outbox>,
+    pending: HashSet<Token>,
+    limits: OutputLimits,
+    egress_tx: Sender<Token>,
+}
+
+impl Outboxes {
+    // Hands a client a frame, forsaking it if its socket is gone or it's
+    // fallen too far behind.
+    fn post(&mut self, token: Token, write: impl FnOnce(&mut Outbox) -> Result<(), Sin>) {
+        let Some(outbox) = self.map.get_mut(&token) else {
+            return;
+        };
+
+        if write(outbox).is_err() || outbox.overflows(&self.limits) {
+            self.forsake(token);
+        } else if outbox.is_pending() {
+            self.pending.insert(token);
+        }
+    }
+
+    // One frame for all of them, shared rather than copied.
+    fn broadcast(&mut self, clients: Vec<Token>, frame: Arc<[u8]>) {
+        for client in clients {
+            self.post(client, |outbox| outbox.share(&frame));
+        }
+    }
+
+    // Gives every client that's behind another go at its socket.
+    fn flush(&mut self) {
+        let pending: Vec<Token> = self.pending.iter().copied().collect();
+
+        for token in pending {
+            let Some(outbox) = self.map.get_mut(&token) else {
+                self.pending.remove(&token);
+                continue;
+            };
+
+            if outbox.flush().is_err() || outbox.overflows(&self.limits) {
+                self.forsake(token);
+            } else if !outbox.is_pending() {
+                self.pending.remove(&token);
+            }
+        }
+    }
+
+    // A client that can't be written to, or won't keep up, is disconnected,
+    // and the server told so it departs the temple.
+    fn forsake(&mut self, token: Token) {
+        self.pending.remove(&token);
+
+        if let Some(outbox) = self.map.remove(&token) {
+            outbox.shutdown();
+        }
+
+        if self.egress_tx.send(token).is_err() {
+            eprintln!("angel panicked");
         }
     }
 }
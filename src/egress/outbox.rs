@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Write};
+use std::net::Shutdown;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mio::net::TcpStream;
+
+use crate::wish::Sin;
+use crate::wish::util::bytes_to_u64;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Class {
+    Normal,
+    Replica,
+    Pubsub,
+}
+
+impl Class {
+    fn name(self) -> &'static [u8] {
+        match self {
+            Class::Normal => b"normal",
+            Class::Replica => b"replica",
+            Class::Pubsub => b"pubsub",
+        }
+    }
+
+    fn parse(name: &[u8]) -> Option<Self> {
+        match name.to_ascii_lowercase().as_slice() {
+            b"normal" => Some(Class::Normal),
+            // Redis still takes the old name for replicas.
+            b"replica" | b"slave" => Some(Class::Replica),
+            b"pubsub" => Some(Class::Pubsub),
+            _ => None,
+        }
+    }
+}
+
+// How many bytes a client may have waiting to be written. Past the hard limit
+// it's disconnected at once, past the soft one only if it stays there for the
+// given seconds. 0 turns a limit off.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Limit {
+    pub hard: u64,
+    pub soft: u64,
+    pub soft_seconds: u64,
+}
+
+// client-output-buffer-limit, one Limit per class. Nothing replicates from
+// Jerusalem yet, but the replica class is kept so the setting round-trips.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OutputLimits {
+    pub normal: Limit,
+    pub replica: Limit,
+    pub pubsub: Limit,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        OutputLimits {
+            normal: Limit {
+                hard: 0,
+                soft: 0,
+                soft_seconds: 0,
+            },
+            replica: Limit {
+                hard: 256 * 1024 * 1024,
+                soft: 64 * 1024 * 1024,
+                soft_seconds: 60,
+            },
+            pubsub: Limit {
+                hard: 32 * 1024 * 1024,
+                soft: 8 * 1024 * 1024,
+                soft_seconds: 60,
+            },
+        }
+    }
+}
+
+impl OutputLimits {
+    pub fn get(&self, class: Class) -> Limit {
+        match class {
+            Class::Normal => self.normal,
+            Class::Replica => self.replica,
+            Class::Pubsub => self.pubsub,
+        }
+    }
+
+    fn of(&mut self, class: Class) -> &mut Limit {
+        match class {
+            Class::Normal => &mut self.normal,
+            Class::Replica => &mut self.replica,
+            Class::Pubsub => &mut self.pubsub,
+        }
+    }
+
+    /// Applies a CONFIG SET value of `<class> <hard> <soft> <seconds>`
+    /// groups, leaving classes it doesn't name alone. Nothing changes unless
+    /// every group parses.
+    pub fn set(&mut self, value: &[u8]) -> bool {
+        let words: Vec<&[u8]> = value
+            .split(|byte| byte.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        if words.is_empty() || !words.len().is_multiple_of(4) {
+            return false;
+        }
+
+        let mut limits = *self;
+
+        for group in words.chunks(4) {
+            let (Some(class), Some(hard), Some(soft), Ok(soft_seconds)) = (
+                Class::parse(group[0]),
+                memory(group[1]),
+                memory(group[2]),
+                bytes_to_u64(group[3]),
+            ) else {
+                return false;
+            };
+
+            *limits.of(class) = Limit {
+                hard,
+                soft,
+                soft_seconds,
+            };
+        }
+
+        *self = limits;
+        true
+    }
+
+    /// The limits as CONFIG GET shows them, in bytes.
+    pub fn entry(&self) -> Vec<u8> {
+        let mut itoa_buf = itoa::Buffer::new();
+        let mut entry = Vec::new();
+
+        for class in [Class::Normal, Class::Replica, Class::Pubsub] {
+            let limit = self.get(class);
+
+            if !entry.is_empty() {
+                entry.push(b' ');
+            }
+
+            entry.extend_from_slice(class.name());
+
+            for value in [limit.hard, limit.soft, limit.soft_seconds] {
+                entry.push(b' ');
+                entry.extend_from_slice(itoa_buf.format(value).as_bytes());
+            }
+        }
+
+        entry
+    }
+}
+
+// A byte count with an optional unit, b, k, kb, m, mb, g or gb, the k, m and
+// g being powers of 1000 and the kb, mb and gb powers of 1024.
+fn memory(bytes: &[u8]) -> Option<u64> {
+    let bytes = bytes.to_ascii_lowercase();
+    let digits = bytes.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let (number, unit) = bytes.split_at(digits);
+
+    let multiplier = match unit {
+        b"" | b"b" => 1,
+        b"k" => 1000,
+        b"kb" => 1024,
+        b"m" => 1000 * 1000,
+        b"mb" => 1024 * 1024,
+        b"g" => 1000 * 1000 * 1000,
+        b"gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    bytes_to_u64(number).ok()?.checked_mul(multiplier)
+}
+
+// What's waiting to be written to one client. Replies are written straight
+// away while nothing is queued; whatever the socket won't take waits here,
+// broadcast frames shared with every other subscriber they went to.
+pub struct Outbox {
+    stream: TcpStream,
+    queue: VecDeque<(Arc<[u8]>, usize)>,
+    queued: u64,
+    over_soft_since: Option<Instant>,
+    pub class: Class,
+}
+
+impl Outbox {
+    pub fn new(stream: TcpStream) -> Self {
+        Outbox {
+            stream,
+            queue: VecDeque::new(),
+            queued: 0,
+            over_soft_since: None,
+            class: Class::Normal,
+        }
+    }
+
+    /// Writes a frame of its own, queuing a copy of what doesn't fit.
+    pub fn send(&mut self, frame: &[u8]) -> Result<(), Sin> {
+        let written = self.write(frame)?;
+
+        if written < frame.len() {
+            self.enqueue(Arc::from(&frame[written..]), 0);
+        }
+
+        Ok(())
+    }
+
+    /// Writes a frame shared with other clients, queuing the frame itself
+    /// rather than a copy of what doesn't fit.
+    pub fn share(&mut self, frame: &Arc<[u8]>) -> Result<(), Sin> {
+        let written = self.write(frame)?;
+
+        if written < frame.len() {
+            self.enqueue(Arc::clone(frame), written);
+        }
+
+        Ok(())
+    }
+
+    /// Writes as much of the queue as the socket takes.
+    pub fn flush(&mut self) -> Result<(), Sin> {
+        while let Some((frame, offset)) = self.queue.front_mut() {
+            let written = write_some(&mut self.stream, &frame[*offset..])?;
+            *offset += written;
+            self.queued -= written as u64;
+
+            if *offset < frame.len() {
+                break;
+            }
+
+            self.queue.pop_front();
+        }
+
+        Ok(())
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Whether the client has fallen too far behind for its class, counting
+    /// how long it's been over the soft limit.
+    pub fn overflows(&mut self, limits: &OutputLimits) -> bool {
+        let limit = limits.get(self.class);
+
+        if limit.hard != 0 && self.queued > limit.hard {
+            return true;
+        }
+
+        if limit.soft == 0 || self.queued <= limit.soft {
+            self.over_soft_since = None;
+            return false;
+        }
+
+        let since = *self.over_soft_since.get_or_insert_with(Instant::now);
+
+        since.elapsed() > Duration::from_secs(limit.soft_seconds)
+    }
+
+    /// Closes the socket for both halves, so the client sees it go even while
+    /// a reader still holds the other one.
+    pub fn shutdown(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    // Nothing jumps the queue, so frames arrive in the order they were sent.
+    fn write(&mut self, frame: &[u8]) -> Result<usize, Sin> {
+        if self.is_pending() {
+            return Ok(0);
+        }
+
+        write_some(&mut self.stream, frame)
+    }
+
+    fn enqueue(&mut self, frame: Arc<[u8]>, offset: usize) {
+        self.queued += (frame.len() - offset) as u64;
+        self.queue.push_back((frame, offset));
+    }
+}
+
+// Writes until the socket would block, returning how much it took.
+fn write_some(stream: &mut TcpStream, bytes: &[u8]) -> Result<usize, Sin> {
+    let mut written = 0;
+
+    while written < bytes.len() {
+        match stream.write(&bytes[written..]) {
+            Ok(0) => return Err(Sin::Disconnected),
+            Ok(n) => written += n,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return Err(Sin::Disconnected),
+        }
+    }
+
+    Ok(written)
+}
@@ -1,19 +1,36 @@
-use mio::net::TcpStream;
-use std::io::Write;
-
+use crate::egress::outbox::Outbox;
 use crate::wish::{Command, InfoType, Response, Sacrilege, Sin, grant::Gift};
 
-pub fn send(stream: &mut TcpStream, gift: Gift, response: &mut Vec<u8>) -> Result<(), Sin> {
+pub fn send(outbox: &mut Outbox, gift: Gift, response: &mut Vec<u8>) -> Result<(), Sin> {
     response.clear();
     let mut itoa_buf = itoa::Buffer::new();
 
-    match gift.response {
+    inscribe(gift.response, response, &mut itoa_buf);
+
+    outbox.send(response)
+}
+
+pub fn inscribe(gift_response: Response, response: &mut Vec<u8>, itoa_buf: &mut itoa::Buffer) {
+    match gift_response {
         Response::Info(InfoType::Ok) => {
             response.extend_from_slice(b"+OK\r\n");
         }
         Response::Info(InfoType::Pong) => {
             response.extend_from_slice(b"+PONG\r\n");
         }
+        Response::Info(InfoType::Queued) => {
+            response.extend_from_slice(b"+QUEUED\r\n");
+        }
+        Response::Info(InfoType::Status(status)) => {
+            response.push(b'+');
+            response.extend_from_slice(&status);
+            response.extend_from_slice(b"\r\n");
+        }
+        Response::Info(InfoType::Type(type_name)) => {
+            response.push(b'+');
+            response.extend_from_slice(type_name);
+            response.extend_from_slice(b"\r\n");
+        }
         Response::BulkString(bulk_string) => match bulk_string {
             Some(value) => {
                 response.push(b'$');
@@ -66,36 +83,46 @@ pub fn send(stream: &mut TcpStream, gift: Gift, response: &mut Vec<u8>) -> Resul
             response.extend_from_slice(itoa_buf.format(length).as_bytes());
             response.extend_from_slice(b"\r\n");
         }
-        Response::SubscribedChannels(subscribed_channels) => {
-            for (subscribed_channel, count) in subscribed_channels {
-                response.extend_from_slice(b"*3\r\n$9\r\nsubscribe\r\n$");
-                response.extend_from_slice(itoa_buf.format(subscribed_channel.len()).as_bytes());
+        Response::Subscribed(kind, subscribed) => {
+            for (subscribed, count) in subscribed {
+                response.extend_from_slice(b"*3\r\n$");
+                response.extend_from_slice(itoa_buf.format(kind.len()).as_bytes());
                 response.extend_from_slice(b"\r\n");
-                response.extend_from_slice(&subscribed_channel);
+                response.extend_from_slice(kind);
+                response.extend_from_slice(b"\r\n$");
+                response.extend_from_slice(itoa_buf.format(subscribed.len()).as_bytes());
+                response.extend_from_slice(b"\r\n");
+                response.extend_from_slice(&subscribed);
                 response.extend_from_slice(b"\r\n:");
 
                 response.extend_from_slice(itoa_buf.format(count).as_bytes());
                 response.extend_from_slice(b"\r\n");
             }
         }
-        Response::UnsubscribedChannels(unsubscribed_channels) => match unsubscribed_channels {
-            Some(unsubscribed_channels) => {
-                for (unsubscribed_channel, count) in unsubscribed_channels {
-                    response.extend_from_slice(b"*3\r\n$11\r\nunsubscribe\r\n$");
-                    response
-                        .extend_from_slice(itoa_buf.format(unsubscribed_channel.len()).as_bytes());
-                    response.extend_from_slice(b"\r\n");
-                    response.extend_from_slice(&unsubscribed_channel);
-                    response.extend_from_slice(b"\r\n:");
+        Response::Unsubscribed(kind, unsubscribed) => {
+            for (unsubscribed, count) in unsubscribed {
+                response.extend_from_slice(b"*3\r\n$");
+                response.extend_from_slice(itoa_buf.format(kind.len()).as_bytes());
+                response.extend_from_slice(b"\r\n");
+                response.extend_from_slice(kind);
+                response.extend_from_slice(b"\r\n$");
 
-                    response.extend_from_slice(itoa_buf.format(count).as_bytes());
-                    response.extend_from_slice(b"\r\n");
+                match unsubscribed {
+                    Some(unsubscribed) => {
+                        response.extend_from_slice(
+                            itoa_buf.format(unsubscribed.len()).as_bytes(),
+                        );
+                        response.extend_from_slice(b"\r\n");
+                        response.extend_from_slice(&unsubscribed);
+                        response.extend_from_slice(b"\r\n:");
+                    }
+                    None => response.extend_from_slice(b"-1\r\n:"),
                 }
+
+                response.extend_from_slice(itoa_buf.format(count).as_bytes());
+                response.extend_from_slice(b"\r\n");
             }
-            None => {
-                response.extend_from_slice(b"*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n");
-            }
-        },
+        }
         Response::Error(sacrilege) => match sacrilege {
             Sacrilege::UnknownCommand => {
                 response.extend_from_slice(b"-ERR unknown command\r\n");
@@ -110,6 +137,54 @@ pub fn send(stream: &mut TcpStream, gift: Gift, response: &mut Vec<u8>) -> Resul
                 Command::CONFIG => {
                     response.extend_from_slice(b"-ERR Unknown Command after 'config'\r\n");
                 }
+                Command::COPY | Command::MOVE => {
+                    response
+                        .extend_from_slice(b"-ERR source and destination objects are the same\r\n");
+                }
+                Command::RESTORE => {
+                    response.extend_from_slice(b"-ERR DUMP payload version or checksum are wrong\r\n");
+                }
+                Command::OBJECT => {
+                    response.extend_from_slice(b"-ERR unknown subcommand. Try OBJECT HELP.\r\n");
+                }
+                Command::MEMORY => {
+                    response.extend_from_slice(b"-ERR unknown subcommand. Try MEMORY HELP.\r\n");
+                }
+                Command::MULTI => {
+                    response.extend_from_slice(b"-ERR MULTI calls can not be nested\r\n");
+                }
+                Command::EXEC => {
+                    response.extend_from_slice(b"-ERR EXEC without MULTI\r\n");
+                }
+                Command::DISCARD => {
+                    response.extend_from_slice(b"-ERR DISCARD without MULTI\r\n");
+                }
+                Command::WATCH => {
+                    response.extend_from_slice(b"-ERR WATCH inside MULTI is not allowed\r\n");
+                }
+                Command::EVAL
+                | Command::EVALSHA
+                | Command::FCALL
+                | Command::FCALLRO
+                | Command::SUBSCRIBE => {
+                    response.extend_from_slice(
+                        b"-ERR Number of keys can't be greater than number of args\r\n",
+                    );
+                }
+                Command::SCRIPT => {
+                    response.extend_from_slice(b"-ERR unknown subcommand. Try SCRIPT HELP.\r\n");
+                }
+                Command::FUNCTION => {
+                    response
+                        .extend_from_slice(b"-ERR unknown subcommand. Try FUNCTION HELP.\r\n");
+                }
+                Command::COMMAND => {
+                    response
+                        .extend_from_slice(b"-ERR unknown subcommand. Try COMMAND HELP.\r\n");
+                }
+                Command::PUBSUB => {
+                    response.extend_from_slice(b"-ERR unknown subcommand. Try PUBSUB HELP.\r\n");
+                }
                 _ => {
                     response.extend_from_slice(
                         b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
@@ -194,14 +269,317 @@ pub fn send(stream: &mut TcpStream, gift: Gift, response: &mut Vec<u8>) -> Resul
                 ),
                 Command::CONFIG => response
                     .extend_from_slice(b"-ERR wrong number of arguments for 'config' command\r\n"),
+                Command::CMSINITBYDIM => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'cms.initbydim' command\r\n",
+                ),
+                Command::CMSINCRBY => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'cms.incrby' command\r\n",
+                ),
+                Command::CMSQUERY => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'cms.query' command\r\n",
+                ),
+                Command::CMSMERGE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'cms.merge' command\r\n",
+                ),
+                Command::TOPKRESERVE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'topk.reserve' command\r\n",
+                ),
+                Command::TOPKADD => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'topk.add' command\r\n",
+                ),
+                Command::TOPKLIST => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'topk.list' command\r\n",
+                ),
+                Command::TOPKCOUNT => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'topk.count' command\r\n",
+                ),
+                Command::TSCREATE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.create' command\r\n",
+                ),
+                Command::TSADD => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.add' command\r\n",
+                ),
+                Command::TSMADD => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.madd' command\r\n",
+                ),
+                Command::TSINCRBY => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.incrby' command\r\n",
+                ),
+                Command::TSRANGE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.range' command\r\n",
+                ),
+                Command::TSREVRANGE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.revrange' command\r\n",
+                ),
+                Command::TSMRANGE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.mrange' command\r\n",
+                ),
+                Command::TSCREATERULE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.createrule' command\r\n",
+                ),
+                Command::TSDELETERULE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ts.deleterule' command\r\n",
+                ),
+                Command::FTCREATE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ft.create' command\r\n",
+                ),
+                Command::FTSEARCH => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ft.search' command\r\n",
+                ),
+                Command::FTDROPINDEX => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ft.dropindex' command\r\n",
+                ),
+                Command::FTLIST => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'ft._list' command\r\n",
+                ),
+                Command::CLTHROTTLE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'cl.throttle' command\r\n",
+                ),
+                Command::SCAN => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'scan' command\r\n",
+                ),
+                Command::HSCAN => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'hscan' command\r\n",
+                ),
+                Command::SSCAN => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'sscan' command\r\n",
+                ),
+                Command::KEYS => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'keys' command\r\n",
+                ),
+                Command::TYPE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'type' command\r\n",
+                ),
+                Command::RENAME => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'rename' command\r\n",
+                ),
+                Command::RENAMENX => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'renamenx' command\r\n",
+                ),
+                Command::COPY => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'copy' command\r\n",
+                ),
+                Command::RANDOMKEY => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'randomkey' command\r\n",
+                ),
+                Command::DBSIZE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'dbsize' command\r\n",
+                ),
+                Command::TOUCH => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'touch' command\r\n",
+                ),
+                Command::UNLINK => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'unlink' command\r\n",
+                ),
+                Command::SELECT => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'select' command\r\n",
+                ),
+                Command::MOVE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'move' command\r\n",
+                ),
+                Command::SWAPDB => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'swapdb' command\r\n",
+                ),
+                Command::FLUSHDB => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'flushdb' command\r\n",
+                ),
+                Command::FLUSHALL => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'flushall' command\r\n",
+                ),
+                Command::DUMP => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'dump' command\r\n",
+                ),
+                Command::RESTORE => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'restore' command\r\n",
+                ),
+                Command::SORT => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'sort' command\r\n",
+                ),
+                Command::SORTRO => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'sort_ro' command\r\n",
+                ),
+                Command::OBJECT => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'object' command\r\n",
+                ),
+                Command::MEMORY => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'memory' command\r\n",
+                ),
+                Command::MULTI => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'multi' command\r\n",
+                ),
+                Command::EXEC => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'exec' command\r\n",
+                ),
+                Command::DISCARD => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'discard' command\r\n",
+                ),
+                Command::WATCH => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'watch' command\r\n",
+                ),
+                Command::UNWATCH => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'unwatch' command\r\n",
+                ),
+                Command::EVAL => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'eval' command\r\n",
+                ),
+                Command::EVALSHA => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'evalsha' command\r\n",
+                ),
+                Command::SCRIPT => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'script' command\r\n",
+                ),
+                Command::FCALL => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'fcall' command\r\n",
+                ),
+                Command::FCALLRO => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'fcall_ro' command\r\n",
+                ),
+                Command::FUNCTION => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'function' command\r\n",
+                ),
+                Command::COMMAND => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'command' command\r\n",
+                ),
+                Command::PUBSUB => response.extend_from_slice(
+                    b"-ERR wrong number of arguments for 'pubsub' command\r\n",
+                ),
             },
+            Sacrilege::WrongNumberOfArguments(name) => {
+                response.extend_from_slice(b"-ERR wrong number of arguments for '");
+                response.extend_from_slice(&name.to_ascii_lowercase());
+                response.extend_from_slice(b"' command\r\n");
+            }
+            Sacrilege::InvalidCommand => {
+                response.extend_from_slice(b"-ERR Invalid command specified\r\n")
+            }
+            Sacrilege::InvalidCommandArity => response.extend_from_slice(
+                b"-ERR Invalid number of arguments specified for command\r\n",
+            ),
+            Sacrilege::NoKeyArguments => {
+                response.extend_from_slice(b"-ERR The command has no key arguments\r\n")
+            }
             Sacrilege::SubscriberOnlyMode => response.extend_from_slice(
-                b"-ERR only SUBSCRIBE / UNSUBSCRIBE / PING / QUIT allowed in this context\r\n",
+                b"-ERR only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT allowed in this context\r\n",
+            ),
+            Sacrilege::KeyAlreadyExists(command) => match command {
+                Command::TOPKRESERVE => response.extend_from_slice(b"-TopK: key already exists\r\n"),
+                Command::TSCREATE => response.extend_from_slice(b"-ERR TSDB: key already exists\r\n"),
+                Command::FTCREATE => response.extend_from_slice(b"-Index already exists\r\n"),
+                Command::RESTORE => {
+                    response.extend_from_slice(b"-BUSYKEY Target key name already exists.\r\n")
+                }
+                _ => response.extend_from_slice(b"-CMS: key already exists\r\n"),
+            },
+            Sacrilege::KeyDoesNotExist(command) => match command {
+                Command::TOPKADD | Command::TOPKLIST | Command::TOPKCOUNT => {
+                    response.extend_from_slice(b"-TopK: key does not exist\r\n")
+                }
+                Command::TSMADD
+                | Command::TSRANGE
+                | Command::TSREVRANGE
+                | Command::TSCREATERULE
+                | Command::TSDELETERULE => {
+                    response.extend_from_slice(b"-ERR TSDB: the key does not exist\r\n")
+                }
+                Command::FTSEARCH | Command::FTDROPINDEX => {
+                    response.extend_from_slice(b"-Unknown Index name\r\n")
+                }
+                Command::RENAME | Command::RENAMENX => {
+                    response.extend_from_slice(b"-ERR no such key\r\n")
+                }
+                _ => response.extend_from_slice(b"-CMS: key does not exist\r\n"),
+            },
+            Sacrilege::InvalidArgument(command) => match command {
+                Command::CMSINITBYDIM => {
+                    response.extend_from_slice(b"-CMS: invalid width/depth\r\n")
+                }
+                Command::CMSINCRBY | Command::CMSMERGE => {
+                    response.extend_from_slice(b"-CMS: Cannot parse number\r\n")
+                }
+                Command::TOPKRESERVE => {
+                    response.extend_from_slice(b"-TopK: invalid k/width/depth/decay\r\n")
+                }
+                Command::TSCREATERULE => {
+                    response.extend_from_slice(b"-ERR TSDB: invalid compaction rule\r\n")
+                }
+                Command::TSDELETERULE => {
+                    response.extend_from_slice(b"-ERR TSDB: compaction rule does not exist\r\n")
+                }
+                Command::TSCREATE
+                | Command::TSADD
+                | Command::TSMADD
+                | Command::TSINCRBY
+                | Command::TSRANGE
+                | Command::TSREVRANGE
+                | Command::TSMRANGE => {
+                    response.extend_from_slice(b"-ERR TSDB: invalid arguments\r\n")
+                }
+                Command::FTCREATE => {
+                    response.extend_from_slice(b"-ERR invalid index definition\r\n")
+                }
+                Command::FTSEARCH => response.extend_from_slice(b"-Syntax error in query\r\n"),
+                Command::CLTHROTTLE => response.extend_from_slice(
+                    b"-ERR count per period and period must be positive integers\r\n",
+                ),
+                Command::CONFIG => response.extend_from_slice(
+                    b"-ERR Unsupported CONFIG parameter or invalid value\r\n",
+                ),
+                Command::SELECT | Command::MOVE | Command::SWAPDB => {
+                    response.extend_from_slice(b"-ERR DB index is out of range\r\n")
+                }
+                Command::EVAL | Command::EVALSHA | Command::FCALL | Command::FCALLRO => {
+                    response.extend_from_slice(b"-ERR value is not an integer or out of range\r\n")
+                }
+                _ => response.extend_from_slice(b"-ERR syntax error\r\n"),
+            },
+            Sacrilege::DimensionMismatch => {
+                response.extend_from_slice(b"-CMS: width/depth is not equal\r\n")
+            }
+            Sacrilege::InvalidScore => response
+                .extend_from_slice(b"-ERR One or more scores can't be converted into double\r\n"),
+            Sacrilege::DuplicateSample => response.extend_from_slice(
+                b"-ERR TSDB: Error at upsert, update is not supported when DUPLICATE_POLICY is set to BLOCK mode\r\n",
+            ),
+            Sacrilege::TransactionAborted => response.extend_from_slice(
+                b"-EXECABORT Transaction discarded because of previous errors.\r\n",
             ),
+            Sacrilege::NegativeNumberOfKeys => {
+                response.extend_from_slice(b"-ERR Number of keys can't be negative\r\n")
+            }
+            Sacrilege::Script(message) | Sacrilege::Rite(message) => {
+                response.push(b'-');
+                response.extend_from_slice(&message);
+                response.extend_from_slice(b"\r\n");
+            }
+            Sacrilege::NoScript => response
+                .extend_from_slice(b"-NOSCRIPT No matching script. Please use EVAL.\r\n"),
+            Sacrilege::NotBusy => response
+                .extend_from_slice(b"-NOTBUSY No scripts in execution right now.\r\n"),
+            Sacrilege::Unkillable => response.extend_from_slice(
+                b"-UNKILLABLE Sorry the script already executed write commands against the dataset. You can either wait the script termination or kill the server in a hard way using the SHUTDOWN NOSAVE command.\r\n",
+            ),
+            Sacrilege::Busy => response.extend_from_slice(
+                b"-BUSY Redis is busy running a script. You can only call SCRIPT KILL or SHUTDOWN NOSAVE.\r\n",
+            ),
+            Sacrilege::InvalidTimestamp(command) => match command {
+                Command::TSINCRBY => response.extend_from_slice(
+                    b"-ERR TSDB: timestamp must be equal to or higher than the maximum existing timestamp\r\n",
+                ),
+                Command::RESTORE => {
+                    response.extend_from_slice(b"-ERR Invalid TTL value, must be >= 0\r\n")
+                }
+                _ => response
+                    .extend_from_slice(b"-ERR TSDB: Timestamp is older than retention\r\n"),
+            },
         },
-    }
-
-    stream.write_all(response).map_err(|_| Sin::Disconnected)?;
+        Response::Array(elements) => {
+            response.push(b'*');
+            response.extend_from_slice(itoa_buf.format(elements.len()).as_bytes());
+            response.extend_from_slice(b"\r\n");
 
-    Ok(())
+            for element in elements {
+                inscribe(element, response, itoa_buf);
+            }
+        }
+    }
 }
@@ -25,6 +25,12 @@ struct Args {
 
     #[arg(long, default_value = "dump.rdb")]
     dbfilename: String,
+
+    #[arg(long, default_value_t = 16)]
+    databases: usize,
+
+    #[arg(long = "notify-keyspace-events", default_value = "")]
+    notify_keyspace_events: String,
 }
 
 fn main() {
@@ -43,5 +49,7 @@ fn main() {
         &args.dbfilename,
         0,
         "no",
+        args.databases,
+        &args.notify_keyspace_events,
     );
 }
@@ -14,6 +14,7 @@ use jerusalem::wish::{self, Pilgrim};
 use mio::net::TcpListener;
 use mio::{Events, Interest, Poll, Token};
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     ipv4_address: &str,
     port: u16,
@@ -23,6 +24,8 @@ pub fn run(
     dbfilename: &str,
     max_memory: u64,
     append_only: &str,
+    databases: usize,
+    notify_keyspace_events: &str,
 ) {
     let ipv4_addr = Ipv4Addr::from_str(ipv4_address).expect("Invalid IPv4 address");
     let socket_addr_v4 = SocketAddrV4::new(ipv4_addr, port);
@@ -59,6 +62,8 @@ pub fn run(
         itoa_buf.format(event_capacity).into(),
         itoa_buf.format(max_memory).into(),
         append_only.into(),
+        itoa_buf.format(databases).into(),
+        notify_keyspace_events.into(),
     );
 
     let mut server_temple = temple.sanctify();
@@ -105,6 +110,8 @@ pub fn run(
             {
                 eprintln!("deregister() failed")
             }
+
+            temple.depart(token);
         }
 
         if poll
@@ -164,6 +171,7 @@ pub fn run(
                                 Pilgrim {
                                     stream: ingress_mio,
                                     virtue: None,
+                                    covenant: None,
                                     tx: pilgrim_tx.clone(),
                                 },
                             );
@@ -186,7 +194,8 @@ pub fn run(
                         let tx = ingress_tx.clone();
 
                         ingress_choir.sing(move || {
-                            match wish::wish(&mut pilgrim, sanctum, Token(token_number)) {
+                            match wish::wish(&mut pilgrim, sanctum.sanctify(), Token(token_number))
+                            {
                                 Ok(_) => {
                                     if tx.send((mio::Token(token_number), pilgrim)).is_err() {
                                         eprintln!("angel panicked");
@@ -194,6 +203,15 @@ pub fn run(
                                 }
                                 Err(_e) => {
                                     // eprintln!("{:?}", e);
+                                    if pilgrim
+                                        .tx
+                                        .send(Decree::Farewell(Token(token_number)))
+                                        .is_err()
+                                    {
+                                        eprintln!("angel panicked");
+                                    }
+
+                                    sanctum.depart(Token(token_number));
                                 }
                             }
                         });
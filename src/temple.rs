@@ -1,37 +1,121 @@
 use crate::temple::{
-    BroadcastCommand::{Publish, Subscribe, Unsubscribe},
-    ClientCommandType::{Broadcast, Database},
-    ServerCommand::{GetFilePath, Save},
+    BroadcastCommand::{Publish, Pubsub, Spublish, Subscribe, Unsubscribe},
+    ClientCommandType::{Broadcast, Database, Script, Transaction},
+    ServerCommand::{Depart, GetFilePath, Save},
+    TransactionCommand::{Abort, Exec, Seal, Unwatch, Watch},
 };
 use crate::temple::{
     CommandType::{Client, Server},
     DatabaseCommand::{
-        Append, ConfigGet, Decr, Del, Exists, Expire, Get, Hdel, Hexists, Hget, Hgetall, Hlen,
-        Hmget, Hset, Incr, Lindex, Llen, Lpop, LpopM, Lpush, Lrange, Lrem, Lset, Mget, Mset, Rpop,
-        RpopM, Rpush, Sadd, Set, Sismember, Smembers, Srem, Strlen, Ttl,
+        Append, ClThrottle, CmsIncrby, CmsInitbydim, CmsMerge, CmsQuery, ConfigGet, ConfigSet,
+        CopyKey, Dbsize, Decr, Del, Dump, Exists, Expire, Flushall, Flushdb, FtCreate, FtDropindex,
+        FtList, FtSearch, Get, Hdel, Hexists, Hget, Hgetall, Hlen, Hmget, Hscan, Hset, Incr, Keys,
+        Lindex, Llen, Lpop, LpopM, Lpush, Lrange, Lrem, Lset, MemoryPurge, MemoryStats,
+        MemoryUsage, Mget, Move, Mset, Object, Randomkey, Rename, Renamenx, Restore, Rite, Rpop,
+        RpopM, Rpush, Sadd, Scan, Select, Set, Sismember, Smembers, Sort, Srem, Sscan, Strlen,
+        Swapdb, TopkAdd, TopkCount, TopkList, TopkReserve, Touch, TsAdd, TsCreate, TsCreaterule,
+        TsDeleterule, TsIncrby, TsMadd, TsMrange, TsRange, Ttl, Type, Unlink,
     },
 };
 
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::vec::IntoIter;
 use std::{collections::HashMap, time::SystemTime};
 
 use mio::Token;
-use rkyv::api::low::deserialize;
-use rkyv::rancor::Error;
 
+use crate::egress::outbox::OutputLimits;
 use crate::temple::soul::ServerError;
-use crate::wish::grant::{Decree, Gift};
-use crate::wish::{InfoType, Response, Sacrilege};
+use crate::wish::grant::{Congregation, Decree, Gift};
+use crate::wish::util::glob_match;
+use crate::wish::{Command, InfoType, Response, Sacrilege};
 
-pub struct EventMap(HashMap<Token, HashSet<Vec<u8>>>);
-pub struct ClientMap(HashMap<Vec<u8>, HashSet<Token>>);
+pub struct EventMap(HashMap<Token, Vows>);
+pub struct ClientMap {
+    channels: HashMap<Vec<u8>, HashSet<Token>>,
+    patterns: HashMap<Vec<u8>, HashSet<Token>>,
+    shards: HashMap<Vec<u8>, HashSet<Token>>,
+}
 
+pub mod allocator;
+pub mod chronicle;
+pub mod rite;
+pub mod scripture;
 pub mod soul;
+pub mod watch;
+
+use soul::compact::EncodingLimits;
+use soul::notify;
+use soul::snapshot;
+use soul::search::{FieldSchema, Query};
+use soul::timeseries::{Aggregation, DuplicatePolicy, LabelFilter, TimeSeriesOptions};
+use soul::{
+    DEFAULT_DATABASES, Libraries, ScanOptions, SearchOptions, SortOptions, Soul, Value,
+};
+use chronicle::Chronicle;
+use scripture::{Petition, Vigil};
+use watch::Watches;
+
+// Only SPUBLISH reaches shard channels.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Subscription {
+    Channel,
+    Pattern,
+    Shard,
+}
+
+impl Subscription {
+    fn subscribe_kind(self) -> &'static [u8] {
+        match self {
+            Subscription::Channel => b"subscribe",
+            Subscription::Pattern => b"psubscribe",
+            Subscription::Shard => b"ssubscribe",
+        }
+    }
+
+    fn unsubscribe_kind(self) -> &'static [u8] {
+        match self {
+            Subscription::Channel => b"unsubscribe",
+            Subscription::Pattern => b"punsubscribe",
+            Subscription::Shard => b"sunsubscribe",
+        }
+    }
+}
+
+// Reply counts are of channels and patterns together, or of shard channels
+// alone.
+#[derive(Default)]
+struct Vows {
+    channels: HashSet<Vec<u8>>,
+    patterns: HashSet<Vec<u8>>,
+    shards: HashSet<Vec<u8>>,
+}
+
+impl Vows {
+    fn of(&mut self, subscription: Subscription) -> &mut HashSet<Vec<u8>> {
+        match subscription {
+            Subscription::Channel => &mut self.channels,
+            Subscription::Pattern => &mut self.patterns,
+            Subscription::Shard => &mut self.shards,
+        }
+    }
+
+    fn count(&self, subscription: Subscription) -> usize {
+        match subscription {
+            Subscription::Channel | Subscription::Pattern => {
+                self.channels.len() + self.patterns.len()
+            }
+            Subscription::Shard => self.shards.len(),
+        }
+    }
 
-use soul::{ArchivedSoul, Soul, Value};
+    fn len(&self) -> usize {
+        self.channels.len() + self.patterns.len() + self.shards.len()
+    }
+}
 
 impl Default for ClientMap {
     fn default() -> Self {
@@ -41,46 +125,134 @@ impl Default for ClientMap {
 
 impl ClientMap {
     pub fn new() -> Self {
-        ClientMap(HashMap::new())
+        ClientMap {
+            channels: HashMap::new(),
+            patterns: HashMap::new(),
+            shards: HashMap::new(),
+        }
     }
 
-    pub fn subscribe(&mut self, token: Token, events: Vec<Vec<u8>>) {
+    fn get(&self, subscription: Subscription) -> &HashMap<Vec<u8>, HashSet<Token>> {
+        match subscription {
+            Subscription::Channel => &self.channels,
+            Subscription::Pattern => &self.patterns,
+            Subscription::Shard => &self.shards,
+        }
+    }
+
+    fn of(&mut self, subscription: Subscription) -> &mut HashMap<Vec<u8>, HashSet<Token>> {
+        match subscription {
+            Subscription::Channel => &mut self.channels,
+            Subscription::Pattern => &mut self.patterns,
+            Subscription::Shard => &mut self.shards,
+        }
+    }
+
+    pub fn subscribe(&mut self, token: Token, subscription: Subscription, events: Vec<Vec<u8>>) {
+        let map = self.of(subscription);
+
         for event in events {
-            match self.0.get_mut(&event) {
-                Some(set) => {
-                    set.insert(token);
-                }
-                None => {
-                    let mut set = HashSet::new();
-                    set.insert(token);
+            map.entry(event).or_default().insert(token);
+        }
+    }
+
+    pub fn unsubscribe(
+        &mut self,
+        token: Token,
+        subscription: Subscription,
+        events: &[(Option<Vec<u8>>, usize)],
+    ) {
+        let map = self.of(subscription);
 
-                    self.0.insert(event, set);
+        for event in events.iter().flat_map(|(event, _)| event) {
+            if let Some(set) = map.get_mut(event) {
+                set.remove(&token);
+                if set.is_empty() {
+                    map.remove(event);
                 }
             }
         }
     }
 
-    pub fn unsubscribe(&mut self, token: Token, events: &Option<Vec<(Vec<u8>, usize)>>) {
-        let Some(events) = events else {
-            return;
-        };
+    fn depart(&mut self, token: Token, vows: Vows) {
+        for (subscription, events) in [
+            (Subscription::Channel, vows.channels),
+            (Subscription::Pattern, vows.patterns),
+            (Subscription::Shard, vows.shards),
+        ] {
+            let map = self.of(subscription);
 
-        for (event, _) in events {
-            if let Some(set) = self.0.get_mut(event) {
-                set.remove(&token);
-                if set.is_empty() {
-                    self.0.remove(event);
+            for event in events {
+                if let Some(set) = map.get_mut(&event) {
+                    set.remove(&token);
+                    if set.is_empty() {
+                        map.remove(&event);
+                    }
                 }
             }
         }
     }
 
-    pub fn publish(&self, event: Vec<u8>) -> Vec<Token> {
-        match self.0.get(&event) {
+    pub fn publish(&self, event: &[u8]) -> Congregation {
+        let clients = match self.channels.get(event) {
             Some(clients) => clients.iter().cloned().collect(),
             None => Vec::new(),
+        };
+
+        let patterns = self
+            .patterns
+            .iter()
+            .filter(|(pattern, _)| glob_match(pattern, event))
+            .map(|(pattern, clients)| (pattern.clone(), clients.iter().cloned().collect()))
+            .collect();
+
+        Congregation {
+            clients,
+            patterns,
+            ..Default::default()
         }
     }
+
+    // Patterns never match shard channels.
+    pub fn spublish(&self, channel: &[u8]) -> Congregation {
+        Congregation {
+            clients: self
+                .shards
+                .get(channel)
+                .map_or(Vec::new(), |clients| clients.iter().cloned().collect()),
+            shard: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn channels(&self, subscription: Subscription, pattern: Option<&[u8]>) -> Vec<Vec<u8>> {
+        self.get(subscription)
+            .keys()
+            .filter(|channel| pattern.is_none_or(|pattern| glob_match(pattern, channel)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn numsub(
+        &self,
+        subscription: Subscription,
+        channels: Vec<Vec<u8>>,
+    ) -> Vec<(Vec<u8>, usize)> {
+        let map = self.get(subscription);
+
+        channels
+            .into_iter()
+            .map(|channel| {
+                let count = map.get(&channel).map_or(0, HashSet::len);
+                (channel, count)
+            })
+            .collect()
+    }
+
+    // Patterns are counted once however many clients share them.
+    pub fn numpat(&self) -> usize {
+        self.patterns.len()
+    }
 }
 
 impl Default for EventMap {
@@ -94,85 +266,68 @@ impl EventMap {
         EventMap(HashMap::new())
     }
 
-    pub fn subscribe(&mut self, token: Token, events: Vec<Vec<u8>>) -> Vec<(Vec<u8>, usize)> {
-        match self.0.get_mut(&token) {
-            Some(set) => {
-                let mut result = Vec::new();
-
-                let mut count = set.len();
-
-                for event in events {
-                    if set.insert(event.clone()) {
-                        count += 1;
-                        result.push((event, count));
-                    }
-                }
-
-                result
-            }
-            None => {
-                let mut set = HashSet::new();
-                let mut result = Vec::new();
-                let mut count = 0;
-
-                for event in events {
-                    if set.insert(event.clone()) {
-                        count += 1;
-                        result.push((event, count));
-                    }
-                }
-
-                self.0.insert(token, set);
+    pub fn subscribe(
+        &mut self,
+        token: Token,
+        subscription: Subscription,
+        events: Vec<Vec<u8>>,
+    ) -> Vec<(Vec<u8>, usize)> {
+        let vows = self.0.entry(token).or_default();
+        let mut result = Vec::new();
 
-                result
+        for event in events {
+            if vows.of(subscription).insert(event.clone()) {
+                result.push((event, vows.count(subscription)));
             }
         }
+
+        result
     }
 
+    // No events leaves every channel or every pattern, whichever it's for.
     pub fn unsubscribe(
         &mut self,
         events: Vec<Vec<u8>>,
         token: Token,
+        subscription: Subscription,
         subscribed_clients: &mut HashSet<Token>,
-    ) -> Option<Vec<(Vec<u8>, usize)>> {
-        match self.0.get_mut(&token) {
-            Some(existing_events) => {
-                let mut result = Vec::new();
-                let mut count = existing_events.len();
-
-                if !events.is_empty() {
-                    for event in events {
-                        if existing_events.remove(&event) {
-                            count -= 1;
-                        }
+    ) -> Vec<(Option<Vec<u8>>, usize)> {
+        let Some(vows) = self.0.get_mut(&token) else {
+            if events.is_empty() {
+                return vec![(None, 0)];
+            }
 
-                        result.push((event, count));
-                    }
+            return events.into_iter().map(|event| (Some(event), 0)).collect();
+        };
 
-                    if existing_events.is_empty() {
-                        self.0.remove(&token);
-                        subscribed_clients.remove(&token);
-                    }
+        let events = if events.is_empty() {
+            std::mem::take(vows.of(subscription)).into_iter().collect()
+        } else {
+            events
+        };
 
-                    Some(result)
-                } else {
-                    let unsubscribed_events: Vec<Vec<u8>> =
-                        std::mem::take(existing_events).into_iter().collect();
-                    let mut count = unsubscribed_events.len();
+        let mut result = Vec::with_capacity(events.len());
 
-                    for event in unsubscribed_events {
-                        count -= 1;
-                        result.push((event, count));
-                    }
+        for event in events {
+            vows.of(subscription).remove(&event);
+            result.push((Some(event), vows.count(subscription)));
+        }
 
-                    subscribed_clients.remove(&token);
-                    self.0.remove(&token);
+        if result.is_empty() {
+            result.push((None, vows.count(subscription)));
+        }
 
-                    Some(result)
-                }
-            }
-            None => None,
+        if vows.len() == 0 {
+            self.0.remove(&token);
+            subscribed_clients.remove(&token);
         }
+
+        result
+    }
+
+    fn depart(&mut self, token: Token, subscribed_clients: &mut HashSet<Token>) -> Option<Vows> {
+        subscribed_clients.remove(&token);
+        self.0.remove(&token)
     }
 }
 
@@ -181,7 +336,6 @@ pub struct Wish {
     command_type: CommandType,
 }
 
-#[derive(Clone)]
 pub enum CommandType {
     Server(ServerCommand),
     Client(ClientCommand),
@@ -196,25 +350,122 @@ pub enum ServerCommand {
     GetFilePath {
         tx: Sender<Result<Vec<u8>, ServerError>>,
     },
+    Depart,
 }
 
-#[derive(Clone)]
 pub struct ClientCommand {
     tx: Sender<Decree>,
     client_command_type: ClientCommandType,
 }
 
-#[derive(Clone)]
 pub enum ClientCommandType {
     Database(DatabaseCommand),
     Broadcast(BroadcastCommand),
+    Transaction(TransactionCommand),
+    Script(ScriptCommand),
+}
+
+// Seal follows the wishes of a transaction and gathers their replies.
+pub enum TransactionCommand {
+    Watch {
+        keys: Vec<Vec<u8>>,
+        time: u64,
+    },
+    Unwatch,
+    Abort,
+    Exec {
+        wishes: Vec<Wish>,
+        replies: Vec<Receiver<Decree>>,
+        time: u64,
+    },
+    Seal {
+        replies: Vec<Receiver<Decree>>,
+    },
+}
+
+pub enum Source {
+    Body(Vec<u8>),
+    Sha(Vec<u8>),
+    Function { name: Vec<u8>, read_only: bool },
+}
+
+// An EVAL holds the temple until its script returns.
+pub enum ScriptCommand {
+    Eval {
+        source: Source,
+        keys: Vec<Vec<u8>>,
+        args: Vec<Vec<u8>>,
+    },
+    Load {
+        body: Vec<u8>,
+    },
+    Exists {
+        shas: Vec<Vec<u8>>,
+    },
+    Flush,
+    Function(FunctionCommand),
+}
+
+pub enum FunctionCommand {
+    Load {
+        code: Vec<u8>,
+        replace: bool,
+    },
+    Delete {
+        library: Vec<u8>,
+    },
+    Flush,
+    List {
+        pattern: Option<Vec<u8>>,
+        with_code: bool,
+    },
+    Dump,
+    Restore {
+        payload: Vec<u8>,
+        policy: RestorePolicy,
+    },
+    Reload {
+        libraries: Libraries,
+    },
+}
+
+pub enum RestorePolicy {
+    Flush,
+    Append,
+    Replace,
 }
 
 #[derive(Clone)]
 pub enum BroadcastCommand {
-    Subscribe { events: Vec<Vec<u8>> },
-    Publish { event: Vec<u8>, message: Vec<u8> },
-    Unsubscribe { terms: Vec<Vec<u8>> },
+    // Replay follows the sequence ID to replay the channels' kept messages
+    // after.
+    Subscribe {
+        subscription: Subscription,
+        events: Vec<Vec<u8>>,
+        replay: Option<u64>,
+    },
+    Publish {
+        event: Vec<u8>,
+        message: Vec<u8>,
+    },
+    Spublish {
+        channel: Vec<u8>,
+        message: Vec<u8>,
+    },
+    Unsubscribe {
+        subscription: Subscription,
+        terms: Vec<Vec<u8>>,
+    },
+    Pubsub(PubsubQuery),
+}
+
+#[derive(Clone)]
+pub enum PubsubQuery {
+    Channels { pattern: Option<Vec<u8>> },
+    Numsub { channels: Vec<Vec<u8>> },
+    Numpat,
+    ShardChannels { pattern: Option<Vec<u8>> },
+    ShardNumsub { channels: Vec<Vec<u8>> },
 }
 
 #[derive(Clone)]
@@ -378,14 +629,256 @@ pub enum DatabaseCommand {
     ConfigGet {
         properties: Vec<Vec<u8>>,
     },
+    ConfigSet {
+        property: Vec<u8>,
+        value: Vec<u8>,
+    },
+    CmsInitbydim {
+        key: Vec<u8>,
+        width: usize,
+        depth: usize,
+        time: u64,
+    },
+    CmsIncrby {
+        key: Vec<u8>,
+        increments: Vec<(Vec<u8>, u64)>,
+        time: u64,
+    },
+    CmsQuery {
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        time: u64,
+    },
+    CmsMerge {
+        destination: Vec<u8>,
+        sources: Vec<Vec<u8>>,
+        weights: Vec<u64>,
+        time: u64,
+    },
+    TopkReserve {
+        key: Vec<u8>,
+        k: usize,
+        width: usize,
+        depth: usize,
+        decay: f64,
+        time: u64,
+    },
+    TopkAdd {
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        time: u64,
+    },
+    TopkList {
+        key: Vec<u8>,
+        with_count: bool,
+        time: u64,
+    },
+    TopkCount {
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        time: u64,
+    },
+    TsCreate {
+        key: Vec<u8>,
+        options: TimeSeriesOptions,
+        time: u64,
+    },
+    TsAdd {
+        key: Vec<u8>,
+        timestamp: u64,
+        value: f64,
+        options: TimeSeriesOptions,
+        on_duplicate: Option<DuplicatePolicy>,
+        time: u64,
+    },
+    TsMadd {
+        samples: Vec<(Vec<u8>, u64, f64)>,
+        time: u64,
+    },
+    TsIncrby {
+        key: Vec<u8>,
+        increment: f64,
+        timestamp: u64,
+        options: TimeSeriesOptions,
+        time: u64,
+    },
+    TsRange {
+        key: Vec<u8>,
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+        count: Option<usize>,
+        reverse: bool,
+        time: u64,
+    },
+    TsMrange {
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+        count: Option<usize>,
+        filters: Vec<LabelFilter>,
+        time: u64,
+    },
+    TsCreaterule {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        aggregation: Aggregation,
+        bucket_duration: u64,
+        time: u64,
+    },
+    TsDeleterule {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        time: u64,
+    },
+    FtCreate {
+        index: Vec<u8>,
+        prefixes: Vec<Vec<u8>>,
+        schema: Vec<FieldSchema>,
+    },
+    FtSearch {
+        index: Vec<u8>,
+        query: Query,
+        options: SearchOptions,
+        time: u64,
+    },
+    FtDropindex {
+        index: Vec<u8>,
+        delete_documents: bool,
+        time: u64,
+    },
+    FtList,
+    ClThrottle {
+        key: Vec<u8>,
+        max_burst: u64,
+        count: u64,
+        period: u64,
+        quantity: u64,
+        time: u64,
+    },
+    Scan {
+        cursor: u64,
+        options: ScanOptions,
+        time: u64,
+    },
+    Hscan {
+        key: Vec<u8>,
+        cursor: u64,
+        options: ScanOptions,
+        time: u64,
+    },
+    Sscan {
+        key: Vec<u8>,
+        cursor: u64,
+        options: ScanOptions,
+        time: u64,
+    },
+    Keys {
+        pattern: Vec<u8>,
+        time: u64,
+    },
+    Type {
+        key: Vec<u8>,
+        time: u64,
+    },
+    Rename {
+        key: Vec<u8>,
+        new_key: Vec<u8>,
+        time: u64,
+    },
+    Renamenx {
+        key: Vec<u8>,
+        new_key: Vec<u8>,
+        time: u64,
+    },
+    CopyKey {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        replace: bool,
+        time: u64,
+    },
+    Randomkey {
+        time: u64,
+    },
+    Dbsize,
+    Touch {
+        keys: Vec<Vec<u8>>,
+        time: u64,
+    },
+    Unlink {
+        keys: Vec<Vec<u8>>,
+        time: u64,
+    },
+    Select {
+        db: usize,
+    },
+    Move {
+        key: Vec<u8>,
+        db: usize,
+        time: u64,
+    },
+    Swapdb {
+        first: usize,
+        second: usize,
+    },
+    Flushdb {
+        asynchronous: bool,
+    },
+    Flushall {
+        asynchronous: bool,
+    },
+    Dump {
+        key: Vec<u8>,
+        time: u64,
+    },
+    Restore {
+        key: Vec<u8>,
+        payload: Vec<u8>,
+        expiry: Option<u64>,
+        replace: bool,
+        time: u64,
+    },
+    Sort {
+        key: Vec<u8>,
+        options: SortOptions,
+        store: Option<Vec<u8>>,
+        time: u64,
+    },
+    Object {
+        key: Vec<u8>,
+        field: ObjectField,
+        time: u64,
+    },
+    MemoryUsage {
+        key: Vec<u8>,
+        samples: usize,
+        time: u64,
+    },
+    MemoryStats,
+    MemoryPurge,
+    Rite {
+        rite: Arc<dyn rite::Rite>,
+        terms: Vec<Vec<u8>>,
+        time: u64,
+    },
+}
+
+#[derive(Clone)]
+pub enum ObjectField {
+    Encoding,
+    IdleTime,
+    Freq,
+    RefCount,
 }
 
 #[derive(Clone)]
 pub struct Temple {
     tx: Sender<Wish>,
+    vigil: Arc<Vigil>,
 }
 
 impl Temple {
+    #[allow(clippy::too_many_arguments)]
     pub fn worship(
         dir: Vec<u8>,
         dbfilename: Vec<u8>,
@@ -395,10 +888,24 @@ impl Temple {
         event_capacity: Vec<u8>,
         max_memory: Vec<u8>,
         append_only: Vec<u8>,
+        databases: Vec<u8>,
+        notify_keyspace_events: Vec<u8>,
     ) -> Self {
         let (tx, rx): (Sender<Wish>, Receiver<Wish>) = std::sync::mpsc::channel();
+        let vigil = Arc::new(Vigil::default());
+        let temple_vigil = vigil.clone();
 
         std::thread::spawn(move || {
+            let vigil = temple_vigil;
+
+            let database_count = std::str::from_utf8(&databases)
+                .ok()
+                .and_then(|databases| databases.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_DATABASES);
+
+            // A snapshot that couldn't be read is never saved over.
+            let mut unreadable_snapshot: Option<PathBuf> = None;
+
             let mut soul: Soul = (|| {
                 let db_file_path = [dir.as_slice(), b"/", dbfilename.as_slice()].concat();
 
@@ -407,33 +914,67 @@ impl Temple {
                     std::str::from_utf8(&db_file_path)
                 else {
                     println!("Couldn't load snapshot, failed to access file");
-                    return Soul::new();
+                    return Soul::with_databases(database_count);
                 };
 
                 let Ok(bytes) = std::fs::read(db_file_path) else {
                     println!("Couldn't load snapshot, failed to read file");
-                    return Soul::new();
-                };
-
-                let Ok(archived_soul) = rkyv::access::<ArchivedSoul, Error>(&bytes) else {
-                    return Soul::new();
+                    return Soul::with_databases(database_count);
                 };
 
-                match deserialize::<_, Error>(archived_soul) {
-                    Ok(snapshot) => {
+                match snapshot::decode(&bytes) {
+                    Ok(mut snapshot) => {
                         println!("Snapshot loaded successfully");
+                        snapshot.grow(database_count);
+                        snapshot.rebuild_indexes();
                         snapshot
                     }
                     Err(e) => {
-                        println!("Couldn't load snapshot: {}", e);
-                        Soul::new()
+                        eprintln!(
+                            "Couldn't load snapshot {}: {}, it won't be overwritten",
+                            db_file_path, e
+                        );
+                        unreadable_snapshot = Some(PathBuf::from(db_file_path));
+                        Soul::with_databases(database_count)
                     }
                 }
             })();
 
+            // Frees what UNLINK and the ASYNC flushes hand over.
+            let (reaper_tx, reaper_rx) = std::sync::mpsc::channel::<Box<dyn Send>>();
+
+            std::thread::spawn(move || for _garbage in reaper_rx {});
+
+            let scripture = scripture::spawn(vigil.clone());
+
+            if !soul.libraries().is_empty()
+                && scripture
+                    .send(Petition {
+                        command: ScriptCommand::Function(FunctionCommand::Reload {
+                            libraries: soul.libraries().clone(),
+                        }),
+                        tx: std::sync::mpsc::channel().0,
+                        token: Token(0),
+                        temple: None,
+                        ledger: None,
+                    })
+                    .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            let mut notify_flags = notify::parse_flags(&notify_keyspace_events).unwrap_or(0);
+            soul.set_notify_flags(notify_flags);
+
+            let mut encoding_limits = EncodingLimits::default();
+            soul.set_encoding_limits(encoding_limits);
+
+            let mut selected_databases: HashMap<Token, usize> = HashMap::new();
             let mut client_map = ClientMap::new();
             let mut event_map = EventMap::new();
             let mut subscribed_clients = HashSet::new();
+            let mut chronicle = Chronicle::default();
+            let mut output_limits = OutputLimits::default();
 
             // let mut info: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
             let mut config: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
@@ -449,19 +990,66 @@ impl Temple {
                 event_capacity,
                 max_memory,
                 append_only,
+                databases,
+                notify::flags_to_string(notify_flags),
             );
 
-            loop {
-                match rx.recv() {
-                    Ok(wish) => {
-                        let token = wish.token;
+            for (property, value) in encoding_limits.entries() {
+                config.insert(property.to_vec(), value);
+            }
 
-                        let command_type = wish.command_type;
+            for (property, value) in chronicle.entries() {
+                config.insert(property.to_vec(), value);
+            }
 
-                        match command_type {
-                            Server(server_command) => match server_command {
+            config.insert(b"client-output-buffer-limit".to_vec(), output_limits.entry());
+
+            // Wishes of a transaction being granted, they go before anything
+            // else waiting on the channel.
+            let mut transaction: VecDeque<Wish> = VecDeque::new();
+            let mut watches = Watches::default();
+
+            // While a script runs only its own wishes are granted.
+            let mut scripting: Option<(Receiver<Wish>, u64)> = None;
+
+            loop {
+                let received = if let Some((script_rx, _)) = &scripting {
+                    match script_rx.recv() {
+                        Ok(wish) => Ok(wish),
+                        Err(_) => {
+                            scripting = None;
+                            continue;
+                        }
+                    }
+                } else {
+                    match transaction.pop_front() {
+                        Some(wish) => Ok(wish),
+                        None => rx.recv(),
+                    }
+                };
+
+                match received {
+                    Ok(wish) => {
+                        let token = wish.token;
+
+                        let command_type = wish.command_type;
+
+                        match command_type {
+                            Server(server_command) => match server_command {
                                 Save { tx, file_path } => {
-                                    if tx.send(soul.save(file_path)).is_err() {
+                                    let saved = if unreadable_snapshot
+                                        .as_ref()
+                                        .is_some_and(|path| *path == file_path && path.exists())
+                                    {
+                                        Err(ServerError::FileWriteError(
+                                            "refusing to overwrite a snapshot that failed to load"
+                                                .to_string(),
+                                        ))
+                                    } else {
+                                        soul.save(file_path)
+                                    };
+
+                                    if tx.send(saved).is_err() {
                                         eprintln!("angel panicked");
                                     }
 
@@ -495,24 +1083,49 @@ impl Temple {
                                         eprintln!("angel panicked");
                                     }
                                 }
+                                Depart => {
+                                    watches.unwatch(token);
+                                    soul.set_watching(!watches.is_empty());
+
+                                    selected_databases.remove(&token);
+                                    chronicle.forget(token);
+
+                                    if let Some(vows) =
+                                        event_map.depart(token, &mut subscribed_clients)
+                                    {
+                                        client_map.depart(token, vows);
+                                    }
+                                }
                             },
                             Client(client_command) => {
                                 let tx = client_command.tx;
 
                                 match client_command.client_command_type {
                                     Broadcast(broadcast_command) => match broadcast_command {
-                                        Subscribe { events } => {
+                                        Subscribe {
+                                            subscription,
+                                            events,
+                                            replay,
+                                        } => {
                                             subscribed_clients.insert(token);
 
-                                            let subscribed_channels =
-                                                event_map.subscribe(token, events.clone());
-                                            client_map.subscribe(token, events.clone());
+                                            let subscribed = event_map.subscribe(
+                                                token,
+                                                subscription,
+                                                events.clone(),
+                                            );
+                                            client_map.subscribe(
+                                                token,
+                                                subscription,
+                                                events.clone(),
+                                            );
 
                                             if tx
                                                 .send(Decree::Deliver(Gift {
                                                     token,
-                                                    response: Response::SubscribedChannels(
-                                                        subscribed_channels,
+                                                    response: Response::Subscribed(
+                                                        subscription.subscribe_kind(),
+                                                        subscribed,
                                                     ),
                                                 }))
                                                 .is_err()
@@ -520,21 +1133,60 @@ impl Temple {
                                                 eprintln!("angel panicked");
                                             }
 
+                                            // What was missed goes out before
+                                            // anything published from here on.
+                                            if let Some(after) = replay {
+                                                chronicle.read(token);
+
+                                                for event in events {
+                                                    for (sequence, message) in
+                                                        chronicle.since(&event, after)
+                                                    {
+                                                        let clients = Congregation {
+                                                            readers: vec![token],
+                                                            sequence: Some(sequence),
+                                                            ..Default::default()
+                                                        };
+
+                                                        if tx
+                                                            .send(Decree::Broadcast(
+                                                                None,
+                                                                event.clone(),
+                                                                message,
+                                                                clients,
+                                                            ))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                }
+                                            }
+
                                             continue;
                                         }
-                                        Unsubscribe { terms } => {
-                                            let unsubscribed_events = event_map.unsubscribe(
+                                        Unsubscribe {
+                                            subscription,
+                                            terms,
+                                        } => {
+                                            let unsubscribed = event_map.unsubscribe(
                                                 terms,
                                                 token,
+                                                subscription,
                                                 &mut subscribed_clients,
                                             );
-                                            client_map.unsubscribe(token, &unsubscribed_events);
+                                            client_map.unsubscribe(
+                                                token,
+                                                subscription,
+                                                &unsubscribed,
+                                            );
 
                                             if tx
                                                 .send(Decree::Deliver(Gift {
                                                     token,
-                                                    response: Response::UnsubscribedChannels(
-                                                        unsubscribed_events,
+                                                    response: Response::Unsubscribed(
+                                                        subscription.unsubscribe_kind(),
+                                                        unsubscribed,
                                                     ),
                                                 }))
                                                 .is_err()
@@ -544,12 +1196,50 @@ impl Temple {
 
                                             continue;
                                         }
+                                        Pubsub(query) => {
+                                            let response = Self::pubsub_response(&client_map, query);
+
+                                            if tx
+                                                .send(Decree::Deliver(Gift { token, response }))
+                                                .is_err()
+                                            {
+                                                eprintln!("angel panicked");
+                                            }
+
+                                            continue;
+                                        }
                                         Publish { event, message } => {
-                                            let clients = client_map.publish(event.clone());
+                                            let mut clients = client_map.publish(&event);
+
+                                            if let Some(sequence) =
+                                                chronicle.record(&event, &message)
+                                            {
+                                                clients.chronicle(sequence, |client| {
+                                                    chronicle.is_reader(client)
+                                                });
+                                            }
+
+                                            if tx
+                                                .send(Decree::Broadcast(
+                                                    Some(token),
+                                                    event,
+                                                    message,
+                                                    clients,
+                                                ))
+                                                .is_err()
+                                            {
+                                                eprintln!("angel panicked");
+                                            }
+                                        }
+                                        Spublish { channel, message } => {
+                                            let clients = client_map.spublish(&channel);
 
                                             if tx
                                                 .send(Decree::Broadcast(
-                                                    token, event, message, clients,
+                                                    Some(token),
+                                                    channel,
+                                                    message,
+                                                    clients,
                                                 ))
                                                 .is_err()
                                             {
@@ -557,6 +1247,185 @@ impl Temple {
                                             }
                                         }
                                     },
+                                    Transaction(transaction_command) => {
+                                        match transaction_command {
+                                            Watch { keys, time } => {
+                                                let db = selected_databases
+                                                    .get(&token)
+                                                    .copied()
+                                                    .unwrap_or(0);
+                                                soul.select(db);
+
+                                                // A key that expired before the watch
+                                                // shouldn't count against it later.
+                                                for key in &keys {
+                                                    soul.expire_if_due(key, time);
+                                                }
+
+                                                for (db, key) in soul.touched() {
+                                                    watches.touch(db, key);
+                                                }
+
+                                                for key in keys {
+                                                    watches.watch(token, db, key);
+                                                }
+
+                                                soul.set_watching(true);
+
+                                                for (db, event, key) in soul.notifications() {
+                                                    Self::notify_keyspace(
+                                                        &tx,
+                                                        &client_map,
+                                                        notify_flags,
+                                                        db,
+                                                        event,
+                                                        key,
+                                                    );
+                                                }
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift {
+                                                        token,
+                                                        response: Response::Info(InfoType::Ok),
+                                                    }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Unwatch | Abort => {
+                                                watches.unwatch(token);
+                                                soul.set_watching(!watches.is_empty());
+
+                                                let response = match transaction_command {
+                                                    Abort => Response::Error(
+                                                        Sacrilege::TransactionAborted,
+                                                    ),
+                                                    _ => Response::Info(InfoType::Ok),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Exec {
+                                                wishes,
+                                                replies,
+                                                time,
+                                            } => {
+                                                // Watched keys that have expired since are
+                                                // dropped now, which counts as a change.
+                                                for (db, key) in watches.watched_by(token) {
+                                                    soul.select(db);
+                                                    soul.expire_if_due(&key, time);
+                                                }
+
+                                                for (db, key) in soul.touched() {
+                                                    watches.touch(db, key);
+                                                }
+
+                                                for (db, event, key) in soul.notifications() {
+                                                    Self::notify_keyspace(
+                                                        &tx,
+                                                        &client_map,
+                                                        notify_flags,
+                                                        db,
+                                                        event,
+                                                        key,
+                                                    );
+                                                }
+
+                                                let intact = watches.intact(token);
+                                                watches.unwatch(token);
+                                                soul.set_watching(!watches.is_empty());
+
+                                                if !intact {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::BulkStringArray(
+                                                                None,
+                                                            ),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+
+                                                    continue;
+                                                }
+
+                                                transaction.extend(wishes);
+                                                transaction.push_back(Wish {
+                                                    token,
+                                                    command_type: Client(ClientCommand {
+                                                        tx,
+                                                        client_command_type: Transaction(Seal {
+                                                            replies,
+                                                        }),
+                                                    }),
+                                                });
+                                            }
+                                            Seal { replies } => {
+                                                let mut responses =
+                                                    Vec::with_capacity(replies.len());
+
+                                                for decree in replies
+                                                    .iter()
+                                                    .flat_map(|reply| reply.try_iter())
+                                                {
+                                                    match decree {
+                                                        Decree::Deliver(gift) => {
+                                                            responses.push(gift.response)
+                                                        }
+                                                        // The publisher's count belongs in the
+                                                        // array, the messages still go out.
+                                                        Decree::Broadcast(
+                                                            publisher,
+                                                            event,
+                                                            message,
+                                                            clients,
+                                                        ) => {
+                                                            if publisher.is_some() {
+                                                                responses.push(Response::Length(
+                                                                    clients.len(),
+                                                                ));
+                                                            }
+
+                                                            if tx
+                                                                .send(Decree::Broadcast(
+                                                                    None, event, message, clients,
+                                                                ))
+                                                                .is_err()
+                                                            {
+                                                                eprintln!("angel panicked");
+                                                            }
+                                                        }
+                                                        decree => {
+                                                            if tx.send(decree).is_err() {
+                                                                eprintln!("angel panicked");
+                                                            }
+                                                        }
+                                                    }
+                                                }
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift {
+                                                        token,
+                                                        response: Response::Array(responses),
+                                                    }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                        }
+
+                                        continue;
+                                    }
                                     Database(database_command) => {
                                         if subscribed_clients.contains(&token) {
                                             if tx
@@ -573,6 +1442,11 @@ impl Temple {
 
                                             continue;
                                         }
+
+                                        soul.select(
+                                            selected_databases.get(&token).copied().unwrap_or(0),
+                                        );
+
                                         match database_command {
                                             Get { key, time } => match soul.get(key, time) {
                                                 Ok(bulk_string) => {
@@ -1428,23 +2302,13 @@ impl Temple {
                                                         result.push(Some(property).cloned());
                                                         result.push(Some(value).cloned());
                                                     }
-
-                                                    result.push(Some(b"databases".to_vec()));
-                                                    result.push(Some(b"1".to_vec()));
                                                 } else {
-                                                    for (_, property) in
-                                                        properties.iter().enumerate()
-                                                    {
+                                                    for property in properties.iter() {
                                                         if let Some(value) = config.get(property) {
                                                             result.push(Some(property).cloned());
                                                             result.push(Some(value).cloned());
                                                         }
                                                     }
-
-                                                    if properties.contains(&b"databases".to_vec()) {
-                                                        result.push(Some(b"databases".to_vec()));
-                                                        result.push(Some(b"1".to_vec()));
-                                                    }
                                                 }
 
                                                 if tx
@@ -1459,118 +2323,2528 @@ impl Temple {
                                                     eprintln!("angel panicked");
                                                 }
                                             }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("GodThread: {}", e);
-                        break;
-                    }
-                }
-            }
-        });
+                                            ConfigSet { property, value } => {
+                                                let flags = property
+                                                    .eq_ignore_ascii_case(b"notify-keyspace-events")
+                                                    .then(|| notify::parse_flags(&value))
+                                                    .flatten();
 
-        Temple { tx }
-    }
+                                                let response = match flags {
+                                                    Some(flags) => {
+                                                        notify_flags = flags;
+                                                        soul.set_notify_flags(flags);
+                                                        config.insert(
+                                                            b"notify-keyspace-events".to_vec(),
+                                                            notify::flags_to_string(flags),
+                                                        );
 
-    pub fn get(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
-        if self
-            .tx
-            .send(Wish {
-                token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(Get { key, time }),
-                }),
-            })
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-    }
+                                                        Response::Info(InfoType::Ok)
+                                                    }
+                                                    None if encoding_limits
+                                                        .set(&property, &value) =>
+                                                    {
+                                                        soul.set_encoding_limits(encoding_limits);
 
-    pub fn set(&self, key: Vec<u8>, value: (Value, Option<u64>), tx: Sender<Decree>, token: Token) {
-        if self
-            .tx
-            .send(Wish {
-                token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(Set { key, value }),
-                }),
-            })
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-    }
+                                                        for (property, value) in
+                                                            encoding_limits.entries()
+                                                        {
+                                                            config.insert(property.to_vec(), value);
+                                                        }
 
-    pub fn del(&self, keys: Vec<Vec<u8>>, tx: Sender<Decree>, token: Token, time: u64) {
-        if self
-            .tx
-            .send(Wish {
-                token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(Del { keys, time }),
-                }),
-            })
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-    }
+                                                        Response::Info(InfoType::Ok)
+                                                    }
+                                                    None if property.eq_ignore_ascii_case(
+                                                        b"client-output-buffer-limit",
+                                                    ) && output_limits.set(&value) =>
+                                                    {
+                                                        config.insert(
+                                                            b"client-output-buffer-limit".to_vec(),
+                                                            output_limits.entry(),
+                                                        );
 
-    pub fn exists(&self, keys: Vec<Vec<u8>>, tx: Sender<Decree>, token: Token, time: u64) {
-        if self
-            .tx
-            .send(Wish {
-                token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(Exists { keys, time }),
-                }),
-            })
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-    }
+                                                        if tx
+                                                            .send(Decree::Limits(output_limits))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
 
-    pub fn append(
-        &self,
-        key: Vec<u8>,
-        value: Vec<u8>,
-        tx: Sender<Decree>,
-        token: Token,
-        time: u64,
-    ) {
-        if self
-            .tx
-            .send(Wish {
-                token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(Append { key, value, time }),
-                }),
-            })
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-    }
+                                                        Response::Info(InfoType::Ok)
+                                                    }
+                                                    None if chronicle.set(&property, &value) => {
+                                                        for (property, value) in
+                                                            chronicle.entries()
+                                                        {
+                                                            config.insert(property.to_vec(), value);
+                                                        }
 
-    pub fn incr(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+                                                        Response::Info(InfoType::Ok)
+                                                    }
+                                                    None => Response::Error(
+                                                        Sacrilege::InvalidArgument(Command::CONFIG),
+                                                    ),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            CmsInitbydim {
+                                                key,
+                                                width,
+                                                depth,
+                                                time,
+                                            } => {
+                                                match soul.cms_initbydim(key, width, depth, time) {
+                                                    Ok(()) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Info(
+                                                                    InfoType::Ok,
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                    Err(sacrilege) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Error(
+                                                                    sacrilege,
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            CmsIncrby {
+                                                key,
+                                                increments,
+                                                time,
+                                            } => match soul.cms_incrby(key, increments, time) {
+                                                Ok(counts) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Array(
+                                                                counts
+                                                                    .into_iter()
+                                                                    .map(|count| {
+                                                                        Response::Number(
+                                                                            count as i64,
+                                                                        )
+                                                                    })
+                                                                    .collect(),
+                                                            ),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                                Err(sacrilege) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Error(sacrilege),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                            },
+                                            CmsQuery { key, items, time } => {
+                                                match soul.cms_query(key, items, time) {
+                                                    Ok(counts) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Array(
+                                                                    counts
+                                                                        .into_iter()
+                                                                        .map(|count| {
+                                                                            Response::Number(
+                                                                                count as i64,
+                                                                            )
+                                                                        })
+                                                                        .collect(),
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                    Err(sacrilege) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Error(
+                                                                    sacrilege,
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            CmsMerge {
+                                                destination,
+                                                sources,
+                                                weights,
+                                                time,
+                                            } => match soul.cms_merge(
+                                                destination,
+                                                sources,
+                                                weights,
+                                                time,
+                                            ) {
+                                                Ok(()) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Info(InfoType::Ok),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                                Err(sacrilege) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Error(sacrilege),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                            },
+                                            TopkReserve {
+                                                key,
+                                                k,
+                                                width,
+                                                depth,
+                                                decay,
+                                                time,
+                                            } => match soul
+                                                .topk_reserve(key, k, width, depth, decay, time)
+                                            {
+                                                Ok(()) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Info(InfoType::Ok),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                                Err(sacrilege) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Error(sacrilege),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                            },
+                                            TopkAdd { key, items, time } => {
+                                                match soul.topk_add(key, items, time) {
+                                                    Ok(expelled) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::BulkStringArray(
+                                                                    Some(expelled),
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                    Err(sacrilege) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Error(
+                                                                    sacrilege,
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            TopkList {
+                                                key,
+                                                with_count,
+                                                time,
+                                            } => match soul.topk_list(key, time) {
+                                                Ok(list) => {
+                                                    let response = if with_count {
+                                                        Response::Array(
+                                                            list.into_iter()
+                                                                .flat_map(|(item, count)| {
+                                                                    [
+                                                                        Response::BulkString(Some(
+                                                                            item,
+                                                                        )),
+                                                                        Response::Number(
+                                                                            count as i64,
+                                                                        ),
+                                                                    ]
+                                                                })
+                                                                .collect(),
+                                                        )
+                                                    } else {
+                                                        Response::BulkStringArray(Some(
+                                                            list.into_iter()
+                                                                .map(|(item, _)| Some(item))
+                                                                .collect(),
+                                                        ))
+                                                    };
+
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response,
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                                Err(sacrilege) => {
+                                                    if tx
+                                                        .send(Decree::Deliver(Gift {
+                                                            token,
+                                                            response: Response::Error(sacrilege),
+                                                        }))
+                                                        .is_err()
+                                                    {
+                                                        eprintln!("angel panicked");
+                                                    }
+                                                }
+                                            },
+                                            TopkCount { key, items, time } => {
+                                                match soul.topk_count(key, items, time) {
+                                                    Ok(counts) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Array(
+                                                                    counts
+                                                                        .into_iter()
+                                                                        .map(|count| {
+                                                                            Response::Number(
+                                                                                count as i64,
+                                                                            )
+                                                                        })
+                                                                        .collect(),
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                    Err(sacrilege) => {
+                                                        if tx
+                                                            .send(Decree::Deliver(Gift {
+                                                                token,
+                                                                response: Response::Error(
+                                                                    sacrilege,
+                                                                ),
+                                                            }))
+                                                            .is_err()
+                                                        {
+                                                            eprintln!("angel panicked");
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            TsCreate { key, options, time } => {
+                                                let response = match soul
+                                                    .ts_create(key, options, time)
+                                                {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsAdd {
+                                                key,
+                                                timestamp,
+                                                value,
+                                                options,
+                                                on_duplicate,
+                                                time,
+                                            } => {
+                                                let response = match soul.ts_add(
+                                                    key,
+                                                    timestamp,
+                                                    value,
+                                                    options,
+                                                    on_duplicate,
+                                                    time,
+                                                ) {
+                                                    Ok(timestamp) => {
+                                                        Response::Number(timestamp as i64)
+                                                    }
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsMadd { samples, time } => {
+                                                let response = Response::Array(
+                                                    soul.ts_madd(samples, time)
+                                                        .into_iter()
+                                                        .map(|result| match result {
+                                                            Ok(timestamp) => {
+                                                                Response::Number(timestamp as i64)
+                                                            }
+                                                            Err(sacrilege) => {
+                                                                Response::Error(sacrilege)
+                                                            }
+                                                        })
+                                                        .collect(),
+                                                );
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsIncrby {
+                                                key,
+                                                increment,
+                                                timestamp,
+                                                options,
+                                                time,
+                                            } => {
+                                                let response = match soul.ts_incrby(
+                                                    key, increment, timestamp, options, time,
+                                                ) {
+                                                    Ok(timestamp) => {
+                                                        Response::Number(timestamp as i64)
+                                                    }
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsRange {
+                                                key,
+                                                from,
+                                                to,
+                                                aggregation,
+                                                count,
+                                                reverse,
+                                                time,
+                                            } => {
+                                                let response = match soul.ts_range(
+                                                    key,
+                                                    from,
+                                                    to,
+                                                    aggregation,
+                                                    count,
+                                                    reverse,
+                                                    time,
+                                                ) {
+                                                    Ok(samples) => Self::samples_response(samples),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsMrange {
+                                                from,
+                                                to,
+                                                aggregation,
+                                                count,
+                                                filters,
+                                                time,
+                                            } => {
+                                                let response = Response::Array(
+                                                    soul.ts_mrange(
+                                                        from,
+                                                        to,
+                                                        aggregation,
+                                                        count,
+                                                        filters,
+                                                        time,
+                                                    )
+                                                    .into_iter()
+                                                    .map(|(key, labels, samples)| {
+                                                        Response::Array(vec![
+                                                            Response::BulkString(Some(key)),
+                                                            Response::Array(
+                                                                labels
+                                                                    .into_iter()
+                                                                    .map(|(label, value)| {
+                                                                        Response::BulkStringArray(
+                                                                            Some(vec![
+                                                                                Some(label),
+                                                                                Some(value),
+                                                                            ]),
+                                                                        )
+                                                                    })
+                                                                    .collect(),
+                                                            ),
+                                                            Self::samples_response(samples),
+                                                        ])
+                                                    })
+                                                    .collect(),
+                                                );
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsCreaterule {
+                                                source,
+                                                destination,
+                                                aggregation,
+                                                bucket_duration,
+                                                time,
+                                            } => {
+                                                let response = match soul.ts_createrule(
+                                                    source,
+                                                    destination,
+                                                    aggregation,
+                                                    bucket_duration,
+                                                    time,
+                                                ) {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            TsDeleterule {
+                                                source,
+                                                destination,
+                                                time,
+                                            } => {
+                                                let response = match soul.ts_deleterule(
+                                                    source,
+                                                    destination,
+                                                    time,
+                                                ) {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            FtCreate {
+                                                index,
+                                                prefixes,
+                                                schema,
+                                            } => {
+                                                let response = match soul
+                                                    .ft_create(index, prefixes, schema)
+                                                {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            FtSearch {
+                                                index,
+                                                query,
+                                                options,
+                                                time,
+                                            } => {
+                                                let no_content = options.no_content;
+
+                                                let response = match soul
+                                                    .ft_search(index, query, options, time)
+                                                {
+                                                    Ok((total, documents)) => {
+                                                        let mut elements =
+                                                            vec![Response::Number(total as i64)];
+
+                                                        for (key, fields) in documents {
+                                                            elements.push(Response::BulkString(
+                                                                Some(key),
+                                                            ));
+
+                                                            if !no_content {
+                                                                elements
+                                                                    .push(Response::BulkStringArray(
+                                                                    Some(
+                                                                        fields
+                                                                            .into_iter()
+                                                                            .flat_map(
+                                                                                |(field, value)| {
+                                                                                    [
+                                                                                        Some(field),
+                                                                                        Some(value),
+                                                                                    ]
+                                                                                },
+                                                                            )
+                                                                            .collect(),
+                                                                    ),
+                                                                ));
+                                                            }
+                                                        }
+
+                                                        Response::Array(elements)
+                                                    }
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            FtDropindex {
+                                                index,
+                                                delete_documents,
+                                                time,
+                                            } => {
+                                                let response = match soul.ft_dropindex(
+                                                    index,
+                                                    delete_documents,
+                                                    time,
+                                                ) {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            FtList => {
+                                                let response = Response::BulkStringArray(Some(
+                                                    soul.ft_list().into_iter().map(Some).collect(),
+                                                ));
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            ClThrottle {
+                                                key,
+                                                max_burst,
+                                                count,
+                                                period,
+                                                quantity,
+                                                time,
+                                            } => {
+                                                let response = match soul.cl_throttle(
+                                                    key, max_burst, count, period, quantity, time,
+                                                ) {
+                                                    Ok(throttle) => Response::Array(vec![
+                                                        Response::Number(
+                                                            throttle.is_limited as i64,
+                                                        ),
+                                                        Response::Number(throttle.limit as i64),
+                                                        Response::Number(throttle.remaining as i64),
+                                                        Response::Number(
+                                                            throttle
+                                                                .retry_after
+                                                                .map_or(-1, |retry| retry as i64),
+                                                        ),
+                                                        Response::Number(
+                                                            throttle.reset_after as i64,
+                                                        ),
+                                                    ]),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Type { key, time } => {
+                                                let response = Response::Info(InfoType::Type(
+                                                    soul.key_type(key, time),
+                                                ));
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Rename { key, new_key, time } => {
+                                                let response = match soul.rename(key, new_key, time)
+                                                {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Renamenx { key, new_key, time } => {
+                                                let response = match soul
+                                                    .renamenx(key, new_key, time)
+                                                {
+                                                    Ok(renamed) => Response::Amount(renamed as u32),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            CopyKey {
+                                                source,
+                                                destination,
+                                                replace,
+                                                time,
+                                            } => {
+                                                let response = match soul.copy(
+                                                    source,
+                                                    destination,
+                                                    replace,
+                                                    time,
+                                                ) {
+                                                    Ok(copied) => Response::Amount(copied as u32),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Randomkey { time } => {
+                                                let response =
+                                                    Response::BulkString(soul.randomkey(time));
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Dbsize => {
+                                                let response = Response::Length(soul.dbsize());
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Touch { keys, time } => {
+                                                let response =
+                                                    Response::Amount(soul.touch(keys, time));
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Unlink { keys, time } => {
+                                                let (unlinked, large_values) =
+                                                    soul.unlink(keys, time);
+
+                                                for value in large_values {
+                                                    if reaper_tx.send(Box::new(value)).is_err() {
+                                                        eprintln!("reaper panicked");
+                                                    }
+                                                }
+
+                                                let response = Response::Amount(unlinked);
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Select { db } => {
+                                                let response = if db < soul.database_count() {
+                                                    selected_databases.insert(token, db);
+                                                    Response::Info(InfoType::Ok)
+                                                } else {
+                                                    Response::Error(Sacrilege::InvalidArgument(
+                                                        Command::SELECT,
+                                                    ))
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Move { key, db, time } => {
+                                                let response = match soul.move_key(key, db, time) {
+                                                    Ok(moved) => Response::Amount(moved as u32),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Swapdb { first, second } => {
+                                                let response = match soul.swapdb(first, second) {
+                                                    Ok(()) => {
+                                                        watches.touch_database(first);
+                                                        watches.touch_database(second);
+
+                                                        Response::Info(InfoType::Ok)
+                                                    }
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Flushdb { asynchronous } => {
+                                                let keyspace = soul.flushdb();
+                                                watches.touch_database(
+                                                    selected_databases
+                                                        .get(&token)
+                                                        .copied()
+                                                        .unwrap_or(0),
+                                                );
+
+                                                if asynchronous
+                                                    && reaper_tx.send(Box::new(keyspace)).is_err()
+                                                {
+                                                    eprintln!("reaper panicked");
+                                                }
+
+                                                let response = Response::Info(InfoType::Ok);
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Flushall { asynchronous } => {
+                                                let keyspaces = soul.flushall();
+
+                                                for db in 0..keyspaces.len() {
+                                                    watches.touch_database(db);
+                                                }
+
+                                                if asynchronous
+                                                    && reaper_tx.send(Box::new(keyspaces)).is_err()
+                                                {
+                                                    eprintln!("reaper panicked");
+                                                }
+
+                                                let response = Response::Info(InfoType::Ok);
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Dump { key, time } => {
+                                                let response =
+                                                    Response::BulkString(soul.dump(key, time));
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Restore {
+                                                key,
+                                                payload,
+                                                expiry,
+                                                replace,
+                                                time,
+                                            } => {
+                                                let response = match soul
+                                                    .restore(key, payload, expiry, replace, time)
+                                                {
+                                                    Ok(()) => Response::Info(InfoType::Ok),
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Sort {
+                                                key,
+                                                options,
+                                                store,
+                                                time,
+                                            } => {
+                                                let result = match store {
+                                                    Some(destination) => soul
+                                                        .sort_store(
+                                                            key,
+                                                            &options,
+                                                            destination,
+                                                            time,
+                                                        )
+                                                        .map(Response::Length),
+                                                    None => soul.sort(key, &options, time).map(
+                                                        |sorted| {
+                                                            Response::BulkStringArray(Some(sorted))
+                                                        },
+                                                    ),
+                                                };
+
+                                                let response = match result {
+                                                    Ok(response) => response,
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Object { key, field, time } => {
+                                                let response = match field {
+                                                    ObjectField::Encoding => Response::BulkString(
+                                                        soul.object_encoding(key, time)
+                                                            .map(|encoding| encoding.to_vec()),
+                                                    ),
+                                                    ObjectField::IdleTime => {
+                                                        soul.object_idletime(key, time).map_or(
+                                                            Response::BulkString(None),
+                                                            |idle| Response::Number(idle as i64),
+                                                        )
+                                                    }
+                                                    ObjectField::Freq => {
+                                                        soul.object_freq(key, time).map_or(
+                                                            Response::BulkString(None),
+                                                            |freq| Response::Number(freq as i64),
+                                                        )
+                                                    }
+                                                    ObjectField::RefCount => {
+                                                        soul.object_refcount(key, time).map_or(
+                                                            Response::BulkString(None),
+                                                            |count| Response::Number(count as i64),
+                                                        )
+                                                    }
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            MemoryUsage { key, samples, time } => {
+                                                let response = soul
+                                                    .memory_usage(key, samples, time)
+                                                    .map_or(Response::BulkString(None), |usage| {
+                                                        Response::Length(usage)
+                                                    });
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            MemoryStats => {
+                                                let response = Self::memory_stats_response(&soul);
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            MemoryPurge => {
+                                                allocator::purge();
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift {
+                                                        token,
+                                                        response: Response::Info(InfoType::Ok),
+                                                    }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Rite { rite, terms, time } => {
+                                                let response = rite.perform(terms, &mut soul, time);
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Scan {
+                                                cursor,
+                                                options,
+                                                time,
+                                            } => {
+                                                let (cursor, keys) =
+                                                    soul.scan(cursor, &options, time);
+                                                let response = Self::scan_response(cursor, keys);
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Hscan {
+                                                key,
+                                                cursor,
+                                                options,
+                                                time,
+                                            } => {
+                                                let response = match soul
+                                                    .hscan(key, cursor, &options, time)
+                                                {
+                                                    Ok((cursor, fields)) => {
+                                                        Self::scan_response(cursor, fields)
+                                                    }
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Sscan {
+                                                key,
+                                                cursor,
+                                                options,
+                                                time,
+                                            } => {
+                                                let response = match soul
+                                                    .sscan(key, cursor, &options, time)
+                                                {
+                                                    Ok((cursor, members)) => {
+                                                        Self::scan_response(cursor, members)
+                                                    }
+                                                    Err(sacrilege) => Response::Error(sacrilege),
+                                                };
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                            Keys { pattern, time } => {
+                                                let response = Response::BulkStringArray(Some(
+                                                    soul.keys(&pattern, time)
+                                                        .into_iter()
+                                                        .map(Some)
+                                                        .collect(),
+                                                ));
+
+                                                if tx
+                                                    .send(Decree::Deliver(Gift { token, response }))
+                                                    .is_err()
+                                                {
+                                                    eprintln!("angel panicked");
+                                                }
+                                            }
+                                        }
+
+                                        for (db, event, key) in soul.notifications() {
+                                            Self::notify_keyspace(
+                                                &tx,
+                                                &client_map,
+                                                notify_flags,
+                                                db,
+                                                event,
+                                                key,
+                                            );
+                                        }
+
+                                        for (db, key) in soul.touched() {
+                                            watches.touch(db, key);
+                                        }
+
+                                        if let Some((_, dirty)) = &scripting
+                                            && soul.dirty() != *dirty
+                                        {
+                                            vigil.mark_dirty();
+                                        }
+                                    }
+                                    Script(script_command) => {
+                                        let temple = match script_command {
+                                            ScriptCommand::Eval { .. } => {
+                                                let (script_tx, script_rx) =
+                                                    std::sync::mpsc::channel();
+
+                                                vigil.begin();
+                                                scripting = Some((script_rx, soul.dirty()));
+
+                                                Some(Temple {
+                                                    tx: script_tx,
+                                                    vigil: vigil.clone(),
+                                                })
+                                            }
+                                            _ => None,
+                                        };
+
+                                        // Kept in the soul so they're saved with it.
+                                        let (ledger_tx, ledger_rx) = std::sync::mpsc::channel();
+                                        let ledger_tx =
+                                            matches!(script_command, ScriptCommand::Function(_))
+                                                .then_some(ledger_tx);
+
+                                        if scripture
+                                            .send(Petition {
+                                                command: script_command,
+                                                tx,
+                                                token,
+                                                temple,
+                                                ledger: ledger_tx,
+                                            })
+                                            .is_err()
+                                        {
+                                            eprintln!("angel panicked");
+                                        }
+
+                                        if let Ok(libraries) = ledger_rx.recv() {
+                                            soul.set_libraries(libraries);
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("GodThread: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Temple { tx, vigil }
+    }
+
+    pub fn get(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Get { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn set(&self, key: Vec<u8>, value: (Value, Option<u64>), tx: Sender<Decree>, token: Token) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Set { key, value }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn del(&self, keys: Vec<Vec<u8>>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Del { keys, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn exists(&self, keys: Vec<Vec<u8>>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Exists { keys, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn append(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        tx: Sender<Decree>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Append { key, value, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn incr(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Incr { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn decr(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Decr { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn strlen(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Strlen { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hset(
+        &self,
+        key: Vec<u8>,
+        field_value_pairs: Vec<(Vec<u8>, Vec<u8>)>,
+        tx: Sender<Decree>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hset {
+                        key,
+                        field_value_pairs,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hget(&self, tx: Sender<Decree>, key: Vec<u8>, field: Vec<u8>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hget { key, field, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hmget(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hmget { key, fields, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hdel(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hdel { key, fields, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hexists(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        field: Vec<u8>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hexists { key, field, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hlen(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hlen { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lpush(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        elements: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Lpush {
+                        key,
+                        elements,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lpop(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Lpop { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lpop_m(&self, tx: Sender<Decree>, key: Vec<u8>, count: usize, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(LpopM { key, count, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn rpush(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        elements: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Rpush {
+                        key,
+                        elements,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn rpop(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Rpop { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn rpop_m(&self, tx: Sender<Decree>, key: Vec<u8>, count: usize, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(RpopM { key, count, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn llen(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Llen { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lrange(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        starting_index: i32,
+        ending_index: i32,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Lrange {
+                        key,
+                        starting_index,
+                        ending_index,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lindex(&self, tx: Sender<Decree>, key: Vec<u8>, index: i32, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Lindex { key, index, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lset(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        index: i32,
+        element: Vec<u8>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Lset {
+                        key,
+                        index,
+                        element,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn lrem(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        count: i32,
+        element: Vec<u8>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Lrem {
+                        key,
+                        count,
+                        element,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn expire(&self, tx: Sender<Decree>, key: Vec<u8>, expiry: u64, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Expire { key, expiry, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn ttl(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: SystemTime) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Ttl { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn mset(&self, terms_iter: IntoIter<Vec<u8>>, tx: Sender<Decree>, token: Token) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Mset { terms_iter }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn mget(&self, terms_iter: IntoIter<Vec<u8>>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Mget { terms_iter, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn sadd(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        values: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Sadd { key, values, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn srem(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        values: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Srem { key, values, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn sismember(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Sismember { key, value, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn hgetall(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Hgetall { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn smembers(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(Smembers { key, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn subscribe(
+        &self,
+        tx: Sender<Decree>,
+        subscription: Subscription,
+        events: Vec<Vec<u8>>,
+        replay: Option<u64>,
+        token: Token,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Broadcast(Subscribe {
+                        subscription,
+                        events,
+                        replay,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn publish(&self, tx: Sender<Decree>, event: Vec<u8>, message: Vec<u8>, token: Token) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Broadcast(Publish { event, message }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn spublish(&self, tx: Sender<Decree>, channel: Vec<u8>, message: Vec<u8>, token: Token) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Broadcast(Spublish { channel, message }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn pubsub(&self, tx: Sender<Decree>, query: PubsubQuery, token: Token) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Broadcast(Pubsub(query)),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn unsubscribe(
+        &self,
+        tx: Sender<Decree>,
+        token: Token,
+        subscription: Subscription,
+        terms: Vec<Vec<u8>>,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Broadcast(Unsubscribe {
+                        subscription,
+                        terms,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn config_set(&self, tx: Sender<Decree>, token: Token, property: Vec<u8>, value: Vec<u8>) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(ConfigSet { property, value }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn config_get(&self, tx: Sender<Decree>, token: Token, properties: Vec<Vec<u8>>) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(ConfigGet { properties }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn cms_initbydim(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        width: usize,
+        depth: usize,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(CmsInitbydim {
+                        key,
+                        width,
+                        depth,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn cms_incrby(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        increments: Vec<(Vec<u8>, u64)>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(CmsIncrby {
+                        key,
+                        increments,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn cms_query(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(CmsQuery { key, items, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn cms_merge(
+        &self,
+        tx: Sender<Decree>,
+        destination: Vec<u8>,
+        sources: Vec<Vec<u8>>,
+        weights: Vec<u64>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(CmsMerge {
+                        destination,
+                        sources,
+                        weights,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn topk_reserve(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        k: usize,
+        width: usize,
+        depth: usize,
+        decay: f64,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TopkReserve {
+                        key,
+                        k,
+                        width,
+                        depth,
+                        decay,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn topk_add(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TopkAdd { key, items, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn topk_list(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        with_count: bool,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TopkList {
+                        key,
+                        with_count,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn topk_count(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TopkCount { key, items, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn ts_create(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        options: TimeSeriesOptions,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsCreate { key, options, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn ts_add(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        timestamp: u64,
+        value: f64,
+        options: TimeSeriesOptions,
+        on_duplicate: Option<DuplicatePolicy>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsAdd {
+                        key,
+                        timestamp,
+                        value,
+                        options,
+                        on_duplicate,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn ts_madd(
+        &self,
+        tx: Sender<Decree>,
+        samples: Vec<(Vec<u8>, u64, f64)>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsMadd { samples, time }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn ts_incrby(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        increment: f64,
+        timestamp: u64,
+        options: TimeSeriesOptions,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsIncrby {
+                        key,
+                        increment,
+                        timestamp,
+                        options,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn ts_range(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+        count: Option<usize>,
+        reverse: bool,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsRange {
+                        key,
+                        from,
+                        to,
+                        aggregation,
+                        count,
+                        reverse,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn ts_mrange(
+        &self,
+        tx: Sender<Decree>,
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+        count: Option<usize>,
+        filters: Vec<LabelFilter>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsMrange {
+                        from,
+                        to,
+                        aggregation,
+                        count,
+                        filters,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn ts_createrule(
+        &self,
+        tx: Sender<Decree>,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        aggregation: Aggregation,
+        bucket_duration: u64,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsCreaterule {
+                        source,
+                        destination,
+                        aggregation,
+                        bucket_duration,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn ts_deleterule(
+        &self,
+        tx: Sender<Decree>,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        token: Token,
+        time: u64,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(TsDeleterule {
+                        source,
+                        destination,
+                        time,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn ft_create(
+        &self,
+        tx: Sender<Decree>,
+        index: Vec<u8>,
+        prefixes: Vec<Vec<u8>>,
+        schema: Vec<FieldSchema>,
+        token: Token,
+    ) {
+        if self
+            .tx
+            .send(Wish {
+                token,
+                command_type: CommandType::Client(ClientCommand {
+                    tx,
+                    client_command_type: Database(FtCreate {
+                        index,
+                        prefixes,
+                        schema,
+                    }),
+                }),
+            })
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    }
+
+    pub fn ft_search(
+        &self,
+        tx: Sender<Decree>,
+        index: Vec<u8>,
+        query: Query,
+        options: SearchOptions,
+        token: Token,
+        time: u64,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Incr { key, time }),
+                    client_command_type: Database(FtSearch {
+                        index,
+                        query,
+                        options,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -1579,14 +4853,25 @@ impl Temple {
         }
     }
 
-    pub fn decr(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+    pub fn ft_dropindex(
+        &self,
+        tx: Sender<Decree>,
+        index: Vec<u8>,
+        delete_documents: bool,
+        token: Token,
+        time: u64,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Decr { key, time }),
+                    client_command_type: Database(FtDropindex {
+                        index,
+                        delete_documents,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -1595,14 +4880,14 @@ impl Temple {
         }
     }
 
-    pub fn strlen(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+    pub fn ft_list(&self, tx: Sender<Decree>, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Strlen { key, time }),
+                    client_command_type: Database(FtList),
                 }),
             })
             .is_err()
@@ -1611,11 +4896,15 @@ impl Temple {
         }
     }
 
-    pub fn hset(
+    #[allow(clippy::too_many_arguments)]
+    pub fn cl_throttle(
         &self,
-        key: Vec<u8>,
-        field_value_pairs: Vec<(Vec<u8>, Vec<u8>)>,
         tx: Sender<Decree>,
+        key: Vec<u8>,
+        max_burst: u64,
+        count: u64,
+        period: u64,
+        quantity: u64,
         token: Token,
         time: u64,
     ) {
@@ -1625,9 +4914,12 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hset {
+                    client_command_type: Database(ClThrottle {
                         key,
-                        field_value_pairs,
+                        max_burst,
+                        count,
+                        period,
+                        quantity,
                         time,
                     }),
                 }),
@@ -1638,14 +4930,25 @@ impl Temple {
         }
     }
 
-    pub fn hget(&self, tx: Sender<Decree>, key: Vec<u8>, field: Vec<u8>, token: Token, time: u64) {
+    pub fn scan(
+        &self,
+        tx: Sender<Decree>,
+        cursor: u64,
+        options: ScanOptions,
+        token: Token,
+        time: u64,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hget { key, field, time }),
+                    client_command_type: Database(Scan {
+                        cursor,
+                        options,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -1654,11 +4957,13 @@ impl Temple {
         }
     }
 
-    pub fn hmget(
+    #[allow(clippy::too_many_arguments)]
+    pub fn hscan(
         &self,
         tx: Sender<Decree>,
         key: Vec<u8>,
-        fields: Vec<Vec<u8>>,
+        cursor: u64,
+        options: ScanOptions,
         token: Token,
         time: u64,
     ) {
@@ -1668,7 +4973,12 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hmget { key, fields, time }),
+                    client_command_type: Database(Hscan {
+                        key,
+                        cursor,
+                        options,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -1677,11 +4987,13 @@ impl Temple {
         }
     }
 
-    pub fn hdel(
+    #[allow(clippy::too_many_arguments)]
+    pub fn sscan(
         &self,
         tx: Sender<Decree>,
         key: Vec<u8>,
-        fields: Vec<Vec<u8>>,
+        cursor: u64,
+        options: ScanOptions,
         token: Token,
         time: u64,
     ) {
@@ -1691,7 +5003,12 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hdel { key, fields, time }),
+                    client_command_type: Database(Sscan {
+                        key,
+                        cursor,
+                        options,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -1700,21 +5017,14 @@ impl Temple {
         }
     }
 
-    pub fn hexists(
-        &self,
-        tx: Sender<Decree>,
-        key: Vec<u8>,
-        field: Vec<u8>,
-        token: Token,
-        time: u64,
-    ) {
+    pub fn keys(&self, tx: Sender<Decree>, pattern: Vec<u8>, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hexists { key, field, time }),
+                    client_command_type: Database(Keys { pattern, time }),
                 }),
             })
             .is_err()
@@ -1723,14 +5033,14 @@ impl Temple {
         }
     }
 
-    pub fn hlen(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+    pub fn key_type(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hlen { key, time }),
+                    client_command_type: Database(Type { key, time }),
                 }),
             })
             .is_err()
@@ -1739,11 +5049,11 @@ impl Temple {
         }
     }
 
-    pub fn lpush(
+    pub fn rename(
         &self,
         tx: Sender<Decree>,
         key: Vec<u8>,
-        elements: Vec<Vec<u8>>,
+        new_key: Vec<u8>,
         token: Token,
         time: u64,
     ) {
@@ -1753,27 +5063,7 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Lpush {
-                        key,
-                        elements,
-                        time,
-                    }),
-                }),
-            })
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-    }
-
-    pub fn lpop(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
-        if self
-            .tx
-            .send(Wish {
-                token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(Lpop { key, time }),
+                    client_command_type: Database(Rename { key, new_key, time }),
                 }),
             })
             .is_err()
@@ -1782,14 +5072,21 @@ impl Temple {
         }
     }
 
-    pub fn lpop_m(&self, tx: Sender<Decree>, key: Vec<u8>, count: usize, token: Token, time: u64) {
+    pub fn renamenx(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        new_key: Vec<u8>,
+        token: Token,
+        time: u64,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(LpopM { key, count, time }),
+                    client_command_type: Database(Renamenx { key, new_key, time }),
                 }),
             })
             .is_err()
@@ -1798,11 +5095,12 @@ impl Temple {
         }
     }
 
-    pub fn rpush(
+    pub fn copy(
         &self,
         tx: Sender<Decree>,
-        key: Vec<u8>,
-        elements: Vec<Vec<u8>>,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        replace: bool,
         token: Token,
         time: u64,
     ) {
@@ -1812,9 +5110,10 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Rpush {
-                        key,
-                        elements,
+                    client_command_type: Database(CopyKey {
+                        source,
+                        destination,
+                        replace,
                         time,
                     }),
                 }),
@@ -1825,14 +5124,14 @@ impl Temple {
         }
     }
 
-    pub fn rpop(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+    pub fn randomkey(&self, tx: Sender<Decree>, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Rpop { key, time }),
+                    client_command_type: Database(Randomkey { time }),
                 }),
             })
             .is_err()
@@ -1841,14 +5140,14 @@ impl Temple {
         }
     }
 
-    pub fn rpop_m(&self, tx: Sender<Decree>, key: Vec<u8>, count: usize, token: Token, time: u64) {
+    pub fn dbsize(&self, tx: Sender<Decree>, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(RpopM { key, count, time }),
+                    client_command_type: Database(Dbsize),
                 }),
             })
             .is_err()
@@ -1857,14 +5156,14 @@ impl Temple {
         }
     }
 
-    pub fn llen(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
+    pub fn touch(&self, tx: Sender<Decree>, keys: Vec<Vec<u8>>, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Llen { key, time }),
+                    client_command_type: Database(Touch { keys, time }),
                 }),
             })
             .is_err()
@@ -1873,27 +5172,14 @@ impl Temple {
         }
     }
 
-    pub fn lrange(
-        &self,
-        tx: Sender<Decree>,
-        key: Vec<u8>,
-        starting_index: i32,
-        ending_index: i32,
-        token: Token,
-        time: u64,
-    ) {
+    pub fn unlink(&self, tx: Sender<Decree>, keys: Vec<Vec<u8>>, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Lrange {
-                        key,
-                        starting_index,
-                        ending_index,
-                        time,
-                    }),
+                    client_command_type: Database(Unlink { keys, time }),
                 }),
             })
             .is_err()
@@ -1902,14 +5188,14 @@ impl Temple {
         }
     }
 
-    pub fn lindex(&self, tx: Sender<Decree>, key: Vec<u8>, index: i32, token: Token, time: u64) {
+    pub fn select(&self, tx: Sender<Decree>, db: usize, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Lindex { key, index, time }),
+                    client_command_type: Database(Select { db }),
                 }),
             })
             .is_err()
@@ -1918,27 +5204,14 @@ impl Temple {
         }
     }
 
-    pub fn lset(
-        &self,
-        tx: Sender<Decree>,
-        key: Vec<u8>,
-        index: i32,
-        element: Vec<u8>,
-        token: Token,
-        time: u64,
-    ) {
+    pub fn move_key(&self, tx: Sender<Decree>, key: Vec<u8>, db: usize, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Lset {
-                        key,
-                        index,
-                        element,
-                        time,
-                    }),
+                    client_command_type: Database(Move { key, db, time }),
                 }),
             })
             .is_err()
@@ -1947,27 +5220,14 @@ impl Temple {
         }
     }
 
-    pub fn lrem(
-        &self,
-        tx: Sender<Decree>,
-        key: Vec<u8>,
-        count: i32,
-        element: Vec<u8>,
-        token: Token,
-        time: u64,
-    ) {
+    pub fn swapdb(&self, tx: Sender<Decree>, first: usize, second: usize, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Lrem {
-                        key,
-                        count,
-                        element,
-                        time,
-                    }),
+                    client_command_type: Database(Swapdb { first, second }),
                 }),
             })
             .is_err()
@@ -1976,14 +5236,14 @@ impl Temple {
         }
     }
 
-    pub fn expire(&self, tx: Sender<Decree>, key: Vec<u8>, expiry: u64, token: Token, time: u64) {
+    pub fn flushdb(&self, tx: Sender<Decree>, asynchronous: bool, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Expire { key, expiry, time }),
+                    client_command_type: Database(Flushdb { asynchronous }),
                 }),
             })
             .is_err()
@@ -1992,14 +5252,14 @@ impl Temple {
         }
     }
 
-    pub fn ttl(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: SystemTime) {
+    pub fn flushall(&self, tx: Sender<Decree>, asynchronous: bool, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Ttl { key, time }),
+                    client_command_type: Database(Flushall { asynchronous }),
                 }),
             })
             .is_err()
@@ -2008,14 +5268,14 @@ impl Temple {
         }
     }
 
-    pub fn mset(&self, terms_iter: IntoIter<Vec<u8>>, tx: Sender<Decree>, token: Token) {
+    pub fn dump(&self, tx: Sender<Decree>, key: Vec<u8>, token: Token, time: u64) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Mset { terms_iter }),
+                    client_command_type: Database(Dump { key, time }),
                 }),
             })
             .is_err()
@@ -2024,14 +5284,30 @@ impl Temple {
         }
     }
 
-    pub fn mget(&self, terms_iter: IntoIter<Vec<u8>>, tx: Sender<Decree>, token: Token, time: u64) {
+    #[allow(clippy::too_many_arguments)]
+    pub fn restore(
+        &self,
+        tx: Sender<Decree>,
+        key: Vec<u8>,
+        payload: Vec<u8>,
+        expiry: Option<u64>,
+        replace: bool,
+        token: Token,
+        time: u64,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Mget { terms_iter, time }),
+                    client_command_type: Database(Restore {
+                        key,
+                        payload,
+                        expiry,
+                        replace,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -2040,11 +5316,13 @@ impl Temple {
         }
     }
 
-    pub fn sadd(
+    #[allow(clippy::too_many_arguments)]
+    pub fn sort(
         &self,
         tx: Sender<Decree>,
         key: Vec<u8>,
-        values: Vec<Vec<u8>>,
+        options: SortOptions,
+        store: Option<Vec<u8>>,
         token: Token,
         time: u64,
     ) {
@@ -2054,7 +5332,12 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Sadd { key, values, time }),
+                    client_command_type: Database(Sort {
+                        key,
+                        options,
+                        store,
+                        time,
+                    }),
                 }),
             })
             .is_err()
@@ -2063,11 +5346,11 @@ impl Temple {
         }
     }
 
-    pub fn srem(
+    pub fn object(
         &self,
         tx: Sender<Decree>,
         key: Vec<u8>,
-        values: Vec<Vec<u8>>,
+        field: ObjectField,
         token: Token,
         time: u64,
     ) {
@@ -2077,7 +5360,7 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Srem { key, values, time }),
+                    client_command_type: Database(Object { key, field, time }),
                 }),
             })
             .is_err()
@@ -2086,11 +5369,11 @@ impl Temple {
         }
     }
 
-    pub fn sismember(
+    pub fn memory_usage(
         &self,
         tx: Sender<Decree>,
         key: Vec<u8>,
-        value: Vec<u8>,
+        samples: usize,
         token: Token,
         time: u64,
     ) {
@@ -2100,7 +5383,7 @@ impl Temple {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Sismember { key, value, time }),
+                    client_command_type: Database(MemoryUsage { key, samples, time }),
                 }),
             })
             .is_err()
@@ -2109,14 +5392,14 @@ impl Temple {
         }
     }
 
-    pub fn hgetall(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+    pub fn memory_stats(&self, tx: Sender<Decree>, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Hgetall { key, time }),
+                    client_command_type: Database(MemoryStats),
                 }),
             })
             .is_err()
@@ -2125,14 +5408,14 @@ impl Temple {
         }
     }
 
-    pub fn smembers(&self, key: Vec<u8>, tx: Sender<Decree>, token: Token, time: u64) {
+    pub fn memory_purge(&self, tx: Sender<Decree>, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Database(Smembers { key, time }),
+                    client_command_type: Database(MemoryPurge),
                 }),
             })
             .is_err()
@@ -2141,14 +5424,21 @@ impl Temple {
         }
     }
 
-    pub fn subscribe(&self, tx: Sender<Decree>, events: Vec<Vec<u8>>, token: Token) {
+    pub fn rite(
+        &self,
+        tx: Sender<Decree>,
+        rite: Arc<dyn rite::Rite>,
+        terms: Vec<Vec<u8>>,
+        token: Token,
+        time: u64,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Broadcast(Subscribe { events }),
+                    client_command_type: Database(Rite { rite, terms, time }),
                 }),
             })
             .is_err()
@@ -2157,14 +5447,103 @@ impl Temple {
         }
     }
 
-    pub fn publish(&self, tx: Sender<Decree>, event: Vec<u8>, message: Vec<u8>, token: Token) {
+    // The allocator numbers are missing if jemalloc won't hand them out.
+    fn memory_stats_response(soul: &Soul) -> Response {
+        let mut stats = Vec::new();
+
+        if let Some(allocator) = allocator::stats() {
+            let fragmentation = if allocator.allocated == 0 {
+                0.0
+            } else {
+                allocator.active as f64 / allocator.allocated as f64
+            };
+
+            for (name, value) in [
+                (&b"total.allocated"[..], allocator.allocated),
+                (b"allocator.allocated", allocator.allocated),
+                (b"allocator.active", allocator.active),
+                (b"allocator.resident", allocator.resident),
+                (b"allocator.mapped", allocator.mapped),
+                (b"allocator.retained", allocator.retained),
+                (b"allocator.metadata", allocator.metadata),
+            ] {
+                stats.push(Response::BulkString(Some(name.to_vec())));
+                stats.push(Response::Length(value));
+            }
+
+            stats.push(Response::BulkString(Some(
+                b"allocator-fragmentation.ratio".to_vec(),
+            )));
+            stats.push(Response::BulkString(Some(
+                format!("{fragmentation:.2}").into_bytes(),
+            )));
+        }
+
+        stats.push(Response::BulkString(Some(b"keys.count".to_vec())));
+        stats.push(Response::Length(soul.key_count()));
+
+        Response::Array(stats)
+    }
+
+    // Turns the commands of a transaction into wishes ahead of EXEC.
+    pub fn recorder() -> (Self, Receiver<Wish>) {
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        (
+            Temple {
+                tx,
+                vigil: Arc::new(Vigil::default()),
+            },
+            rx,
+        )
+    }
+
+    pub fn exec(
+        &self,
+        tx: Sender<Decree>,
+        wishes: Vec<Wish>,
+        replies: Vec<Receiver<Decree>>,
+        token: Token,
+        time: u64,
+    ) {
+        self.transaction(
+            tx,
+            Exec {
+                wishes,
+                replies,
+                time,
+            },
+            token,
+        );
+    }
+
+    pub fn watch(&self, tx: Sender<Decree>, keys: Vec<Vec<u8>>, token: Token, time: u64) {
+        self.transaction(tx, Watch { keys, time }, token);
+    }
+
+    /// Releases the keys the connection watches, UNWATCH and DISCARD.
+    pub fn unwatch(&self, tx: Sender<Decree>, token: Token) {
+        self.transaction(tx, Unwatch, token);
+    }
+
+    /// Releases the keys the connection watches and refuses its EXEC.
+    pub fn abort(&self, tx: Sender<Decree>, token: Token) {
+        self.transaction(tx, Abort, token);
+    }
+
+    fn transaction(
+        &self,
+        tx: Sender<Decree>,
+        transaction_command: TransactionCommand,
+        token: Token,
+    ) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Broadcast(Publish { event, message }),
+                    client_command_type: ClientCommandType::Transaction(transaction_command),
                 }),
             })
             .is_err()
@@ -2173,14 +5552,25 @@ impl Temple {
         }
     }
 
-    pub fn unsubscribe(&self, tx: Sender<Decree>, token: Token, terms: Vec<Vec<u8>>) {
+    pub fn eval(
+        &self,
+        tx: Sender<Decree>,
+        source: Source,
+        keys: Vec<Vec<u8>>,
+        args: Vec<Vec<u8>>,
+        token: Token,
+    ) {
+        self.script(tx, ScriptCommand::Eval { source, keys, args }, token);
+    }
+
+    pub fn script(&self, tx: Sender<Decree>, script_command: ScriptCommand, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
                 command_type: CommandType::Client(ClientCommand {
                     tx,
-                    client_command_type: Broadcast(Unsubscribe { terms }),
+                    client_command_type: Script(script_command),
                 }),
             })
             .is_err()
@@ -2189,15 +5579,21 @@ impl Temple {
         }
     }
 
-    pub fn config_get(&self, tx: Sender<Decree>, token: Token, properties: Vec<Vec<u8>>) {
+    /// SCRIPT KILL doesn't go through the temple, the script is holding it.
+    pub fn script_kill(&self) -> Response {
+        self.vigil.kill()
+    }
+
+    pub fn busy(&self) -> bool {
+        self.vigil.busy()
+    }
+
+    pub fn depart(&self, token: Token) {
         if self
             .tx
             .send(Wish {
                 token,
-                command_type: CommandType::Client(ClientCommand {
-                    tx,
-                    client_command_type: Database(ConfigGet { properties }),
-                }),
+                command_type: Server(Depart),
             })
             .is_err()
         {
@@ -2205,6 +5601,102 @@ impl Temple {
         }
     }
 
+    // Nobody gets a reply count back, the client that caused it isn't publishing.
+    fn notify_keyspace(
+        tx: &Sender<Decree>,
+        client_map: &ClientMap,
+        flags: u16,
+        db: usize,
+        event: &[u8],
+        key: Vec<u8>,
+    ) {
+        let mut itoa_buf = itoa::Buffer::new();
+        let db = itoa_buf.format(db).as_bytes();
+
+        let mut broadcasts = Vec::with_capacity(2);
+
+        if flags & notify::KEYSPACE != 0 {
+            broadcasts.push(([b"__keyspace@", db, b"__:", &key].concat(), event.to_vec()));
+        }
+
+        if flags & notify::KEYEVENT != 0 {
+            broadcasts.push(([b"__keyevent@", db, b"__:", event].concat(), key));
+        }
+
+        for (channel, message) in broadcasts {
+            let clients = client_map.publish(&channel);
+
+            if !clients.is_empty()
+                && tx
+                    .send(Decree::Broadcast(None, channel, message, clients))
+                    .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+        }
+    }
+
+    fn pubsub_response(client_map: &ClientMap, query: PubsubQuery) -> Response {
+        let numsub = |counts: Vec<(Vec<u8>, usize)>| {
+            Response::Array(
+                counts
+                    .into_iter()
+                    .flat_map(|(channel, count)| {
+                        [
+                            Response::BulkString(Some(channel)),
+                            Response::Length(count),
+                        ]
+                    })
+                    .collect(),
+            )
+        };
+
+        match query {
+            PubsubQuery::Channels { pattern } => Response::BulkStringArray(Some(
+                client_map
+                    .channels(Subscription::Channel, pattern.as_deref())
+                    .into_iter()
+                    .map(Some)
+                    .collect(),
+            )),
+            PubsubQuery::Numsub { channels } => {
+                numsub(client_map.numsub(Subscription::Channel, channels))
+            }
+            PubsubQuery::Numpat => Response::Length(client_map.numpat()),
+            PubsubQuery::ShardChannels { pattern } => Response::BulkStringArray(Some(
+                client_map
+                    .channels(Subscription::Shard, pattern.as_deref())
+                    .into_iter()
+                    .map(Some)
+                    .collect(),
+            )),
+            PubsubQuery::ShardNumsub { channels } => {
+                numsub(client_map.numsub(Subscription::Shard, channels))
+            }
+        }
+    }
+
+    fn samples_response(samples: Vec<(u64, f64)>) -> Response {
+        Response::Array(
+            samples
+                .into_iter()
+                .map(|(timestamp, value)| {
+                    Response::Array(vec![
+                        Response::Number(timestamp as i64),
+                        Response::BulkString(Some(value.to_string().into_bytes())),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    fn scan_response(cursor: u64, items: Vec<Vec<u8>>) -> Response {
+        Response::Array(vec![
+            Response::BulkString(Some(cursor.to_string().into_bytes())),
+            Response::BulkStringArray(Some(items.into_iter().map(Some).collect())),
+        ])
+    }
+
     pub fn save(&mut self, tx: Sender<Result<(), ServerError>>, token: Token) {
         let (server_tx, server_rx) = std::sync::mpsc::channel();
 
@@ -2244,6 +5736,7 @@ impl Temple {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn initialize_config(
         config: &mut HashMap<Vec<u8>, Vec<u8>>,
         dir: Vec<u8>,
@@ -2254,6 +5747,8 @@ impl Temple {
         event_capacity: Vec<u8>,
         max_memory: Vec<u8>,
         append_only: Vec<u8>,
+        databases: Vec<u8>,
+        notify_keyspace_events: Vec<u8>,
     ) {
         config.insert("dir".as_bytes().to_vec(), dir);
         config.insert("dbfilename".as_bytes().to_vec(), dbfilename);
@@ -2263,6 +5758,11 @@ impl Temple {
         config.insert("max_memory".as_bytes().to_vec(), max_memory);
         config.insert("event_capacity".as_bytes().to_vec(), event_capacity);
         config.insert("append_only".as_bytes().to_vec(), append_only);
+        config.insert("databases".as_bytes().to_vec(), databases);
+        config.insert(
+            "notify-keyspace-events".as_bytes().to_vec(),
+            notify_keyspace_events,
+        );
     }
 
     pub fn sanctify(&self) -> Self {
@@ -0,0 +1,80 @@
+use std::ffi::{CStr, c_void};
+use std::ptr;
+
+// jemalloc's name for "every arena" in `arena.<i>.*` controls.
+const ARENAS_ALL: u32 = 4096;
+
+pub struct AllocatorStats {
+    pub allocated: usize,
+    pub active: usize,
+    pub resident: usize,
+    pub mapped: usize,
+    pub retained: usize,
+    pub metadata: usize,
+}
+
+/// The allocator's own view of the heap. jemalloc caches its statistics, so
+/// the epoch is bumped first to get current numbers.
+pub fn stats() -> Option<AllocatorStats> {
+    let mut epoch: u64 = 1;
+
+    // SAFETY: `epoch` is a u64 as the control expects, and no old value is read.
+    let refreshed = unsafe {
+        jemalloc_sys::mallctl(
+            c"epoch".as_ptr(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            (&raw mut epoch).cast::<c_void>(),
+            size_of::<u64>(),
+        )
+    };
+
+    if refreshed != 0 {
+        return None;
+    }
+
+    Some(AllocatorStats {
+        allocated: read(c"stats.allocated")?,
+        active: read(c"stats.active")?,
+        resident: read(c"stats.resident")?,
+        mapped: read(c"stats.mapped")?,
+        retained: read(c"stats.retained")?,
+        metadata: read(c"stats.metadata")?,
+    })
+}
+
+/// Hands the dirty pages of every arena back to the OS.
+pub fn purge() -> bool {
+    let Ok(name) = std::ffi::CString::new(format!("arena.{ARENAS_ALL}.purge")) else {
+        return false;
+    };
+
+    // SAFETY: purge takes and returns nothing.
+    unsafe {
+        jemalloc_sys::mallctl(
+            name.as_ptr(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            0,
+        ) == 0
+    }
+}
+
+fn read(name: &CStr) -> Option<usize> {
+    let mut value: usize = 0;
+    let mut len = size_of::<usize>();
+
+    // SAFETY: every stat read here is a size_t, and `len` says so.
+    let read = unsafe {
+        jemalloc_sys::mallctl(
+            name.as_ptr(),
+            (&raw mut value).cast::<c_void>(),
+            &mut len,
+            ptr::null_mut(),
+            0,
+        )
+    };
+
+    (read == 0).then_some(value)
+}
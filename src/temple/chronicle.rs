@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use mio::Token;
+
+use crate::wish::util::bytes_to_u64;
+
+struct Entry {
+    sequence: u64,
+    published: Instant,
+    message: Vec<u8>,
+}
+
+// A channel's recent messages, oldest first. The sequence keeps counting when
+// old messages fall off, so an ID never names two messages.
+#[derive(Default)]
+struct Scroll {
+    sequence: u64,
+    entries: VecDeque<Entry>,
+}
+
+impl Scroll {
+    // Drops the messages older than the age, in milliseconds, 0 keeping them
+    // however old.
+    fn wither(&mut self, age: u64) {
+        if age == 0 {
+            return;
+        }
+
+        let age = Duration::from_millis(age);
+
+        while self
+            .entries
+            .front()
+            .is_some_and(|entry| entry.published.elapsed() > age)
+        {
+            self.entries.pop_front();
+        }
+    }
+}
+
+// The last messages PUBLISHed on each channel, so a subscriber coming back can
+// ask for what it missed. Nothing is kept until pubsub-replay-size is set, and
+// pubsub-replay-age, in milliseconds, drops messages older than that when it
+// isn't 0.
+//
+// Clients that SUBSCRIBE with REPLAY are readers: they're sent the sequence ID
+// with every message, replayed or live, so they know where to resume from.
+#[derive(Default)]
+pub struct Chronicle {
+    size: usize,
+    age: u64,
+    scrolls: HashMap<Vec<u8>, Scroll>,
+    readers: HashSet<Token>,
+}
+
+impl Chronicle {
+    /// Applies one CONFIG SET, returns false for names it doesn't know or
+    /// values out of range.
+    pub fn set(&mut self, name: &[u8], value: &[u8]) -> bool {
+        let Ok(value) = bytes_to_u64(value) else {
+            return false;
+        };
+
+        match name.to_ascii_lowercase().as_slice() {
+            b"pubsub-replay-size" => {
+                let Ok(size) = usize::try_from(value) else {
+                    return false;
+                };
+
+                self.size = size;
+
+                if size == 0 {
+                    self.scrolls.clear();
+                }
+
+                for scroll in self.scrolls.values_mut() {
+                    while scroll.entries.len() > size {
+                        scroll.entries.pop_front();
+                    }
+                }
+            }
+            b"pubsub-replay-age" => self.age = value,
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// The limits under their CONFIG names.
+    pub fn entries(&self) -> [(&'static [u8], Vec<u8>); 2] {
+        let mut itoa_buf = itoa::Buffer::new();
+
+        [
+            (
+                b"pubsub-replay-size",
+                itoa_buf.format(self.size).as_bytes().to_vec(),
+            ),
+            (
+                b"pubsub-replay-age",
+                itoa_buf.format(self.age).as_bytes().to_vec(),
+            ),
+        ]
+    }
+
+    /// Keeps a published message, returning its sequence ID, or None while
+    /// replay is off.
+    pub fn record(&mut self, channel: &[u8], message: &[u8]) -> Option<u64> {
+        if self.size == 0 {
+            return None;
+        }
+
+        let scroll = self.scrolls.entry(channel.to_vec()).or_default();
+        scroll.wither(self.age);
+        scroll.sequence += 1;
+
+        if scroll.entries.len() == self.size {
+            scroll.entries.pop_front();
+        }
+
+        scroll.entries.push_back(Entry {
+            sequence: scroll.sequence,
+            published: Instant::now(),
+            message: message.to_vec(),
+        });
+
+        Some(scroll.sequence)
+    }
+
+    /// The kept messages of a channel published after the given sequence ID,
+    /// with their IDs, oldest first.
+    pub fn since(&mut self, channel: &[u8], after: u64) -> Vec<(u64, Vec<u8>)> {
+        let Some(scroll) = self.scrolls.get_mut(channel) else {
+            return Vec::new();
+        };
+
+        scroll.wither(self.age);
+
+        scroll
+            .entries
+            .iter()
+            .filter(|entry| entry.sequence > after)
+            .map(|entry| (entry.sequence, entry.message.clone()))
+            .collect()
+    }
+
+    pub fn read(&mut self, token: Token) {
+        self.readers.insert(token);
+    }
+
+    pub fn is_reader(&self, token: &Token) -> bool {
+        self.readers.contains(token)
+    }
+
+    pub fn forget(&mut self, token: Token) {
+        self.readers.remove(&token);
+    }
+}
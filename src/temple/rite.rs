@@ -0,0 +1,143 @@
+//! Commands and value types added by an embedding crate.
+//!
+//! A [`Rite`] is a command of its own, run on the temple thread against the
+//! soul so it's as atomic as any built-in: nothing else runs while it does,
+//! and MULTI, EXEC, WATCH, scripts and keyspace notifications treat it like
+//! one. A [`Relic`] is a value type of its own, kept in the soul in the bytes
+//! it serializes itself to, which is also how it goes into snapshots and
+//! DUMP payloads.
+//!
+//! Both are registered once for the whole process, usually before the temple
+//! starts:
+//!
+//! ```ignore
+//! rite::enshrine::<Counter>();
+//! rite::consecrate(CounterIncr)?;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+
+use crate::temple::soul::Soul;
+use crate::wish::Response;
+use crate::wish::grant::canon::{self, Keys};
+
+pub use crate::wish::grant::canon::{ADMIN, DENYOOM, FAST, PUBSUB, READONLY, WRITE};
+
+pub trait Rite: Send + Sync + 'static {
+    /// What clients call it by, matched case insensitively.
+    fn name(&self) -> &'static [u8];
+
+    /// How many terms it takes, the name included. A negative arity means at
+    /// least that many, the way Redis counts them.
+    fn arity(&self) -> i64;
+
+    /// Reported by COMMAND, a rite flagged `write` can't be called from
+    /// read-only scripts.
+    fn flags(&self) -> &'static [&'static [u8]] {
+        &[]
+    }
+
+    /// Which terms are keys, for COMMAND GETKEYS and cluster-aware clients.
+    fn keys(&self) -> Keys {
+        Keys::None
+    }
+
+    /// Runs the command against the issuing connection's database. `now` is
+    /// the unix time in milliseconds, what the soul's own methods expire keys by.
+    fn perform(&self, terms: Vec<Vec<u8>>, soul: &mut Soul, now: u64) -> Response;
+}
+
+pub trait Relic: Sized {
+    /// What TYPE reports for keys holding it, and how its bytes are told
+    /// apart from other relics'.
+    const TYPE_NAME: &'static [u8];
+
+    fn inscribe(&self) -> Vec<u8>;
+
+    /// None if `bytes` can't be read back, a key holding them then acts as if
+    /// it held another type.
+    fn decipher(bytes: &[u8]) -> Option<Self>;
+}
+
+pub enum Profanation {
+    // The name is already taken by a built-in command or another rite.
+    NameTaken,
+    // Names are a single term, printable and without spaces.
+    InvalidName,
+    InvalidArity,
+}
+
+type Rites = HashMap<Vec<u8>, Arc<dyn Rite>>;
+
+static RITES: LazyLock<RwLock<Rites>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+static RELICS: RwLock<Vec<&'static [u8]>> = RwLock::new(Vec::new());
+
+/// Makes a rite callable by every connection.
+pub fn consecrate(rite: impl Rite) -> Result<(), Profanation> {
+    let name = rite.name();
+
+    if name.is_empty() || !name.iter().all(|byte| byte.is_ascii_graphic()) {
+        return Err(Profanation::InvalidName);
+    }
+
+    if rite.arity() == 0 {
+        return Err(Profanation::InvalidArity);
+    }
+
+    let mut rites = RITES.write().unwrap_or_else(PoisonError::into_inner);
+    let name = name.to_ascii_uppercase();
+
+    if rites.contains_key(&name) || canon::find(&name).is_some() {
+        return Err(Profanation::NameTaken);
+    }
+
+    rites.insert(name, Arc::new(rite));
+
+    Ok(())
+}
+
+pub fn find(name: &[u8]) -> Option<Arc<dyn Rite>> {
+    RITES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&name.to_ascii_uppercase())
+        .cloned()
+}
+
+/// Every consecrated rite, in no particular order.
+pub fn rites() -> Vec<Arc<dyn Rite>> {
+    RITES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// Whether `terms` is a count the rite takes.
+pub fn fits(rite: &dyn Rite, terms: usize) -> bool {
+    canon::fits(rite.arity(), terms)
+}
+
+/// Lets TYPE and SCAN's TYPE filter report `T` by its name.
+pub fn enshrine<T: Relic>() {
+    let mut relics = RELICS.write().unwrap_or_else(PoisonError::into_inner);
+
+    if !relics.contains(&T::TYPE_NAME) {
+        relics.push(T::TYPE_NAME);
+    }
+}
+
+/// The registered name `type_name` matches. Relics whose type isn't
+/// registered, say from a snapshot saved by another build, are just `relic`.
+pub fn relic_type_name(type_name: &[u8]) -> &'static [u8] {
+    RELICS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .find(|name| **name == type_name)
+        .copied()
+        .unwrap_or(b"relic")
+}
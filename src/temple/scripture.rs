@@ -0,0 +1,1034 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mio::Token;
+use mlua::{
+    Function, HookTriggers, Lua, LuaOptions, MultiValue, RegistryKey, StdLib, Table,
+    Value as LuaValue,
+};
+use sha1::{Digest, Sha1};
+
+use crate::egress::send::inscribe;
+use crate::temple::soul::{Libraries, dump};
+use crate::temple::{FunctionCommand, RestorePolicy, ScriptCommand, Source, Temple};
+use crate::wish::grant::{self, Decree, Gift, canon};
+use crate::wish::util::{bytes_to_i64, find_crlf, glob_match};
+use crate::wish::{InfoType, Response, Sacrilege};
+
+mod library;
+
+use library::{Library, Registrar, Shrine};
+
+// Other clients are told BUSY once a script has held the temple this long,
+// and the interpreter looks for SCRIPT KILL every so many instructions.
+const BUSY_AFTER_MILLIS: u64 = 5000;
+const KILL_CHECK_INSTRUCTIONS: u32 = 10_000;
+
+const KILLED: &[u8] = b"ERR Script killed by user with SCRIPT KILL...";
+
+// What functions flagged no-writes can't call: commands flagged as changing
+// the dataset, the server or what other clients receive.
+const WRITES: [&[u8]; 3] = [canon::WRITE, canon::ADMIN, canon::PUBSUB];
+
+// redis.call raises the error reply redis.pcall would return. Globals are
+// locked so that scripts can't leak state into each other.
+const PRELUDE: &str = r#"
+redis.call = function(...)
+    local reply = redis.pcall(...)
+    if type(reply) == "table" and reply.err then
+        error(reply, 2)
+    end
+    return reply
+end
+
+-- rawset would write past __newindex, and __metatable keeps the lock itself
+-- from being read or replaced.
+local raw_set = rawset
+rawset = function(t, k, v)
+    if t == _G then
+        error("Attempt to modify a readonly table", 2)
+    end
+    return raw_set(t, k, v)
+end
+
+setmetatable(_G, {
+    __metatable = false,
+    __newindex = function(_, name)
+        error("Script attempted to create global variable '" .. tostring(name) .. "'", 2)
+    end,
+    __index = function(_, name)
+        error("Script attempted to access nonexistent global variable '" .. tostring(name) .. "'", 2)
+    end,
+})
+"#;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Whether a script is running, shared with every connection so that SCRIPT
+/// KILL doesn't wait on the temple the script is holding.
+#[derive(Default)]
+pub struct Vigil {
+    running: AtomicBool,
+    dirty: AtomicBool,
+    kill: AtomicBool,
+    since: AtomicU64,
+}
+
+impl Vigil {
+    pub fn begin(&self) {
+        self.since.store(now_millis(), Ordering::Relaxed);
+        self.dirty.store(false, Ordering::Relaxed);
+        self.kill.store(false, Ordering::Relaxed);
+        self.running.store(true, Ordering::Release);
+    }
+
+    fn end(&self) {
+        self.running.store(false, Ordering::Release);
+    }
+
+    /// The running script wrote to the dataset, killing it would leave the
+    /// write half done.
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn busy(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+            && now_millis().saturating_sub(self.since.load(Ordering::Relaxed)) > BUSY_AFTER_MILLIS
+    }
+
+    pub fn kill(&self) -> Response {
+        if !self.running.load(Ordering::Acquire) {
+            Response::Error(Sacrilege::NotBusy)
+        } else if self.dirty.load(Ordering::Relaxed) {
+            Response::Error(Sacrilege::Unkillable)
+        } else {
+            self.kill.store(true, Ordering::Relaxed);
+            Response::Info(InfoType::Ok)
+        }
+    }
+}
+
+/// A script command on its way to the interpreter. EVAL comes with a temple
+/// of its own, the real one grants nothing else until it's dropped.
+pub struct Petition {
+    pub command: ScriptCommand,
+    pub tx: Sender<Decree>,
+    pub token: Token,
+    pub temple: Option<Temple>,
+    // Gets every library once a FUNCTION command has changed them.
+    pub ledger: Option<Sender<Libraries>>,
+}
+
+// What redis.pcall needs while a script runs.
+struct Petitioner {
+    temple: Temple,
+    tx: Sender<Decree>,
+    token: Token,
+    read_only: bool,
+}
+
+/// Starts the interpreter on a thread of its own.
+pub fn spawn(vigil: Arc<Vigil>) -> Sender<Petition> {
+    let (tx, rx) = mpsc::channel::<Petition>();
+
+    std::thread::spawn(move || {
+        let mut scripture = match Scripture::new(vigil) {
+            Ok(scripture) => scripture,
+            Err(e) => {
+                eprintln!("Couldn't start the Lua interpreter: {}", e);
+                return;
+            }
+        };
+
+        for petition in rx {
+            scripture.answer(petition);
+        }
+    });
+
+    tx
+}
+
+pub fn sha1_hex(body: &[u8]) -> Vec<u8> {
+    Sha1::digest(body)
+        .iter()
+        .flat_map(|byte| {
+            [
+                b"0123456789abcdef"[(byte >> 4) as usize],
+                b"0123456789abcdef"[(byte & 0xf) as usize],
+            ]
+        })
+        .collect()
+}
+
+struct Scripture {
+    lua: Lua,
+    scripts: HashMap<Vec<u8>, RegistryKey>,
+    libraries: BTreeMap<Vec<u8>, Library>,
+    functions: HashMap<Vec<u8>, Shrine>,
+    pcall: RegistryKey,
+    vigil: Arc<Vigil>,
+}
+
+impl Scripture {
+    fn new(vigil: Arc<Vigil>) -> mlua::Result<Self> {
+        let lua = Lua::new_with(
+            StdLib::TABLE | StdLib::STRING | StdLib::MATH,
+            LuaOptions::default(),
+        )?;
+        let globals = lua.globals();
+
+        for name in ["dofile", "loadfile", "load", "loadstring", "print"] {
+            globals.raw_set(name, LuaValue::Nil)?;
+        }
+
+        let redis = lua.create_table()?;
+        redis.set("pcall", lua.create_function(pcall)?)?;
+        redis.set("register_function", lua.create_function(register_function)?)?;
+        redis.set(
+            "sha1hex",
+            lua.create_function(|lua, body: mlua::String| {
+                lua.create_string(sha1_hex(body.as_bytes()))
+            })?,
+        )?;
+        redis.set(
+            "error_reply",
+            lua.create_function(|lua, message: mlua::String| {
+                reply_table(lua, "err", message.as_bytes())
+            })?,
+        )?;
+        redis.set(
+            "status_reply",
+            lua.create_function(|lua, message: mlua::String| {
+                reply_table(lua, "ok", message.as_bytes())
+            })?,
+        )?;
+        // There's no log file to write to.
+        redis.set("log", lua.create_function(|_, _: MultiValue| Ok(()))?)?;
+
+        for (level, name) in ["LOG_DEBUG", "LOG_VERBOSE", "LOG_NOTICE", "LOG_WARNING"]
+            .into_iter()
+            .enumerate()
+        {
+            redis.set(name, level)?;
+        }
+
+        globals.set("redis", redis)?;
+
+        // The script's own errors are caught with the pcall it can't replace.
+        let pcall = lua.create_registry_value(globals.get::<_, Function>("pcall")?)?;
+
+        lua.load(PRELUDE).exec()?;
+
+        let hook_vigil = vigil.clone();
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(KILL_CHECK_INSTRUCTIONS),
+            move |_, _| {
+                if hook_vigil.kill.load(Ordering::Relaxed) {
+                    Err(mlua::Error::runtime(String::from_utf8_lossy(KILLED)))
+                } else {
+                    Ok(())
+                }
+            },
+        );
+
+        drop(globals);
+
+        Ok(Scripture {
+            lua,
+            scripts: HashMap::new(),
+            libraries: BTreeMap::new(),
+            functions: HashMap::new(),
+            pcall,
+            vigil,
+        })
+    }
+
+    fn answer(&mut self, petition: Petition) {
+        let Petition {
+            command,
+            tx,
+            token,
+            temple,
+            ledger,
+        } = petition;
+
+        let response =
+            match command {
+                ScriptCommand::Eval { source, keys, args } => {
+                    let Some(temple) = temple else {
+                        return;
+                    };
+
+                    let response = self.eval(
+                        source,
+                        keys,
+                        args,
+                        Petitioner {
+                            temple,
+                            tx: tx.clone(),
+                            token,
+                            read_only: false,
+                        },
+                    );
+
+                    // The reply goes out before the temple is let go of, so it
+                    // can't be overtaken by the client's next command.
+                    deliver(&tx, response, token);
+                    self.vigil.end();
+                    self.lua.remove_app_data::<Petitioner>();
+
+                    return;
+                }
+                ScriptCommand::Load { body } => match self.load(&body) {
+                    Ok(sha) => Response::BulkString(Some(sha)),
+                    Err(response) => response,
+                },
+                ScriptCommand::Exists { shas } => Response::Array(
+                    shas.iter()
+                        .map(|sha| {
+                            Response::Number(
+                                self.scripts.contains_key(&sha.to_ascii_lowercase()) as i64
+                            )
+                        })
+                        .collect(),
+                ),
+                ScriptCommand::Flush => {
+                    for (_, key) in self.scripts.drain() {
+                        if self.lua.remove_registry_value(key).is_err() {
+                            eprintln!("Couldn't drop a cached script");
+                        }
+                    }
+
+                    Response::Info(InfoType::Ok)
+                }
+                ScriptCommand::Function(function_command) => {
+                    match self.function(function_command, ledger) {
+                        Some(response) => response,
+                        None => return,
+                    }
+                }
+            };
+
+        deliver(&tx, response, token);
+    }
+
+    fn function(
+        &mut self,
+        function_command: FunctionCommand,
+        ledger: Option<Sender<Libraries>>,
+    ) -> Option<Response> {
+        let response = match function_command {
+            FunctionCommand::Load { code, replace } => match self.load_library(code, replace) {
+                Ok(name) => Response::BulkString(Some(name)),
+                Err(message) => return Some(Response::Error(Sacrilege::Script(message))),
+            },
+            FunctionCommand::Delete { library } => {
+                if !self.libraries.contains_key(&library) {
+                    return Some(Response::Error(Sacrilege::Script(
+                        b"ERR Library not found".to_vec(),
+                    )));
+                }
+
+                self.unload_library(&library);
+
+                Response::Info(InfoType::Ok)
+            }
+            FunctionCommand::Flush => {
+                self.unload_libraries();
+
+                Response::Info(InfoType::Ok)
+            }
+            FunctionCommand::List { pattern, with_code } => {
+                return Some(self.list(pattern, with_code));
+            }
+            FunctionCommand::Dump => {
+                return Some(Response::BulkString(dump::encode_libraries(
+                    &self.entries(),
+                )));
+            }
+            FunctionCommand::Restore { payload, policy } => {
+                match self.restore_libraries(&payload, policy) {
+                    Ok(()) => Response::Info(InfoType::Ok),
+                    Err(message) => return Some(Response::Error(Sacrilege::Script(message))),
+                }
+            }
+            FunctionCommand::Reload { libraries } => {
+                for (name, code) in libraries {
+                    if let Err(message) = self.load_library(code, true) {
+                        eprintln!(
+                            "Couldn't load library {}: {}",
+                            String::from_utf8_lossy(&name),
+                            String::from_utf8_lossy(&message)
+                        );
+                    }
+                }
+
+                return None;
+            }
+        };
+
+        if let Some(ledger) = ledger
+            && ledger.send(self.entries()).is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        Some(response)
+    }
+
+    // Runs a library's code, which registers its functions, and swaps them
+    // in for the library's old ones. Nothing changes if any of it fails.
+    fn load_library(&mut self, code: Vec<u8>, replace: bool) -> Result<Vec<u8>, Vec<u8>> {
+        let (name, body) = library::metadata(&code)?;
+
+        if !replace && self.libraries.contains_key(&name) {
+            return Err([b"ERR Library '".as_slice(), &name, b"' already exists"].concat());
+        }
+
+        let chunk = self
+            .lua
+            .load(body)
+            .set_name("@user_function")
+            .into_function()
+            .map_err(|e| {
+                [
+                    b"ERR Error compiling function: ".as_slice(),
+                    error_message(&e).as_bytes(),
+                ]
+                .concat()
+            })?;
+
+        self.lua.set_app_data(Registrar::default());
+        let registered = chunk.call::<_, ()>(());
+        drop(chunk);
+        let registrar = self.lua.remove_app_data::<Registrar>().unwrap_or_default();
+
+        registered.map_err(|e| {
+            [
+                b"ERR Error registering functions: ".as_slice(),
+                error_message(&e).as_bytes(),
+            ]
+            .concat()
+        })?;
+
+        if registrar.functions.is_empty() {
+            return Err(b"ERR No functions registered".to_vec());
+        }
+
+        for (function, _) in &registrar.functions {
+            if self
+                .functions
+                .get(function)
+                .is_some_and(|shrine| shrine.library != name)
+            {
+                return Err([b"ERR Function ".as_slice(), function, b" already exists"].concat());
+            }
+        }
+
+        self.unload_library(&name);
+
+        let mut functions = Vec::with_capacity(registrar.functions.len());
+
+        for (function, mut shrine) in registrar.functions {
+            shrine.library = name.clone();
+            functions.push(function.clone());
+            self.functions.insert(function, shrine);
+        }
+
+        self.libraries
+            .insert(name.clone(), Library { code, functions });
+
+        Ok(name)
+    }
+
+    fn unload_library(&mut self, name: &[u8]) {
+        let Some(library) = self.libraries.remove(name) else {
+            return;
+        };
+
+        for function in library.functions {
+            if let Some(shrine) = self.functions.remove(&function)
+                && self.lua.remove_registry_value(shrine.callback).is_err()
+            {
+                eprintln!("Couldn't drop a function");
+            }
+        }
+    }
+
+    fn unload_libraries(&mut self) {
+        let names: Vec<Vec<u8>> = self.libraries.keys().cloned().collect();
+
+        for name in names {
+            self.unload_library(&name);
+        }
+    }
+
+    // Every library by name, with its code.
+    fn entries(&self) -> Libraries {
+        self.libraries
+            .iter()
+            .map(|(name, library)| (name.clone(), library.code.clone()))
+            .collect()
+    }
+
+    // FUNCTION RESTORE is all or nothing, the libraries there were come back
+    // if any of the payload's fails to load.
+    fn restore_libraries(&mut self, payload: &[u8], policy: RestorePolicy) -> Result<(), Vec<u8>> {
+        let Some(libraries) = dump::decode_libraries(payload) else {
+            return Err(b"ERR payload version or checksum are wrong".to_vec());
+        };
+
+        if let RestorePolicy::Append = policy
+            && let Some((name, _)) = libraries
+                .iter()
+                .find(|(name, _)| self.libraries.contains_key(name))
+        {
+            return Err([b"ERR Library '".as_slice(), name, b"' already exists"].concat());
+        }
+
+        let before = self.entries();
+
+        if let RestorePolicy::Flush = policy {
+            self.unload_libraries();
+        }
+
+        for (_, code) in libraries {
+            if let Err(message) = self.load_library(code, true) {
+                self.unload_libraries();
+
+                for (_, code) in before {
+                    let _ = self.load_library(code, true);
+                }
+
+                return Err(message);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn list(&self, pattern: Option<Vec<u8>>, with_code: bool) -> Response {
+        let bulk = |bytes: &[u8]| Response::BulkString(Some(bytes.to_vec()));
+
+        Response::Array(
+            self.libraries
+                .iter()
+                .filter(|(name, _)| {
+                    pattern
+                        .as_ref()
+                        .is_none_or(|pattern| glob_match(pattern, name))
+                })
+                .map(|(name, library)| {
+                    let functions = library
+                        .functions
+                        .iter()
+                        .filter_map(|function| {
+                            let shrine = self.functions.get(function)?;
+
+                            Some(Response::Array(vec![
+                                bulk(b"name"),
+                                bulk(function),
+                                bulk(b"description"),
+                                Response::BulkString(shrine.description.clone()),
+                                bulk(b"flags"),
+                                Response::Array(
+                                    shrine.flags.iter().map(|flag| bulk(flag)).collect(),
+                                ),
+                            ]))
+                        })
+                        .collect();
+
+                    let mut entry = vec![
+                        bulk(b"library_name"),
+                        bulk(name),
+                        bulk(b"engine"),
+                        bulk(b"LUA"),
+                        bulk(b"functions"),
+                        Response::Array(functions),
+                    ];
+
+                    if with_code {
+                        entry.push(bulk(b"library_code"));
+                        entry.push(bulk(&library.code));
+                    }
+
+                    Response::Array(entry)
+                })
+                .collect(),
+        )
+    }
+
+    // Compiles and caches `body` under its SHA1, unless it already is.
+    fn load(&mut self, body: &[u8]) -> Result<Vec<u8>, Response> {
+        let sha = sha1_hex(body);
+
+        if self.scripts.contains_key(&sha) {
+            return Ok(sha);
+        }
+
+        let function = self
+            .lua
+            .load(body)
+            .set_name("@user_script")
+            .into_function()
+            .map_err(|e| {
+                let mut message = b"ERR Error compiling script (new function): ".to_vec();
+                message.extend_from_slice(error_message(&e).as_bytes());
+                Response::Error(Sacrilege::Script(message))
+            })?;
+
+        let key = self
+            .lua
+            .create_registry_value(function)
+            .map_err(|e| script_error(&e))?;
+        self.scripts.insert(sha.clone(), key);
+
+        Ok(sha)
+    }
+
+    fn eval(
+        &mut self,
+        source: Source,
+        keys: Vec<Vec<u8>>,
+        args: Vec<Vec<u8>>,
+        petitioner: Petitioner,
+    ) -> Response {
+        let sha = match source {
+            Source::Body(body) => match self.load(&body) {
+                Ok(sha) => sha,
+                Err(response) => return response,
+            },
+            Source::Sha(sha) => sha.to_ascii_lowercase(),
+            Source::Function { name, read_only } => {
+                return self.fcall(name, read_only, keys, args, petitioner);
+            }
+        };
+
+        let Some(key) = self.scripts.get(&sha) else {
+            return Response::Error(Sacrilege::NoScript);
+        };
+
+        self.lua.set_app_data(petitioner);
+
+        match self.run(key, keys, args) {
+            Ok(response) => response,
+            Err(e) => script_error(&e),
+        }
+    }
+
+    // Functions get KEYS and ARGV as their two arguments rather than as
+    // globals. One flagged no-writes can't call a write command.
+    fn fcall(
+        &mut self,
+        name: Vec<u8>,
+        read_only: bool,
+        keys: Vec<Vec<u8>>,
+        args: Vec<Vec<u8>>,
+        mut petitioner: Petitioner,
+    ) -> Response {
+        let Some(shrine) = self.functions.get(&name) else {
+            return Response::Error(Sacrilege::Script(b"ERR Function not found".to_vec()));
+        };
+
+        if read_only && !shrine.no_writes() {
+            return Response::Error(Sacrilege::Script(
+                b"ERR Can not execute a script with write flag using *_ro command.".to_vec(),
+            ));
+        }
+
+        petitioner.read_only = shrine.no_writes();
+        self.lua.set_app_data(petitioner);
+
+        match self.call(&shrine.callback, keys, args) {
+            Ok(response) => response,
+            Err(e) => script_error(&e),
+        }
+    }
+
+    fn run(
+        &self,
+        key: &RegistryKey,
+        keys: Vec<Vec<u8>>,
+        args: Vec<Vec<u8>>,
+    ) -> mlua::Result<Response> {
+        let lua = &self.lua;
+        let function: Function = lua.registry_value(key)?;
+        let pcall: Function = lua.registry_value(&self.pcall)?;
+
+        let globals = lua.globals();
+        globals.raw_set("KEYS", sequence(lua, &keys)?)?;
+        globals.raw_set("ARGV", sequence(lua, &args)?)?;
+
+        let (ok, value): (bool, LuaValue) = pcall.call(function)?;
+
+        self.settle(ok, value)
+    }
+
+    fn call(
+        &self,
+        callback: &RegistryKey,
+        keys: Vec<Vec<u8>>,
+        args: Vec<Vec<u8>>,
+    ) -> mlua::Result<Response> {
+        let lua = &self.lua;
+        let function: Function = lua.registry_value(callback)?;
+        let pcall: Function = lua.registry_value(&self.pcall)?;
+
+        let (ok, value): (bool, LuaValue) =
+            pcall.call((function, sequence(lua, &keys)?, sequence(lua, &args)?))?;
+
+        self.settle(ok, value)
+    }
+
+    // Turns what a script returned, or the error it raised, into its reply.
+    fn settle(&self, ok: bool, value: LuaValue) -> mlua::Result<Response> {
+        if ok {
+            return Ok(to_response(value));
+        }
+
+        let message = if self.vigil.kill.load(Ordering::Relaxed) {
+            KILLED.to_vec()
+        } else {
+            match value {
+                LuaValue::Table(reply) => match reply.raw_get::<_, LuaValue>("err")? {
+                    LuaValue::String(err) => err.as_bytes().to_vec(),
+                    _ => b"ERR Error running script".to_vec(),
+                },
+                LuaValue::String(err) => [b"ERR ".as_slice(), err.as_bytes()].concat(),
+                LuaValue::Error(e) => [b"ERR ".as_slice(), error_message(&e).as_bytes()].concat(),
+                _ => b"ERR Error running script".to_vec(),
+            }
+        };
+
+        Ok(Response::Error(Sacrilege::Script(message)))
+    }
+}
+
+// redis.pcall: grants the command against the script's temple, which the
+// real temple is waiting on, and hands back its reply as a Lua value.
+fn pcall<'lua>(lua: &'lua Lua, args: MultiValue<'lua>) -> mlua::Result<LuaValue<'lua>> {
+    let mut terms = Vec::with_capacity(args.len());
+    let mut itoa_buf = itoa::Buffer::new();
+
+    for arg in args {
+        match arg {
+            LuaValue::String(term) => terms.push(term.as_bytes().to_vec()),
+            LuaValue::Integer(term) => terms.push(itoa_buf.format(term).as_bytes().to_vec()),
+            LuaValue::Number(term) => terms.push(term.to_string().into_bytes()),
+            _ => {
+                return reply_table(
+                    lua,
+                    "err",
+                    b"ERR Lua redis lib command arguments must be strings or integers",
+                );
+            }
+        }
+    }
+
+    let Some(cmd) = terms.first() else {
+        return reply_table(
+            lua,
+            "err",
+            b"ERR Please specify at least one argument for this redis lib call",
+        );
+    };
+
+    // Commands that would block the script or outlive it.
+    if canon::flagged(cmd, canon::NOSCRIPT) {
+        return reply_table(
+            lua,
+            "err",
+            b"ERR This Redis command is not allowed from script",
+        );
+    }
+
+    let Some(mut petitioner) = lua.app_data_mut::<Petitioner>() else {
+        return reply_table(lua, "err", b"ERR redis.pcall called outside of a script");
+    };
+
+    if petitioner.read_only && WRITES.iter().any(|write| canon::flagged(cmd, write)) {
+        return reply_table(
+            lua,
+            "err",
+            b"ERR Write commands are not allowed from read-only scripts.",
+        );
+    }
+
+    let (reply_tx, reply_rx) = mpsc::channel();
+    let token = petitioner.token;
+    grant::grant(terms, &mut petitioner.temple, reply_tx, token);
+
+    let mut response = None;
+
+    // The temple drops its end once the command is granted.
+    for decree in reply_rx {
+        match decree {
+            Decree::Deliver(gift) => {
+                response.get_or_insert(gift.response);
+            }
+            Decree::Broadcast(publisher, event, message, clients) => {
+                if publisher.is_some() {
+                    response.get_or_insert(Response::Length(clients.len()));
+                }
+
+                if petitioner
+                    .tx
+                    .send(Decree::Broadcast(None, event, message, clients))
+                    .is_err()
+                {
+                    eprintln!("angel panicked");
+                }
+            }
+            Decree::Limits(limits) => {
+                if petitioner.tx.send(Decree::Limits(limits)).is_err() {
+                    eprintln!("angel panicked");
+                }
+            }
+            Decree::Welcome(..) | Decree::Farewell(_) => {}
+        }
+    }
+
+    drop(petitioner);
+
+    match response {
+        Some(response) => to_lua(lua, response),
+        None => Ok(LuaValue::Boolean(false)),
+    }
+}
+
+// redis.register_function('name', callback), or with a table that can
+// also carry flags and a description. Only works while a library loads.
+fn register_function(lua: &Lua, args: MultiValue) -> mlua::Result<()> {
+    let mut args = args.into_iter();
+
+    let (name, callback, flags, description) = match (args.next(), args.next()) {
+        (Some(LuaValue::String(name)), Some(LuaValue::Function(callback))) => {
+            (name.as_bytes().to_vec(), callback, None, None)
+        }
+        (Some(LuaValue::Table(named)), None) => {
+            let name = match named.raw_get::<_, LuaValue>("function_name")? {
+                LuaValue::String(name) => name.as_bytes().to_vec(),
+                _ => {
+                    return Err(mlua::Error::runtime(
+                        "function_name argument given to redis.register_function must be a string",
+                    ));
+                }
+            };
+            let LuaValue::Function(callback) = named.raw_get::<_, LuaValue>("callback")? else {
+                return Err(mlua::Error::runtime(
+                    "callback argument given to redis.register_function must be a function",
+                ));
+            };
+            let flags = match named.raw_get::<_, LuaValue>("flags")? {
+                LuaValue::Table(flags) => Some(flags),
+                LuaValue::Nil => None,
+                _ => {
+                    return Err(mlua::Error::runtime(
+                        "flags argument to redis.register_function must be a table representing function flags",
+                    ));
+                }
+            };
+            let description = match named.raw_get::<_, LuaValue>("description")? {
+                LuaValue::String(description) => Some(description.as_bytes().to_vec()),
+                LuaValue::Nil => None,
+                _ => {
+                    return Err(mlua::Error::runtime(
+                        "description argument given to redis.register_function must be a string",
+                    ));
+                }
+            };
+
+            (name, callback, flags, description)
+        }
+        _ => {
+            return Err(mlua::Error::runtime(
+                "wrong number of arguments to redis.register_function",
+            ));
+        }
+    };
+
+    if !library::valid_name(&name) {
+        return Err(mlua::Error::runtime(
+            "Function names can only contain letters, numbers, or underscores(_) and must be at least one character long",
+        ));
+    }
+
+    let mut registered_flags = Vec::new();
+
+    for flag in flags
+        .into_iter()
+        .flat_map(|flags| flags.sequence_values::<mlua::String>())
+    {
+        let flag = flag?;
+        let Some(known) = library::FLAGS
+            .iter()
+            .find(|known| flag.as_bytes() == **known)
+        else {
+            return Err(mlua::Error::runtime("unknown flag given"));
+        };
+
+        registered_flags.push(*known);
+    }
+
+    let callback = lua.create_registry_value(callback)?;
+
+    let Some(mut registrar) = lua.app_data_mut::<Registrar>() else {
+        return Err(mlua::Error::runtime(
+            "redis.register_function can only be called on FUNCTION LOAD command",
+        ));
+    };
+
+    if registrar
+        .functions
+        .iter()
+        .any(|(registered, _)| *registered == name)
+    {
+        return Err(mlua::Error::runtime(
+            "Function already exists in the library",
+        ));
+    }
+
+    registrar.functions.push((
+        name,
+        Shrine {
+            library: Vec::new(),
+            callback,
+            flags: registered_flags,
+            description,
+        },
+    ));
+
+    Ok(())
+}
+
+fn sequence<'lua>(lua: &'lua Lua, terms: &[Vec<u8>]) -> mlua::Result<Table<'lua>> {
+    lua.create_sequence_from(
+        terms
+            .iter()
+            .map(|term| lua.create_string(term))
+            .collect::<mlua::Result<Vec<_>>>()?,
+    )
+}
+
+fn reply_table<'lua>(lua: &'lua Lua, kind: &str, message: &[u8]) -> mlua::Result<LuaValue<'lua>> {
+    let reply = lua.create_table()?;
+    reply.raw_set(kind, lua.create_string(message)?)?;
+
+    Ok(LuaValue::Table(reply))
+}
+
+// Replies are converted the way Redis does it, through the RESP they'd be
+// written as: status and error replies become tables with an `ok` or `err`
+// field, nil becomes false.
+fn to_lua(lua: &Lua, response: Response) -> mlua::Result<LuaValue<'_>> {
+    let mut resp = Vec::new();
+    inscribe(response, &mut resp, &mut itoa::Buffer::new());
+
+    let mut cursor = 0;
+    parse_resp(lua, &resp, &mut cursor)
+}
+
+fn parse_resp<'lua>(
+    lua: &'lua Lua,
+    resp: &[u8],
+    cursor: &mut usize,
+) -> mlua::Result<LuaValue<'lua>> {
+    let malformed = || mlua::Error::runtime("malformed reply");
+
+    let start = *cursor;
+    let end = start + find_crlf(&resp[start..]).ok_or_else(malformed)?;
+    let line = &resp[start + 1..end];
+    *cursor = end + 2;
+
+    match resp[start] {
+        b'+' => reply_table(lua, "ok", line),
+        b'-' => reply_table(lua, "err", line),
+        b':' => Ok(LuaValue::Number(
+            bytes_to_i64(line).map_err(|_| malformed())? as f64,
+        )),
+        b'$' => {
+            let Ok(len) = usize::try_from(bytes_to_i64(line).map_err(|_| malformed())?) else {
+                return Ok(LuaValue::Boolean(false));
+            };
+
+            let bulk = resp.get(*cursor..*cursor + len).ok_or_else(malformed)?;
+            *cursor += len + 2;
+
+            Ok(LuaValue::String(lua.create_string(bulk)?))
+        }
+        b'*' => {
+            let Ok(len) = usize::try_from(bytes_to_i64(line).map_err(|_| malformed())?) else {
+                return Ok(LuaValue::Boolean(false));
+            };
+
+            let array = lua.create_table_with_capacity(len, 0)?;
+
+            for index in 1..=len {
+                array.raw_set(index, parse_resp(lua, resp, cursor)?)?;
+            }
+
+            Ok(LuaValue::Table(array))
+        }
+        _ => Err(malformed()),
+    }
+}
+
+fn to_response(value: LuaValue) -> Response {
+    match value {
+        LuaValue::String(bulk) => Response::BulkString(Some(bulk.as_bytes().to_vec())),
+        LuaValue::Integer(number) => Response::Number(number),
+        LuaValue::Number(number) => Response::Number(number as i64),
+        LuaValue::Boolean(true) => Response::Number(1),
+        LuaValue::Table(table) => table_to_response(table),
+        _ => Response::BulkString(None),
+    }
+}
+
+// An array stops at its first nil, like it does for Redis.
+fn table_to_response(table: Table) -> Response {
+    if let Ok(LuaValue::String(err)) = table.raw_get::<_, LuaValue>("err") {
+        return Response::Error(Sacrilege::Script(err.as_bytes().to_vec()));
+    }
+
+    if let Ok(LuaValue::String(ok)) = table.raw_get::<_, LuaValue>("ok") {
+        return Response::Info(InfoType::Status(ok.as_bytes().to_vec()));
+    }
+
+    Response::Array(
+        table
+            .sequence_values::<LuaValue>()
+            .map_while(Result::ok)
+            .map(to_response)
+            .collect(),
+    )
+}
+
+fn error_message(e: &mlua::Error) -> String {
+    match e {
+        mlua::Error::SyntaxError { message, .. } => message.clone(),
+        mlua::Error::RuntimeError(message) => message.clone(),
+        mlua::Error::CallbackError { cause, .. } => error_message(cause),
+        e => e.to_string(),
+    }
+}
+
+fn script_error(e: &mlua::Error) -> Response {
+    let mut message = b"ERR ".to_vec();
+    message.extend_from_slice(error_message(e).as_bytes());
+    Response::Error(Sacrilege::Script(message))
+}
+
+fn deliver(tx: &Sender<Decree>, response: Response, token: Token) {
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
+        eprintln!("angel panicked");
+    }
+}
@@ -0,0 +1,88 @@
+use mlua::RegistryKey;
+
+/// The flags a function can be registered with. Only no-writes changes how
+/// it runs, the rest are accepted for compatibility.
+pub const FLAGS: [&[u8]; 5] = [
+    b"no-writes",
+    b"allow-oom",
+    b"allow-stale",
+    b"no-cluster",
+    b"allow-cross-slot-keys",
+];
+
+pub struct Library {
+    pub code: Vec<u8>,
+    pub functions: Vec<Vec<u8>>,
+}
+
+// A registered function, kept alive in the Lua registry.
+pub struct Shrine {
+    pub library: Vec<u8>,
+    pub callback: RegistryKey,
+    pub flags: Vec<&'static [u8]>,
+    pub description: Option<Vec<u8>>,
+}
+
+impl Shrine {
+    pub fn no_writes(&self) -> bool {
+        self.flags.contains(&FLAGS[0])
+    }
+}
+
+/// What redis.register_function collects while a library's code runs.
+#[derive(Default)]
+pub struct Registrar {
+    pub functions: Vec<(Vec<u8>, Shrine)>,
+}
+
+/// Reads the `#!lua name=<library>` line a library starts with. Returns the
+/// library's name and its code with that line blanked out, which keeps the
+/// line numbers of error messages right.
+pub fn metadata(code: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Vec<u8>> {
+    let Some(shebang) = code.strip_prefix(b"#!") else {
+        return Err(b"ERR Missing library metadata".to_vec());
+    };
+
+    let end = shebang
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .unwrap_or(shebang.len());
+    let mut fields = shebang[..end]
+        .split(|&byte| byte == b' ')
+        .filter(|field| !field.is_empty());
+
+    let engine = fields.next().unwrap_or_default();
+
+    if !engine.eq_ignore_ascii_case(b"lua") {
+        return Err([b"ERR Engine '".as_slice(), engine, b"' not found"].concat());
+    }
+
+    let mut name = None;
+
+    for field in fields {
+        match field.strip_prefix(b"name=") {
+            Some(value) => name = Some(value.to_vec()),
+            None => return Err([b"ERR Invalid metadata value given: ".as_slice(), field].concat()),
+        }
+    }
+
+    let Some(name) = name else {
+        return Err(b"ERR Library name was not given".to_vec());
+    };
+
+    if !valid_name(&name) {
+        return Err(
+            b"ERR Library names can only contain letters, numbers, or underscores(_) and must be at least one character long"
+                .to_vec(),
+        );
+    }
+
+    Ok((name, shebang[end..].to_vec()))
+}
+
+pub fn valid_name(name: &[u8]) -> bool {
+    !name.is_empty()
+        && name
+            .iter()
+            .all(|&byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
@@ -1,26 +1,192 @@
-use std::collections::hash_map::Entry;
-use std::collections::{HashSet, VecDeque};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::time::UNIX_EPOCH;
 use std::vec::IntoIter;
 use std::{collections::HashMap, time::SystemTime};
 
-use rkyv::rancor::Error;
-use rkyv::{Archive, Deserialize, Serialize};
+use rkyv::{Archive, Deserialize, Serialize, with::Skip};
 
-use crate::wish::util::bytes_to_i64;
+use crate::temple::rite::Relic;
+use crate::wish::util::{bytes_to_f64, bytes_to_i64, glob_match};
 use crate::wish::{Command, Sacrilege};
 
+pub mod access;
+pub mod compact;
+pub mod dict;
+pub mod dump;
+pub mod gcra;
+pub mod memory;
+pub mod notify;
+pub mod reliquary;
+pub mod search;
+pub mod sketch;
+pub mod snapshot;
+pub mod timeseries;
+pub mod vector;
+
+use access::AccessLog;
+use compact::{EncodingLimits, Hash, List, Set};
+use dict::{Dict, Entry, Lots};
+use gcra::{Gcra, Throttle};
+use notify::Notifier;
+use reliquary::Reliquary;
+use search::{FieldSchema, Query, SearchIndex};
+use sketch::{CountMinSketch, TopK};
+use timeseries::{
+    Aggregation, CompactionRule, DuplicatePolicy, LabelFilter, SampleError, TimeSeries,
+    TimeSeriesOptions,
+};
+
 #[derive(Clone, Archive, Serialize, Deserialize)]
 pub enum Value {
     String(Vec<u8>),
-    List(VecDeque<Vec<u8>>),
-    Hash(HashMap<Vec<u8>, Vec<u8>>),
-    Set(HashSet<Vec<u8>>),
+    List(List),
+    Hash(Hash),
+    Set(Set),
+    CountMinSketch(CountMinSketch),
+    TopK(TopK),
+    TimeSeries(TimeSeries),
+    Gcra(Gcra),
+    Relic(Reliquary),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static [u8] {
+        match self {
+            Value::String(_) => b"string",
+            Value::List(_) => b"list",
+            Value::Hash(_) => b"hash",
+            Value::Set(_) => b"set",
+            Value::CountMinSketch(_) => b"CMSk-TYPE",
+            Value::TopK(_) => b"TopK-TYPE",
+            Value::TimeSeries(_) => b"TSDB-TYPE",
+            Value::Gcra(_) => b"gcra",
+            Value::Relic(reliquary) => reliquary.type_name(),
+        }
+    }
+
+    /// Roughly how many separate allocations dropping the value frees.
+    pub fn element_count(&self) -> usize {
+        match self {
+            Value::List(list) => list.len(),
+            Value::Hash(hash) => hash.len(),
+            Value::Set(set) => set.len(),
+            Value::TimeSeries(series) => series.sample_count(),
+            Value::String(_)
+            | Value::CountMinSketch(_)
+            | Value::TopK(_)
+            | Value::Gcra(_)
+            | Value::Relic(_) => 1,
+        }
+    }
+
+    pub fn encoding(&self) -> &'static [u8] {
+        match self {
+            Value::String(string) => {
+                if string.len() <= 20 && bytes_to_i64(string).is_ok() {
+                    b"int"
+                } else if string.len() <= 44 {
+                    b"embstr"
+                } else {
+                    b"raw"
+                }
+            }
+            Value::List(list) => list.encoding(),
+            Value::Hash(hash) => hash.encoding(),
+            Value::Set(set) => set.encoding(),
+            Value::CountMinSketch(_)
+            | Value::TopK(_)
+            | Value::TimeSeries(_)
+            | Value::Gcra(_)
+            | Value::Relic(_) => b"raw",
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Value::List(list) => list.is_valid(),
+            Value::Hash(hash) => hash.is_valid(),
+            Value::Set(set) => set.is_valid(),
+            Value::CountMinSketch(sketch) => sketch.is_valid(),
+            Value::TopK(topk) => topk.is_valid(),
+            Value::TimeSeries(series) => series.is_valid(),
+            Value::String(_) | Value::Gcra(_) | Value::Relic(_) => true,
+        }
+    }
+}
+
+const LAZY_FREE_THRESHOLD: usize = 64;
+
+const WRONGTYPE: &[u8] = b"WRONGTYPE Operation against a key holding the wrong kind of value";
+
+#[derive(Clone)]
+pub struct ScanOptions {
+    pub pattern: Option<Vec<u8>>,
+    pub count: usize,
+    pub type_name: Option<Vec<u8>>,
+    pub no_values: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            pattern: None,
+            count: 10,
+            type_name: None,
+            no_values: false,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SortOptions {
+    pub by: Option<Vec<u8>>,
+    pub limit: Option<(i64, i64)>,
+    pub get: Vec<Vec<u8>>,
+    pub descending: bool,
+    pub alpha: bool,
+}
+
+#[derive(Clone)]
+pub struct SearchOptions {
+    pub no_content: bool,
+    pub return_fields: Option<Vec<Vec<u8>>>,
+    pub sort_by: Option<(Vec<u8>, bool)>,
+    pub limit: (usize, usize),
+    pub knn: Option<Box<KnnQuery>>,
+}
+
+#[derive(Clone)]
+pub struct KnnQuery {
+    pub k: usize,
+    pub field: Vec<u8>,
+    pub vector: Vec<u8>,
+    pub ef_runtime: Option<usize>,
+    pub score_field: Vec<u8>,
 }
 
 #[derive(Archive, Serialize, Deserialize)]
-pub struct Soul(HashMap<Vec<u8>, (Value, Option<u64>)>);
+pub struct Database(
+    Keyspace,
+    HashMap<Vec<u8>, SearchIndex>,
+    #[rkyv(with = Skip)] Notifier,
+    #[rkyv(with = Skip)] AccessLog,
+    #[rkyv(with = Skip)] EncodingLimits,
+    #[rkyv(with = Skip)] Lots,
+);
+
+pub const DEFAULT_DATABASES: usize = 16;
+
+#[derive(Archive, Serialize, Deserialize)]
+pub struct Soul {
+    databases: Vec<Database>,
+    // Compiled again when a snapshot loads.
+    libraries: Libraries,
+    #[rkyv(with = Skip)]
+    selected: usize,
+}
 
 pub enum ServerError {
     SerializationError(String),
@@ -34,17 +200,45 @@ impl Default for Soul {
     }
 }
 
+impl Default for Database {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 use ServerError::{FileWriteError, SerializationError};
 
+impl Deref for Soul {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.databases[self.selected]
+    }
+}
+
+impl DerefMut for Soul {
+    fn deref_mut(&mut self) -> &mut Database {
+        &mut self.databases[self.selected]
+    }
+}
+
 impl Soul {
     pub fn new() -> Self {
-        Soul(HashMap::new())
+        Soul::with_databases(DEFAULT_DATABASES)
+    }
+
+    pub fn with_databases(count: usize) -> Self {
+        Soul {
+            databases: (0..count.max(1)).map(|_| Database::new()).collect(),
+            libraries: Vec::new(),
+            selected: 0,
+        }
     }
 
     pub fn save(&self, path: PathBuf) -> Result<(), ServerError> {
-        let bytes = match rkyv::to_bytes::<Error>(self) {
+        let bytes = match snapshot::encode(self) {
             Ok(bytes) => bytes,
-            Err(err) => return Err(SerializationError(err.to_string())),
+            Err(err) => return Err(SerializationError(err)),
         };
 
         if let Err(e) = std::fs::write(path, bytes) {
@@ -54,8 +248,164 @@ impl Soul {
         Ok(())
     }
 
+    pub fn database_count(&self) -> usize {
+        self.databases.len()
+    }
+
+    pub fn grow(&mut self, count: usize) {
+        while self.databases.len() < count {
+            self.databases.push(Database::new());
+        }
+    }
+
+    pub fn set_notify_flags(&mut self, flags: u16) {
+        for database in &mut self.databases {
+            database.2.set_flags(flags);
+        }
+    }
+
+    pub fn set_encoding_limits(&mut self, limits: EncodingLimits) {
+        for database in &mut self.databases {
+            database.4 = limits;
+        }
+    }
+
+    pub fn notifications(&mut self) -> Vec<(usize, &'static [u8], Vec<u8>)> {
+        let mut notifications = Vec::new();
+
+        for (db, database) in self.databases.iter_mut().enumerate() {
+            notifications.extend(database.2.drain().map(|(event, key)| (db, event, key)));
+        }
+
+        notifications
+    }
+
+    pub fn set_watching(&mut self, watching: bool) {
+        for database in &mut self.databases {
+            database.2.set_watching(watching);
+        }
+    }
+
+    /// Only recorded while `set_watching` is on.
+    pub fn touched(&mut self) -> Vec<(usize, Vec<u8>)> {
+        let mut touched = Vec::new();
+
+        for (db, database) in self.databases.iter_mut().enumerate() {
+            touched.extend(database.2.drain_touched().map(|key| (db, key)));
+        }
+
+        touched
+    }
+
+    pub fn dirty(&self) -> u64 {
+        self.databases.iter().map(|database| database.2.dirty()).sum()
+    }
+
+    pub fn libraries(&self) -> &Libraries {
+        &self.libraries
+    }
+
+    pub fn set_libraries(&mut self, libraries: Libraries) {
+        self.libraries = libraries;
+    }
+
+    /// Keys across every database, expired ones included until they're noticed.
+    pub fn key_count(&self) -> usize {
+        self.databases.iter().map(Database::dbsize).sum()
+    }
+
+    pub fn select(&mut self, db: usize) {
+        self.selected = db.min(self.databases.len() - 1);
+    }
+
+    pub fn rebuild_indexes(&mut self) {
+        for database in &mut self.databases {
+            database.rebuild_indexes();
+        }
+    }
+
+    pub fn move_key(&mut self, key: Vec<u8>, db: usize, now: u64) -> Result<bool, Sacrilege> {
+        if db >= self.databases.len() {
+            return Err(Sacrilege::InvalidArgument(Command::MOVE));
+        }
+
+        if db == self.selected {
+            return Err(Sacrilege::IncorrectUsage(Command::MOVE));
+        }
+
+        if self.databases[db].get_valid_value(&key, now).is_some() {
+            return Ok(false);
+        }
+
+        let source = &mut self.databases[self.selected];
+
+        if source.get_valid_value(&key, now).is_none() {
+            return Ok(false);
+        }
+
+        let Some(entry) = source.0.remove(&key) else {
+            return Ok(false);
+        };
+        source.3.forget(&key);
+        source.reindex(&key);
+        source.2.notify(notify::GENERIC, b"move_from", &key);
+
+        let destination = &mut self.databases[db];
+        destination.insert(key.clone(), entry);
+        destination.reindex(&key);
+        destination.2.notify(notify::GENERIC, b"move_to", &key);
+
+        Ok(true)
+    }
+
+    pub fn swapdb(&mut self, first: usize, second: usize) -> Result<(), Sacrilege> {
+        if first >= self.databases.len() || second >= self.databases.len() {
+            return Err(Sacrilege::InvalidArgument(Command::SWAPDB));
+        }
+
+        self.databases.swap(first, second);
+
+        Ok(())
+    }
+
+    pub fn flushdb(&mut self) -> Keyspace {
+        self.flush()
+    }
+
+    pub fn flushall(&mut self) -> Vec<Keyspace> {
+        self.databases.iter_mut().map(Database::flush).collect()
+    }
+}
+
+pub type Keyspace = Dict<(Value, Option<u64>)>;
+
+pub type Libraries = Vec<(Vec<u8>, Vec<u8>)>;
+
+impl Database {
+    pub fn new() -> Self {
+        Database(
+            Dict::new(),
+            HashMap::new(),
+            Notifier::default(),
+            AccessLog::default(),
+            EncodingLimits::default(),
+            Lots::default(),
+        )
+    }
+
+    // Index definitions outlive a flush, only their postings go.
+    fn flush(&mut self) -> Keyspace {
+        for search_index in self.1.values_mut() {
+            search_index.clear();
+        }
+
+        self.3.clear();
+        self.2.mark_dirty(self.0.len() as u64);
+        std::mem::take(&mut self.0)
+    }
+
     pub fn get(&mut self, key: Vec<u8>, now: u64) -> Result<Option<Vec<u8>>, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::String(value)) => Ok(Some(value.clone())),
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::GET)),
             None => Ok(None),
@@ -63,7 +413,16 @@ impl Soul {
     }
 
     pub fn set(&mut self, key: Vec<u8>, val: (Value, Option<u64>)) {
-        self.0.insert(key, val);
+        let has_expiry = val.1.is_some();
+
+        self.insert(key.clone(), val);
+        self.reindex(&key);
+
+        self.2.notify(notify::STRING, b"set", &key);
+
+        if has_expiry {
+            self.2.notify(notify::GENERIC, b"expire", &key);
+        }
     }
 
     pub fn append(
@@ -72,7 +431,7 @@ impl Soul {
         mut incoming_value: Vec<u8>,
         now: u64,
     ) -> Result<usize, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::String(value)) => {
                 value.append(&mut incoming_value);
                 Ok(value.len())
@@ -80,14 +439,20 @@ impl Soul {
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::APPEND)),
             None => {
                 let incoming_value_len = incoming_value.len();
-                self.0.insert(key, (Value::String(incoming_value), None));
+                self.insert(key.clone(), (Value::String(incoming_value), None));
                 Ok(incoming_value_len)
             }
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::STRING, b"append", &key);
         }
+
+        result
     }
 
     pub fn incr(&mut self, key: Vec<u8>, now: u64) -> Result<i64, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::String(value)) => {
                 let mut itoa_buf = itoa::Buffer::new();
 
@@ -104,14 +469,20 @@ impl Soul {
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::INCR)),
             None => {
-                self.0.insert(key, (Value::String(b"1".into()), None));
+                self.insert(key.clone(), (Value::String(b"1".into()), None));
                 Ok(1)
             }
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::STRING, b"incrby", &key);
         }
+
+        result
     }
 
     pub fn decr(&mut self, key: Vec<u8>, now: u64) -> Result<i64, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::String(value)) => {
                 let mut itoa_buf = itoa::Buffer::new();
 
@@ -128,14 +499,20 @@ impl Soul {
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::DECR)),
             None => {
-                self.0.insert(key, (Value::String(b"-1".into()), None));
+                self.insert(key.clone(), (Value::String(b"-1".into()), None));
                 Ok(-1)
             }
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::STRING, b"decrby", &key);
         }
+
+        result
     }
 
     pub fn strlen(&mut self, key: Vec<u8>, now: u64) -> Result<usize, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::String(value)) => Ok(value.len()),
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::STRLEN)),
             None => Ok(0),
@@ -147,6 +524,7 @@ impl Soul {
 
         for key in keys {
             if self.remove_valid_value(&key, now).is_some() {
+                self.2.notify(notify::GENERIC, b"del", &key);
                 number_of_entries_deleted += 1;
             }
         }
@@ -166,20 +544,207 @@ impl Soul {
         number_of_entries_that_exist
     }
 
+    pub fn key_type(&mut self, key: Vec<u8>, now: u64) -> &'static [u8] {
+        match self.get_valid_value(&key, now) {
+            Some(value) => value.type_name(),
+            None => b"none",
+        }
+    }
+
+    pub fn rename(&mut self, key: Vec<u8>, new_key: Vec<u8>, now: u64) -> Result<(), Sacrilege> {
+        if self.get_valid_value(&key, now).is_none() {
+            return Err(Sacrilege::KeyDoesNotExist(Command::RENAME));
+        }
+
+        if key == new_key {
+            return Ok(());
+        }
+
+        if let Some(entry) = self.0.remove(&key) {
+            self.3.forget(&key);
+            self.2.notify(notify::GENERIC, b"rename_from", &key);
+            self.insert(new_key.clone(), entry);
+            self.2.notify(notify::GENERIC, b"rename_to", &new_key);
+        }
+
+        self.reindex(&key);
+        self.reindex(&new_key);
+
+        Ok(())
+    }
+
+    pub fn renamenx(
+        &mut self,
+        key: Vec<u8>,
+        new_key: Vec<u8>,
+        now: u64,
+    ) -> Result<bool, Sacrilege> {
+        if self.get_valid_value(&key, now).is_none() {
+            return Err(Sacrilege::KeyDoesNotExist(Command::RENAMENX));
+        }
+
+        if self.get_valid_value(&new_key, now).is_some() {
+            return Ok(false);
+        }
+
+        self.rename(key, new_key, now)?;
+
+        Ok(true)
+    }
+
+    pub fn copy(
+        &mut self,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        replace: bool,
+        now: u64,
+    ) -> Result<bool, Sacrilege> {
+        if source == destination {
+            return Err(Sacrilege::IncorrectUsage(Command::COPY));
+        }
+
+        if self.get_valid_value(&source, now).is_none()
+            || (!replace && self.get_valid_value(&destination, now).is_some())
+        {
+            return Ok(false);
+        }
+
+        if let Some(entry) = self.0.get(&source).cloned() {
+            self.insert(destination.clone(), entry);
+            self.2.notify(notify::GENERIC, b"copy_to", &destination);
+        }
+
+        self.reindex(&destination);
+
+        Ok(true)
+    }
+
+    pub fn dump(&mut self, key: Vec<u8>, now: u64) -> Option<Vec<u8>> {
+        dump::encode(self.get_readable_value(&key, now)?)
+    }
+
+    /// `expiry` is absolute, like the rest of the keyspace.
+    pub fn restore(
+        &mut self,
+        key: Vec<u8>,
+        payload: Vec<u8>,
+        expiry: Option<u64>,
+        replace: bool,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        if !replace && self.get_valid_value(&key, now).is_some() {
+            return Err(Sacrilege::KeyAlreadyExists(Command::RESTORE));
+        }
+
+        let Some(mut value) = dump::decode(&payload) else {
+            return Err(Sacrilege::IncorrectUsage(Command::RESTORE));
+        };
+
+        // Only TS.CREATERULE links a series to its source, so rules can't loop.
+        if let Value::TimeSeries(series) = &mut value {
+            series.compacted_from = None;
+        }
+
+        self.insert(key.clone(), (value, expiry));
+        self.reindex(&key);
+        self.2.notify(notify::GENERIC, b"restore", &key);
+
+        Ok(())
+    }
+
+    pub fn randomkey(&mut self, now: u64) -> Option<Vec<u8>> {
+        // A handful of tries, in case the first picks have expired.
+        for _ in 0..8 {
+            let (key, _) = self.0.random(&mut self.5)?;
+            let key = key.clone();
+
+            if self.get_valid_value(&key, now).is_some() {
+                return Some(key);
+            }
+        }
+
+        None
+    }
+
+    pub fn dbsize(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn touch(&mut self, keys: Vec<Vec<u8>>, now: u64) -> u32 {
+        let mut number_of_entries_touched = 0;
+
+        for key in keys {
+            if self.get_valid_value(&key, now).is_some() {
+                number_of_entries_touched += 1;
+            }
+        }
+
+        number_of_entries_touched
+    }
+
+    /// Drops `key` if it has expired, so a watcher sees it as modified.
+    pub fn expire_if_due(&mut self, key: &[u8], now: u64) {
+        self.peek_valid_value(key, now);
+    }
+
+    // OBJECT and MEMORY look at keys without counting as a use of them.
+    pub fn object_encoding(&mut self, key: Vec<u8>, now: u64) -> Option<&'static [u8]> {
+        self.peek_valid_value(&key, now).map(Value::encoding)
+    }
+
+    pub fn object_idletime(&mut self, key: Vec<u8>, now: u64) -> Option<u64> {
+        self.peek_valid_value(&key, now)?;
+        Some(self.3.idle(&key, now))
+    }
+
+    pub fn object_freq(&mut self, key: Vec<u8>, now: u64) -> Option<u8> {
+        self.peek_valid_value(&key, now)?;
+        Some(self.3.frequency(&key, now))
+    }
+
+    pub fn object_refcount(&mut self, key: Vec<u8>, now: u64) -> Option<u32> {
+        self.peek_valid_value(&key, now).map(|_| 1)
+    }
+
+    pub fn memory_usage(&mut self, key: Vec<u8>, samples: usize, now: u64) -> Option<usize> {
+        self.peek_valid_value(&key, now)
+            .map(|value| memory::usage(&key, value, samples))
+    }
+
+    pub fn unlink(&mut self, keys: Vec<Vec<u8>>, now: u64) -> (u32, Vec<Value>) {
+        let mut number_of_entries_deleted = 0;
+        let mut large_values = Vec::new();
+
+        for key in keys {
+            if let Some(value) = self.remove_valid_value(&key, now) {
+                self.2.notify(notify::GENERIC, b"del", &key);
+                number_of_entries_deleted += 1;
+
+                if value.element_count() > LAZY_FREE_THRESHOLD {
+                    large_values.push(value);
+                }
+            }
+        }
+
+        (number_of_entries_deleted, large_values)
+    }
+
     pub fn hset(
         &mut self,
         key: Vec<u8>,
         field_value_pairs: Vec<(Vec<u8>, Vec<u8>)>,
         now: u64,
     ) -> Result<u32, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let limits = self.4;
+
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::Hash(map)) => {
                 let mut new_values_added = 0;
 
                 for field_value_pair in field_value_pairs {
                     let (field, value) = field_value_pair;
 
-                    if map.insert(field, value).is_none() {
+                    if map.insert(field, value, &limits) {
                         new_values_added += 1;
                     }
                 }
@@ -188,21 +753,30 @@ impl Soul {
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::HSET)),
             None => {
-                let mut map = HashMap::new();
+                let mut map = Hash::default();
                 let mut new_values_added = 0;
 
                 for field_value_pair in field_value_pairs {
                     let (field, value) = field_value_pair;
 
-                    map.insert(field, value);
-                    new_values_added += 1;
+                    if map.insert(field, value, &limits) {
+                        new_values_added += 1;
+                    }
                 }
 
-                self.0.insert(key, (Value::Hash(map), None));
+                self.insert(key.clone(), (Value::Hash(map), None));
 
                 Ok(new_values_added)
             }
+        };
+
+        self.reindex(&key);
+
+        if result.is_ok() {
+            self.2.notify(notify::HASH, b"hset", &key);
         }
+
+        result
     }
 
     pub fn hget(
@@ -211,8 +785,8 @@ impl Soul {
         field: Vec<u8>,
         now: u64,
     ) -> Result<Option<Vec<u8>>, Sacrilege> {
-        match self.get_valid_value(&key, now) {
-            Some(Value::Hash(map)) => Ok(map.get(&field).cloned()),
+        match self.get_readable_value(&key, now) {
+            Some(Value::Hash(map)) => Ok(map.get(&field).map(<[u8]>::to_vec)),
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::HGET)),
             None => Ok(None),
         }
@@ -226,10 +800,10 @@ impl Soul {
     ) -> Result<Option<Vec<Option<Vec<u8>>>>, Sacrilege> {
         let mut values = Vec::new();
 
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::Hash(map)) => {
                 for field in fields {
-                    values.push(map.get(&field).cloned());
+                    values.push(map.get(&field).map(<[u8]>::to_vec));
                 }
 
                 Ok(Some(values))
@@ -242,10 +816,10 @@ impl Soul {
     pub fn hdel(&mut self, key: Vec<u8>, fields: Vec<Vec<u8>>, now: u64) -> Result<u32, Sacrilege> {
         let mut amount_of_deleted_values = 0;
 
-        match self.get_mut_valid_value(&key, now) {
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::Hash(map)) => {
                 for field in fields {
-                    if map.remove(&field).is_some() {
+                    if map.remove(&field) {
                         amount_of_deleted_values += 1
                     }
                 }
@@ -254,13 +828,21 @@ impl Soul {
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::HDEL)),
             None => Ok(0),
+        };
+
+        self.reindex(&key);
+
+        if let Ok(1..) = result {
+            self.2.notify(notify::HASH, b"hdel", &key);
         }
+
+        result
     }
 
     pub fn hexists(&mut self, key: Vec<u8>, field: Vec<u8>, now: u64) -> Result<u32, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::Hash(map)) => {
-                if map.get(&field).is_some() {
+                if map.contains_key(&field) {
                     Ok(1)
                 } else {
                     Ok(0)
@@ -272,7 +854,7 @@ impl Soul {
     }
 
     pub fn hlen(&mut self, key: Vec<u8>, now: u64) -> Result<usize, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::Hash(map)) => Ok(map.len()),
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::HLEN)),
             None => Ok(0),
@@ -285,10 +867,12 @@ impl Soul {
         mut elements: Vec<Vec<u8>>,
         now: u64,
     ) -> Result<usize, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let limits = self.4;
+
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::List(list)) => {
                 for element in elements {
-                    list.push_front(element);
+                    list.push_front(element, &limits);
                 }
                 Ok(list.len())
             }
@@ -297,29 +881,46 @@ impl Soul {
                 let elements_len = elements.len();
                 elements.reverse();
 
-                self.0
-                    .insert(key, (Value::List(VecDeque::from(elements)), None));
+                self.insert(
+                    key.clone(),
+                    (Value::List(List::from_elements(elements, &limits)), None),
+                );
 
                 Ok(elements_len)
             }
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::LIST, b"lpush", &key);
         }
+
+        result
     }
 
     pub fn lpop(&mut self, key: Vec<u8>, now: u64) -> Result<Option<Vec<u8>>, Sacrilege> {
+        self.3.touch(&key, now);
+
         match self.0.entry(key) {
             Entry::Occupied(mut occupied) => {
                 if let (Value::List(list), expiry) = occupied.get_mut() {
                     if let Some(expiry) = expiry
                         && *expiry < now
                     {
-                        occupied.remove();
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::EXPIRED, b"expired", &key);
                         return Ok(None);
                     }
 
                     let element = list.pop_front();
 
-                    if list.is_empty() {
-                        occupied.remove();
+                    let is_empty = list.is_empty();
+                    self.2.notify(notify::LIST, b"lpop", occupied.key());
+
+                    if is_empty {
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::GENERIC, b"del", &key);
                     }
 
                     Ok(element)
@@ -337,13 +938,17 @@ impl Soul {
         count: usize,
         now: u64,
     ) -> Result<Option<Vec<Option<Vec<u8>>>>, Sacrilege> {
+        self.3.touch(&key, now);
+
         match self.0.entry(key) {
             Entry::Occupied(mut occupied) => {
                 if let (Value::List(list), expiry) = occupied.get_mut() {
                     if let Some(expiry) = expiry
                         && *expiry < now
                     {
-                        occupied.remove();
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::EXPIRED, b"expired", &key);
                         return Ok(None);
                     }
 
@@ -357,8 +962,13 @@ impl Soul {
                         }
                     }
 
-                    if list.is_empty() {
-                        occupied.remove();
+                    let is_empty = list.is_empty();
+                    self.2.notify(notify::LIST, b"lpop", occupied.key());
+
+                    if is_empty {
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::GENERIC, b"del", &key);
                     }
 
                     Ok(Some(popped))
@@ -376,10 +986,12 @@ impl Soul {
         elements: Vec<Vec<u8>>,
         now: u64,
     ) -> Result<usize, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let limits = self.4;
+
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::List(list)) => {
                 for element in elements {
-                    list.push_back(element);
+                    list.push_back(element, &limits);
                 }
                 Ok(list.len())
             }
@@ -387,29 +999,46 @@ impl Soul {
             None => {
                 let elements_len = elements.len();
 
-                self.0
-                    .insert(key, (Value::List(VecDeque::from(elements)), None));
+                self.insert(
+                    key.clone(),
+                    (Value::List(List::from_elements(elements, &limits)), None),
+                );
 
                 Ok(elements_len)
             }
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::LIST, b"rpush", &key);
         }
+
+        result
     }
 
     pub fn rpop(&mut self, key: Vec<u8>, now: u64) -> Result<Option<Vec<u8>>, Sacrilege> {
+        self.3.touch(&key, now);
+
         match self.0.entry(key) {
             Entry::Occupied(mut occupied) => {
                 if let (Value::List(list), expiry) = occupied.get_mut() {
                     if let Some(expiry) = expiry
                         && *expiry < now
                     {
-                        occupied.remove();
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::EXPIRED, b"expired", &key);
                         return Ok(None);
                     }
 
                     let element = list.pop_back();
 
-                    if list.is_empty() {
-                        occupied.remove();
+                    let is_empty = list.is_empty();
+                    self.2.notify(notify::LIST, b"rpop", occupied.key());
+
+                    if is_empty {
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::GENERIC, b"del", &key);
                     }
 
                     Ok(element)
@@ -427,13 +1056,17 @@ impl Soul {
         count: usize,
         now: u64,
     ) -> Result<Option<Vec<Option<Vec<u8>>>>, Sacrilege> {
+        self.3.touch(&key, now);
+
         match self.0.entry(key) {
             Entry::Occupied(mut occupied) => {
                 if let (Value::List(list), expiry) = occupied.get_mut() {
                     if let Some(expiry) = expiry
                         && *expiry < now
                     {
-                        occupied.remove();
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::EXPIRED, b"expired", &key);
                         return Ok(None);
                     }
 
@@ -447,8 +1080,13 @@ impl Soul {
                         }
                     }
 
-                    if list.is_empty() {
-                        occupied.remove();
+                    let is_empty = list.is_empty();
+                    self.2.notify(notify::LIST, b"rpop", occupied.key());
+
+                    if is_empty {
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.2.notify(notify::GENERIC, b"del", &key);
                     }
 
                     Ok(Some(popped))
@@ -461,7 +1099,7 @@ impl Soul {
     }
 
     pub fn llen(&mut self, key: Vec<u8>, now: u64) -> Result<usize, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::List(list)) => Ok(list.len()),
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::LLEN)),
             None => Ok(0),
@@ -475,7 +1113,7 @@ impl Soul {
         mut ending_index: i32,
         now: u64,
     ) -> Result<Option<Vec<Option<Vec<u8>>>>, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::List(list)) => {
                 let list_len = list.len() as i32;
 
@@ -500,8 +1138,10 @@ impl Soul {
                     && ending_index < list_len
                 {
                     Ok(Some(
-                        list.range(starting_index as usize..(ending_index + 1) as usize)
-                            .map(|e| Some(e.clone()))
+                        list.iter()
+                            .skip(starting_index as usize)
+                            .take((ending_index - starting_index + 1) as usize)
+                            .map(|e| Some(e.to_vec()))
                             .collect(),
                     ))
                 } else {
@@ -519,7 +1159,7 @@ impl Soul {
         mut index: i32,
         now: u64,
     ) -> Result<Option<Vec<u8>>, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::List(list)) => {
                 let list_len = list.len() as i32;
 
@@ -531,7 +1171,7 @@ impl Soul {
                     return Ok(None);
                 }
 
-                Ok(list.get(index as usize).cloned())
+                Ok(list.get(index as usize).map(<[u8]>::to_vec))
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::LINDEX)),
             None => Ok(None),
@@ -545,6 +1185,8 @@ impl Soul {
         element: Vec<u8>,
         now: u64,
     ) -> Result<(), Sacrilege> {
+        let limits = self.4;
+
         match self.get_mut_valid_value(&key, now) {
             Some(Value::List(list)) => {
                 let list_len = list.len() as i32;
@@ -557,7 +1199,8 @@ impl Soul {
                     return Err(Sacrilege::IncorrectUsage(Command::LSET));
                 }
 
-                list[index as usize] = element;
+                list.set(index as usize, element, &limits);
+                self.2.notify(notify::LIST, b"lset", &key);
 
                 Ok(())
             }
@@ -581,7 +1224,7 @@ impl Soul {
                     let mut idx: i32 = list.len() as i32 - 1;
 
                     while idx >= 0 && count < 0 {
-                        if list[idx as usize] == element {
+                        if list.get(idx as usize) == Some(element.as_slice()) {
                             list.remove(idx as usize);
                             count += 1;
                         }
@@ -593,7 +1236,7 @@ impl Soul {
                     let mut idx = 0;
 
                     while idx < list_len && count > 0 {
-                        if list[idx] == element {
+                        if list.get(idx) == Some(element.as_slice()) {
                             list.remove(idx);
                             count -= 1;
                             list_len -= 1;
@@ -604,10 +1247,16 @@ impl Soul {
                         idx += 1;
                     }
                 } else {
-                    list.retain(|existing_element| *existing_element != element);
+                    list.retain(|existing_element| existing_element != element.as_slice());
                 }
 
-                Ok(initial_len - list.len())
+                let removed = initial_len - list.len();
+
+                if removed > 0 {
+                    self.2.notify(notify::LIST, b"lrem", &key);
+                }
+
+                Ok(removed)
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::LREM)),
             None => Ok(0),
@@ -622,11 +1271,15 @@ impl Soul {
                 if let Some(expiry) = existing_expiry
                     && *expiry < now
                 {
-                    occupied.remove();
+                    let (key, _) = occupied.remove_entry();
+                    self.3.forget(&key);
+                    self.reindex(&key);
+                    self.2.notify(notify::EXPIRED, b"expired", &key);
                     return 0;
                 }
 
                 *existing_expiry = Some(expiry);
+                self.2.notify(notify::GENERIC, b"expire", occupied.key());
                 1
             }
             Entry::Vacant(_) => 0,
@@ -639,18 +1292,24 @@ impl Soul {
                 let (_, existing_expiry) = occupied.get_mut();
 
                 if let Some(expiry) = existing_expiry {
-                    let expiry = UNIX_EPOCH + std::time::Duration::from_secs(*expiry);
+                    let expiry = UNIX_EPOCH + std::time::Duration::from_millis(*expiry);
 
                     if expiry < now {
-                        occupied.remove();
+                        let (key, _) = occupied.remove_entry();
+                        self.3.forget(&key);
+                        self.reindex(&key);
+                        self.2.notify(notify::EXPIRED, b"expired", &key);
                         -2
                     } else {
                         let Ok(duration) = expiry.duration_since(now) else {
-                            occupied.remove();
+                            let (key, _) = occupied.remove_entry();
+                            self.3.forget(&key);
+                            self.reindex(&key);
+                            self.2.notify(notify::EXPIRED, b"expired", &key);
                             return -2;
                         };
 
-                        duration.as_secs() as i64
+                        ((duration.as_millis() + 500) / 1000) as i64
                     }
                 } else {
                     -1
@@ -662,7 +1321,9 @@ impl Soul {
 
     pub fn mset(&mut self, mut terms_iter: IntoIter<Vec<u8>>) {
         while let (Some(key), Some(value)) = (terms_iter.next(), terms_iter.next()) {
-            self.0.insert(key, (Value::String(value), None));
+            self.insert(key.clone(), (Value::String(value), None));
+            self.reindex(&key);
+            self.2.notify(notify::STRING, b"set", &key);
         }
     }
 
@@ -674,7 +1335,7 @@ impl Soul {
         let mut result = Vec::with_capacity(terms_iter.len());
 
         for key in terms_iter {
-            match self.get_valid_value(&key, now) {
+            match self.get_readable_value(&key, now) {
                 Some(Value::String(value)) => {
                     result.push(Some(value.clone()));
                 }
@@ -691,12 +1352,14 @@ impl Soul {
         values: Vec<Vec<u8>>,
         now: u64,
     ) -> Result<usize, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let limits = self.4;
+
+        let result = match self.get_mut_valid_value(&key, now) {
             Some(Value::Set(set)) => {
                 let mut count = 0;
 
                 for value in values {
-                    if set.insert(value) {
+                    if set.insert(value, &limits) {
                         count += 1;
                     }
                 }
@@ -705,20 +1368,26 @@ impl Soul {
             }
             Some(_) => Err(Sacrilege::IncorrectUsage(Command::SADD)),
             None => {
-                let mut set = HashSet::new();
+                let mut set = Set::default();
                 let mut count = 0;
 
                 for value in values {
-                    if set.insert(value) {
+                    if set.insert(value, &limits) {
                         count += 1;
                     }
                 }
 
-                self.0.insert(key, (Value::Set(set), None));
+                self.insert(key.clone(), (Value::Set(set), None));
 
                 Ok(count)
             }
+        };
+
+        if let Ok(1..) = result {
+            self.2.notify(notify::SET, b"sadd", &key);
         }
+
+        result
     }
 
     pub fn srem(
@@ -727,7 +1396,7 @@ impl Soul {
         values: Vec<Vec<u8>>,
         now: u64,
     ) -> Result<usize, Sacrilege> {
-        match self.get_mut_valid_value(&key, now) {
+        let (count, is_empty) = match self.get_mut_valid_value(&key, now) {
             Some(Value::Set(set)) => {
                 let mut count = 0;
 
@@ -737,15 +1406,23 @@ impl Soul {
                     }
                 }
 
-                if set.is_empty() {
-                    self.0.remove(&key);
-                }
-
-                Ok(count)
+                (count, set.is_empty())
             }
-            Some(_) => Err(Sacrilege::IncorrectUsage(Command::SREM)),
-            None => Ok(0),
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::SREM)),
+            None => return Ok(0),
+        };
+
+        if count > 0 {
+            self.2.notify(notify::SET, b"srem", &key);
+        }
+
+        if is_empty {
+            self.0.remove(&key);
+            self.3.forget(&key);
+            self.2.notify(notify::GENERIC, b"del", &key);
         }
+
+        Ok(count)
     }
 
     pub fn sismember(
@@ -754,7 +1431,7 @@ impl Soul {
         value: Vec<u8>,
         now: u64,
     ) -> Result<usize, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::Set(set)) => {
                 if set.contains(&value) {
                     Ok(1)
@@ -772,13 +1449,13 @@ impl Soul {
         key: Vec<u8>,
         now: u64,
     ) -> Result<Option<Vec<Option<Vec<u8>>>>, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::Hash(map)) => {
                 let mut result = Vec::with_capacity(map.len() * 2);
 
-                for (field, value) in map {
-                    result.push(Some(field.clone()));
-                    result.push(Some(value.clone()));
+                for (field, value) in map.iter() {
+                    result.push(Some(field.to_vec()));
+                    result.push(Some(value.to_vec()));
                 }
 
                 Ok(Some(result))
@@ -793,12 +1470,12 @@ impl Soul {
         key: Vec<u8>,
         now: u64,
     ) -> Result<Option<Vec<Option<Vec<u8>>>>, Sacrilege> {
-        match self.get_valid_value(&key, now) {
+        match self.get_readable_value(&key, now) {
             Some(Value::Set(set)) => {
                 let mut result = Vec::with_capacity(set.len());
 
-                for value in set {
-                    result.push(Some(value.clone()));
+                for value in set.iter() {
+                    result.push(Some(value.into_owned()));
                 }
 
                 Ok(Some(result))
@@ -808,7 +1485,1061 @@ impl Soul {
         }
     }
 
-    fn get_valid_value(&mut self, key: &Vec<u8>, now: u64) -> Option<&Value> {
+    pub fn sort(
+        &mut self,
+        key: Vec<u8>,
+        options: &SortOptions,
+        now: u64,
+    ) -> Result<Vec<Option<Vec<u8>>>, Sacrilege> {
+        let mut elements: Vec<Vec<u8>> = match self.get_readable_value(&key, now) {
+            Some(Value::List(list)) => list.iter().map(<[u8]>::to_vec).collect(),
+            Some(Value::Set(set)) => set.iter().map(Cow::into_owned).collect(),
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::SORT)),
+            None => Vec::new(),
+        };
+
+        // A BY pattern without a `*` means "don't sort", handy with GET.
+        let sorting = options.by.as_ref().is_none_or(|by| by.contains(&b'*'));
+
+        if sorting {
+            let mut sortables = Vec::with_capacity(elements.len());
+
+            for element in elements {
+                let by = match &options.by {
+                    Some(pattern) => self.lookup_pattern(pattern, &element, now),
+                    None => Some(element.clone()),
+                };
+
+                let weight = match (&by, options.alpha) {
+                    (Some(by), false) => match bytes_to_f64(by) {
+                        Ok(weight) if !weight.is_nan() => weight,
+                        _ => return Err(Sacrilege::InvalidScore),
+                    },
+                    _ => 0.0,
+                };
+
+                sortables.push((element, by, weight));
+            }
+
+            sortables.sort_by(|(a, a_by, a_weight), (b, b_by, b_weight)| {
+                let ordering = if options.alpha {
+                    a_by.cmp(b_by)
+                } else {
+                    a_weight.total_cmp(b_weight)
+                }
+                .then_with(|| a.cmp(b));
+
+                if options.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+
+            elements = sortables
+                .into_iter()
+                .map(|(element, _, _)| element)
+                .collect();
+        }
+
+        if let Some((offset, count)) = options.limit {
+            let offset = offset.max(0) as usize;
+            let count = if count < 0 {
+                elements.len()
+            } else {
+                count as usize
+            };
+
+            elements = elements.into_iter().skip(offset).take(count).collect();
+        }
+
+        if options.get.is_empty() {
+            return Ok(elements.into_iter().map(Some).collect());
+        }
+
+        let mut result = Vec::with_capacity(elements.len() * options.get.len());
+
+        for element in elements {
+            for pattern in &options.get {
+                result.push(self.lookup_pattern(pattern, &element, now));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Missing GET values are stored as empty strings.
+    pub fn sort_store(
+        &mut self,
+        key: Vec<u8>,
+        options: &SortOptions,
+        destination: Vec<u8>,
+        now: u64,
+    ) -> Result<usize, Sacrilege> {
+        let sorted: Vec<Vec<u8>> = self
+            .sort(key, options, now)?
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect();
+        let sorted_len = sorted.len();
+
+        if sorted.is_empty() {
+            if self.remove_valid_value(&destination, now).is_some() {
+                self.2.notify(notify::GENERIC, b"del", &destination);
+            }
+        } else {
+            let list = List::from_elements(sorted, &self.4);
+            self.insert(destination.clone(), (Value::List(list), None));
+            self.reindex(&destination);
+            self.2.notify(notify::LIST, b"sortstore", &destination);
+        }
+
+        Ok(sorted_len)
+    }
+
+    // `#` is the element itself. Otherwise the first `*` is replaced by the
+    // element to get a key, and a trailing `->field` reads a hash field of it.
+    fn lookup_pattern(&mut self, pattern: &[u8], element: &[u8], now: u64) -> Option<Vec<u8>> {
+        if pattern == b"#" {
+            return Some(element.to_vec());
+        }
+
+        let star = pattern.iter().position(|&b| b == b'*')?;
+
+        let (key_pattern, field) = match pattern[star + 1..]
+            .windows(2)
+            .position(|window| window == b"->")
+        {
+            Some(arrow) if star + 1 + arrow + 2 < pattern.len() => (
+                &pattern[..star + 1 + arrow],
+                Some(&pattern[star + 1 + arrow + 2..]),
+            ),
+            _ => (pattern, None),
+        };
+
+        let key = [&key_pattern[..star], element, &key_pattern[star + 1..]].concat();
+
+        match (self.get_valid_value(&key, now)?, field) {
+            (Value::String(value), None) => Some(value.clone()),
+            (Value::Hash(hash), Some(field)) => hash.get(field).map(<[u8]>::to_vec),
+            _ => None,
+        }
+    }
+
+    /// MATCH and TYPE are applied after a page is cut, so a page may come back
+    /// empty even though the scan isn't over.
+    pub fn scan(&self, cursor: u64, options: &ScanOptions, now: u64) -> (u64, Vec<Vec<u8>>) {
+        let (cursor, keys) = self.0.scan(cursor, options.count);
+
+        let keys = keys
+            .into_iter()
+            .filter(|(_, (_, expiry))| expiry.is_none_or(|expiry| expiry >= now))
+            .filter(|(key, _)| {
+                options
+                    .pattern
+                    .as_ref()
+                    .is_none_or(|pattern| glob_match(pattern, key))
+            })
+            .filter(|(_, (value, _))| {
+                options
+                    .type_name
+                    .as_ref()
+                    .is_none_or(|type_name| type_name.eq_ignore_ascii_case(value.type_name()))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        (cursor, keys)
+    }
+
+    pub fn hscan(
+        &mut self,
+        key: Vec<u8>,
+        cursor: u64,
+        options: &ScanOptions,
+        now: u64,
+    ) -> Result<(u64, Vec<Vec<u8>>), Sacrilege> {
+        let hash = match self.get_valid_value(&key, now) {
+            Some(Value::Hash(hash)) => hash,
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::HSCAN)),
+            None => return Ok((0, Vec::new())),
+        };
+
+        // Small encodings come back whole.
+        let (cursor, fields) = match hash {
+            Hash::Table(table) => {
+                let (cursor, fields) = table.scan(cursor, options.count);
+                let fields: Vec<(&[u8], &[u8])> = fields
+                    .into_iter()
+                    .map(|(field, value)| (field.as_slice(), value.as_slice()))
+                    .collect();
+
+                (cursor, fields)
+            }
+            Hash::Listpack(_) => (0, hash.iter().collect()),
+        };
+
+        let mut result = Vec::with_capacity(fields.len() * 2);
+
+        for (field, value) in fields {
+            if options
+                .pattern
+                .as_ref()
+                .is_some_and(|pattern| !glob_match(pattern, field))
+            {
+                continue;
+            }
+
+            result.push(field.to_vec());
+
+            if !options.no_values {
+                result.push(value.to_vec());
+            }
+        }
+
+        Ok((cursor, result))
+    }
+
+    pub fn sscan(
+        &mut self,
+        key: Vec<u8>,
+        cursor: u64,
+        options: &ScanOptions,
+        now: u64,
+    ) -> Result<(u64, Vec<Vec<u8>>), Sacrilege> {
+        let set = match self.get_valid_value(&key, now) {
+            Some(Value::Set(set)) => set,
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::SSCAN)),
+            None => return Ok((0, Vec::new())),
+        };
+
+        let (cursor, members): (u64, Vec<Cow<[u8]>>) = match set {
+            Set::Table(table) => {
+                let (cursor, members) = table.scan(cursor, options.count);
+                let members = members
+                    .into_iter()
+                    .map(|(member, _)| Cow::Borrowed(member.as_slice()))
+                    .collect();
+
+                (cursor, members)
+            }
+            Set::Intset(_) | Set::Listpack(_) => (0, set.iter().collect()),
+        };
+
+        let members = members
+            .into_iter()
+            .filter(|member| {
+                options
+                    .pattern
+                    .as_ref()
+                    .is_none_or(|pattern| glob_match(pattern, member))
+            })
+            .map(|member| member.to_vec())
+            .collect();
+
+        Ok((cursor, members))
+    }
+
+    pub fn keys(&self, pattern: &[u8], now: u64) -> Vec<Vec<u8>> {
+        let mut keys: Vec<Vec<u8>> = self
+            .0
+            .iter()
+            .filter(|(key, (_, expiry))| {
+                expiry.is_none_or(|expiry| expiry >= now) && glob_match(pattern, key)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        keys.sort();
+        keys
+    }
+
+    pub fn cms_initbydim(
+        &mut self,
+        key: Vec<u8>,
+        width: usize,
+        depth: usize,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        if self.get_valid_value(&key, now).is_some() {
+            return Err(Sacrilege::KeyAlreadyExists(Command::CMSINITBYDIM));
+        }
+
+        self.insert(
+            key.clone(),
+            (
+                Value::CountMinSketch(CountMinSketch::new(width, depth)),
+                None,
+            ),
+        );
+        self.2.notify(notify::MODULE, b"cms.initbydim", &key);
+
+        Ok(())
+    }
+
+    pub fn cms_incrby(
+        &mut self,
+        key: Vec<u8>,
+        increments: Vec<(Vec<u8>, u64)>,
+        now: u64,
+    ) -> Result<Vec<u64>, Sacrilege> {
+        let result = match self.get_mut_valid_value(&key, now) {
+            Some(Value::CountMinSketch(sketch)) => Ok(increments
+                .into_iter()
+                .map(|(item, increment)| sketch.incr_by(&item, increment))
+                .collect()),
+            Some(_) => Err(Sacrilege::IncorrectUsage(Command::CMSINCRBY)),
+            None => Err(Sacrilege::KeyDoesNotExist(Command::CMSINCRBY)),
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::MODULE, b"cms.incrby", &key);
+        }
+
+        result
+    }
+
+    pub fn cms_query(
+        &mut self,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        now: u64,
+    ) -> Result<Vec<u64>, Sacrilege> {
+        match self.get_readable_value(&key, now) {
+            Some(Value::CountMinSketch(sketch)) => {
+                Ok(items.iter().map(|item| sketch.query(item)).collect())
+            }
+            Some(_) => Err(Sacrilege::IncorrectUsage(Command::CMSQUERY)),
+            None => Err(Sacrilege::KeyDoesNotExist(Command::CMSQUERY)),
+        }
+    }
+
+    pub fn cms_merge(
+        &mut self,
+        destination: Vec<u8>,
+        sources: Vec<Vec<u8>>,
+        weights: Vec<u64>,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        let (width, depth) = match self.get_valid_value(&destination, now) {
+            Some(Value::CountMinSketch(sketch)) => (sketch.width(), sketch.depth()),
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::CMSMERGE)),
+            None => return Err(Sacrilege::KeyDoesNotExist(Command::CMSMERGE)),
+        };
+
+        for source in &sources {
+            match self.get_valid_value(source, now) {
+                Some(Value::CountMinSketch(sketch)) => {
+                    if sketch.width() != width || sketch.depth() != depth {
+                        return Err(Sacrilege::DimensionMismatch);
+                    }
+                }
+                Some(_) => return Err(Sacrilege::IncorrectUsage(Command::CMSMERGE)),
+                None => return Err(Sacrilege::KeyDoesNotExist(Command::CMSMERGE)),
+            }
+        }
+
+        let weighted_sources: Vec<(&CountMinSketch, u64)> = sources
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, source)| match self.0.get(source) {
+                Some((Value::CountMinSketch(sketch), _)) => {
+                    Some((sketch, weights.get(idx).copied().unwrap_or(1)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Merged apart, the destination can be one of its own sources.
+        let mut merged = CountMinSketch::new(width, depth);
+        merged.merge(&weighted_sources);
+
+        if let Some((Value::CountMinSketch(sketch), _)) = self.0.get_mut(&destination) {
+            *sketch = merged;
+        }
+
+        self.2.notify(notify::MODULE, b"cms.merge", &destination);
+
+        Ok(())
+    }
+
+    pub fn topk_reserve(
+        &mut self,
+        key: Vec<u8>,
+        k: usize,
+        width: usize,
+        depth: usize,
+        decay: f64,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        if self.get_valid_value(&key, now).is_some() {
+            return Err(Sacrilege::KeyAlreadyExists(Command::TOPKRESERVE));
+        }
+
+        self.insert(
+            key.clone(),
+            (Value::TopK(TopK::new(k, width, depth, decay)), None),
+        );
+        self.2.notify(notify::MODULE, b"topk.reserve", &key);
+
+        Ok(())
+    }
+
+    pub fn topk_add(
+        &mut self,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        now: u64,
+    ) -> Result<Vec<Option<Vec<u8>>>, Sacrilege> {
+        let result = match self.get_mut_valid_value(&key, now) {
+            Some(Value::TopK(top_k)) => Ok(items.into_iter().map(|item| top_k.add(item)).collect()),
+            Some(_) => Err(Sacrilege::IncorrectUsage(Command::TOPKADD)),
+            None => Err(Sacrilege::KeyDoesNotExist(Command::TOPKADD)),
+        };
+
+        if result.is_ok() {
+            self.2.notify(notify::MODULE, b"topk.add", &key);
+        }
+
+        result
+    }
+
+    pub fn topk_count(
+        &mut self,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+        now: u64,
+    ) -> Result<Vec<u64>, Sacrilege> {
+        match self.get_readable_value(&key, now) {
+            Some(Value::TopK(top_k)) => Ok(items.iter().map(|item| top_k.count(item)).collect()),
+            Some(_) => Err(Sacrilege::IncorrectUsage(Command::TOPKCOUNT)),
+            None => Err(Sacrilege::KeyDoesNotExist(Command::TOPKCOUNT)),
+        }
+    }
+
+    pub fn topk_list(&mut self, key: Vec<u8>, now: u64) -> Result<Vec<(Vec<u8>, u64)>, Sacrilege> {
+        match self.get_readable_value(&key, now) {
+            Some(Value::TopK(top_k)) => Ok(top_k.list()),
+            Some(_) => Err(Sacrilege::IncorrectUsage(Command::TOPKLIST)),
+            None => Err(Sacrilege::KeyDoesNotExist(Command::TOPKLIST)),
+        }
+    }
+
+    pub fn ts_create(
+        &mut self,
+        key: Vec<u8>,
+        options: TimeSeriesOptions,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        if self.get_valid_value(&key, now).is_some() {
+            return Err(Sacrilege::KeyAlreadyExists(Command::TSCREATE));
+        }
+
+        self.insert(
+            key.clone(),
+            (Value::TimeSeries(TimeSeries::new(options)), None),
+        );
+        self.2.notify(notify::MODULE, b"ts.create", &key);
+
+        Ok(())
+    }
+
+    pub fn ts_add(
+        &mut self,
+        key: Vec<u8>,
+        timestamp: u64,
+        value: f64,
+        options: TimeSeriesOptions,
+        on_duplicate: Option<DuplicatePolicy>,
+        now: u64,
+    ) -> Result<u64, Sacrilege> {
+        if self.get_valid_value(&key, now).is_none() {
+            self.insert(
+                key.clone(),
+                (Value::TimeSeries(TimeSeries::new(options)), None),
+            );
+        }
+
+        let Some((Value::TimeSeries(series), _)) = self.0.get_mut(&key) else {
+            return Err(Sacrilege::IncorrectUsage(Command::TSADD));
+        };
+
+        let timestamp =
+            series
+                .add(timestamp, value, on_duplicate)
+                .map_err(|error| match error {
+                    SampleError::Duplicate => Sacrilege::DuplicateSample,
+                    SampleError::TooOld => Sacrilege::InvalidTimestamp(Command::TSADD),
+                })?;
+
+        self.ts_compact(&key, timestamp);
+        self.2.notify(notify::MODULE, b"ts.add", &key);
+
+        Ok(timestamp)
+    }
+
+    pub fn ts_madd(
+        &mut self,
+        samples: Vec<(Vec<u8>, u64, f64)>,
+        now: u64,
+    ) -> Vec<Result<u64, Sacrilege>> {
+        samples
+            .into_iter()
+            .map(|(key, timestamp, value)| {
+                let timestamp =
+                    match self.get_mut_valid_value(&key, now) {
+                        Some(Value::TimeSeries(series)) => series
+                            .add(timestamp, value, None)
+                            .map_err(|error| match error {
+                                SampleError::Duplicate => Sacrilege::DuplicateSample,
+                                SampleError::TooOld => Sacrilege::InvalidTimestamp(Command::TSMADD),
+                            })?,
+                        Some(_) => return Err(Sacrilege::IncorrectUsage(Command::TSMADD)),
+                        None => return Err(Sacrilege::KeyDoesNotExist(Command::TSMADD)),
+                    };
+
+                self.ts_compact(&key, timestamp);
+                self.2.notify(notify::MODULE, b"ts.add", &key);
+
+                Ok(timestamp)
+            })
+            .collect()
+    }
+
+    pub fn ts_incrby(
+        &mut self,
+        key: Vec<u8>,
+        increment: f64,
+        timestamp: u64,
+        options: TimeSeriesOptions,
+        now: u64,
+    ) -> Result<u64, Sacrilege> {
+        if self.get_valid_value(&key, now).is_none() {
+            self.insert(
+                key.clone(),
+                (Value::TimeSeries(TimeSeries::new(options)), None),
+            );
+        }
+
+        let Some((Value::TimeSeries(series), _)) = self.0.get_mut(&key) else {
+            return Err(Sacrilege::IncorrectUsage(Command::TSINCRBY));
+        };
+
+        let value = match series.last() {
+            Some((last_timestamp, _)) if timestamp < last_timestamp => {
+                return Err(Sacrilege::InvalidTimestamp(Command::TSINCRBY));
+            }
+            Some((_, last_value)) => last_value + increment,
+            None => increment,
+        };
+
+        series.upsert(timestamp, value);
+
+        self.ts_compact(&key, timestamp);
+        self.2.notify(notify::MODULE, b"ts.incrby", &key);
+
+        Ok(timestamp)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn ts_range(
+        &mut self,
+        key: Vec<u8>,
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+        count: Option<usize>,
+        reverse: bool,
+        now: u64,
+    ) -> Result<Vec<(u64, f64)>, Sacrilege> {
+        let command = if reverse {
+            Command::TSREVRANGE
+        } else {
+            Command::TSRANGE
+        };
+
+        match self.get_readable_value(&key, now) {
+            Some(Value::TimeSeries(series)) => {
+                let mut samples = series.range(from, to, aggregation);
+
+                if reverse {
+                    samples.reverse();
+                }
+
+                if let Some(count) = count {
+                    samples.truncate(count);
+                }
+
+                Ok(samples)
+            }
+            Some(_) => Err(Sacrilege::IncorrectUsage(command)),
+            None => Err(Sacrilege::KeyDoesNotExist(command)),
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn ts_mrange(
+        &self,
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+        count: Option<usize>,
+        filters: Vec<LabelFilter>,
+        now: u64,
+    ) -> Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>, Vec<(u64, f64)>)> {
+        let mut result: Vec<_> = self
+            .0
+            .iter()
+            .filter_map(|(key, (value, expiry))| match (value, expiry) {
+                (_, Some(expiry)) if *expiry < now => None,
+                (Value::TimeSeries(series), _) if series.matches(&filters) => {
+                    let mut samples = series.range(from, to, aggregation);
+
+                    if let Some(count) = count {
+                        samples.truncate(count);
+                    }
+
+                    Some((key.clone(), series.labels().to_vec(), samples))
+                }
+                _ => None,
+            })
+            .collect();
+
+        result.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+        result
+    }
+
+    pub fn ts_createrule(
+        &mut self,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        aggregation: Aggregation,
+        bucket_duration: u64,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        if source == destination {
+            return Err(Sacrilege::InvalidArgument(Command::TSCREATERULE));
+        }
+
+        match self.get_valid_value(&destination, now) {
+            Some(Value::TimeSeries(_)) => {}
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::TSCREATERULE)),
+            None => return Err(Sacrilege::KeyDoesNotExist(Command::TSCREATERULE)),
+        }
+
+        match self.get_valid_value(&source, now) {
+            Some(Value::TimeSeries(_)) => {}
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::TSCREATERULE)),
+            None => return Err(Sacrilege::KeyDoesNotExist(Command::TSCREATERULE)),
+        }
+
+        // Two sources feeding one destination would overwrite each other's buckets.
+        if self.ts_compacted_from(&destination).is_some() {
+            return Err(Sacrilege::InvalidArgument(Command::TSCREATERULE));
+        }
+
+        let mut ancestor = source.clone();
+
+        while let Some(parent) = self.ts_compacted_from(&ancestor) {
+            if *parent == destination {
+                return Err(Sacrilege::InvalidArgument(Command::TSCREATERULE));
+            }
+
+            ancestor = parent.clone();
+        }
+
+        if let Some((Value::TimeSeries(series), _)) = self.0.get_mut(&destination) {
+            series.compacted_from = Some(source.clone());
+        }
+
+        if let Some((Value::TimeSeries(series), _)) = self.0.get_mut(&source) {
+            series.rules.push(CompactionRule {
+                destination,
+                aggregation,
+                bucket_duration,
+            });
+        }
+
+        self.2.notify(notify::MODULE, b"ts.createrule", &source);
+
+        Ok(())
+    }
+
+    pub fn ts_deleterule(
+        &mut self,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        match self.get_mut_valid_value(&source, now) {
+            Some(Value::TimeSeries(series)) => {
+                let rules_len = series.rules.len();
+                series.rules.retain(|rule| rule.destination != destination);
+
+                if series.rules.len() == rules_len {
+                    return Err(Sacrilege::InvalidArgument(Command::TSDELETERULE));
+                }
+
+                if let Some((Value::TimeSeries(series), _)) = self.0.get_mut(&destination)
+                    && series.compacted_from.as_ref() == Some(&source)
+                {
+                    series.compacted_from = None;
+                }
+
+                self.2.notify(notify::MODULE, b"ts.deleterule", &source);
+
+                Ok(())
+            }
+            Some(_) => Err(Sacrilege::IncorrectUsage(Command::TSDELETERULE)),
+            None => Err(Sacrilege::KeyDoesNotExist(Command::TSDELETERULE)),
+        }
+    }
+
+    // None once either end of the rule was deleted or renamed.
+    fn ts_compacted_from(&self, key: &[u8]) -> Option<&Vec<u8>> {
+        let Some((Value::TimeSeries(series), _)) = self.0.get(key) else {
+            return None;
+        };
+
+        let source = series.compacted_from.as_ref()?;
+
+        match self.0.get(source) {
+            Some((Value::TimeSeries(parent), _))
+                if parent.rules.iter().any(|rule| rule.destination == key) =>
+            {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+
+    fn ts_compact(&mut self, key: &[u8], timestamp: u64) {
+        let mut pending = vec![(key.to_vec(), timestamp)];
+
+        while let Some((source, timestamp)) = pending.pop() {
+            let compactions = match self.0.get(&source) {
+                Some((Value::TimeSeries(series), _)) => series.compactions(timestamp),
+                _ => continue,
+            };
+
+            for (destination, bucket_start, value) in compactions {
+                if let Some((Value::TimeSeries(series), _)) = self.0.get_mut(&destination)
+                    && series.compacted_from.as_ref() == Some(&source)
+                {
+                    series.upsert(bucket_start, value);
+                    self.2.notify(notify::MODULE, b"ts.add", &destination);
+                    pending.push((destination, bucket_start));
+                }
+            }
+        }
+    }
+
+    pub fn cl_throttle(
+        &mut self,
+        key: Vec<u8>,
+        max_burst: u64,
+        count: u64,
+        period: u64,
+        quantity: u64,
+        now: u64,
+    ) -> Result<Throttle, Sacrilege> {
+        let mut gcra = match self.get_valid_value(&key, now) {
+            Some(Value::Gcra(gcra)) => gcra.clone(),
+            Some(_) => return Err(Sacrilege::IncorrectUsage(Command::CLTHROTTLE)),
+            None => Gcra::new(),
+        };
+
+        let throttle = gcra.throttle(max_burst, count, period, quantity, now);
+
+        // A limited request leaves the state as it was, so there's nothing to store.
+        if !throttle.is_limited {
+            let expiry = gcra.expires_at();
+
+            self.insert(key.clone(), (Value::Gcra(gcra), Some(expiry)));
+            self.2.notify(notify::MODULE, b"cl.throttle", &key);
+        }
+
+        Ok(throttle)
+    }
+
+    pub fn relic<T: Relic>(&mut self, key: &[u8], now: u64) -> Result<Option<T>, Sacrilege> {
+        match self.get_readable_value(key, now) {
+            Some(Value::Relic(reliquary)) => match reliquary.open() {
+                Some(relic) => Ok(Some(relic)),
+                None => Err(Sacrilege::Rite(WRONGTYPE.to_vec())),
+            },
+            Some(_) => Err(Sacrilege::Rite(WRONGTYPE.to_vec())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_relic<T: Relic>(&mut self, key: Vec<u8>, relic: &T, expiry: Option<u64>) {
+        self.insert(key.clone(), (Value::Relic(Reliquary::seal(relic)), expiry));
+        self.reindex(&key);
+
+        self.2.notify(notify::MODULE, T::TYPE_NAME, &key);
+    }
+
+    pub fn ft_create(
+        &mut self,
+        index: Vec<u8>,
+        prefixes: Vec<Vec<u8>>,
+        schema: Vec<FieldSchema>,
+    ) -> Result<(), Sacrilege> {
+        if self.1.contains_key(&index) {
+            return Err(Sacrilege::KeyAlreadyExists(Command::FTCREATE));
+        }
+
+        let mut search_index = SearchIndex::new(prefixes, schema);
+
+        for (key, (value, _)) in &self.0 {
+            if let Value::Hash(hash) = value
+                && search_index.covers(key)
+            {
+                search_index.add(key, hash);
+            }
+        }
+
+        self.1.insert(index, search_index);
+
+        Ok(())
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn ft_search(
+        &mut self,
+        index: Vec<u8>,
+        query: Query,
+        options: SearchOptions,
+        now: u64,
+    ) -> Result<(usize, Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>), Sacrilege> {
+        let Some(search_index) = self.1.get(&index) else {
+            return Err(Sacrilege::KeyDoesNotExist(Command::FTSEARCH));
+        };
+
+        let Some(keys) = search_index.search(&query) else {
+            return Err(Sacrilege::InvalidArgument(Command::FTSEARCH));
+        };
+
+        // The index only hears of an expired hash once something touches it.
+        let mut hits = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            if let Some(Value::Hash(_)) = self.get_valid_value(&key, now) {
+                hits.push(key);
+            }
+        }
+
+        // With a KNN clause the query above only pre-filters.
+        let mut scores = HashMap::new();
+
+        if let Some(knn) = &options.knn {
+            let Some(search_index) = self.1.get(&index) else {
+                return Err(Sacrilege::KeyDoesNotExist(Command::FTSEARCH));
+            };
+
+            let filter: HashSet<Vec<u8>> = hits.into_iter().collect();
+
+            let Some(nearest) = search_index.knn(
+                &knn.field,
+                &knn.vector,
+                knn.k,
+                knn.ef_runtime,
+                Some(&filter),
+            ) else {
+                return Err(Sacrilege::InvalidArgument(Command::FTSEARCH));
+            };
+
+            hits = nearest.iter().map(|(key, _)| key.clone()).collect();
+            scores = nearest
+                .into_iter()
+                .map(|(key, distance)| (key, distance.to_string().into_bytes()))
+                .collect();
+        }
+
+        let score_field = options.knn.as_ref().map(|knn| &knn.score_field);
+
+        let field_of = |soul: &Database, key: &Vec<u8>, field: &Vec<u8>| {
+            if score_field == Some(field) {
+                return scores.get(key).cloned();
+            }
+
+            match soul.0.get(key) {
+                Some((Value::Hash(hash), _)) => hash.get(field).map(<[u8]>::to_vec),
+                _ => None,
+            }
+        };
+
+        match &options.sort_by {
+            Some((field, ascending)) => {
+                let mut sortable: Vec<_> = hits
+                    .into_iter()
+                    .map(|key| (field_of(self, &key, field), key))
+                    .collect();
+
+                sortable.sort_by(|(a, a_key), (b, b_key)| {
+                    let ordering = match (a, b) {
+                        (Some(a), Some(b)) => match (bytes_to_f64(a), bytes_to_f64(b)) {
+                            (Ok(a), Ok(b)) => a.total_cmp(&b),
+                            _ => a.cmp(b),
+                        },
+                        // Documents missing the field always go last.
+                        (Some(_), None) => return std::cmp::Ordering::Less,
+                        (None, Some(_)) => return std::cmp::Ordering::Greater,
+                        (None, None) => std::cmp::Ordering::Equal,
+                    };
+
+                    let ordering = if *ascending {
+                        ordering
+                    } else {
+                        ordering.reverse()
+                    };
+                    ordering.then_with(|| a_key.cmp(b_key))
+                });
+
+                hits = sortable.into_iter().map(|(_, key)| key).collect();
+            }
+            // KNN hits are already ordered nearest first.
+            None if options.knn.is_some() => {}
+            None => hits.sort(),
+        }
+
+        let total = hits.len();
+        let (offset, count) = options.limit;
+
+        let documents = hits
+            .into_iter()
+            .skip(offset)
+            .take(count)
+            .map(|key| {
+                let fields = match (&options.return_fields, self.0.get(&key)) {
+                    (_, _) if options.no_content => Vec::new(),
+                    (Some(return_fields), Some((Value::Hash(_), _))) => return_fields
+                        .iter()
+                        .filter_map(|field| {
+                            field_of(self, &key, field).map(|value| (field.clone(), value))
+                        })
+                        .collect(),
+                    (None, Some((Value::Hash(hash), _))) => score_field
+                        .and_then(|field| Some((field.clone(), scores.get(&key)?.clone())))
+                        .into_iter()
+                        .chain(
+                            hash.iter()
+                                .map(|(field, value)| (field.to_vec(), value.to_vec())),
+                        )
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                (key, fields)
+            })
+            .collect();
+
+        Ok((total, documents))
+    }
+
+    pub fn ft_dropindex(
+        &mut self,
+        index: Vec<u8>,
+        delete_documents: bool,
+        now: u64,
+    ) -> Result<(), Sacrilege> {
+        let Some(search_index) = self.1.remove(&index) else {
+            return Err(Sacrilege::KeyDoesNotExist(Command::FTDROPINDEX));
+        };
+
+        if delete_documents {
+            for key in search_index.documents() {
+                if self.remove_valid_value(key, now).is_some() {
+                    self.2.notify(notify::GENERIC, b"del", key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn ft_list(&self) -> Vec<Vec<u8>> {
+        let mut indexes: Vec<Vec<u8>> = self.1.keys().cloned().collect();
+        indexes.sort();
+        indexes
+    }
+
+    /// The postings aren't part of the snapshot.
+    pub fn rebuild_indexes(&mut self) {
+        for search_index in self.1.values_mut() {
+            search_index.clear();
+
+            for (key, (value, _)) in &self.0 {
+                if let Value::Hash(hash) = value
+                    && search_index.covers(key)
+                {
+                    search_index.add(key, hash);
+                }
+            }
+        }
+    }
+
+    fn reindex(&mut self, key: &[u8]) {
+        if self.1.is_empty() {
+            return;
+        }
+
+        let hash = match self.0.get(key) {
+            Some((Value::Hash(hash), _)) => Some(hash),
+            _ => None,
+        };
+
+        for search_index in self.1.values_mut() {
+            if !search_index.covers(key) {
+                continue;
+            }
+
+            search_index.remove(key);
+
+            if let Some(hash) = hash {
+                search_index.add(key, hash);
+            }
+        }
+    }
+
+    // Every new key goes through here, so `new` events can't be missed.
+    fn insert(&mut self, key: Vec<u8>, entry: (Value, Option<u64>)) {
+        if !self.0.contains_key(&key) {
+            self.3.forget(&key);
+            self.2.notify(notify::NEW, b"new", &key);
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        self.3.touch(&key, now);
+        self.0.insert(key, entry);
+    }
+
+    // For commands that only read, a missing key is worth a `keymiss` event.
+    fn get_readable_value(&mut self, key: &[u8], now: u64) -> Option<&Value> {
+        if self.get_valid_value(key, now).is_none() {
+            self.2.notify(notify::KEY_MISS, b"keymiss", key);
+            return None;
+        }
+
+        self.0.get(key).map(|(value, _)| value)
+    }
+
+    // Counts as a use of the key, for OBJECT IDLETIME and FREQ.
+    fn get_valid_value(&mut self, key: &[u8], now: u64) -> Option<&Value> {
+        self.peek_valid_value(key, now)?;
+        self.3.touch(key, now);
+
+        self.0.get(key).map(|(value, _)| value)
+    }
+
+    // Drops `key` if it has expired, without counting as a use of it.
+    fn peek_valid_value(&mut self, key: &[u8], now: u64) -> Option<&Value> {
         let is_expired = match self.0.get(key) {
             Some((_, Some(expiry))) => *expiry < now,
             _ => false,
@@ -816,13 +2547,16 @@ impl Soul {
 
         if is_expired {
             self.0.remove(key);
-            return None;
+            self.3.forget(key);
+            self.reindex(key);
+            self.2.notify(notify::EXPIRED, b"expired", key);
+            None
         } else {
-            return self.0.get(key).map(|(value, _)| value);
+            self.0.get(key).map(|(value, _)| value)
         }
     }
 
-    fn get_mut_valid_value(&mut self, key: &Vec<u8>, now: u64) -> Option<&mut Value> {
+    fn get_mut_valid_value(&mut self, key: &[u8], now: u64) -> Option<&mut Value> {
         let is_expired = match self.0.get(key) {
             Some((_, Some(expiry))) => *expiry < now,
             _ => false,
@@ -830,16 +2564,25 @@ impl Soul {
 
         if is_expired {
             self.0.remove(key);
-            return None;
+            self.3.forget(key);
+            self.reindex(key);
+            self.2.notify(notify::EXPIRED, b"expired", key);
+            None
         } else {
-            return self.0.get_mut(key).map(|(value, _)| value);
+            self.3.touch(key, now);
+            self.0.get_mut(key).map(|(value, _)| value)
         }
     }
 
-    pub fn remove_valid_value(&mut self, key: &Vec<u8>, now: u64) -> Option<Value> {
-        match self.0.remove(key) {
+    pub fn remove_valid_value(&mut self, key: &[u8], now: u64) -> Option<Value> {
+        let removed = self.0.remove(key);
+        self.3.forget(key);
+        self.reindex(key);
+
+        match removed {
             Some((value, Some(expiry))) => {
                 if expiry < now {
+                    self.2.notify(notify::EXPIRED, b"expired", key);
                     None
                 } else {
                     Some(value)
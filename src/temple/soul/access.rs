@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+// The same knobs Redis ships with: new keys start at 5 so they aren't the
+// first to look cold, the counter climbs logarithmically and loses a point
+// for every minute nobody looks at the key.
+const INITIAL_FREQUENCY: u8 = 5;
+const LOG_FACTOR: f64 = 10.0;
+const DECAY_MILLIS: u64 = 60_000;
+
+struct Access {
+    last: u64,
+    frequency: u8,
+}
+
+impl Access {
+    fn decayed_frequency(&self, now: u64) -> u8 {
+        let periods = now.saturating_sub(self.last) / DECAY_MILLIS;
+        self.frequency
+            .saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+}
+
+// When every key was last read or written, and a rough LFU counter of how
+// often. It isn't part of the snapshot, keys loaded from disk count as fresh.
+#[derive(Default)]
+pub struct AccessLog(HashMap<Vec<u8>, Access>);
+
+impl AccessLog {
+    pub fn touch(&mut self, key: &[u8], now: u64) {
+        match self.0.get_mut(key) {
+            Some(access) => {
+                let mut frequency = access.decayed_frequency(now);
+
+                if frequency < u8::MAX {
+                    let base = frequency.saturating_sub(INITIAL_FREQUENCY) as f64;
+                    let roll = RandomState::new().hash_one(now) as f64 / u64::MAX as f64;
+
+                    if roll < 1.0 / (base * LOG_FACTOR + 1.0) {
+                        frequency += 1;
+                    }
+                }
+
+                access.last = now;
+                access.frequency = frequency;
+            }
+            None => {
+                self.0.insert(
+                    key.to_vec(),
+                    Access {
+                        last: now,
+                        frequency: INITIAL_FREQUENCY,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn forget(&mut self, key: &[u8]) {
+        self.0.remove(key);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Seconds since the key was last used.
+    pub fn idle(&self, key: &[u8], now: u64) -> u64 {
+        self.0
+            .get(key)
+            .map_or(0, |access| now.saturating_sub(access.last) / 1000)
+    }
+
+    pub fn frequency(&self, key: &[u8], now: u64) -> u8 {
+        self.0
+            .get(key)
+            .map_or(INITIAL_FREQUENCY, |access| access.decayed_frequency(now))
+    }
+}
@@ -0,0 +1,807 @@
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::dict::Dict;
+use super::memory::sampled;
+use crate::wish::util::bytes_to_i64;
+
+// A collection that outgrows these limits never goes back to a compact encoding.
+#[derive(Clone, Copy)]
+pub struct EncodingLimits {
+    pub hash_max_listpack_entries: usize,
+    pub hash_max_listpack_value: usize,
+    pub set_max_intset_entries: usize,
+    pub set_max_listpack_entries: usize,
+    pub set_max_listpack_value: usize,
+    // Positive counts entries, -1 to -5 cap the buffer at 4 to 64 KiB.
+    pub list_max_listpack_size: i64,
+}
+
+impl Default for EncodingLimits {
+    fn default() -> Self {
+        EncodingLimits {
+            hash_max_listpack_entries: 128,
+            hash_max_listpack_value: 64,
+            set_max_intset_entries: 512,
+            set_max_listpack_entries: 128,
+            set_max_listpack_value: 64,
+            list_max_listpack_size: -2,
+        }
+    }
+}
+
+impl EncodingLimits {
+    /// Returns false for unknown names and values out of range.
+    pub fn set(&mut self, name: &[u8], value: &[u8]) -> bool {
+        let Ok(value) = bytes_to_i64(value) else {
+            return false;
+        };
+
+        let name = name.to_ascii_lowercase();
+
+        if name == b"list-max-listpack-size" {
+            if value == 0 || value < -5 {
+                return false;
+            }
+
+            self.list_max_listpack_size = value;
+            return true;
+        }
+
+        let Ok(value) = usize::try_from(value) else {
+            return false;
+        };
+
+        let limit = match name.as_slice() {
+            b"hash-max-listpack-entries" => &mut self.hash_max_listpack_entries,
+            b"hash-max-listpack-value" => &mut self.hash_max_listpack_value,
+            b"set-max-intset-entries" => &mut self.set_max_intset_entries,
+            b"set-max-listpack-entries" => &mut self.set_max_listpack_entries,
+            b"set-max-listpack-value" => &mut self.set_max_listpack_value,
+            _ => return false,
+        };
+
+        *limit = value;
+        true
+    }
+
+    pub fn entries(&self) -> [(&'static [u8], Vec<u8>); 6] {
+        let mut itoa_buf = itoa::Buffer::new();
+        let mut format = |value: i64| itoa_buf.format(value).as_bytes().to_vec();
+
+        [
+            (
+                b"hash-max-listpack-entries",
+                format(self.hash_max_listpack_entries as i64),
+            ),
+            (
+                b"hash-max-listpack-value",
+                format(self.hash_max_listpack_value as i64),
+            ),
+            (
+                b"set-max-intset-entries",
+                format(self.set_max_intset_entries as i64),
+            ),
+            (
+                b"set-max-listpack-entries",
+                format(self.set_max_listpack_entries as i64),
+            ),
+            (
+                b"set-max-listpack-value",
+                format(self.set_max_listpack_value as i64),
+            ),
+            (
+                b"list-max-listpack-size",
+                format(self.list_max_listpack_size),
+            ),
+        ]
+    }
+
+    fn list_fits(&self, len: usize, bytes: usize) -> bool {
+        match self.list_max_listpack_size {
+            entries @ 1.. => len <= entries as usize,
+            class => bytes <= 4096 << (-class - 1),
+        }
+    }
+}
+
+// Entries back to back, each prefixed with its length as a LEB128 varint.
+#[derive(Clone, Default, Archive, Serialize, Deserialize)]
+pub struct Listpack {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+pub struct ListpackIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for ListpackIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let (_, entry) = self.next_span()?;
+        Some(entry)
+    }
+}
+
+impl<'a> ListpackIter<'a> {
+    // The entry along with where it starts, header included.
+    fn next_span(&mut self) -> Option<(usize, &'a [u8])> {
+        let start = self.offset;
+        let mut len = 0;
+        let mut shift = 0;
+
+        loop {
+            if shift >= usize::BITS {
+                return None;
+            }
+
+            let byte = *self.bytes.get(self.offset)?;
+            self.offset += 1;
+            len |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        let entry = self.bytes.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+
+        Some((start, entry))
+    }
+}
+
+impl Listpack {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn byte_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn is_valid(&self) -> bool {
+        let mut iter = self.iter();
+        let (mut len, mut end) = (0, 0);
+
+        while iter.next_span().is_some() {
+            len += 1;
+            end = iter.offset;
+        }
+
+        len == self.len && end == self.bytes.len()
+    }
+
+    // No entry among every `step`th from the first repeats.
+    fn is_distinct(&self, step: usize) -> bool {
+        let mut seen = HashSet::new();
+        self.iter().step_by(step).all(|entry| seen.insert(entry))
+    }
+
+    pub fn iter(&self) -> ListpackIter<'_> {
+        ListpackIter {
+            bytes: &self.bytes,
+            offset: 0,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        self.iter().nth(index)
+    }
+
+    pub fn push_back(&mut self, entry: &[u8]) {
+        self.bytes.extend_from_slice(&encode_entry(entry));
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, entry: &[u8]) {
+        self.bytes.splice(0..0, encode_entry(entry));
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Vec<u8>> {
+        let (span, entry) = self.span(index)?;
+        let entry = entry.to_vec();
+
+        self.bytes.drain(span);
+        self.len -= 1;
+
+        Some(entry)
+    }
+
+    pub fn replace(&mut self, index: usize, entry: &[u8]) {
+        if let Some((span, _)) = self.span(index) {
+            self.bytes.splice(span, encode_entry(entry));
+        }
+    }
+
+    fn span(&self, index: usize) -> Option<(std::ops::Range<usize>, &[u8])> {
+        let mut iter = self.iter();
+
+        for _ in 0..index {
+            iter.next_span()?;
+        }
+
+        let (start, entry) = iter.next_span()?;
+
+        Some((start..iter.offset, entry))
+    }
+}
+
+fn encode_entry(entry: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(entry.len() + 2);
+    let mut len = entry.len();
+
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+
+        if len == 0 {
+            encoded.push(byte);
+            break;
+        }
+
+        encoded.push(byte | 0x80);
+    }
+
+    encoded.extend_from_slice(entry);
+    encoded
+}
+
+enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<T, A: Iterator<Item = T>, B: Iterator<Item = T>> Iterator for Either<A, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            Either::Left(iter) => iter.next(),
+            Either::Right(iter) => iter.next(),
+        }
+    }
+}
+
+// Fields and values alternate in the listpack.
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub enum Hash {
+    Listpack(Listpack),
+    Table(Dict<Vec<u8>>),
+}
+
+impl Default for Hash {
+    fn default() -> Self {
+        Hash::Listpack(Listpack::default())
+    }
+}
+
+impl Hash {
+    pub fn len(&self) -> usize {
+        match self {
+            Hash::Listpack(listpack) => listpack.len() / 2,
+            Hash::Table(table) => table.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn encoding(&self) -> &'static [u8] {
+        match self {
+            Hash::Listpack(_) => b"listpack",
+            Hash::Table(_) => b"hashtable",
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Hash::Listpack(listpack) => {
+                listpack.is_valid() && listpack.len() % 2 == 0 && listpack.is_distinct(2)
+            }
+            Hash::Table(_) => true,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        match self {
+            Hash::Listpack(listpack) => {
+                let mut entries = listpack.iter();
+                Either::Left(std::iter::from_fn(move || {
+                    Some((entries.next()?, entries.next()?))
+                }))
+            }
+            Hash::Table(table) => Either::Right(
+                table
+                    .iter()
+                    .map(|(field, value)| (field.as_slice(), value.as_slice())),
+            ),
+        }
+    }
+
+    pub fn get(&self, field: &[u8]) -> Option<&[u8]> {
+        match self {
+            Hash::Listpack(_) => self
+                .iter()
+                .find(|(existing, _)| *existing == field)
+                .map(|(_, value)| value),
+            Hash::Table(table) => table.get(field).map(Vec::as_slice),
+        }
+    }
+
+    pub fn contains_key(&self, field: &[u8]) -> bool {
+        self.get(field).is_some()
+    }
+
+    /// Returns whether the field is new.
+    pub fn insert(&mut self, field: Vec<u8>, value: Vec<u8>, limits: &EncodingLimits) -> bool {
+        if let Hash::Listpack(listpack) = self {
+            let position = listpack
+                .iter()
+                .step_by(2)
+                .position(|existing| existing == field);
+            let len = listpack.len() / 2 + usize::from(position.is_none());
+
+            if len <= limits.hash_max_listpack_entries
+                && field.len() <= limits.hash_max_listpack_value
+                && value.len() <= limits.hash_max_listpack_value
+            {
+                match position {
+                    Some(position) => listpack.replace(position * 2 + 1, &value),
+                    None => {
+                        listpack.push_back(&field);
+                        listpack.push_back(&value);
+                    }
+                }
+
+                return position.is_none();
+            }
+
+            self.convert();
+        }
+
+        match self {
+            Hash::Table(table) => table.insert(field, value).is_none(),
+            Hash::Listpack(_) => false,
+        }
+    }
+
+    /// Returns whether the field was there.
+    pub fn remove(&mut self, field: &[u8]) -> bool {
+        match self {
+            Hash::Listpack(listpack) => {
+                let Some(position) = listpack
+                    .iter()
+                    .step_by(2)
+                    .position(|existing| existing == field)
+                else {
+                    return false;
+                };
+
+                listpack.remove(position * 2);
+                listpack.remove(position * 2);
+                true
+            }
+            Hash::Table(table) => table.remove(field).is_some(),
+        }
+    }
+
+    pub fn heap_size(&self, samples: usize) -> usize {
+        match self {
+            Hash::Listpack(listpack) => listpack.byte_len(),
+            Hash::Table(table) => {
+                table.capacity() * size_of::<Vec<(Vec<u8>, Vec<u8>)>>()
+                    + table.len() * size_of::<(Vec<u8>, Vec<u8>)>()
+                    + sampled(
+                        table.len(),
+                        samples,
+                        table
+                            .iter()
+                            .map(|(field, value)| field.capacity() + value.capacity()),
+                    )
+            }
+        }
+    }
+
+    fn convert(&mut self) {
+        let table = self
+            .iter()
+            .map(|(field, value)| (field.to_vec(), value.to_vec()))
+            .collect();
+
+        *self = Hash::Table(table);
+    }
+}
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub enum Set {
+    Intset(Vec<i64>),
+    Listpack(Listpack),
+    Table(Dict<()>),
+}
+
+impl Default for Set {
+    fn default() -> Self {
+        Set::Intset(Vec::new())
+    }
+}
+
+// Only members that print back exactly as given can go in an intset, "007"
+// and "+7" have to stay strings.
+fn as_integer(member: &[u8]) -> Option<i64> {
+    let integer = bytes_to_i64(member).ok()?;
+    let mut itoa_buf = itoa::Buffer::new();
+
+    (itoa_buf.format(integer).as_bytes() == member).then_some(integer)
+}
+
+impl Set {
+    pub fn len(&self) -> usize {
+        match self {
+            Set::Intset(integers) => integers.len(),
+            Set::Listpack(listpack) => listpack.len(),
+            Set::Table(table) => table.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn encoding(&self) -> &'static [u8] {
+        match self {
+            Set::Intset(_) => b"intset",
+            Set::Listpack(_) => b"listpack",
+            Set::Table(_) => b"hashtable",
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Set::Intset(integers) => integers.is_sorted_by(|a, b| a < b),
+            Set::Listpack(listpack) => listpack.is_valid() && listpack.is_distinct(1),
+            Set::Table(_) => true,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Cow<'_, [u8]>> {
+        match self {
+            Set::Intset(integers) => Either::Left(integers.iter().map(|integer| {
+                let mut itoa_buf = itoa::Buffer::new();
+                Cow::Owned(itoa_buf.format(*integer).as_bytes().to_vec())
+            })),
+            Set::Listpack(listpack) => {
+                Either::Right(Either::Left(listpack.iter().map(Cow::Borrowed)))
+            }
+            Set::Table(table) => Either::Right(Either::Right(
+                table.keys().map(|member| Cow::Borrowed(member.as_slice())),
+            )),
+        }
+    }
+
+    pub fn contains(&self, member: &[u8]) -> bool {
+        match self {
+            Set::Intset(integers) => {
+                as_integer(member).is_some_and(|integer| integers.binary_search(&integer).is_ok())
+            }
+            Set::Listpack(listpack) => listpack.iter().any(|existing| existing == member),
+            Set::Table(table) => table.contains_key(member),
+        }
+    }
+
+    /// Returns whether the member is new.
+    pub fn insert(&mut self, member: Vec<u8>, limits: &EncodingLimits) -> bool {
+        if self.contains(&member) {
+            return false;
+        }
+
+        if let Set::Intset(integers) = self {
+            if let Some(integer) = as_integer(&member)
+                && integers.len() < limits.set_max_intset_entries
+            {
+                let position = integers.partition_point(|existing| *existing < integer);
+                integers.insert(position, integer);
+                return true;
+            }
+
+            self.convert(limits, self.len() + 1, member.len());
+        }
+
+        match self {
+            Set::Listpack(listpack) => {
+                if listpack.len() < limits.set_max_listpack_entries
+                    && member.len() <= limits.set_max_listpack_value
+                {
+                    listpack.push_back(&member);
+                    return true;
+                }
+
+                self.convert(limits, usize::MAX, member.len());
+            }
+            Set::Intset(_) | Set::Table(_) => {}
+        }
+
+        match self {
+            Set::Table(table) => table.insert(member, ()).is_none(),
+            Set::Intset(_) | Set::Listpack(_) => false,
+        }
+    }
+
+    /// Returns whether the member was there.
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self {
+            Set::Intset(integers) => {
+                match as_integer(member).map(|integer| integers.binary_search(&integer)) {
+                    Some(Ok(position)) => {
+                        integers.remove(position);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Set::Listpack(listpack) => {
+                match listpack.iter().position(|existing| existing == member) {
+                    Some(position) => {
+                        listpack.remove(position);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Set::Table(table) => table.remove(member).is_some(),
+        }
+    }
+
+    pub fn heap_size(&self, samples: usize) -> usize {
+        match self {
+            Set::Intset(integers) => integers.capacity() * size_of::<i64>(),
+            Set::Listpack(listpack) => listpack.byte_len(),
+            Set::Table(table) => {
+                table.capacity() * size_of::<Vec<(Vec<u8>, ())>>()
+                    + table.len() * size_of::<Vec<u8>>()
+                    + sampled(table.len(), samples, table.keys().map(Vec::capacity))
+            }
+        }
+    }
+
+    fn convert(&mut self, limits: &EncodingLimits, len: usize, longest: usize) {
+        let members: Vec<Vec<u8>> = self.iter().map(Cow::into_owned).collect();
+        let longest = members.iter().map(Vec::len).fold(longest, usize::max);
+
+        *self =
+            if len <= limits.set_max_listpack_entries && longest <= limits.set_max_listpack_value {
+                let mut listpack = Listpack::default();
+
+                for member in &members {
+                    listpack.push_back(member);
+                }
+
+                Set::Listpack(listpack)
+            } else {
+                Set::Table(members.into_iter().map(|member| (member, ())).collect())
+            };
+    }
+}
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub enum List {
+    Listpack(Listpack),
+    Quicklist(VecDeque<Vec<u8>>),
+}
+
+impl Default for List {
+    fn default() -> Self {
+        List::Listpack(Listpack::default())
+    }
+}
+
+impl List {
+    pub fn from_elements(
+        elements: impl IntoIterator<Item = Vec<u8>>,
+        limits: &EncodingLimits,
+    ) -> Self {
+        let mut list = List::default();
+
+        for element in elements {
+            list.push_back(element, limits);
+        }
+
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            List::Listpack(listpack) => listpack.len(),
+            List::Quicklist(quicklist) => quicklist.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn encoding(&self) -> &'static [u8] {
+        match self {
+            List::Listpack(_) => b"listpack",
+            List::Quicklist(_) => b"quicklist",
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            List::Listpack(listpack) => listpack.is_valid(),
+            List::Quicklist(_) => true,
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &[u8]> + ExactSizeIterator {
+        match self {
+            List::Listpack(listpack) => {
+                let entries: Vec<&[u8]> = listpack.iter().collect();
+                ListIter::Listpack(entries.into_iter())
+            }
+            List::Quicklist(quicklist) => ListIter::Quicklist(quicklist.iter()),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        match self {
+            List::Listpack(listpack) => listpack.get(index),
+            List::Quicklist(quicklist) => quicklist.get(index).map(Vec::as_slice),
+        }
+    }
+
+    pub fn push_front(&mut self, element: Vec<u8>, limits: &EncodingLimits) {
+        if let List::Listpack(listpack) = self {
+            if limits.list_fits(listpack.len() + 1, listpack.byte_len() + element.len()) {
+                listpack.push_front(&element);
+                return;
+            }
+
+            self.convert();
+        }
+
+        if let List::Quicklist(quicklist) = self {
+            quicklist.push_front(element);
+        }
+    }
+
+    pub fn push_back(&mut self, element: Vec<u8>, limits: &EncodingLimits) {
+        if let List::Listpack(listpack) = self {
+            if limits.list_fits(listpack.len() + 1, listpack.byte_len() + element.len()) {
+                listpack.push_back(&element);
+                return;
+            }
+
+            self.convert();
+        }
+
+        if let List::Quicklist(quicklist) = self {
+            quicklist.push_back(element);
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<Vec<u8>> {
+        match self {
+            List::Listpack(listpack) => listpack.remove(0),
+            List::Quicklist(quicklist) => quicklist.pop_front(),
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<Vec<u8>> {
+        match self {
+            List::Listpack(listpack) => listpack.remove(listpack.len().checked_sub(1)?),
+            List::Quicklist(quicklist) => quicklist.pop_back(),
+        }
+    }
+
+    pub fn set(&mut self, index: usize, element: Vec<u8>, limits: &EncodingLimits) {
+        if let List::Listpack(listpack) = self {
+            let replaced = listpack.get(index).map_or(0, <[u8]>::len);
+
+            if limits.list_fits(
+                listpack.len(),
+                listpack.byte_len() - replaced + element.len(),
+            ) {
+                listpack.replace(index, &element);
+                return;
+            }
+
+            self.convert();
+        }
+
+        if let List::Quicklist(quicklist) = self
+            && let Some(existing) = quicklist.get_mut(index)
+        {
+            *existing = element;
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Vec<u8>> {
+        match self {
+            List::Listpack(listpack) => listpack.remove(index),
+            List::Quicklist(quicklist) => quicklist.remove(index),
+        }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&[u8]) -> bool) {
+        match self {
+            List::Listpack(listpack) => {
+                let mut kept = Listpack::default();
+
+                for element in listpack.iter().filter(|element| keep(element)) {
+                    kept.push_back(element);
+                }
+
+                *listpack = kept;
+            }
+            List::Quicklist(quicklist) => quicklist.retain(|element| keep(element)),
+        }
+    }
+
+    pub fn heap_size(&self, samples: usize) -> usize {
+        match self {
+            List::Listpack(listpack) => listpack.byte_len(),
+            List::Quicklist(quicklist) => {
+                quicklist.capacity() * size_of::<Vec<u8>>()
+                    + sampled(
+                        quicklist.len(),
+                        samples,
+                        quicklist.iter().map(Vec::capacity),
+                    )
+            }
+        }
+    }
+
+    fn convert(&mut self) {
+        if let List::Listpack(listpack) = self {
+            *self = List::Quicklist(listpack.iter().map(<[u8]>::to_vec).collect());
+        }
+    }
+}
+
+enum ListIter<'a> {
+    Listpack(std::vec::IntoIter<&'a [u8]>),
+    Quicklist(std::collections::vec_deque::Iter<'a, Vec<u8>>),
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        match self {
+            ListIter::Listpack(iter) => iter.next(),
+            ListIter::Quicklist(iter) => iter.next().map(Vec::as_slice),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            ListIter::Listpack(iter) => iter.size_hint(),
+            ListIter::Quicklist(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl DoubleEndedIterator for ListIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            ListIter::Listpack(iter) => iter.next_back(),
+            ListIter::Quicklist(iter) => iter.next_back().map(Vec::as_slice),
+        }
+    }
+}
+
+impl ExactSizeIterator for ListIter<'_> {}
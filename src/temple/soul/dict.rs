@@ -0,0 +1,319 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+const MIN_BUCKETS: usize = 4;
+
+// A chained hash table, the one the keyspace and big hashes and sets live in.
+// Its bucket positions are its own, so a SCAN cursor can name one and resume
+// from it. The keys it hashes with are drawn when it's created and kept in the
+// snapshot with it, so its buckets mean the same after a restart.
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct Dict<V> {
+    keys: (u64, u64),
+    buckets: Vec<Vec<(Vec<u8>, V)>>,
+    len: usize,
+}
+
+impl<V> Default for Dict<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Dict<V> {
+    pub fn new() -> Self {
+        let random = RandomState::new();
+
+        Dict {
+            keys: (random.hash_one(0u8), random.hash_one(1u8)),
+            buckets: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many entries fit before it grows.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len()
+    }
+
+    #[allow(deprecated)]
+    fn index(&self, key: &[u8], buckets: usize) -> usize {
+        let mut hasher = std::hash::SipHasher::new_with_keys(self.keys.0, self.keys.1);
+        hasher.write(key);
+
+        hasher.finish() as usize & (buckets - 1)
+    }
+
+    fn bucket(&self, key: &[u8]) -> Option<&Vec<(Vec<u8>, V)>> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+
+        self.buckets.get(self.index(key, self.buckets.len()))
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.bucket(key)?
+            .iter()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+
+        let index = self.index(key, self.buckets.len());
+
+        self.buckets[index]
+            .iter_mut()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the value it replaced.
+    pub fn insert(&mut self, key: Vec<u8>, value: V) -> Option<V> {
+        if let Some(existing) = self.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+
+        if self.len >= self.buckets.len() {
+            self.resize((self.buckets.len() * 2).max(MIN_BUCKETS));
+        }
+
+        let index = self.index(&key, self.buckets.len());
+        self.buckets[index].push((key, value));
+        self.len += 1;
+
+        None
+    }
+
+    pub fn entry(&mut self, key: Vec<u8>) -> Entry<'_, V> {
+        let position = match self.buckets.is_empty() {
+            true => None,
+            false => {
+                let index = self.index(&key, self.buckets.len());
+
+                self.buckets[index]
+                    .iter()
+                    .position(|(existing, _)| *existing == key)
+                    .map(|position| (index, position))
+            }
+        };
+
+        match position {
+            Some((index, position)) => Entry::Occupied(OccupiedEntry {
+                dict: self,
+                index,
+                position,
+            }),
+            None => Entry::Vacant(key),
+        }
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry(&mut self, key: &[u8]) -> Option<(Vec<u8>, V)> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+
+        let index = self.index(key, self.buckets.len());
+        let position = self.buckets[index]
+            .iter()
+            .position(|(existing, _)| existing == key)?;
+
+        Some(self.take(index, position))
+    }
+
+    fn take(&mut self, index: usize, position: usize) -> (Vec<u8>, V) {
+        let entry = self.buckets[index].swap_remove(position);
+        self.len -= 1;
+
+        if self.len * 8 < self.buckets.len() && self.buckets.len() > MIN_BUCKETS {
+            self.resize(self.buckets.len() / 2);
+        }
+
+        entry
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets = Vec::new();
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, &V)> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Vec<u8>, &mut V)> {
+        self.buckets
+            .iter_mut()
+            .flatten()
+            .map(|(key, value)| (&*key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Visits buckets from `cursor` until at least `count` entries turned up,
+    /// returning them with the cursor to carry on from, 0 once every bucket
+    /// has been seen. The cursor counts in reversed bits, so whatever is in
+    /// the table for the whole scan is returned at least once, even if the
+    /// table is resized between calls.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&Vec<u8>, &V)>) {
+        let mut entries = Vec::new();
+
+        if self.buckets.is_empty() {
+            return (0, entries);
+        }
+
+        let mask = self.buckets.len() as u64 - 1;
+        let mut cursor = cursor;
+        // Runs of empty buckets end a call early too, the way Redis bounds it.
+        let mut visits = count.max(1).saturating_mul(10);
+
+        loop {
+            entries.extend(
+                self.buckets[(cursor & mask) as usize]
+                    .iter()
+                    .map(|(key, value)| (key, value)),
+            );
+
+            cursor |= !mask;
+            cursor = cursor.reverse_bits().wrapping_add(1).reverse_bits();
+            visits -= 1;
+
+            if cursor == 0 || entries.len() >= count || visits == 0 {
+                return (cursor, entries);
+            }
+        }
+    }
+
+    /// An entry picked at random, by drawing buckets until one isn't empty.
+    /// The table never drops below an eighth full, so that takes a few draws.
+    pub fn random(&self, lots: &mut Lots) -> Option<(&Vec<u8>, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        loop {
+            let bucket = &self.buckets[lots.draw() as usize & (self.buckets.len() - 1)];
+
+            if !bucket.is_empty() {
+                let (key, value) = &bucket[lots.draw() as usize % bucket.len()];
+                return Some((key, value));
+            }
+        }
+    }
+
+    fn resize(&mut self, buckets: usize) {
+        let old = std::mem::replace(
+            &mut self.buckets,
+            std::iter::repeat_with(Vec::new).take(buckets).collect(),
+        );
+
+        for (key, value) in old.into_iter().flatten() {
+            let index = self.index(&key, buckets);
+            self.buckets[index].push((key, value));
+        }
+    }
+}
+
+// A random number source that's seeded once and then only counts up.
+#[derive(Default)]
+pub struct Lots {
+    seed: RandomState,
+    drawn: u64,
+}
+
+impl Lots {
+    pub fn draw(&mut self) -> u64 {
+        self.drawn = self.drawn.wrapping_add(1);
+        self.seed.hash_one(self.drawn)
+    }
+}
+
+pub enum Entry<'a, V> {
+    Occupied(OccupiedEntry<'a, V>),
+    Vacant(Vec<u8>),
+}
+
+pub struct OccupiedEntry<'a, V> {
+    dict: &'a mut Dict<V>,
+    index: usize,
+    position: usize,
+}
+
+impl<V> OccupiedEntry<'_, V> {
+    pub fn key(&self) -> &Vec<u8> {
+        &self.dict.buckets[self.index][self.position].0
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.dict.buckets[self.index][self.position].1
+    }
+
+    pub fn remove_entry(self) -> (Vec<u8>, V) {
+        self.dict.take(self.index, self.position)
+    }
+}
+
+impl<V> FromIterator<(Vec<u8>, V)> for Dict<V> {
+    fn from_iter<I: IntoIterator<Item = (Vec<u8>, V)>>(entries: I) -> Self {
+        let mut dict = Dict::new();
+
+        for (key, value) in entries {
+            dict.insert(key, value);
+        }
+
+        dict
+    }
+}
+
+impl<V> IntoIterator for Dict<V> {
+    type Item = (Vec<u8>, V);
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Vec<(Vec<u8>, V)>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.buckets.into_iter().flatten()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a Dict<V> {
+    type Item = (&'a Vec<u8>, &'a V);
+    type IntoIter = Box<dyn Iterator<Item = (&'a Vec<u8>, &'a V)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
@@ -0,0 +1,81 @@
+use rkyv::rancor::Error;
+use rkyv::util::AlignedVec;
+
+use super::{Libraries, Value};
+
+// Bumped whenever Value's archived layout changes, so that a payload from an
+// incompatible build is refused instead of being misread.
+const DUMP_VERSION: u16 = 3;
+
+// Same polynomial Redis uses for its DUMP trailer (CRC-64/Jones), reflected.
+const CRC64_POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+
+// Payload layout: the archived Value, then the version as two little endian
+// bytes, then a CRC-64 of everything before it as eight little endian bytes.
+pub fn encode(value: &Value) -> Option<Vec<u8>> {
+    Some(seal(&rkyv::to_bytes::<Error>(value).ok()?))
+}
+
+pub fn decode(payload: &[u8]) -> Option<Value> {
+    rkyv::from_bytes::<Value, Error>(&unseal(payload)?)
+        .ok()
+        .filter(Value::is_valid)
+}
+
+/// FUNCTION DUMP's payload, every library's name and code laid out the same
+/// way a key's value is.
+pub fn encode_libraries(libraries: &Libraries) -> Option<Vec<u8>> {
+    Some(seal(&rkyv::to_bytes::<Error>(libraries).ok()?))
+}
+
+pub fn decode_libraries(payload: &[u8]) -> Option<Libraries> {
+    rkyv::from_bytes::<Libraries, Error>(&unseal(payload)?).ok()
+}
+
+fn seal(archived: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(archived.len() + 10);
+    payload.extend_from_slice(archived);
+    payload.extend_from_slice(&DUMP_VERSION.to_le_bytes());
+    payload.extend_from_slice(&crc64(&payload).to_le_bytes());
+
+    payload
+}
+
+fn unseal(payload: &[u8]) -> Option<AlignedVec> {
+    let (body, checksum) = payload.split_last_chunk::<8>()?;
+
+    if crc64(body) != u64::from_le_bytes(*checksum) {
+        return None;
+    }
+
+    let (archived, version) = body.split_last_chunk::<2>()?;
+
+    if u16::from_le_bytes(*version) != DUMP_VERSION {
+        return None;
+    }
+
+    // The archived bytes start wherever the client's buffer put them, rkyv
+    // wants them aligned.
+    let mut aligned: AlignedVec = AlignedVec::with_capacity(archived.len());
+    aligned.extend_from_slice(archived);
+
+    Some(aligned)
+}
+
+pub fn crc64(bytes: &[u8]) -> u64 {
+    let mut crc = 0u64;
+
+    for &byte in bytes {
+        crc ^= byte as u64;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC64_POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    crc
+}
@@ -0,0 +1,76 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+// Generic cell rate algorithm. The only state is the theoretical arrival
+// time: the instant at which the bucket would be empty again if nothing else
+// arrived. Times are kept in microseconds so that rates finer than one
+// request per millisecond don't round the emission interval down to zero.
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct Gcra {
+    tat: u64,
+}
+
+pub struct Throttle {
+    pub is_limited: bool,
+    pub limit: u64,
+    pub remaining: u64,
+    pub retry_after: Option<u64>,
+    pub reset_after: u64,
+}
+
+impl Gcra {
+    pub fn new() -> Self {
+        Gcra { tat: 0 }
+    }
+
+    /// The unix time in milliseconds after which the state is
+    /// indistinguishable from a fresh one, which is when the key can expire.
+    pub fn expires_at(&self) -> u64 {
+        self.tat / 1000
+    }
+
+    /// Tries to take `quantity` tokens from a bucket holding `max_burst + 1`
+    /// that refills by `count` every `period` milliseconds. The state only
+    /// moves forward if the request is allowed.
+    pub fn throttle(
+        &mut self,
+        max_burst: u64,
+        count: u64,
+        period: u64,
+        quantity: u64,
+        now: u64,
+    ) -> Throttle {
+        let now = now.saturating_mul(1000);
+        let limit = max_burst.saturating_add(1);
+
+        let emission_interval = (period.saturating_mul(1000) / count).max(1);
+        let tolerance = emission_interval.saturating_mul(limit);
+        let increment = emission_interval.saturating_mul(quantity);
+
+        let tat = self.tat.max(now);
+        let new_tat = tat.saturating_add(increment);
+        let allow_at = new_tat.saturating_sub(tolerance);
+
+        let (is_limited, retry_after, ttl) = if allow_at > now {
+            // A request bigger than the whole bucket can never succeed.
+            let retry_after = (increment <= tolerance).then(|| allow_at - now);
+            (true, retry_after, tat - now)
+        } else {
+            self.tat = new_tat;
+            (false, None, new_tat - now)
+        };
+
+        Throttle {
+            is_limited,
+            limit,
+            remaining: tolerance.saturating_sub(ttl) / emission_interval,
+            retry_after: retry_after.map(|retry_after| retry_after.div_ceil(1_000_000)),
+            reset_after: ttl.div_ceil(1_000_000),
+        }
+    }
+}
+
+impl Default for Gcra {
+    fn default() -> Self {
+        Self::new()
+    }
+}
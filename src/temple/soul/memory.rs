@@ -0,0 +1,36 @@
+use super::Value;
+
+// What the keyspace spends on a key before its value: the entry holding the
+// key, the value and the expiry, and about a bucket for it.
+const ENTRY_OVERHEAD: usize =
+    size_of::<(Vec<u8>, (Value, Option<u64>))>() + size_of::<Vec<(Vec<u8>, Value)>>();
+
+/// An estimate of the bytes behind `key`. Collections bigger than `samples`
+/// elements are sized from the average of their first `samples` elements,
+/// `samples` of 0 looks at all of them.
+pub fn usage(key: &[u8], value: &Value, samples: usize) -> usize {
+    ENTRY_OVERHEAD + key.len() + value_size(value, samples)
+}
+
+fn value_size(value: &Value, samples: usize) -> usize {
+    match value {
+        Value::String(string) => string.capacity(),
+        Value::List(list) => list.heap_size(samples),
+        Value::Hash(hash) => hash.heap_size(samples),
+        Value::Set(set) => set.heap_size(samples),
+        Value::CountMinSketch(sketch) => sketch.heap_size(),
+        Value::TopK(top_k) => top_k.heap_size(),
+        Value::TimeSeries(series) => series.heap_size(),
+        Value::Gcra(_) => 0,
+        Value::Relic(reliquary) => reliquary.heap_size(),
+    }
+}
+
+// Scales the sizes of the first `samples` elements up to all `len` of them.
+pub fn sampled(len: usize, samples: usize, sizes: impl Iterator<Item = usize>) -> usize {
+    if samples == 0 || len <= samples {
+        return sizes.sum();
+    }
+
+    sizes.take(samples).sum::<usize>() * len / samples
+}
@@ -0,0 +1,126 @@
+// The event classes of notify-keyspace-events. K and E pick the channels an
+// event goes out on, the rest pick which events go out at all.
+pub const KEYSPACE: u16 = 1 << 0;
+pub const KEYEVENT: u16 = 1 << 1;
+pub const GENERIC: u16 = 1 << 2;
+pub const STRING: u16 = 1 << 3;
+pub const LIST: u16 = 1 << 4;
+pub const SET: u16 = 1 << 5;
+pub const HASH: u16 = 1 << 6;
+pub const ZSET: u16 = 1 << 7;
+pub const EXPIRED: u16 = 1 << 8;
+pub const EVICTED: u16 = 1 << 9;
+pub const STREAM: u16 = 1 << 10;
+pub const KEY_MISS: u16 = 1 << 11;
+pub const MODULE: u16 = 1 << 12;
+pub const NEW: u16 = 1 << 13;
+
+// What `A` stands for, key misses and new keys have to be asked for by name.
+pub const ALL: u16 =
+    GENERIC | STRING | LIST | SET | HASH | ZSET | EXPIRED | EVICTED | STREAM | MODULE;
+
+// In the order CONFIG GET spells them.
+const CLASSES: [(u8, u16); 14] = [
+    (b'g', GENERIC),
+    (b'$', STRING),
+    (b'l', LIST),
+    (b's', SET),
+    (b'h', HASH),
+    (b'z', ZSET),
+    (b'x', EXPIRED),
+    (b'e', EVICTED),
+    (b't', STREAM),
+    (b'd', MODULE),
+    (b'K', KEYSPACE),
+    (b'E', KEYEVENT),
+    (b'm', KEY_MISS),
+    (b'n', NEW),
+];
+
+pub fn parse_flags(flags: &[u8]) -> Option<u16> {
+    let mut parsed = 0;
+
+    for &flag in flags {
+        parsed |= match flag {
+            b'A' => ALL,
+            flag => CLASSES
+                .iter()
+                .find(|(name, _)| *name == flag)
+                .map(|(_, class)| *class)?,
+        };
+    }
+
+    Some(parsed)
+}
+
+/// The canonical spelling of `flags`, the way CONFIG GET reports them.
+pub fn flags_to_string(flags: u16) -> Vec<u8> {
+    let mut string = Vec::new();
+    let all = flags & ALL == ALL;
+
+    if all {
+        string.push(b'A');
+    }
+
+    for (name, class) in CLASSES {
+        if flags & class != 0 && !(all && class & ALL != 0) {
+            string.push(name);
+        }
+    }
+
+    string
+}
+
+// Events raised while a command runs, waiting for the temple to publish them
+// once it's done. While some connection WATCHes keys it also keeps every key
+// that was modified, whichever events are enabled, and it always counts the
+// changes made.
+#[derive(Default)]
+pub struct Notifier {
+    flags: u16,
+    pending: Vec<(&'static [u8], Vec<u8>)>,
+    watching: bool,
+    touched: Vec<Vec<u8>>,
+    dirty: u64,
+}
+
+impl Notifier {
+    pub fn set_flags(&mut self, flags: u16) {
+        self.flags = flags;
+    }
+
+    pub fn set_watching(&mut self, watching: bool) {
+        self.watching = watching;
+    }
+
+    pub fn notify(&mut self, class: u16, event: &'static [u8], key: &[u8]) {
+        if self.flags & class != 0 && self.flags & (KEYSPACE | KEYEVENT) != 0 {
+            self.pending.push((event, key.to_vec()));
+        }
+
+        if class != KEY_MISS {
+            self.dirty += 1;
+
+            if self.watching {
+                self.touched.push(key.to_vec());
+            }
+        }
+    }
+
+    /// Counts changes that raise no event of their own, like a flush.
+    pub fn mark_dirty(&mut self, changes: u64) {
+        self.dirty += changes;
+    }
+
+    pub fn dirty(&self) -> u64 {
+        self.dirty
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, (&'static [u8], Vec<u8>)> {
+        self.pending.drain(..)
+    }
+
+    pub fn drain_touched(&mut self) -> std::vec::Drain<'_, Vec<u8>> {
+        self.touched.drain(..)
+    }
+}
@@ -0,0 +1,37 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::temple::rite::{self, Relic};
+
+// A relic as the soul keeps it: the bytes it inscribed, under its type's
+// name. It's read back from them on every access.
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct Reliquary {
+    type_name: Vec<u8>,
+    bytes: Vec<u8>,
+}
+
+impl Reliquary {
+    pub fn seal<T: Relic>(relic: &T) -> Self {
+        Reliquary {
+            type_name: T::TYPE_NAME.to_vec(),
+            bytes: relic.inscribe(),
+        }
+    }
+
+    /// None if it holds another type of relic, or bytes `T` can't read.
+    pub fn open<T: Relic>(&self) -> Option<T> {
+        if self.type_name != T::TYPE_NAME {
+            return None;
+        }
+
+        T::decipher(&self.bytes)
+    }
+
+    pub fn type_name(&self) -> &'static [u8] {
+        rite::relic_type_name(&self.type_name)
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.type_name.capacity() + self.bytes.capacity()
+    }
+}
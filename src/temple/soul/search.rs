@@ -0,0 +1,634 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+
+use rkyv::{Archive, Deserialize, Serialize, with::Skip};
+
+use super::compact::Hash;
+use super::vector::{self, DistanceMetric, VectorAlgorithm, VectorIndex};
+
+#[derive(Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+pub enum FieldType {
+    Tag {
+        separator: u8,
+    },
+    Numeric,
+    Text,
+    Vector {
+        algorithm: VectorAlgorithm,
+        dimension: usize,
+        metric: DistanceMetric,
+    },
+}
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub name: Vec<u8>,
+    pub field_type: FieldType,
+}
+
+enum FieldIndex {
+    Tag(HashMap<Vec<u8>, HashSet<Vec<u8>>>),
+    Numeric(BTreeMap<u64, HashSet<Vec<u8>>>),
+    Text(HashMap<Vec<u8>, HashSet<Vec<u8>>>),
+    Vector(VectorIndex),
+}
+
+// Everything derived from the keyspace. It is left out of the snapshot and
+// rebuilt from the hashes on startup.
+#[derive(Default)]
+struct Postings {
+    documents: HashMap<Vec<u8>, Vec<Option<Vec<u8>>>>,
+    fields: Vec<FieldIndex>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+pub struct SearchIndex {
+    prefixes: Vec<Vec<u8>>,
+    schema: Vec<FieldSchema>,
+    #[rkyv(with = Skip)]
+    postings: Postings,
+}
+
+#[derive(Clone)]
+pub enum Query {
+    All,
+    Tag(Vec<u8>, Vec<Vec<u8>>),
+    Numeric(Vec<u8>, Bound<f64>, Bound<f64>),
+    Text {
+        field: Option<Vec<u8>>,
+        term: Vec<u8>,
+        is_prefix: bool,
+    },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+// f64 -> u64 mapping that keeps the ordering, so numbers can key a BTreeMap.
+fn sortable(number: f64) -> u64 {
+    let bits = number.to_bits();
+
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    }
+}
+
+fn parse_number(bytes: &[u8]) -> Option<f64> {
+    let number = std::str::from_utf8(bytes).ok()?.parse::<f64>().ok()?;
+
+    if number.is_nan() { None } else { Some(number) }
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+fn tokenize(text: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    text.split(|&byte| !is_word_byte(byte))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+}
+
+fn split_tags(value: &[u8], separator: u8) -> impl Iterator<Item = Vec<u8>> + '_ {
+    value
+        .split(move |&byte| byte == separator)
+        .map(|tag| tag.trim_ascii().to_ascii_lowercase())
+        .filter(|tag| !tag.is_empty())
+}
+
+impl SearchIndex {
+    pub fn new(prefixes: Vec<Vec<u8>>, schema: Vec<FieldSchema>) -> Self {
+        let mut index = SearchIndex {
+            prefixes,
+            schema,
+            postings: Postings::default(),
+        };
+
+        index.clear();
+        index
+    }
+
+    pub fn clear(&mut self) {
+        self.postings = Postings {
+            documents: HashMap::new(),
+            fields: self
+                .schema
+                .iter()
+                .map(|field| match field.field_type {
+                    FieldType::Tag { .. } => FieldIndex::Tag(HashMap::new()),
+                    FieldType::Numeric => FieldIndex::Numeric(BTreeMap::new()),
+                    FieldType::Text => FieldIndex::Text(HashMap::new()),
+                    FieldType::Vector {
+                        algorithm, metric, ..
+                    } => FieldIndex::Vector(VectorIndex::new(algorithm, metric)),
+                })
+                .collect(),
+        };
+    }
+
+    pub fn covers(&self, key: &[u8]) -> bool {
+        self.prefixes.is_empty() || self.prefixes.iter().any(|prefix| key.starts_with(prefix))
+    }
+
+    pub fn documents(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.postings.documents.keys()
+    }
+
+    pub fn add(&mut self, key: &[u8], hash: &Hash) {
+        let values: Vec<Option<Vec<u8>>> = self
+            .schema
+            .iter()
+            .map(|field| hash.get(&field.name).map(<[u8]>::to_vec))
+            .collect();
+
+        for ((field, field_index), value) in self
+            .schema
+            .iter()
+            .zip(self.postings.fields.iter_mut())
+            .zip(values.iter())
+        {
+            let Some(value) = value else {
+                continue;
+            };
+
+            match (field.field_type, field_index) {
+                (FieldType::Tag { separator }, FieldIndex::Tag(tags)) => {
+                    for tag in split_tags(value, separator) {
+                        tags.entry(tag).or_default().insert(key.to_vec());
+                    }
+                }
+                (FieldType::Numeric, FieldIndex::Numeric(numbers)) => {
+                    if let Some(number) = parse_number(value) {
+                        numbers
+                            .entry(sortable(number))
+                            .or_default()
+                            .insert(key.to_vec());
+                    }
+                }
+                (FieldType::Text, FieldIndex::Text(terms)) => {
+                    for term in tokenize(value) {
+                        terms.entry(term).or_default().insert(key.to_vec());
+                    }
+                }
+                (FieldType::Vector { dimension, .. }, FieldIndex::Vector(vectors)) => {
+                    if let Some(vector) = vector::parse_vector(value, dimension) {
+                        vectors.insert(key, vector);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.postings.documents.insert(key.to_vec(), values);
+    }
+
+    pub fn remove(&mut self, key: &[u8]) {
+        let Some(values) = self.postings.documents.remove(key) else {
+            return;
+        };
+
+        for ((field, field_index), value) in self
+            .schema
+            .iter()
+            .zip(self.postings.fields.iter_mut())
+            .zip(values.iter())
+        {
+            let Some(value) = value else {
+                continue;
+            };
+
+            match (field.field_type, field_index) {
+                (FieldType::Tag { separator }, FieldIndex::Tag(tags)) => {
+                    for tag in split_tags(value, separator) {
+                        unpost(tags, &tag, key);
+                    }
+                }
+                (FieldType::Numeric, FieldIndex::Numeric(numbers)) => {
+                    if let Some(number) = parse_number(value) {
+                        let number = sortable(number);
+
+                        if let Some(keys) = numbers.get_mut(&number) {
+                            keys.remove(key);
+
+                            if keys.is_empty() {
+                                numbers.remove(&number);
+                            }
+                        }
+                    }
+                }
+                (FieldType::Text, FieldIndex::Text(terms)) => {
+                    for term in tokenize(value) {
+                        unpost(terms, &term, key);
+                    }
+                }
+                (FieldType::Vector { .. }, FieldIndex::Vector(vectors)) => vectors.remove(key),
+                _ => {}
+            }
+        }
+    }
+
+    fn field(&self, name: &[u8]) -> Option<(&FieldSchema, &FieldIndex)> {
+        self.schema
+            .iter()
+            .zip(self.postings.fields.iter())
+            .find(|(field, _)| field.name == name)
+    }
+
+    /// The `k` documents whose vector in `name` is nearest to `blob`, closest
+    /// first, restricted to `filter` when given. Returns None if `name` isn't
+    /// a vector field or `blob` doesn't match its dimension.
+    pub fn knn(
+        &self,
+        name: &[u8],
+        blob: &[u8],
+        k: usize,
+        ef_runtime: Option<usize>,
+        filter: Option<&HashSet<Vec<u8>>>,
+    ) -> Option<Vec<(Vec<u8>, f32)>> {
+        let (
+            FieldSchema {
+                field_type: FieldType::Vector { dimension, .. },
+                ..
+            },
+            FieldIndex::Vector(vectors),
+        ) = self.field(name)?
+        else {
+            return None;
+        };
+
+        let query = vector::parse_vector(blob, *dimension)?;
+
+        Some(vectors.knn(&query, k, ef_runtime, filter))
+    }
+
+    /// Evaluates `query`, returns None if it refers to a field the index
+    /// doesn't have or uses a field with the wrong kind of filter.
+    pub fn search(&self, query: &Query) -> Option<HashSet<Vec<u8>>> {
+        match query {
+            Query::All => Some(self.postings.documents.keys().cloned().collect()),
+            Query::Tag(name, wanted) => {
+                let (_, FieldIndex::Tag(tags)) = self.field(name)? else {
+                    return None;
+                };
+
+                Some(
+                    wanted
+                        .iter()
+                        .filter_map(|tag| tags.get(tag))
+                        .flatten()
+                        .cloned()
+                        .collect(),
+                )
+            }
+            Query::Numeric(name, min, max) => {
+                let (_, FieldIndex::Numeric(numbers)) = self.field(name)? else {
+                    return None;
+                };
+
+                let (min, max) = (min.map(sortable), max.map(sortable));
+
+                // BTreeMap::range panics on an inverted or empty exclusive range.
+                let is_empty = match (min, max) {
+                    (Bound::Included(min), Bound::Included(max)) => min > max,
+                    (
+                        Bound::Included(min) | Bound::Excluded(min),
+                        Bound::Included(max) | Bound::Excluded(max),
+                    ) => min >= max,
+                    _ => false,
+                };
+
+                if is_empty {
+                    return Some(HashSet::new());
+                }
+
+                Some(
+                    numbers
+                        .range((min, max))
+                        .flat_map(|(_, keys)| keys)
+                        .cloned()
+                        .collect(),
+                )
+            }
+            Query::Text {
+                field,
+                term,
+                is_prefix,
+            } => {
+                let mut text_indexes = Vec::new();
+
+                match field {
+                    Some(name) => match self.field(name)? {
+                        (_, FieldIndex::Text(terms)) => text_indexes.push(terms),
+                        _ => return None,
+                    },
+                    None => {
+                        for field_index in &self.postings.fields {
+                            if let FieldIndex::Text(terms) = field_index {
+                                text_indexes.push(terms);
+                            }
+                        }
+                    }
+                }
+
+                let mut keys = HashSet::new();
+
+                for terms in text_indexes {
+                    if *is_prefix {
+                        for (_, postings) in terms.iter().filter(|(t, _)| t.starts_with(term)) {
+                            keys.extend(postings.iter().cloned());
+                        }
+                    } else if let Some(postings) = terms.get(term) {
+                        keys.extend(postings.iter().cloned());
+                    }
+                }
+
+                Some(keys)
+            }
+            Query::And(queries) => {
+                let mut queries = queries.iter();
+                let mut keys = self.search(queries.next()?)?;
+
+                for query in queries {
+                    let other = self.search(query)?;
+                    keys.retain(|key| other.contains(key));
+                }
+
+                Some(keys)
+            }
+            Query::Or(queries) => {
+                let mut keys = HashSet::new();
+
+                for query in queries {
+                    keys.extend(self.search(query)?);
+                }
+
+                Some(keys)
+            }
+            Query::Not(query) => {
+                let excluded = self.search(query)?;
+
+                Some(
+                    self.postings
+                        .documents
+                        .keys()
+                        .filter(|key| !excluded.contains(*key))
+                        .cloned()
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+// Drops `key` from a posting list, and the list itself once it's empty.
+fn unpost(postings: &mut HashMap<Vec<u8>, HashSet<Vec<u8>>>, term: &[u8], key: &[u8]) {
+    if let Some(keys) = postings.get_mut(term) {
+        keys.remove(key);
+
+        if keys.is_empty() {
+            postings.remove(term);
+        }
+    }
+}
+
+impl Query {
+    /// Parses the FT.SEARCH query language: space separated terms are
+    /// intersected, `|` unions, `-` negates, parentheses group, and
+    /// `@field:{a|b}` / `@field:[min max]` / `@field:term` filter on a field.
+    /// A trailing `*` on a term matches by prefix.
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        let mut parser = QueryParser {
+            input,
+            position: 0,
+            depth: 0,
+        };
+        let query = parser.union()?;
+
+        parser.skip_whitespace();
+
+        if parser.position == input.len() {
+            Some(query)
+        } else {
+            None
+        }
+    }
+}
+
+// How deep negations and parentheses may nest, the parser recursing once for
+// each.
+const MAX_DEPTH: usize = 128;
+
+struct QueryParser<'a> {
+    input: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl QueryParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn descend(&mut self) -> Option<()> {
+        self.depth += 1;
+        (self.depth <= MAX_DEPTH).then_some(())
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+
+        if self.peek()? == byte {
+            self.position += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn union(&mut self) -> Option<Query> {
+        let mut queries = vec![self.intersection()?];
+
+        loop {
+            self.skip_whitespace();
+
+            if self.peek() != Some(b'|') {
+                break;
+            }
+
+            self.position += 1;
+            queries.push(self.intersection()?);
+        }
+
+        if queries.len() == 1 {
+            queries.pop()
+        } else {
+            Some(Query::Or(queries))
+        }
+    }
+
+    fn intersection(&mut self) -> Option<Query> {
+        let mut queries = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None | Some(b')') | Some(b'|') => break,
+                _ => queries.push(self.unary()?),
+            }
+        }
+
+        match queries.len() {
+            0 => None,
+            1 => queries.pop(),
+            _ => Some(Query::And(queries)),
+        }
+    }
+
+    fn unary(&mut self) -> Option<Query> {
+        self.skip_whitespace();
+
+        if self.peek()? == b'-' {
+            self.position += 1;
+            self.descend()?;
+            let query = Query::Not(Box::new(self.unary()?));
+            self.depth -= 1;
+            return Some(query);
+        }
+
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Option<Query> {
+        match self.peek()? {
+            b'(' => {
+                self.position += 1;
+                self.descend()?;
+                let query = self.union()?;
+                self.expect(b')')?;
+                self.depth -= 1;
+                Some(query)
+            }
+            b'*' => {
+                self.position += 1;
+                Some(Query::All)
+            }
+            b'@' => {
+                self.position += 1;
+                let field = self.word()?;
+                self.expect(b':')?;
+                self.skip_whitespace();
+
+                match self.peek()? {
+                    b'{' => self.tags(field),
+                    b'[' => self.range(field),
+                    _ => self.term(Some(field)),
+                }
+            }
+            _ => self.term(None),
+        }
+    }
+
+    fn word(&mut self) -> Option<Vec<u8>> {
+        let start = self.position;
+
+        while self.peek().is_some_and(is_word_byte) {
+            self.position += 1;
+        }
+
+        if self.position == start {
+            None
+        } else {
+            Some(self.input[start..self.position].to_vec())
+        }
+    }
+
+    fn term(&mut self, field: Option<Vec<u8>>) -> Option<Query> {
+        let term = self.word()?.to_ascii_lowercase();
+        let is_prefix = self.peek() == Some(b'*');
+
+        if is_prefix {
+            self.position += 1;
+        }
+
+        Some(Query::Text {
+            field,
+            term,
+            is_prefix,
+        })
+    }
+
+    fn tags(&mut self, field: Vec<u8>) -> Option<Query> {
+        self.position += 1;
+        let start = self.position;
+
+        while self.peek()? != b'}' {
+            self.position += 1;
+        }
+
+        let tags = split_tags(&self.input[start..self.position], b'|').collect();
+        self.position += 1;
+
+        Some(Query::Tag(field, tags))
+    }
+
+    fn range(&mut self, field: Vec<u8>) -> Option<Query> {
+        self.position += 1;
+        let start = self.position;
+
+        while self.peek()? != b']' {
+            self.position += 1;
+        }
+
+        let mut bounds = self.input[start..self.position]
+            .split(|byte| byte.is_ascii_whitespace())
+            .filter(|bound| !bound.is_empty());
+
+        let (Some(min), Some(max), None) = (bounds.next(), bounds.next(), bounds.next()) else {
+            return None;
+        };
+
+        self.position += 1;
+
+        Some(Query::Numeric(
+            field,
+            parse_bound(min, true)?,
+            parse_bound(max, false)?,
+        ))
+    }
+}
+
+fn parse_bound(bytes: &[u8], is_min: bool) -> Option<Bound<f64>> {
+    let (is_exclusive, bytes) = match bytes.strip_prefix(b"(") {
+        Some(rest) => (true, rest),
+        None => (false, bytes),
+    };
+
+    let is_negative = bytes.eq_ignore_ascii_case(b"-inf");
+
+    if is_negative || bytes.eq_ignore_ascii_case(b"+inf") || bytes.eq_ignore_ascii_case(b"inf") {
+        // Infinity on its own side bounds nothing, on the other it's past
+        // every number.
+        return Some(match (is_min, is_negative) {
+            (true, true) | (false, false) => Bound::Unbounded,
+            (true, false) => Bound::Excluded(f64::INFINITY),
+            (false, true) => Bound::Excluded(f64::NEG_INFINITY),
+        });
+    }
+
+    let number = parse_number(bytes)?;
+
+    if is_exclusive {
+        Some(Bound::Excluded(number))
+    } else {
+        Some(Bound::Included(number))
+    }
+}
@@ -0,0 +1,242 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+// Seeded FNV-1a followed by a splitmix64 finalizer. The sketches are persisted
+// in the snapshot, so the hash has to stay stable across restarts, which rules
+// out the randomly keyed std hasher.
+fn hash(item: &[u8], seed: u64) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+
+    for &byte in item {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+// The most counters a Count-Min sketch, or buckets a TopK, may have: 16M,
+// 128 MiB of CMS counters. Past that a dimension is a mistake, and the
+// allocation failing would take the whole server down with it.
+pub const MAX_COUNTERS: usize = 1 << 24;
+
+// The most items a TopK keeps.
+pub const MAX_TOPK: usize = 1 << 16;
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    counters: Vec<u64>,
+}
+
+impl CountMinSketch {
+    pub fn new(width: usize, depth: usize) -> Self {
+        CountMinSketch {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+        }
+    }
+
+    /// Bytes held on the heap, for MEMORY USAGE.
+    pub fn heap_size(&self) -> usize {
+        self.counters.capacity() * size_of::<u64>()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.width > 0
+            && self.depth > 0
+            && self.width.checked_mul(self.depth) == Some(self.counters.len())
+            && self.counters.len() <= MAX_COUNTERS
+    }
+
+    pub fn incr_by(&mut self, item: &[u8], increment: u64) -> u64 {
+        let mut estimate = u64::MAX;
+
+        for row in 0..self.depth {
+            let idx = row * self.width + (hash(item, row as u64) % self.width as u64) as usize;
+
+            self.counters[idx] = self.counters[idx].saturating_add(increment);
+            estimate = estimate.min(self.counters[idx]);
+        }
+
+        estimate
+    }
+
+    pub fn query(&self, item: &[u8]) -> u64 {
+        let mut estimate = u64::MAX;
+
+        for row in 0..self.depth {
+            let idx = row * self.width + (hash(item, row as u64) % self.width as u64) as usize;
+
+            estimate = estimate.min(self.counters[idx]);
+        }
+
+        estimate
+    }
+
+    pub fn merge(&mut self, sources: &[(&CountMinSketch, u64)]) {
+        for (idx, counter) in self.counters.iter_mut().enumerate() {
+            *counter = sources.iter().fold(0u64, |sum, (source, weight)| {
+                sum.saturating_add(source.counters[idx].saturating_mul(*weight))
+            });
+        }
+    }
+}
+
+#[derive(Clone, Copy, Archive, Serialize, Deserialize)]
+struct Bucket {
+    fingerprint: u64,
+    count: u64,
+}
+
+// HeavyKeeper: every row holds one fingerprinted counter per bucket, and a
+// colliding item decays the incumbent with probability decay^count. The
+// current heavy hitters are kept in a small list next to the buckets.
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct TopK {
+    k: usize,
+    width: usize,
+    depth: usize,
+    decay: f64,
+    buckets: Vec<Bucket>,
+    heap: Vec<(Vec<u8>, u64)>,
+    rng: u64,
+}
+
+impl TopK {
+    pub fn new(k: usize, width: usize, depth: usize, decay: f64) -> Self {
+        TopK {
+            k,
+            width,
+            depth,
+            decay,
+            buckets: vec![
+                Bucket {
+                    fingerprint: 0,
+                    count: 0
+                };
+                width * depth
+            ],
+            heap: Vec::with_capacity(k),
+            rng: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.buckets.capacity() * size_of::<Bucket>()
+            + self
+                .heap
+                .iter()
+                .map(|(item, _)| size_of::<(Vec<u8>, u64)>() + item.capacity())
+                .sum::<usize>()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (1..=MAX_TOPK).contains(&self.k)
+            && self.width > 0
+            && self.depth > 0
+            && self.width.checked_mul(self.depth) == Some(self.buckets.len())
+            && self.buckets.len() <= MAX_COUNTERS
+            && self.decay > 0.0
+            && self.decay <= 1.0
+            && self.heap.len() <= self.k
+    }
+
+    fn next_random(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns the item that got expelled from the top-k list, if any.
+    pub fn add(&mut self, item: Vec<u8>) -> Option<Vec<u8>> {
+        let fingerprint = hash(&item, u64::MAX);
+        let mut estimate = 0;
+
+        for row in 0..self.depth {
+            let idx = row * self.width + (hash(&item, row as u64) % self.width as u64) as usize;
+            let bucket = self.buckets[idx];
+
+            if bucket.count == 0 {
+                self.buckets[idx] = Bucket {
+                    fingerprint,
+                    count: 1,
+                };
+                estimate = estimate.max(1);
+            } else if bucket.fingerprint == fingerprint {
+                self.buckets[idx].count = bucket.count.saturating_add(1);
+                estimate = estimate.max(self.buckets[idx].count);
+            } else if self.next_random() < self.decay.powf(bucket.count as f64) {
+                self.buckets[idx].count -= 1;
+
+                if self.buckets[idx].count == 0 {
+                    self.buckets[idx] = Bucket {
+                        fingerprint,
+                        count: 1,
+                    };
+                    estimate = estimate.max(1);
+                }
+            }
+        }
+
+        if let Some(entry) = self.heap.iter_mut().find(|(existing, _)| *existing == item) {
+            entry.1 = entry.1.max(estimate);
+            return None;
+        }
+
+        if self.heap.len() < self.k {
+            self.heap.push((item, estimate));
+            return None;
+        }
+
+        let (min_idx, min_count) = self
+            .heap
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, count))| (idx, *count))
+            .min_by_key(|(_, count)| *count)?;
+
+        if estimate > min_count {
+            let (expelled, _) = std::mem::replace(&mut self.heap[min_idx], (item, estimate));
+            return Some(expelled);
+        }
+
+        None
+    }
+
+    pub fn count(&self, item: &[u8]) -> u64 {
+        let fingerprint = hash(item, u64::MAX);
+        let mut estimate = 0;
+
+        for row in 0..self.depth {
+            let idx = row * self.width + (hash(item, row as u64) % self.width as u64) as usize;
+            let bucket = self.buckets[idx];
+
+            if bucket.fingerprint == fingerprint {
+                estimate = estimate.max(bucket.count);
+            }
+        }
+
+        estimate
+    }
+
+    /// The tracked heavy hitters, highest count first.
+    pub fn list(&self) -> Vec<(Vec<u8>, u64)> {
+        let mut list = self.heap.clone();
+        list.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        list
+    }
+}
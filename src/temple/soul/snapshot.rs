@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use rkyv::rancor::Error;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::compact::{EncodingLimits, Hash, List, Set};
+use super::{Database, Soul, Value};
+
+const MAGIC: &[u8; 8] = b"JERUSLM\0";
+
+// Bumped whenever Soul's archived layout changes, with a migration from the
+// old one.
+const SNAPSHOT_VERSION: u16 = 1;
+
+// The layout from before snapshots had a header: one keyspace holding the
+// four original types.
+#[derive(Archive, Serialize, Deserialize)]
+enum LegacyValue {
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Hash(HashMap<Vec<u8>, Vec<u8>>),
+    Set(HashSet<Vec<u8>>),
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+struct LegacySoul(HashMap<Vec<u8>, (LegacyValue, Option<u64>)>);
+
+// File layout: the magic, the version as two little endian bytes, then the
+// archived Soul.
+pub fn encode(soul: &Soul) -> Result<Vec<u8>, String> {
+    let archived = rkyv::to_bytes::<Error>(soul).map_err(|e| e.to_string())?;
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 2 + archived.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&archived);
+
+    Ok(bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Soul, String> {
+    let Some(body) = bytes.strip_prefix(MAGIC) else {
+        let legacy = rkyv::from_bytes::<LegacySoul, Error>(&aligned(bytes))
+            .map_err(|_| "not a snapshot Jerusalem can read".to_string())?;
+
+        return Ok(migrate(legacy));
+    };
+
+    let Some((version, archived)) = body.split_first_chunk::<2>() else {
+        return Err("truncated header".to_string());
+    };
+
+    match u16::from_le_bytes(*version) {
+        SNAPSHOT_VERSION => {
+            rkyv::from_bytes::<Soul, Error>(&aligned(archived))
+                .map_err(|_| "the snapshot is corrupted".to_string())
+        }
+        version => Err(format!("unknown format version {version}")),
+    }
+}
+
+// rkyv wants the archived bytes aligned, which a slice past the header isn't.
+fn aligned(bytes: &[u8]) -> AlignedVec {
+    let mut aligned = AlignedVec::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    aligned
+}
+
+fn migrate(legacy: LegacySoul) -> Soul {
+    let limits = EncodingLimits::default();
+    let mut soul = Soul::with_databases(1);
+    let database: &mut Database = &mut soul.databases[0];
+
+    for (key, (value, expiry)) in legacy.0 {
+        let value = match value {
+            LegacyValue::String(string) => Value::String(string),
+            LegacyValue::List(list) => Value::List(List::from_elements(list, &limits)),
+            LegacyValue::Hash(table) => {
+                let mut hash = Hash::default();
+
+                for (field, value) in table {
+                    hash.insert(field, value, &limits);
+                }
+
+                Value::Hash(hash)
+            }
+            LegacyValue::Set(table) => {
+                let mut set = Set::default();
+
+                for member in table {
+                    set.insert(member, &limits);
+                }
+
+                Value::Set(set)
+            }
+        };
+
+        // Expiries were kept in seconds back then.
+        database.0.insert(key, (value, expiry.map(|expiry| expiry.saturating_mul(1000))));
+    }
+
+    soul
+}
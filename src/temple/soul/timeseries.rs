@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+pub enum DuplicatePolicy {
+    Block,
+    First,
+    Last,
+    Min,
+    Max,
+    Sum,
+}
+
+impl DuplicatePolicy {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.eq_ignore_ascii_case(b"BLOCK") {
+            Some(DuplicatePolicy::Block)
+        } else if bytes.eq_ignore_ascii_case(b"FIRST") {
+            Some(DuplicatePolicy::First)
+        } else if bytes.eq_ignore_ascii_case(b"LAST") {
+            Some(DuplicatePolicy::Last)
+        } else if bytes.eq_ignore_ascii_case(b"MIN") {
+            Some(DuplicatePolicy::Min)
+        } else if bytes.eq_ignore_ascii_case(b"MAX") {
+            Some(DuplicatePolicy::Max)
+        } else if bytes.eq_ignore_ascii_case(b"SUM") {
+            Some(DuplicatePolicy::Sum)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+pub enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+}
+
+impl Aggregation {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.eq_ignore_ascii_case(b"AVG") {
+            Some(Aggregation::Avg)
+        } else if bytes.eq_ignore_ascii_case(b"SUM") {
+            Some(Aggregation::Sum)
+        } else if bytes.eq_ignore_ascii_case(b"MIN") {
+            Some(Aggregation::Min)
+        } else if bytes.eq_ignore_ascii_case(b"MAX") {
+            Some(Aggregation::Max)
+        } else if bytes.eq_ignore_ascii_case(b"COUNT") {
+            Some(Aggregation::Count)
+        } else {
+            None
+        }
+    }
+
+    fn apply(&self, values: &[f64]) -> f64 {
+        match self {
+            Aggregation::Avg => values.iter().sum::<f64>() / values.len() as f64,
+            Aggregation::Sum => values.iter().sum(),
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Count => values.len() as f64,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TimeSeriesOptions {
+    pub retention: Option<u64>,
+    pub duplicate_policy: Option<DuplicatePolicy>,
+    pub labels: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+#[derive(Clone)]
+pub enum LabelFilter {
+    Equals(Vec<u8>, Vec<u8>),
+    NotEquals(Vec<u8>, Vec<u8>),
+}
+
+impl LabelFilter {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let idx = bytes.iter().position(|&b| b == b'=')?;
+
+        if idx > 0 && bytes[idx - 1] == b'!' {
+            Some(LabelFilter::NotEquals(
+                bytes[..idx - 1].to_vec(),
+                bytes[idx + 1..].to_vec(),
+            ))
+        } else {
+            Some(LabelFilter::Equals(
+                bytes[..idx].to_vec(),
+                bytes[idx + 1..].to_vec(),
+            ))
+        }
+    }
+
+    // An empty value matches a missing label, as in `label=` / `label!=`.
+    fn matches(&self, labels: &[(Vec<u8>, Vec<u8>)]) -> bool {
+        let lookup = |name: &Vec<u8>| {
+            labels
+                .iter()
+                .find(|(label, _)| label == name)
+                .map(|(_, value)| value.as_slice())
+                .unwrap_or(b"")
+        };
+
+        match self {
+            LabelFilter::Equals(name, value) => lookup(name) == value.as_slice(),
+            LabelFilter::NotEquals(name, value) => lookup(name) != value.as_slice(),
+        }
+    }
+}
+
+pub enum SampleError {
+    Duplicate,
+    TooOld,
+}
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct CompactionRule {
+    pub destination: Vec<u8>,
+    pub aggregation: Aggregation,
+    pub bucket_duration: u64,
+}
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+pub struct TimeSeries {
+    retention: u64,
+    duplicate_policy: DuplicatePolicy,
+    labels: Vec<(Vec<u8>, Vec<u8>)>,
+    samples: BTreeMap<u64, f64>,
+    pub rules: Vec<CompactionRule>,
+    pub compacted_from: Option<Vec<u8>>,
+}
+
+impl TimeSeries {
+    pub fn new(options: TimeSeriesOptions) -> Self {
+        TimeSeries {
+            retention: options.retention.unwrap_or(0),
+            duplicate_policy: options.duplicate_policy.unwrap_or(DuplicatePolicy::Block),
+            labels: options.labels,
+            samples: BTreeMap::new(),
+            rules: Vec::new(),
+            compacted_from: None,
+        }
+    }
+
+    pub fn labels(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        &self.labels
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.rules.iter().all(|rule| rule.bucket_duration > 0)
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Bytes held on the heap, counting each sample as its key and value
+    /// since the B-tree packs them densely.
+    pub fn heap_size(&self) -> usize {
+        self.samples.len() * size_of::<(u64, f64)>()
+            + self
+                .labels
+                .iter()
+                .map(|(name, value)| {
+                    size_of::<(Vec<u8>, Vec<u8>)>() + name.capacity() + value.capacity()
+                })
+                .sum::<usize>()
+            + self
+                .rules
+                .iter()
+                .map(|rule| size_of::<CompactionRule>() + rule.destination.capacity())
+                .sum::<usize>()
+            + self.compacted_from.as_ref().map_or(0, Vec::capacity)
+    }
+
+    pub fn last(&self) -> Option<(u64, f64)> {
+        self.samples
+            .last_key_value()
+            .map(|(timestamp, value)| (*timestamp, *value))
+    }
+
+    pub fn matches(&self, filters: &[LabelFilter]) -> bool {
+        filters.iter().all(|filter| filter.matches(&self.labels))
+    }
+
+    pub fn add(
+        &mut self,
+        timestamp: u64,
+        value: f64,
+        on_duplicate: Option<DuplicatePolicy>,
+    ) -> Result<u64, SampleError> {
+        if let Some((last_timestamp, _)) = self.last()
+            && self.retention > 0
+            && timestamp < last_timestamp.saturating_sub(self.retention)
+        {
+            return Err(SampleError::TooOld);
+        }
+
+        match self.samples.get_mut(&timestamp) {
+            Some(existing) => match on_duplicate.unwrap_or(self.duplicate_policy) {
+                DuplicatePolicy::Block => return Err(SampleError::Duplicate),
+                DuplicatePolicy::First => {}
+                DuplicatePolicy::Last => *existing = value,
+                DuplicatePolicy::Min => *existing = existing.min(value),
+                DuplicatePolicy::Max => *existing = existing.max(value),
+                DuplicatePolicy::Sum => *existing += value,
+            },
+            None => {
+                self.samples.insert(timestamp, value);
+            }
+        }
+
+        self.trim();
+
+        Ok(timestamp)
+    }
+
+    pub fn upsert(&mut self, timestamp: u64, value: f64) {
+        self.samples.insert(timestamp, value);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.retention == 0 {
+            return;
+        }
+
+        if let Some((last_timestamp, _)) = self.last() {
+            let oldest_allowed = last_timestamp.saturating_sub(self.retention);
+            self.samples = self.samples.split_off(&oldest_allowed);
+        }
+    }
+
+    pub fn range(
+        &self,
+        from: u64,
+        to: u64,
+        aggregation: Option<(Aggregation, u64)>,
+    ) -> Vec<(u64, f64)> {
+        if from > to {
+            return Vec::new();
+        }
+
+        let samples = self.samples.range(from..=to);
+
+        let Some((aggregation, bucket_duration)) = aggregation else {
+            return samples
+                .map(|(timestamp, value)| (*timestamp, *value))
+                .collect();
+        };
+
+        let mut result = Vec::new();
+        let mut bucket: Option<(u64, Vec<f64>)> = None;
+
+        for (timestamp, value) in samples {
+            let bucket_start = timestamp - timestamp % bucket_duration;
+
+            match &mut bucket {
+                Some((start, values)) if *start == bucket_start => values.push(*value),
+                _ => {
+                    if let Some((start, values)) = bucket.take() {
+                        result.push((start, aggregation.apply(&values)));
+                    }
+
+                    bucket = Some((bucket_start, vec![*value]));
+                }
+            }
+        }
+
+        if let Some((start, values)) = bucket {
+            result.push((start, aggregation.apply(&values)));
+        }
+
+        result
+    }
+
+    /// Aggregates the bucket `timestamp` falls into for every compaction rule,
+    /// so the destinations can be brought up to date.
+    pub fn compactions(&self, timestamp: u64) -> Vec<(Vec<u8>, u64, f64)> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let bucket_start = timestamp - timestamp % rule.bucket_duration;
+                let bucket_end = bucket_start.saturating_add(rule.bucket_duration - 1);
+
+                let values: Vec<f64> = self
+                    .samples
+                    .range(bucket_start..=bucket_end)
+                    .map(|(_, value)| *value)
+                    .collect();
+
+                if values.is_empty() {
+                    return None;
+                }
+
+                Some((
+                    rule.destination.clone(),
+                    bucket_start,
+                    rule.aggregation.apply(&values),
+                ))
+            })
+            .collect()
+    }
+}
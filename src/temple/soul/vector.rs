@@ -0,0 +1,485 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+// The most dimensions a vector field may have, 128 KiB per vector.
+pub const MAX_DIMENSION: usize = 32768;
+
+#[derive(Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    L2,
+    InnerProduct,
+    Cosine,
+}
+
+impl DistanceMetric {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.eq_ignore_ascii_case(b"L2") {
+            Some(DistanceMetric::L2)
+        } else if bytes.eq_ignore_ascii_case(b"IP") {
+            Some(DistanceMetric::InnerProduct)
+        } else if bytes.eq_ignore_ascii_case(b"COSINE") {
+            Some(DistanceMetric::Cosine)
+        } else {
+            None
+        }
+    }
+
+    // Smaller is closer for every metric: squared euclidean distance for L2,
+    // 1 - dot product for IP and 1 - cosine similarity for COSINE.
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+            DistanceMetric::InnerProduct => 1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>(),
+            DistanceMetric::Cosine => {
+                let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+                let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    1.0
+                } else {
+                    1.0 - dot / (norm_a * norm_b)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+pub enum VectorAlgorithm {
+    Flat,
+    Hnsw {
+        m: usize,
+        ef_construction: usize,
+        ef_runtime: usize,
+    },
+}
+
+/// Decodes a FLOAT32 blob, as stored in a hash field or passed as a query
+/// parameter. Returns None if its length doesn't match `dimension`.
+pub fn parse_vector(blob: &[u8], dimension: usize) -> Option<Vec<f32>> {
+    if dimension.checked_mul(4) != Some(blob.len()) {
+        return None;
+    }
+
+    Some(
+        blob.chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect(),
+    )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Candidate(f32, usize);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+struct Node {
+    key: Vec<u8>,
+    vector: Vec<f32>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+pub enum VectorIndex {
+    Flat {
+        metric: DistanceMetric,
+        vectors: HashMap<Vec<u8>, Vec<f32>>,
+    },
+    Hnsw(Hnsw),
+}
+
+impl VectorIndex {
+    pub fn new(algorithm: VectorAlgorithm, metric: DistanceMetric) -> Self {
+        match algorithm {
+            VectorAlgorithm::Flat => VectorIndex::Flat {
+                metric,
+                vectors: HashMap::new(),
+            },
+            VectorAlgorithm::Hnsw {
+                m,
+                ef_construction,
+                ef_runtime,
+            } => VectorIndex::Hnsw(Hnsw::new(metric, m, ef_construction, ef_runtime)),
+        }
+    }
+
+    pub fn insert(&mut self, key: &[u8], vector: Vec<f32>) {
+        match self {
+            VectorIndex::Flat { vectors, .. } => {
+                vectors.insert(key.to_vec(), vector);
+            }
+            VectorIndex::Hnsw(hnsw) => hnsw.insert(key, vector),
+        }
+    }
+
+    pub fn remove(&mut self, key: &[u8]) {
+        match self {
+            VectorIndex::Flat { vectors, .. } => {
+                vectors.remove(key);
+            }
+            VectorIndex::Hnsw(hnsw) => hnsw.remove(key),
+        }
+    }
+
+    /// The `k` nearest neighbours of `query`, closest first. With a filter,
+    /// only keys in it are considered.
+    pub fn knn(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef_runtime: Option<usize>,
+        filter: Option<&HashSet<Vec<u8>>>,
+    ) -> Vec<(Vec<u8>, f32)> {
+        match self {
+            VectorIndex::Flat { metric, vectors } => {
+                let candidates: Box<dyn Iterator<Item = (&Vec<u8>, &Vec<f32>)>> = match filter {
+                    Some(filter) => {
+                        Box::new(filter.iter().filter_map(|key| vectors.get_key_value(key)))
+                    }
+                    None => Box::new(vectors.iter()),
+                };
+
+                let mut results: Vec<(Vec<u8>, f32)> = candidates
+                    .map(|(key, vector)| (key.clone(), metric.distance(query, vector)))
+                    .collect();
+
+                results.sort_by(|(a_key, a), (b_key, b)| a.total_cmp(b).then(a_key.cmp(b_key)));
+                results.truncate(k);
+                results
+            }
+            VectorIndex::Hnsw(hnsw) => hnsw.knn(query, k, ef_runtime, filter),
+        }
+    }
+}
+
+// Hierarchical navigable small world graph. Removed nodes leave a hole in
+// `nodes` rather than being reused, so stale links left behind in other
+// neighbour lists simply get skipped.
+pub struct Hnsw {
+    metric: DistanceMetric,
+    m: usize,
+    ef_construction: usize,
+    ef_runtime: usize,
+    nodes: Vec<Option<Node>>,
+    ids: HashMap<Vec<u8>, usize>,
+    entry_point: Option<usize>,
+    rng: u64,
+}
+
+impl Hnsw {
+    fn new(metric: DistanceMetric, m: usize, ef_construction: usize, ef_runtime: usize) -> Self {
+        Hnsw {
+            metric,
+            m,
+            ef_construction,
+            ef_runtime,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry_point: None,
+            rng: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        let uniform = ((self.rng >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() / (self.m as f64).ln()).floor() as usize
+    }
+
+    fn max_neighbours(&self, level: usize) -> usize {
+        if level == 0 { self.m * 2 } else { self.m }
+    }
+
+    fn node(&self, id: usize) -> Option<&Node> {
+        self.nodes.get(id).and_then(Option::as_ref)
+    }
+
+    fn distance(&self, query: &[f32], id: usize) -> f32 {
+        match self.node(id) {
+            Some(node) => self.metric.distance(query, &node.vector),
+            None => f32::INFINITY,
+        }
+    }
+
+    fn top_level(&self) -> usize {
+        self.entry_point
+            .and_then(|id| self.node(id))
+            .map(|node| node.neighbours.len() - 1)
+            .unwrap_or(0)
+    }
+
+    // Best-first search of a single layer, returns up to `ef` nodes closest first.
+    fn search_layer(&self, query: &[f32], entry: usize, ef: usize, level: usize) -> Vec<Candidate> {
+        let mut visited = HashSet::from([entry]);
+        let first = Candidate(self.distance(query, entry), entry);
+
+        let mut candidates = BinaryHeap::from([Reverse(first)]);
+        let mut results = BinaryHeap::from([first]);
+
+        while let Some(Reverse(Candidate(distance, id))) = candidates.pop() {
+            if let Some(furthest) = results.peek()
+                && distance > furthest.0
+                && results.len() >= ef
+            {
+                break;
+            }
+
+            let Some(node) = self.node(id) else {
+                continue;
+            };
+
+            let Some(neighbours) = node.neighbours.get(level) else {
+                continue;
+            };
+
+            for &neighbour in neighbours {
+                if !visited.insert(neighbour) || self.node(neighbour).is_none() {
+                    continue;
+                }
+
+                let distance = self.distance(query, neighbour);
+
+                if results.len() < ef || results.peek().is_some_and(|f| distance < f.0) {
+                    candidates.push(Reverse(Candidate(distance, neighbour)));
+                    results.push(Candidate(distance, neighbour));
+
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    fn descend(&self, query: &[f32], to_level: usize) -> Option<usize> {
+        let mut entry = self.entry_point?;
+
+        for level in (to_level + 1..=self.top_level()).rev() {
+            if let Some(closest) = self.search_layer(query, entry, 1, level).first() {
+                entry = closest.1;
+            }
+        }
+
+        Some(entry)
+    }
+
+    fn insert(&mut self, key: &[u8], vector: Vec<f32>) {
+        self.remove(key);
+
+        let level = self.random_level();
+        let id = self.nodes.len();
+        let top_level = self.top_level();
+
+        let Some(mut entry) = self.descend(&vector, level) else {
+            self.nodes.push(Some(Node {
+                key: key.to_vec(),
+                vector,
+                neighbours: vec![Vec::new(); level + 1],
+            }));
+            self.ids.insert(key.to_vec(), id);
+            self.entry_point = Some(id);
+            return;
+        };
+
+        let mut neighbours = vec![Vec::new(); level + 1];
+
+        for layer in (0..=level.min(top_level)).rev() {
+            let found = self.search_layer(&vector, entry, self.ef_construction, layer);
+
+            neighbours[layer] = self.select_neighbours(&found, self.m);
+
+            if let Some(closest) = found.first() {
+                entry = closest.1;
+            }
+        }
+
+        self.nodes.push(Some(Node {
+            key: key.to_vec(),
+            vector,
+            neighbours: neighbours.clone(),
+        }));
+        self.ids.insert(key.to_vec(), id);
+
+        for (layer, layer_neighbours) in neighbours.iter().enumerate() {
+            for &neighbour in layer_neighbours {
+                self.relink(neighbour, &[id], layer);
+            }
+        }
+
+        if level > top_level {
+            self.entry_point = Some(id);
+        }
+    }
+
+    // Picks neighbours from `candidates` (closest first), skipping any that
+    // is closer to an already picked neighbour than to `vector`. Spreading
+    // links out like this keeps distant clusters reachable.
+    fn select_neighbours(&self, candidates: &[Candidate], max_neighbours: usize) -> Vec<usize> {
+        let mut selected: Vec<usize> = Vec::with_capacity(max_neighbours);
+        let mut pruned = Vec::new();
+
+        for &Candidate(distance, id) in candidates {
+            if selected.len() >= max_neighbours {
+                break;
+            }
+
+            let Some(node) = self.node(id) else {
+                continue;
+            };
+
+            if selected
+                .iter()
+                .any(|&other| self.distance(&node.vector, other) < distance)
+            {
+                pruned.push(id);
+            } else {
+                selected.push(id);
+            }
+        }
+
+        let remaining = max_neighbours - selected.len();
+        selected.extend(pruned.into_iter().take(remaining));
+        selected
+    }
+
+    // Points `node`'s links at layer `layer` to the best of its current
+    // neighbours plus `extra`.
+    fn relink(&mut self, node: usize, extra: &[usize], layer: usize) {
+        let Some(target) = self.node(node) else {
+            return;
+        };
+
+        let Some(neighbours) = target.neighbours.get(layer) else {
+            return;
+        };
+
+        let mut candidates: Vec<Candidate> = neighbours
+            .iter()
+            .chain(extra)
+            .copied()
+            .filter(|&neighbour| neighbour != node)
+            .collect::<HashSet<usize>>()
+            .into_iter()
+            .filter(|&neighbour| self.node(neighbour).is_some())
+            .map(|neighbour| Candidate(self.distance(&target.vector, neighbour), neighbour))
+            .collect();
+
+        candidates.sort();
+
+        let selected = if candidates.len() > self.max_neighbours(layer) {
+            self.select_neighbours(&candidates, self.max_neighbours(layer))
+        } else {
+            candidates.into_iter().map(|c| c.1).collect()
+        };
+
+        if let Some(Some(target)) = self.nodes.get_mut(node) {
+            target.neighbours[layer] = selected;
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        let Some(id) = self.ids.remove(key) else {
+            return;
+        };
+
+        let Some(node) = self.nodes[id].take() else {
+            return;
+        };
+
+        // Stitch the removed node's neighbourhood back together so it
+        // doesn't split the graph.
+        for (layer, neighbours) in node.neighbours.iter().enumerate() {
+            for &neighbour in neighbours {
+                self.relink(neighbour, neighbours, layer);
+            }
+        }
+
+        if self.entry_point == Some(id) {
+            self.entry_point = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter_map(|(id, node)| node.as_ref().map(|node| (id, node.neighbours.len())))
+                .max_by_key(|(_, levels)| *levels)
+                .map(|(id, _)| id);
+        }
+
+        if self.ids.is_empty() {
+            self.nodes.clear();
+        }
+    }
+
+    fn knn(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef_runtime: Option<usize>,
+        filter: Option<&HashSet<Vec<u8>>>,
+    ) -> Vec<(Vec<u8>, f32)> {
+        // A selective filter is cheaper to answer exactly than by widening
+        // the graph search until enough matches turn up.
+        if let Some(filter) = filter
+            && filter.len().saturating_mul(10) < self.ids.len()
+        {
+            let mut results: Vec<(Vec<u8>, f32)> = filter
+                .iter()
+                .filter_map(|key| self.ids.get(key))
+                .filter_map(|&id| {
+                    self.node(id)
+                        .map(|node| (node.key.clone(), self.metric.distance(query, &node.vector)))
+                })
+                .collect();
+
+            results.sort_by(|(a_key, a), (b_key, b)| a.total_cmp(b).then(a_key.cmp(b_key)));
+            results.truncate(k);
+            return results;
+        }
+
+        let Some(entry) = self.descend(query, 0) else {
+            return Vec::new();
+        };
+
+        let mut ef = ef_runtime.unwrap_or(self.ef_runtime).max(k);
+
+        loop {
+            let results: Vec<(Vec<u8>, f32)> = self
+                .search_layer(query, entry, ef, 0)
+                .into_iter()
+                .filter_map(|Candidate(distance, id)| {
+                    let node = self.node(id)?;
+
+                    match filter {
+                        Some(filter) if !filter.contains(&node.key) => None,
+                        _ => Some((node.key.clone(), distance)),
+                    }
+                })
+                .take(k)
+                .collect();
+
+            if results.len() >= k || ef >= self.ids.len() {
+                return results;
+            }
+
+            ef = ef.saturating_mul(2);
+        }
+    }
+}
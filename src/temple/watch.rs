@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use mio::Token;
+
+struct Watched {
+    version: u64,
+    watchers: usize,
+}
+
+// The keys connections WATCH, each with a version that goes up every time
+// the key is modified, expires or is deleted. Only watched keys are tracked,
+// and a key is forgotten once its last watcher lets go of it.
+#[derive(Default)]
+pub struct Watches {
+    keys: HashMap<(usize, Vec<u8>), Watched>,
+    observed: HashMap<Token, Vec<(usize, Vec<u8>, u64)>>,
+}
+
+impl Watches {
+    pub fn watch(&mut self, token: Token, db: usize, key: Vec<u8>) {
+        let observed = self.observed.entry(token).or_default();
+
+        if observed
+            .iter()
+            .any(|(watched_db, watched_key, _)| *watched_db == db && *watched_key == key)
+        {
+            return;
+        }
+
+        let watched = self.keys.entry((db, key.clone())).or_insert(Watched {
+            version: 0,
+            watchers: 0,
+        });
+        watched.watchers += 1;
+
+        observed.push((db, key, watched.version));
+    }
+
+    pub fn touch(&mut self, db: usize, key: Vec<u8>) {
+        if let Some(watched) = self.keys.get_mut(&(db, key)) {
+            watched.version += 1;
+        }
+    }
+
+    // FLUSHDB, FLUSHALL and SWAPDB count as modifying every watched key of
+    // the databases they hit.
+    pub fn touch_database(&mut self, db: usize) {
+        for ((watched_db, _), watched) in &mut self.keys {
+            if *watched_db == db {
+                watched.version += 1;
+            }
+        }
+    }
+
+    /// The keys `token` watches.
+    pub fn watched_by(&self, token: Token) -> Vec<(usize, Vec<u8>)> {
+        self.observed.get(&token).map_or_else(Vec::new, |observed| {
+            observed
+                .iter()
+                .map(|(db, key, _)| (*db, key.clone()))
+                .collect()
+        })
+    }
+
+    /// Whether none of the keys `token` watches changed since it watched them.
+    pub fn intact(&self, token: Token) -> bool {
+        self.observed.get(&token).is_none_or(|observed| {
+            observed.iter().all(|(db, key, version)| {
+                self.keys
+                    .get(&(*db, key.clone()))
+                    .is_some_and(|watched| watched.version == *version)
+            })
+        })
+    }
+
+    pub fn unwatch(&mut self, token: Token) {
+        for (db, key, _) in self.observed.remove(&token).unwrap_or_default() {
+            let key = (db, key);
+
+            if let Some(watched) = self.keys.get_mut(&key) {
+                watched.watchers -= 1;
+
+                if watched.watchers == 0 {
+                    self.keys.remove(&key);
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
@@ -5,4 +5,8 @@ mod integration_test;
 mod pubsub_test;
 
 //unit tests
+mod canon_test;
+mod chronicle_test;
+mod dict_test;
+mod outbox_test;
 mod soul_test;
@@ -0,0 +1,110 @@
+// src/tests/canon_test.rs
+//
+// Unit tests for the command table: lookup, arity, key positions, flags and
+// ACL categories. The table is static data, so these need no running server.
+
+use crate::wish::grant::canon::{self, NOSCRIPT, WRITE};
+
+fn terms(args: &[&str]) -> Vec<Vec<u8>> {
+    args.iter().map(|arg| arg.as_bytes().to_vec()).collect()
+}
+
+fn keys(args: &[&str]) -> Vec<Vec<u8>> {
+    let terms = terms(args);
+    canon::find(&terms[0])
+        .unwrap()
+        .keys(&terms)
+        .into_iter()
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+// ── Lookup ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn every_command_is_listed_once_under_an_uppercase_name() {
+    let all = canon::all();
+
+    for canon in &all {
+        assert_eq!(canon.name, canon.name.to_ascii_uppercase().as_slice());
+        assert_eq!(all.iter().filter(|other| other.name == canon.name).count(), 1);
+        assert_ne!(canon.arity, 0);
+    }
+}
+
+#[test]
+fn find_is_case_insensitive() {
+    assert_eq!(canon::find(b"get").unwrap().name, b"GET");
+    assert_eq!(canon::find(b"Fcall_Ro").unwrap().name, b"FCALL_RO");
+    assert!(canon::find(b"NOSUCHCOMMAND").is_none());
+}
+
+#[test]
+fn connection_commands_have_no_grant() {
+    for name in [b"MULTI".as_slice(), b"EXEC", b"DISCARD"] {
+        assert!(canon::find(name).unwrap().grant.is_none());
+    }
+    assert!(canon::find(b"GET").unwrap().grant.is_some());
+}
+
+// ── Arity ────────────────────────────────────────────────────────────────────
+
+#[test]
+fn fits_exact_and_minimum_arity() {
+    assert!(canon::fits(2, 2));
+    assert!(!canon::fits(2, 3));
+    assert!(canon::fits(-3, 3));
+    assert!(canon::fits(-3, 10));
+    assert!(!canon::fits(-3, 2));
+}
+
+// ── Keys ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn keys_follow_the_range() {
+    assert_eq!(keys(&["GET", "a"]), terms(&["a"]));
+    assert_eq!(keys(&["DEL", "a", "b", "c"]), terms(&["a", "b", "c"]));
+    assert_eq!(keys(&["MSET", "a", "1", "b", "2"]), terms(&["a", "b"]));
+    assert_eq!(keys(&["RENAME", "a", "b"]), terms(&["a", "b"]));
+    assert_eq!(keys(&["OBJECT", "ENCODING", "a"]), terms(&["a"]));
+    assert!(keys(&["PUBLISH", "channel", "message"]).is_empty());
+    assert_eq!(keys(&["SPUBLISH", "channel", "message"]), terms(&["channel"]));
+    assert_eq!(keys(&["SSUBSCRIBE", "a", "b"]), terms(&["a", "b"]));
+    assert!(keys(&["SUNSUBSCRIBE"]).is_empty());
+}
+
+#[test]
+fn keys_are_counted_for_scripts() {
+    assert_eq!(
+        keys(&["EVAL", "return 1", "2", "a", "b", "arg"]),
+        terms(&["a", "b"])
+    );
+    assert!(keys(&["FCALL", "f", "0", "arg"]).is_empty());
+    // A count past the end only yields the keys there are.
+    assert_eq!(keys(&["EVALSHA", "sha", "5", "a"]), terms(&["a"]));
+}
+
+// ── Flags and categories ─────────────────────────────────────────────────────
+
+#[test]
+fn flags_are_looked_up_by_name() {
+    assert!(canon::flagged(b"set", WRITE));
+    assert!(!canon::flagged(b"get", WRITE));
+    assert!(canon::flagged(b"EVAL", NOSCRIPT));
+    assert!(!canon::flagged(b"NOSUCHCOMMAND", WRITE));
+}
+
+#[test]
+fn acl_categories_come_from_flags_group_and_table() {
+    let get = canon::find(b"GET").unwrap().acl_categories();
+    assert_eq!(get, [b"@read".as_slice(), b"@fast", b"@string"]);
+
+    let flushall = canon::find(b"FLUSHALL").unwrap().acl_categories();
+    assert!(flushall.contains(&b"@write".as_slice()));
+    assert!(flushall.contains(&b"@slow".as_slice()));
+    assert!(flushall.contains(&b"@dangerous".as_slice()));
+
+    let config = canon::find(b"CONFIG").unwrap().acl_categories();
+    assert!(config.contains(&b"@admin".as_slice()));
+    assert!(config.contains(&b"@dangerous".as_slice()));
+}
@@ -0,0 +1,118 @@
+// src/tests/chronicle_test.rs
+//
+// Unit tests for the pub/sub replay buffers: what's kept, for how long, and
+// which messages a returning subscriber is given. No running server needed.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::temple::chronicle::Chronicle;
+
+fn chronicle(size: &str, age: &str) -> Chronicle {
+    let mut chronicle = Chronicle::default();
+    assert!(chronicle.set(b"pubsub-replay-size", size.as_bytes()));
+    assert!(chronicle.set(b"pubsub-replay-age", age.as_bytes()));
+    chronicle
+}
+
+fn messages(replayed: Vec<(u64, Vec<u8>)>) -> Vec<(u64, String)> {
+    replayed
+        .into_iter()
+        .map(|(sequence, message)| (sequence, String::from_utf8(message).unwrap()))
+        .collect()
+}
+
+#[test]
+fn nothing_is_kept_until_a_size_is_set() {
+    let mut chronicle = Chronicle::default();
+
+    assert_eq!(chronicle.record(b"news", b"hello"), None);
+    assert!(chronicle.since(b"news", 0).is_empty());
+}
+
+#[test]
+fn sequence_ids_count_up_per_channel() {
+    let mut chronicle = chronicle("10", "0");
+
+    assert_eq!(chronicle.record(b"news", b"a"), Some(1));
+    assert_eq!(chronicle.record(b"news", b"b"), Some(2));
+    assert_eq!(chronicle.record(b"sport", b"c"), Some(1));
+
+    assert_eq!(
+        messages(chronicle.since(b"news", 1)),
+        vec![(2, "b".to_string())]
+    );
+    assert!(chronicle.since(b"weather", 0).is_empty());
+}
+
+#[test]
+fn oldest_messages_fall_off_but_ids_keep_counting() {
+    let mut chronicle = chronicle("2", "0");
+
+    for message in [b"a", b"b", b"c"] {
+        chronicle.record(b"news", message);
+    }
+
+    assert_eq!(
+        messages(chronicle.since(b"news", 0)),
+        vec![(2, "b".to_string()), (3, "c".to_string())]
+    );
+    assert_eq!(chronicle.record(b"news", b"d"), Some(4));
+}
+
+#[test]
+fn messages_older_than_the_age_are_dropped() {
+    let mut chronicle = chronicle("10", "20");
+
+    chronicle.record(b"news", b"old");
+    thread::sleep(Duration::from_millis(40));
+    chronicle.record(b"news", b"new");
+
+    assert_eq!(
+        messages(chronicle.since(b"news", 0)),
+        vec![(2, "new".to_string())]
+    );
+}
+
+#[test]
+fn shrinking_or_disabling_the_buffer_trims_it() {
+    let mut chronicle = chronicle("3", "0");
+
+    for message in [b"a", b"b", b"c"] {
+        chronicle.record(b"news", message);
+    }
+
+    assert!(chronicle.set(b"PUBSUB-REPLAY-SIZE", b"1"));
+    assert_eq!(
+        messages(chronicle.since(b"news", 0)),
+        vec![(3, "c".to_string())]
+    );
+
+    assert!(chronicle.set(b"pubsub-replay-size", b"0"));
+    assert!(chronicle.since(b"news", 0).is_empty());
+}
+
+#[test]
+fn config_rejects_unknown_names_and_bad_values() {
+    let mut chronicle = Chronicle::default();
+
+    assert!(!chronicle.set(b"pubsub-replay-size", b"-1"));
+    assert!(!chronicle.set(b"pubsub-replay-age", b"soon"));
+    assert!(!chronicle.set(b"pubsub-replay-depth", b"1"));
+
+    let entries = chronicle.entries();
+    assert_eq!(entries[0], (b"pubsub-replay-size".as_slice(), b"0".to_vec()));
+    assert_eq!(entries[1], (b"pubsub-replay-age".as_slice(), b"0".to_vec()));
+}
+
+#[test]
+fn readers_are_forgotten() {
+    let mut chronicle = Chronicle::default();
+    let token = mio::Token(7);
+
+    chronicle.read(token);
+    assert!(chronicle.is_reader(&token));
+
+    chronicle.forget(token);
+    assert!(!chronicle.is_reader(&token));
+}
@@ -0,0 +1,137 @@
+// src/tests/dict_test.rs
+//
+// Unit tests for the keyspace's hash table: lookups through growth and
+// shrinking, SCAN cursors that keep their promise while it's resized, and
+// random picks.
+
+use std::collections::HashMap;
+
+use crate::temple::soul::dict::{Dict, Entry, Lots};
+
+fn key(i: usize) -> Vec<u8> {
+    format!("key:{i}").into_bytes()
+}
+
+fn filled(len: usize) -> Dict<usize> {
+    (0..len).map(|i| (key(i), i)).collect()
+}
+
+// Scans to the end, resizing with `between` after the first call, and counts
+// how often each key came back.
+fn scan_all(
+    dict: &mut Dict<usize>,
+    count: usize,
+    between: impl Fn(&mut Dict<usize>),
+) -> (HashMap<Vec<u8>, usize>, usize) {
+    let mut seen = HashMap::new();
+    let mut calls = 0;
+    let mut cursor = 0;
+
+    loop {
+        let (next, entries) = dict.scan(cursor, count);
+        calls += 1;
+
+        for (key, _) in entries {
+            *seen.entry(key.clone()).or_insert(0) += 1;
+        }
+
+        if calls == 1 {
+            between(dict);
+        }
+
+        cursor = next;
+
+        if cursor == 0 {
+            return (seen, calls);
+        }
+    }
+}
+
+#[test]
+fn dict_keeps_entries_through_resizes() {
+    let mut dict = filled(1000);
+    assert_eq!(dict.len(), 1000);
+    assert_eq!(dict.get(&key(500)), Some(&500));
+
+    assert_eq!(dict.insert(key(500), 0), Some(500));
+    assert_eq!(dict.get(&key(500)), Some(&0));
+
+    for i in 0..990 {
+        assert_eq!(dict.remove(&key(i)), Some(if i == 500 { 0 } else { i }));
+    }
+
+    assert_eq!(dict.len(), 10);
+    assert!(dict.capacity() < 1000);
+    assert!((990..1000).all(|i| dict.get(&key(i)) == Some(&i)));
+    assert_eq!(dict.remove(&key(0)), None);
+}
+
+#[test]
+fn dict_entry_finds_and_removes() {
+    let mut dict = filled(3);
+
+    match dict.entry(key(1)) {
+        Entry::Occupied(mut occupied) => {
+            *occupied.get_mut() += 10;
+            assert_eq!(occupied.key(), &key(1));
+            assert_eq!(occupied.remove_entry(), (key(1), 11));
+        }
+        Entry::Vacant(_) => panic!("expected an occupied entry"),
+    }
+
+    assert!(matches!(dict.entry(key(1)), Entry::Vacant(_)));
+    assert_eq!(dict.len(), 2);
+}
+
+#[test]
+fn dict_scan_visits_every_entry_once() {
+    let mut dict = filled(1000);
+    let (seen, calls) = scan_all(&mut dict, 10, |_| {});
+
+    assert_eq!(seen.len(), 1000);
+    assert!(seen.values().all(|&times| times == 1));
+    assert!(calls <= dict.capacity());
+}
+
+#[test]
+fn dict_scan_survives_growing_and_shrinking() {
+    let mut dict = filled(100);
+    let (seen, _) = scan_all(&mut dict, 5, |dict| {
+        for i in 100..1000 {
+            dict.insert(key(i), i);
+        }
+    });
+    assert!((0..100).all(|i| seen.get(&key(i)) == Some(&1)));
+
+    let mut dict = filled(1000);
+    let (seen, _) = scan_all(&mut dict, 5, |dict| {
+        for i in 10..1000 {
+            dict.remove(&key(i));
+        }
+    });
+    assert!((0..10).all(|i| seen.contains_key(&key(i))));
+}
+
+#[test]
+fn dict_scan_of_nothing_ends_at_once() {
+    let dict: Dict<usize> = Dict::new();
+    assert_eq!(dict.scan(12345, 10), (0, Vec::new()));
+}
+
+#[test]
+fn dict_random_reaches_every_entry() {
+    let mut lots = Lots::default();
+    let mut dict = filled(1000);
+    for i in 8..1000 {
+        dict.remove(&key(i));
+    }
+
+    let mut seen = HashMap::new();
+    for _ in 0..1000 {
+        let (key, _) = dict.random(&mut lots).unwrap();
+        *seen.entry(key.clone()).or_insert(0) += 1;
+    }
+    assert_eq!(seen.len(), 8);
+
+    assert!(Dict::<usize>::new().random(&mut lots).is_none());
+}
@@ -425,3 +425,759 @@ fn test_wrongtype_errors() {
     assert_error(&cmd(&mut s, &[b!("HSET"),  b!("integ:wt:str"), b!("f"), b!("v")]));
     assert_error(&cmd(&mut s, &[b!("SADD"),  b!("integ:wt:str"), b!("m")]));
 }
+
+// ── CMS.* / TOPK.* ────────────────────────────────────────────────────────────
+
+#[test]
+fn test_count_min_sketch() {
+    let mut s = connect();
+    let key = b!("integ:cms:sketch");
+    cmd(&mut s, &[b!("DEL"), key]);
+
+    assert_ok(&cmd(&mut s, &[b!("CMS.INITBYDIM"), key, b!("1000"), b!("5")]));
+    assert_error(&cmd(&mut s, &[b!("CMS.INITBYDIM"), key, b!("1000"), b!("5")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("CMS.INCRBY"), key, b!("a"), b!("3"), b!("b"), b!("1")]),
+        b"*2\r\n:3\r\n:1\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("CMS.QUERY"), key, b!("a"), b!("missing")]),
+        b"*2\r\n:3\r\n:0\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("CMS.QUERY"), b!("integ:cms:missing"), b!("a")]));
+}
+
+#[test]
+fn test_top_k() {
+    let mut s = connect();
+    let key = b!("integ:topk:tk");
+    cmd(&mut s, &[b!("DEL"), key]);
+
+    assert_ok(&cmd(&mut s, &[b!("TOPK.RESERVE"), key, b!("1")]));
+    assert_eq!(
+        parse_array(&cmd(&mut s, &[b!("TOPK.ADD"), key, b!("a"), b!("b"), b!("b")])),
+        vec![None, None, Some(b"a".to_vec())],
+    );
+    assert_eq!(
+        parse_array(&cmd(&mut s, &[b!("TOPK.LIST"), key])),
+        vec![Some(b"b".to_vec())],
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("TOPK.LIST"), key, b!("WITHCOUNT")]),
+        b"*2\r\n$1\r\nb\r\n:2\r\n"
+    );
+    assert_eq!(cmd(&mut s, &[b!("TOPK.COUNT"), key, b!("b")]), b"*1\r\n:2\r\n");
+}
+
+/// Dimensions whose counters overflow, or would take more memory than a
+/// sketch may, are refused before anything is allocated, and the temple keeps
+/// answering.
+#[test]
+fn test_sketch_dimensions_are_bounded() {
+    let mut s = connect();
+    let key = b!("integ:sketch:huge");
+    cmd(&mut s, &[b!("DEL"), key]);
+
+    assert_error(&cmd(&mut s, &[b!("CMS.INITBYDIM"), key, b!("4294967296"), b!("4294967297")]));
+    assert_error(&cmd(&mut s, &[b!("CMS.INITBYDIM"), key, b!("100000000"), b!("100")]));
+    assert_error(&cmd(&mut s, &[b!("TOPK.RESERVE"), key, b!("5"), b!("4294967296"), b!("4294967297"), b!("0.9")]));
+    assert_error(&cmd(&mut s, &[b!("TOPK.RESERVE"), key, b!("5"), b!("100000000"), b!("100"), b!("0.9")]));
+    assert_error(&cmd(&mut s, &[b!("TOPK.RESERVE"), key, b!("4294967296")]));
+
+    assert_integer(&cmd(&mut s, &[b!("EXISTS"), key]), 0);
+    assert_ok(&cmd(&mut s, &[b!("CMS.INITBYDIM"), key, b!("100"), b!("5")]));
+}
+
+// ── TS.* ──────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_time_series() {
+    let mut s = connect();
+    let key = b!("integ:ts:temp");
+    let compacted = b!("integ:ts:temp:sum");
+    cmd(&mut s, &[b!("DEL"), key, compacted]);
+
+    assert_ok(&cmd(&mut s, &[
+        b!("TS.CREATE"), key, b!("RETENTION"), b!("0"), b!("LABELS"), b!("sensor"), b!("integ-ts"),
+    ]));
+    assert_error(&cmd(&mut s, &[b!("TS.CREATE"), key]));
+    assert_ok(&cmd(&mut s, &[b!("TS.CREATE"), compacted]));
+    assert_ok(&cmd(&mut s, &[
+        b!("TS.CREATERULE"), key, compacted, b!("AGGREGATION"), b!("sum"), b!("10"),
+    ]));
+
+    assert_integer(&cmd(&mut s, &[b!("TS.ADD"), key, b!("1"), b!("1.5")]), 1);
+    assert_error(&cmd(&mut s, &[b!("TS.ADD"), key, b!("1"), b!("2")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("TS.MADD"), key, b!("5"), b!("2"), key, b!("12"), b!("4")]),
+        b"*2\r\n:5\r\n:12\r\n"
+    );
+    assert_integer(&cmd(&mut s, &[b!("TS.INCRBY"), key, b!("1"), b!("TIMESTAMP"), b!("15")]), 15);
+
+    assert_eq!(
+        cmd(&mut s, &[b!("TS.RANGE"), key, b!("-"), b!("+"), b!("COUNT"), b!("2")]),
+        b"*2\r\n*2\r\n:1\r\n$3\r\n1.5\r\n*2\r\n:5\r\n$1\r\n2\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("TS.REVRANGE"), key, b!("-"), b!("+"), b!("AGGREGATION"), b!("max"), b!("10")]),
+        b"*2\r\n*2\r\n:10\r\n$1\r\n5\r\n*2\r\n:0\r\n$1\r\n2\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("TS.RANGE"), compacted, b!("-"), b!("+")]),
+        b"*2\r\n*2\r\n:0\r\n$3\r\n3.5\r\n*2\r\n:10\r\n$1\r\n9\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[
+            b!("TS.MRANGE"), b!("0"), b!("1"), b!("FILTER"), b!("sensor=integ-ts"),
+        ]),
+        b"*1\r\n*3\r\n$13\r\ninteg:ts:temp\r\n*1\r\n*2\r\n$6\r\nsensor\r\n$8\r\ninteg-ts\r\n*1\r\n*2\r\n:1\r\n$3\r\n1.5\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("TS.RANGE"), b!("integ:ts:missing"), b!("-"), b!("+")]));
+}
+
+// ── FT.* ──────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_search_index() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("FT.DROPINDEX"), b!("integ:ft:idx"), b!("DD")]);
+
+    assert_ok(&cmd(&mut s, &[
+        b!("FT.CREATE"), b!("integ:ft:idx"), b!("ON"), b!("HASH"), b!("PREFIX"), b!("1"), b!("integ:ft:doc:"),
+        b!("SCHEMA"), b!("title"), b!("TEXT"), b!("year"), b!("NUMERIC"), b!("SORTABLE"), b!("genre"), b!("TAG"),
+    ]));
+    assert_error(&cmd(&mut s, &[
+        b!("FT.CREATE"), b!("integ:ft:idx"), b!("SCHEMA"), b!("title"), b!("TEXT"),
+    ]));
+
+    cmd(&mut s, &[b!("HSET"), b!("integ:ft:doc:1"), b!("title"), b!("The Matrix"), b!("year"), b!("1999"), b!("genre"), b!("scifi,action")]);
+    cmd(&mut s, &[b!("HSET"), b!("integ:ft:doc:2"), b!("title"), b!("The Matrix Reloaded"), b!("year"), b!("2003"), b!("genre"), b!("scifi")]);
+    cmd(&mut s, &[b!("HSET"), b!("integ:ft:doc:3"), b!("title"), b!("Heat"), b!("year"), b!("1995"), b!("genre"), b!("crime")]);
+
+    assert_eq!(
+        cmd(&mut s, &[b!("FT.SEARCH"), b!("integ:ft:idx"), b!("matrix @year:[2000 +inf]"), b!("RETURN"), b!("1"), b!("year")]),
+        b"*3\r\n:1\r\n$14\r\ninteg:ft:doc:2\r\n*2\r\n$4\r\nyear\r\n$4\r\n2003\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[
+            b!("FT.SEARCH"), b!("integ:ft:idx"), b!("@genre:{scifi|crime}"), b!("NOCONTENT"),
+            b!("SORTBY"), b!("year"), b!("DESC"), b!("LIMIT"), b!("0"), b!("2"),
+        ]),
+        b"*3\r\n:3\r\n$14\r\ninteg:ft:doc:2\r\n$14\r\ninteg:ft:doc:1\r\n"
+    );
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:ft:doc:2")]);
+    assert_eq!(
+        cmd(&mut s, &[b!("FT.SEARCH"), b!("integ:ft:idx"), b!("matrix"), b!("NOCONTENT")]),
+        b"*2\r\n:1\r\n$14\r\ninteg:ft:doc:1\r\n"
+    );
+
+    assert_error(&cmd(&mut s, &[b!("FT.SEARCH"), b!("integ:ft:idx"), b!("@year:[1")]));
+    assert_error(&cmd(&mut s, &[b!("FT.SEARCH"), b!("integ:ft:missing"), b!("*")]));
+    assert_ok(&cmd(&mut s, &[b!("FT.DROPINDEX"), b!("integ:ft:idx"), b!("DD")]));
+    assert_integer(&cmd(&mut s, &[b!("EXISTS"), b!("integ:ft:doc:1")]), 0);
+}
+
+#[test]
+fn test_vector_search() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("FT.DROPINDEX"), b!("integ:vec:idx"), b!("DD")]);
+
+    assert_ok(&cmd(&mut s, &[
+        b!("FT.CREATE"), b!("integ:vec:idx"), b!("PREFIX"), b!("1"), b!("integ:vec:doc:"),
+        b!("SCHEMA"), b!("color"), b!("TAG"), b!("embedding"), b!("VECTOR"), b!("HNSW"), b!("6"),
+        b!("TYPE"), b!("FLOAT32"), b!("DIM"), b!("2"), b!("DISTANCE_METRIC"), b!("L2"),
+    ]));
+
+    let blob = |x: f32, y: f32| [x.to_le_bytes(), y.to_le_bytes()].concat();
+
+    for (key, color, x) in [("integ:vec:doc:1", "red", 1.0), ("integ:vec:doc:2", "blue", 2.0), ("integ:vec:doc:3", "red", 5.0)] {
+        cmd(&mut s, &[b!("HSET"), b!(key), b!("color"), b!(color), b!("embedding"), &blob(x, 0.0)]);
+    }
+
+    let query = blob(2.5, 0.0);
+    assert_eq!(
+        cmd(&mut s, &[
+            b!("FT.SEARCH"), b!("integ:vec:idx"), b!("*=>[KNN 2 @embedding $vec AS dist]"),
+            b!("RETURN"), b!("1"), b!("dist"), b!("PARAMS"), b!("2"), b!("vec"), &query, b!("DIALECT"), b!("2"),
+        ]),
+        b"*5\r\n:2\r\n$15\r\ninteg:vec:doc:2\r\n*2\r\n$4\r\ndist\r\n$4\r\n0.25\r\n$15\r\ninteg:vec:doc:1\r\n*2\r\n$4\r\ndist\r\n$4\r\n2.25\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[
+            b!("FT.SEARCH"), b!("integ:vec:idx"), b!("@color:{red}=>[KNN 1 @embedding $vec]"), b!("NOCONTENT"),
+            b!("PARAMS"), b!("2"), b!("vec"), &query,
+        ]),
+        b"*2\r\n:1\r\n$15\r\ninteg:vec:doc:1\r\n"
+    );
+
+    assert_error(&cmd(&mut s, &[b!("FT.SEARCH"), b!("integ:vec:idx"), b!("*=>[KNN 1 @embedding $missing]")]));
+    assert_error(&cmd(&mut s, &[
+        b!("FT.CREATE"), b!("integ:vec:huge"), b!("SCHEMA"), b!("embedding"), b!("VECTOR"), b!("FLAT"), b!("6"),
+        b!("TYPE"), b!("FLOAT32"), b!("DIM"), b!("4611686018427387904"), b!("DISTANCE_METRIC"), b!("L2"),
+    ]));
+    assert_ok(&cmd(&mut s, &[b!("FT.DROPINDEX"), b!("integ:vec:idx"), b!("DD")]));
+}
+
+#[test]
+fn test_cl_throttle() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:throttle")]);
+
+    let throttle = || [b!("CL.THROTTLE"), b!("integ:throttle"), b!("1"), b!("1"), b!("60")];
+
+    assert_eq!(cmd(&mut s, &throttle()), b"*5\r\n:0\r\n:2\r\n:1\r\n:-1\r\n:60\r\n");
+    assert_eq!(cmd(&mut s, &throttle()), b"*5\r\n:0\r\n:2\r\n:0\r\n:-1\r\n:120\r\n");
+    assert_eq!(cmd(&mut s, &throttle()), b"*5\r\n:1\r\n:2\r\n:0\r\n:60\r\n:120\r\n");
+
+    assert_error(&cmd(&mut s, &[b!("CL.THROTTLE"), b!("integ:throttle"), b!("1"), b!("0"), b!("60")]));
+    assert_error(&cmd(&mut s, &[b!("CL.THROTTLE"), b!("integ:throttle"), b!("1")]));
+    cmd(&mut s, &[b!("DEL"), b!("integ:throttle")]);
+}
+
+#[test]
+fn test_scan_and_keys() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:scan:a"), b!("integ:scan:b"), b!("integ:scan:h")]);
+
+    cmd(&mut s, &[b!("SET"), b!("integ:scan:a"), b!("1")]);
+    cmd(&mut s, &[b!("SET"), b!("integ:scan:b"), b!("2")]);
+    cmd(&mut s, &[b!("HSET"), b!("integ:scan:h"), b!("field"), b!("value")]);
+
+    let reply = cmd(&mut s, &[b!("SCAN"), b!("0"), b!("MATCH"), b!("integ:scan:*"), b!("COUNT"), b!("1000000"), b!("TYPE"), b!("string")]);
+    assert!(reply.starts_with(b"*2\r\n$1\r\n0\r\n"));
+    let mut keys = parse_array(&reply[11..]);
+    keys.sort();
+    assert_eq!(keys, vec![Some(b"integ:scan:a".to_vec()), Some(b"integ:scan:b".to_vec())]);
+
+    assert_eq!(
+        cmd(&mut s, &[b!("HSCAN"), b!("integ:scan:h"), b!("0")]),
+        b"*2\r\n$1\r\n0\r\n*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("KEYS"), b!("integ:scan:[ab]")]),
+        b"*2\r\n$12\r\ninteg:scan:a\r\n$12\r\ninteg:scan:b\r\n"
+    );
+
+    assert_error(&cmd(&mut s, &[b!("SCAN"), b!("nope")]));
+    assert_error(&cmd(&mut s, &[b!("SCAN"), b!("0"), b!("COUNT"), b!("0")]));
+    assert_error(&cmd(&mut s, &[b!("SSCAN"), b!("integ:scan:h"), b!("0")]));
+    cmd(&mut s, &[b!("DEL"), b!("integ:scan:a"), b!("integ:scan:b"), b!("integ:scan:h")]);
+}
+
+#[test]
+fn test_generic_key_commands() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:gen:a"), b!("integ:gen:b"), b!("integ:gen:c")]);
+
+    cmd(&mut s, &[b!("SET"), b!("integ:gen:a"), b!("1"), b!("EX"), b!("100")]);
+    assert_eq!(cmd(&mut s, &[b!("TYPE"), b!("integ:gen:a")]), b"+string\r\n");
+    assert_eq!(cmd(&mut s, &[b!("TYPE"), b!("integ:gen:none")]), b"+none\r\n");
+
+    assert_ok(&cmd(&mut s, &[b!("RENAME"), b!("integ:gen:a"), b!("integ:gen:b")]));
+    assert_integer(&cmd(&mut s, &[b!("EXISTS"), b!("integ:gen:a")]), 0);
+    assert_error(&cmd(&mut s, &[b!("RENAME"), b!("integ:gen:a"), b!("integ:gen:b")]));
+
+    assert_integer(&cmd(&mut s, &[b!("COPY"), b!("integ:gen:b"), b!("integ:gen:c")]), 1);
+    assert_integer(&cmd(&mut s, &[b!("COPY"), b!("integ:gen:b"), b!("integ:gen:c")]), 0);
+    assert_integer(&cmd(&mut s, &[b!("RENAMENX"), b!("integ:gen:b"), b!("integ:gen:c")]), 0);
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:gen:c")]), b"1");
+
+    assert_integer(&cmd(&mut s, &[b!("TOUCH"), b!("integ:gen:b"), b!("integ:gen:c"), b!("integ:gen:a")]), 2);
+    assert!(cmd(&mut s, &[b!("RANDOMKEY")]).starts_with(b"$"));
+    assert!(cmd(&mut s, &[b!("DBSIZE")]).starts_with(b":"));
+
+    assert_integer(&cmd(&mut s, &[b!("UNLINK"), b!("integ:gen:b"), b!("integ:gen:c")]), 2);
+}
+
+#[test]
+fn test_multiple_databases() {
+    let mut s = connect();
+    assert_ok(&cmd(&mut s, &[b!("SELECT"), b!("15")]));
+    assert_ok(&cmd(&mut s, &[b!("FLUSHDB")]));
+
+    cmd(&mut s, &[b!("SET"), b!("integ:db:k"), b!("fifteen")]);
+    assert_integer(&cmd(&mut s, &[b!("DBSIZE")]), 1);
+
+    let mut other = connect();
+    assert_integer(&cmd(&mut other, &[b!("EXISTS"), b!("integ:db:k")]), 0);
+
+    assert_integer(&cmd(&mut s, &[b!("MOVE"), b!("integ:db:k"), b!("14")]), 1);
+    assert_integer(&cmd(&mut s, &[b!("EXISTS"), b!("integ:db:k")]), 0);
+    assert_error(&cmd(&mut s, &[b!("MOVE"), b!("integ:db:k"), b!("15")]));
+
+    assert_ok(&cmd(&mut s, &[b!("SWAPDB"), b!("14"), b!("15")]));
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:db:k")]), b"fifteen");
+    assert_ok(&cmd(&mut s, &[b!("SWAPDB"), b!("14"), b!("15")]));
+
+    assert_error(&cmd(&mut s, &[b!("SELECT"), b!("16")]));
+    assert_error(&cmd(&mut s, &[b!("FLUSHDB"), b!("LATER")]));
+
+    assert_ok(&cmd(&mut s, &[b!("SELECT"), b!("14")]));
+    assert_ok(&cmd(&mut s, &[b!("FLUSHDB"), b!("ASYNC")]));
+    assert_integer(&cmd(&mut s, &[b!("DBSIZE")]), 0);
+
+    assert_eq!(
+        cmd(&mut s, &[b!("CONFIG"), b!("GET"), b!("databases")]),
+        b"*2\r\n$9\r\ndatabases\r\n$2\r\n16\r\n"
+    );
+    assert_ok(&cmd(&mut s, &[b!("SELECT"), b!("0")]));
+}
+
+#[test]
+fn test_dump_restore() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:dump:src"), b!("integ:dump:dst")]);
+
+    cmd(&mut s, &[b!("HSET"), b!("integ:dump:src"), b!("field"), b!("value")]);
+    assert_eq!(cmd(&mut s, &[b!("DUMP"), b!("integ:dump:none")]), b"$-1\r\n");
+
+    // The payload is binary, so take it by its length rather than by lines.
+    let reply = cmd(&mut s, &[b!("DUMP"), b!("integ:dump:src")]);
+    let header_end = reply.iter().position(|&b| b == b'\r').unwrap();
+    let len: usize = std::str::from_utf8(&reply[1..header_end]).unwrap().parse().unwrap();
+    let payload = reply[header_end + 2..header_end + 2 + len].to_vec();
+
+    assert_ok(&cmd(&mut s, &[b!("RESTORE"), b!("integ:dump:dst"), b!("100000"), &payload]));
+    assert_bulk(&cmd(&mut s, &[b!("HGET"), b!("integ:dump:dst"), b!("field")]), b"value");
+    assert_integer(&cmd(&mut s, &[b!("TTL"), b!("integ:dump:dst")]), 100);
+
+    assert_error(&cmd(&mut s, &[b!("RESTORE"), b!("integ:dump:dst"), b!("0"), &payload]));
+    assert_ok(&cmd(&mut s, &[b!("RESTORE"), b!("integ:dump:dst"), b!("0"), &payload, b!("REPLACE")]));
+    assert_integer(&cmd(&mut s, &[b!("TTL"), b!("integ:dump:dst")]), -1);
+
+    assert_error(&cmd(&mut s, &[b!("RESTORE"), b!("integ:dump:bad"), b!("0"), b!("garbage")]));
+    assert_error(&cmd(&mut s, &[b!("RESTORE"), b!("integ:dump:bad"), b!("-1"), &payload]));
+    cmd(&mut s, &[b!("DEL"), b!("integ:dump:src"), b!("integ:dump:dst")]);
+}
+
+#[test]
+fn test_keyspace_notifications() {
+    let mut s = connect();
+    let mut subscriber = connect();
+
+    assert_ok(&cmd(&mut s, &[b!("CONFIG"), b!("SET"), b!("notify-keyspace-events"), b!("KEA")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("CONFIG"), b!("GET"), b!("notify-keyspace-events")]),
+        b"*2\r\n$22\r\nnotify-keyspace-events\r\n$3\r\nAKE\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("CONFIG"), b!("SET"), b!("notify-keyspace-events"), b!("Kq")]));
+
+    cmd(&mut subscriber, &[b!("SUBSCRIBE"), b!("__keyevent@15__:set")]);
+    cmd(&mut subscriber, &[b!("SUBSCRIBE"), b!("__keyspace@15__:integ:notify:k")]);
+
+    assert_ok(&cmd(&mut s, &[b!("SELECT"), b!("15")]));
+    assert_ok(&cmd(&mut s, &[b!("SET"), b!("integ:notify:k"), b!("v")]));
+
+    let mut messages = read_response(&mut subscriber);
+    if messages.len() < 100 {
+        messages.extend(read_response(&mut subscriber));
+    }
+
+    assert_eq!(
+        messages,
+        [
+            &b"*3\r\n$7\r\nmessage\r\n$30\r\n__keyspace@15__:integ:notify:k\r\n$3\r\nset\r\n"[..],
+            b"*3\r\n$7\r\nmessage\r\n$19\r\n__keyevent@15__:set\r\n$14\r\ninteg:notify:k\r\n",
+        ]
+        .concat()
+    );
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:notify:k")]);
+    assert_ok(&cmd(&mut s, &[b!("CONFIG"), b!("SET"), b!("notify-keyspace-events"), b!("")]));
+    assert_ok(&cmd(&mut s, &[b!("SELECT"), b!("0")]));
+}
+
+#[test]
+fn test_sort() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:sort:l"), b!("integ:sort:dst"), b!("integ:sort:w_a"), b!("integ:sort:w_b")]);
+
+    cmd(&mut s, &[b!("RPUSH"), b!("integ:sort:l"), b!("b"), b!("a"), b!("c")]);
+    cmd(&mut s, &[b!("SET"), b!("integ:sort:w_a"), b!("3")]);
+    cmd(&mut s, &[b!("SET"), b!("integ:sort:w_b"), b!("1")]);
+
+    assert_eq!(
+        cmd(&mut s, &[b!("SORT"), b!("integ:sort:l"), b!("ALPHA"), b!("DESC"), b!("LIMIT"), b!("0"), b!("2")]),
+        b"*2\r\n$1\r\nc\r\n$1\r\nb\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("SORT_RO"), b!("integ:sort:l"), b!("BY"), b!("integ:sort:w_*"), b!("GET"), b!("integ:sort:w_*")]),
+        b"*3\r\n$-1\r\n$1\r\n1\r\n$1\r\n3\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("SORT"), b!("integ:sort:l")]));
+    assert_error(&cmd(&mut s, &[b!("SORT_RO"), b!("integ:sort:l"), b!("ALPHA"), b!("STORE"), b!("integ:sort:dst")]));
+
+    assert_integer(&cmd(&mut s, &[b!("SORT"), b!("integ:sort:l"), b!("ALPHA"), b!("STORE"), b!("integ:sort:dst")]), 3);
+    assert_eq!(
+        cmd(&mut s, &[b!("LRANGE"), b!("integ:sort:dst"), b!("0"), b!("-1")]),
+        b"*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n"
+    );
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:sort:l"), b!("integ:sort:dst"), b!("integ:sort:w_a"), b!("integ:sort:w_b")]);
+}
+
+#[test]
+fn test_object_and_memory() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:object:k")]);
+
+    assert_ok(&cmd(&mut s, &[b!("SET"), b!("integ:object:k"), b!("123")]));
+    assert_bulk(&cmd(&mut s, &[b!("OBJECT"), b!("ENCODING"), b!("integ:object:k")]), b"int");
+    assert_integer(&cmd(&mut s, &[b!("OBJECT"), b!("REFCOUNT"), b!("integ:object:k")]), 1);
+    assert_integer(&cmd(&mut s, &[b!("OBJECT"), b!("IDLETIME"), b!("integ:object:k")]), 0);
+    assert_eq!(cmd(&mut s, &[b!("OBJECT"), b!("FREQ"), b!("integ:object:none")]), b"$-1\r\n");
+    assert_error(&cmd(&mut s, &[b!("OBJECT"), b!("BOGUS"), b!("integ:object:k")]));
+
+    assert_eq!(cmd(&mut s, &[b!("MEMORY"), b!("USAGE"), b!("integ:object:k")])[0], b':');
+    assert_eq!(
+        cmd(&mut s, &[b!("MEMORY"), b!("USAGE"), b!("integ:object:k"), b!("SAMPLES"), b!("0")])[0],
+        b':'
+    );
+    assert_error(&cmd(&mut s, &[b!("MEMORY"), b!("USAGE"), b!("integ:object:k"), b!("SAMPLES")]));
+
+    let stats = cmd(&mut s, &[b!("MEMORY"), b!("STATS")]);
+    assert!(stats.windows(19).any(|window| window == b"allocator.allocated"));
+    assert_ok(&cmd(&mut s, &[b!("MEMORY"), b!("PURGE")]));
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:object:k")]);
+}
+
+#[test]
+fn test_compact_encodings() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:compact:h"), b!("integ:compact:s")]);
+
+    cmd(&mut s, &[b!("HSET"), b!("integ:compact:h"), b!("f"), b!("v")]);
+    cmd(&mut s, &[b!("SADD"), b!("integ:compact:s"), b!("1"), b!("2")]);
+    assert_bulk(&cmd(&mut s, &[b!("OBJECT"), b!("ENCODING"), b!("integ:compact:h")]), b"listpack");
+    assert_bulk(&cmd(&mut s, &[b!("OBJECT"), b!("ENCODING"), b!("integ:compact:s")]), b"intset");
+
+    cmd(&mut s, &[b!("SADD"), b!("integ:compact:s"), b!("x")]);
+    assert_bulk(&cmd(&mut s, &[b!("OBJECT"), b!("ENCODING"), b!("integ:compact:s")]), b"listpack");
+
+    assert_ok(&cmd(&mut s, &[b!("CONFIG"), b!("SET"), b!("set-max-intset-entries"), b!("512")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("CONFIG"), b!("GET"), b!("set-max-intset-entries")]),
+        b"*2\r\n$22\r\nset-max-intset-entries\r\n$3\r\n512\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("CONFIG"), b!("SET"), b!("set-max-intset-entries"), b!("-1")]));
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:compact:h"), b!("integ:compact:s")]);
+}
+
+#[test]
+fn test_transactions() {
+    let mut s = connect();
+    let mut other = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:multi:n"), b!("integ:multi:l")]);
+
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    assert_error(&cmd(&mut s, &[b!("MULTI")]));
+    assert_eq!(cmd(&mut s, &[b!("INCR"), b!("integ:multi:n")]), b"+QUEUED\r\n");
+    assert_eq!(cmd(&mut s, &[b!("RPUSH"), b!("integ:multi:l"), b!("a")]), b"+QUEUED\r\n");
+    assert_eq!(cmd(&mut s, &[b!("PING")]), b"+QUEUED\r\n");
+    assert_null_bulk(&cmd(&mut other, &[b!("GET"), b!("integ:multi:n")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("EXEC")]),
+        b"*3\r\n:1\r\n:1\r\n+PONG\r\n"
+    );
+    assert_bulk(&cmd(&mut other, &[b!("GET"), b!("integ:multi:n")]), b"1");
+
+    // Errors at run time are replied in place, the rest still runs.
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    cmd(&mut s, &[b!("INCR"), b!("integ:multi:l")]);
+    cmd(&mut s, &[b!("INCR"), b!("integ:multi:n")]);
+    let resp = cmd(&mut s, &[b!("EXEC")]);
+    assert!(resp.starts_with(b"*2\r\n-ERR"));
+    assert!(resp.ends_with(b":2\r\n"));
+
+    // A command refused while queuing discards the whole transaction.
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    cmd(&mut s, &[b!("INCR"), b!("integ:multi:n")]);
+    assert_error(&cmd(&mut s, &[b!("INCR")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("EXEC")]),
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n"
+    );
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:multi:n")]), b"2");
+
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    cmd(&mut s, &[b!("INCR"), b!("integ:multi:n")]);
+    assert_ok(&cmd(&mut s, &[b!("DISCARD")]));
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:multi:n")]), b"2");
+
+    assert_error(&cmd(&mut s, &[b!("EXEC")]));
+    assert_error(&cmd(&mut s, &[b!("DISCARD")]));
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:multi:n"), b!("integ:multi:l")]);
+}
+
+#[test]
+fn test_watch() {
+    let mut s = connect();
+    let mut other = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:watch:k"), b!("integ:watch:e")]);
+    cmd(&mut s, &[b!("SET"), b!("integ:watch:k"), b!("1")]);
+
+    // Untouched watched keys let EXEC through.
+    assert_ok(&cmd(&mut s, &[b!("WATCH"), b!("integ:watch:k")]));
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    cmd(&mut s, &[b!("INCR"), b!("integ:watch:k")]);
+    assert_eq!(cmd(&mut s, &[b!("EXEC")]), b"*1\r\n:2\r\n");
+
+    // A write from another client makes it fail.
+    assert_ok(&cmd(&mut s, &[b!("WATCH"), b!("integ:watch:k")]));
+    cmd(&mut other, &[b!("SET"), b!("integ:watch:k"), b!("10")]);
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    cmd(&mut s, &[b!("INCR"), b!("integ:watch:k")]);
+    assert_null_bulk(&cmd(&mut s, &[b!("EXEC")]));
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:watch:k")]), b"10");
+
+    // EXEC released the watch, so the next transaction goes through.
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    cmd(&mut s, &[b!("INCR"), b!("integ:watch:k")]);
+    assert_eq!(cmd(&mut s, &[b!("EXEC")]), b"*1\r\n:11\r\n");
+
+    // So do deletion and expiry.
+    assert_ok(&cmd(&mut s, &[b!("WATCH"), b!("integ:watch:k")]));
+    cmd(&mut other, &[b!("DEL"), b!("integ:watch:k")]);
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    assert_null_bulk(&cmd(&mut s, &[b!("EXEC")]));
+
+    cmd(&mut s, &[b!("SET"), b!("integ:watch:e"), b!("v"), b!("EX"), b!("1")]);
+    assert_ok(&cmd(&mut s, &[b!("WATCH"), b!("integ:watch:e")]));
+    std::thread::sleep(std::time::Duration::from_millis(2100));
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    assert_null_bulk(&cmd(&mut s, &[b!("EXEC")]));
+
+    // UNWATCH and DISCARD let go of the keys.
+    assert_ok(&cmd(&mut s, &[b!("WATCH"), b!("integ:watch:k")]));
+    assert_ok(&cmd(&mut s, &[b!("UNWATCH")]));
+    cmd(&mut other, &[b!("SET"), b!("integ:watch:k"), b!("1")]);
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    assert_eq!(cmd(&mut s, &[b!("EXEC")]), b"*0\r\n");
+
+    assert_ok(&cmd(&mut s, &[b!("MULTI")]));
+    assert_error(&cmd(&mut s, &[b!("WATCH"), b!("integ:watch:k")]));
+    assert_error(&cmd(&mut s, &[b!("EXEC")]));
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:watch:k"), b!("integ:watch:e")]);
+}
+
+#[test]
+fn test_scripting() {
+    let mut s = connect();
+    let mut other = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:script:k")]);
+
+    // KEYS and ARGV, and redis.call straight into the dataset.
+    assert_eq!(
+        cmd(
+            &mut s,
+            &[
+                b!("EVAL"),
+                b!("return {KEYS[1], ARGV[1], #KEYS, #ARGV}"),
+                b!("1"),
+                b!("integ:script:k"),
+                b!("a"),
+                b!("b"),
+            ]
+        ),
+        b"*4\r\n$14\r\ninteg:script:k\r\n$1\r\na\r\n:1\r\n:2\r\n"
+    );
+    assert_ok(&cmd(
+        &mut s,
+        &[
+            b!("EVAL"),
+            b!("return redis.call('SET', KEYS[1], ARGV[1])"),
+            b!("1"),
+            b!("integ:script:k"),
+            b!("7"),
+        ],
+    ));
+    assert_integer(
+        &cmd(
+            &mut s,
+            &[
+                b!("EVAL"),
+                b!("return redis.call('INCR', KEYS[1]) + 1"),
+                b!("1"),
+                b!("integ:script:k"),
+            ],
+        ),
+        9,
+    );
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:script:k")]), b"8");
+    assert_eq!(
+        cmd(&mut s, &[b!("EVAL"), b!("return redis.call('GET', 'integ:script:none')"), b!("0")]),
+        b"$-1\r\n"
+    );
+
+    // Scripts are cached by their SHA1.
+    assert_bulk(
+        &cmd(&mut s, &[b!("SCRIPT"), b!("LOAD"), b!("return 1")]),
+        b"e0e1f9fabfc9d4800c877a703b823ac0578ff8db",
+    );
+    assert_integer(
+        &cmd(&mut s, &[b!("EVALSHA"), b!("e0e1f9fabfc9d4800c877a703b823ac0578ff8db"), b!("0")]),
+        1,
+    );
+    assert_eq!(
+        cmd(
+            &mut s,
+            &[b!("SCRIPT"), b!("EXISTS"), b!("e0e1f9fabfc9d4800c877a703b823ac0578ff8db"), b!("ffff")]
+        ),
+        b"*2\r\n:1\r\n:0\r\n"
+    );
+    assert_ok(&cmd(&mut s, &[b!("SCRIPT"), b!("FLUSH")]));
+    assert!(
+        cmd(&mut s, &[b!("EVALSHA"), b!("e0e1f9fabfc9d4800c877a703b823ac0578ff8db"), b!("0")])
+            .starts_with(b"-NOSCRIPT")
+    );
+
+    // Error and status replies, caught and raised.
+    assert_eq!(
+        cmd(&mut s, &[b!("EVAL"), b!("return redis.error_reply('MY failure')"), b!("0")]),
+        b"-MY failure\r\n"
+    );
+    assert_eq!(
+        cmd(&mut s, &[b!("EVAL"), b!("return redis.status_reply('FINE')"), b!("0")]),
+        b"+FINE\r\n"
+    );
+    assert_eq!(
+        cmd(
+            &mut s,
+            &[
+                b!("EVAL"),
+                b!("return redis.pcall('INCR', KEYS[1], 'x')['err'] ~= nil"),
+                b!("1"),
+                b!("integ:script:k"),
+            ]
+        ),
+        b":1\r\n"
+    );
+    assert_error(&cmd(
+        &mut s,
+        &[b!("EVAL"), b!("return redis.call('NOSUCHCOMMAND')"), b!("0")],
+    ));
+    assert_error(&cmd(&mut s, &[b!("EVAL"), b!("leak = 1"), b!("0")]));
+    assert_error(&cmd(&mut s, &[b!("EVAL"), b!("rawset(_G, 'x', 1)"), b!("0")]));
+    assert_error(&cmd(&mut s, &[b!("EVAL"), b!("setmetatable(_G, nil)"), b!("0")]));
+    assert_eq!(
+        cmd(&mut s, &[b!("EVAL"), b!("return getmetatable(_G) == false"), b!("0")]),
+        b":1\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("EVAL"), b!("return x"), b!("0")]));
+    assert_integer(
+        &cmd(&mut s, &[b!("EVAL"), b!("local t = {} rawset(t, 'x', 1) return t.x"), b!("0")]),
+        1,
+    );
+    assert_error(&cmd(&mut s, &[b!("EVAL"), b!("return ("), b!("0")]));
+    assert_error(&cmd(&mut s, &[b!("EVAL"), b!("return 1"), b!("2"), b!("k")]));
+
+    // A runaway read-only script can be killed from another connection.
+    assert!(cmd(&mut s, &[b!("SCRIPT"), b!("KILL")]).starts_with(b"-NOTBUSY"));
+    send_command(&mut s, &[b!("EVAL"), b!("while true do end"), b!("0")]);
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    assert_ok(&cmd(&mut other, &[b!("SCRIPT"), b!("KILL")]));
+    assert!(read_response(&mut s).starts_with(b"-ERR Script killed"));
+    assert_pong(&cmd(&mut s, &[b!("PING")]));
+
+    cmd(&mut s, &[b!("DEL"), b!("integ:script:k")]);
+}
+
+#[test]
+fn test_functions() {
+    let mut s = connect();
+    cmd(&mut s, &[b!("DEL"), b!("integ:function:k")]);
+    cmd(&mut s, &[b!("FUNCTION"), b!("DELETE"), b!("integlib")]);
+
+    let library: &[u8] = b"#!lua name=integlib\n\
+        redis.register_function('integ_set', function(keys, args) return redis.call('SET', keys[1], args[1]) end)\n\
+        redis.register_function{function_name='integ_get', callback=function(keys) return redis.call('GET', keys[1]) end, flags={'no-writes'}}\n\
+        redis.register_function{function_name='integ_sneaky', callback=function(keys) return redis.call('SET', keys[1], 'x') end, flags={'no-writes'}}";
+
+    assert_bulk(&cmd(&mut s, &[b!("FUNCTION"), b!("LOAD"), library]), b"integlib");
+    assert!(
+        cmd(&mut s, &[b!("FUNCTION"), b!("LOAD"), library])
+            .starts_with(b"-ERR Library 'integlib' already exists")
+    );
+    assert_bulk(
+        &cmd(&mut s, &[b!("FUNCTION"), b!("LOAD"), b!("REPLACE"), library]),
+        b"integlib",
+    );
+    assert_error(&cmd(&mut s, &[b!("FUNCTION"), b!("LOAD"), b!("return 1")]));
+
+    // FCALL_RO only runs no-writes functions, and those can't write.
+    assert_ok(&cmd(
+        &mut s,
+        &[b!("FCALL"), b!("integ_set"), b!("1"), b!("integ:function:k"), b!("v")],
+    ));
+    assert_bulk(
+        &cmd(&mut s, &[b!("FCALL_RO"), b!("integ_get"), b!("1"), b!("integ:function:k")]),
+        b"v",
+    );
+    assert_error(&cmd(
+        &mut s,
+        &[b!("FCALL_RO"), b!("integ_set"), b!("1"), b!("integ:function:k"), b!("w")],
+    ));
+    assert_error(&cmd(
+        &mut s,
+        &[b!("FCALL"), b!("integ_sneaky"), b!("1"), b!("integ:function:k")],
+    ));
+    assert_bulk(&cmd(&mut s, &[b!("GET"), b!("integ:function:k")]), b"v");
+    assert_error(&cmd(&mut s, &[b!("FCALL"), b!("integ_none"), b!("0")]));
+
+    let list = cmd(&mut s, &[b!("FUNCTION"), b!("LIST"), b!("LIBRARYNAME"), b!("integ*")]);
+    assert!(list.windows(8).any(|w| w == b"integlib"));
+    assert!(list.windows(9).any(|w| w == b"no-writes"));
+
+    // DUMP and RESTORE bring a deleted library back.
+    let dump = cmd(&mut s, &[b!("FUNCTION"), b!("DUMP")]);
+    let header_end = dump.iter().position(|&b| b == b'\n').unwrap() + 1;
+    let payload = dump[header_end..dump.len() - 2].to_vec();
+    assert_ok(&cmd(&mut s, &[b!("FUNCTION"), b!("DELETE"), b!("integlib")]));
+    assert_error(&cmd(&mut s, &[b!("FCALL"), b!("integ_set"), b!("0")]));
+    assert_ok(&cmd(&mut s, &[b!("FUNCTION"), b!("RESTORE"), &payload]));
+    assert_bulk(
+        &cmd(&mut s, &[b!("FCALL_RO"), b!("integ_get"), b!("1"), b!("integ:function:k")]),
+        b"v",
+    );
+    assert_error(&cmd(&mut s, &[b!("FUNCTION"), b!("RESTORE"), &payload, b!("APPEND")]));
+    assert_ok(&cmd(&mut s, &[b!("FUNCTION"), b!("RESTORE"), &payload, b!("REPLACE")]));
+
+    assert_ok(&cmd(&mut s, &[b!("FUNCTION"), b!("DELETE"), b!("integlib")]));
+    assert_error(&cmd(&mut s, &[b!("FUNCTION"), b!("DELETE"), b!("integlib")]));
+    cmd(&mut s, &[b!("DEL"), b!("integ:function:k")]);
+}
+
+// ── COMMAND ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_command_table() {
+    let mut s = connect();
+
+    let count = cmd(&mut s, &[b!("COMMAND"), b!("COUNT")]);
+    assert!(parse_integer_line(&count[..count.len() - 2]) > 90);
+
+    let info = cmd(&mut s, &[b!("COMMAND"), b!("INFO"), b!("get"), b!("nosuchcommand")]);
+    assert!(info.starts_with(b"*2\r\n*10\r\n$3\r\nget\r\n:2\r\n"));
+    assert!(info.windows(9).any(|w| w == b"+readonly"));
+    assert!(info.ends_with(b"$-1\r\n"));
+
+    let keys = cmd(
+        &mut s,
+        &[b!("COMMAND"), b!("GETKEYS"), b!("MSET"), b!("a"), b!("1"), b!("b"), b!("2")],
+    );
+    assert_eq!(parse_array(&keys), vec![Some(b"a".to_vec()), Some(b"b".to_vec())]);
+    assert_error(&cmd(&mut s, &[b!("COMMAND"), b!("GETKEYS"), b!("PING")]));
+    assert_error(&cmd(&mut s, &[b!("COMMAND"), b!("GETKEYS"), b!("GET")]));
+
+    // The table's arity is checked before the command runs.
+    assert_eq!(
+        cmd(&mut s, &[b!("GET")]),
+        b"-ERR wrong number of arguments for 'get' command\r\n"
+    );
+    assert_error(&cmd(&mut s, &[b!("COMMAND"), b!("NOSUCH")]));
+}
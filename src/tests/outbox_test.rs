@@ -0,0 +1,59 @@
+// src/tests/outbox_test.rs
+//
+// Unit tests for client-output-buffer-limit parsing. The outboxes themselves
+// need a socket and are covered by the pub/sub integration tests.
+
+use crate::egress::outbox::{Class, Limit, OutputLimits};
+
+#[test]
+fn defaults_match_redis() {
+    assert_eq!(
+        OutputLimits::default().entry(),
+        b"normal 0 0 0 replica 268435456 67108864 60 pubsub 33554432 8388608 60".to_vec()
+    );
+}
+
+#[test]
+fn set_changes_only_the_classes_named() {
+    let mut limits = OutputLimits::default();
+
+    assert!(limits.set(b"pubsub 1mb 256kb 10"));
+    assert_eq!(
+        limits.get(Class::Pubsub),
+        Limit {
+            hard: 1024 * 1024,
+            soft: 256 * 1024,
+            soft_seconds: 10,
+        }
+    );
+    assert_eq!(limits.get(Class::Normal), OutputLimits::default().normal);
+}
+
+#[test]
+fn set_takes_several_classes_and_every_unit() {
+    let mut limits = OutputLimits::default();
+
+    assert!(limits.set(b"NORMAL 2k 1b 0  slave 3g 1gb 5"));
+    assert_eq!(limits.get(Class::Normal).hard, 2000);
+    assert_eq!(limits.get(Class::Normal).soft, 1);
+    assert_eq!(limits.get(Class::Replica).hard, 3_000_000_000);
+    assert_eq!(limits.get(Class::Replica).soft, 1024 * 1024 * 1024);
+}
+
+#[test]
+fn set_rejects_bad_values_without_changing_anything() {
+    let mut limits = OutputLimits::default();
+
+    for value in [
+        b"".as_slice(),
+        b"pubsub 1mb 1mb",
+        b"pubsub 1mb 1mb 10 normal 1tb 0 0",
+        b"master 0 0 0",
+        b"pubsub -1 0 0",
+        b"pubsub 1mb 1mb soon",
+    ] {
+        assert!(!limits.set(value), "{:?}", value);
+    }
+
+    assert_eq!(limits, OutputLimits::default());
+}
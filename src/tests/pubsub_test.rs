@@ -47,6 +47,19 @@ fn read_response(stream: &mut TcpStream) -> Vec<u8> {
     buf
 }
 
+/// Keep reading until `needle` has arrived, or the server goes quiet.
+fn read_until(stream: &mut TcpStream, needle: &[u8]) -> Vec<u8> {
+    let mut received = Vec::new();
+    while !contains_bytes(&received, needle) {
+        let chunk = read_response(stream);
+        if chunk.is_empty() {
+            break;
+        }
+        received.extend_from_slice(&chunk);
+    }
+    received
+}
+
 // ── RESP2 helpers ─────────────────────────────────────────────────────────────
 
 /// Count non-overlapping occurrences of `needle` in `haystack`.
@@ -263,3 +276,236 @@ fn test_ping_in_subscriber_mode() {
         "Expected pong in subscriber mode, got: {:?}", resp
     );
 }
+
+/// A pattern subscriber gets a pmessage carrying the pattern and the channel,
+/// and both kinds of subscription count towards the reply counts.
+#[test]
+fn test_psubscribe_receives_matching_messages() {
+    let barrier = Arc::new(Barrier::new(2));
+    let barrier_sub = Arc::clone(&barrier);
+
+    let subscriber = thread::spawn(move || {
+        let mut s = connect();
+
+        send_command(&mut s, &[b!("PSUBSCRIBE"), b!("pubsub:pattern:*")]);
+        let confirm = read_response(&mut s);
+        assert!(
+            contains_bytes(&confirm, b"psubscribe") && contains_bytes(&confirm, b":1\r\n"),
+            "Expected psubscribe confirmation, got: {:?}", confirm
+        );
+
+        send_command(&mut s, &[b!("SUBSCRIBE"), b!("pubsub:pattern:a")]);
+        let confirm = read_response(&mut s);
+        assert!(
+            contains_bytes(&confirm, b":2\r\n"),
+            "Expected the channel to count after the pattern, got: {:?}", confirm
+        );
+
+        barrier_sub.wait();
+
+        let mut msgs = read_response(&mut s);
+        if count_subsequence(&msgs, b"hello") < 2 {
+            msgs.extend(read_response(&mut s));
+        }
+        assert!(contains_bytes(&msgs, b"$7\r\nmessage"), "Missing message: {:?}", msgs);
+        assert!(contains_bytes(&msgs, b"$8\r\npmessage"), "Missing pmessage: {:?}", msgs);
+        assert!(contains_bytes(&msgs, b"pubsub:pattern:*"), "Missing pattern: {:?}", msgs);
+
+        send_command(&mut s, &[b!("PUNSUBSCRIBE")]);
+        let resp = read_response(&mut s);
+        assert!(
+            contains_bytes(&resp, b"punsubscribe") && contains_bytes(&resp, b":1\r\n"),
+            "Expected the channel to remain, got: {:?}", resp
+        );
+    });
+
+    let mut s = connect();
+    barrier.wait();
+    thread::sleep(Duration::from_millis(50));
+
+    send_command(&mut s, &[b!("PUBLISH"), b!("pubsub:pattern:a"), b!("hello")]);
+    let resp = read_response(&mut s);
+    assert_eq!(parse_integer(&resp), 2, "Expected 2 deliveries, got: {:?}", resp);
+
+    subscriber.join().unwrap();
+}
+
+/// PUBSUB reports the channels with subscribers, how many each has and how
+/// many patterns are subscribed to.
+#[test]
+fn test_pubsub_introspection() {
+    let mut sub = connect();
+    send_command(&mut sub, &[b!("SUBSCRIBE"), b!("pubsub:intro:a"), b!("pubsub:intro:b")]);
+    read_response(&mut sub);
+    send_command(&mut sub, &[b!("PSUBSCRIBE"), b!("pubsub:intro:*")]);
+    read_response(&mut sub);
+
+    let mut s = connect();
+    thread::sleep(Duration::from_millis(50));
+
+    send_command(&mut s, &[b!("PUBSUB"), b!("CHANNELS"), b!("pubsub:intro:*")]);
+    let resp = read_response(&mut s);
+    assert!(resp.starts_with(b"*2\r\n"), "Expected 2 channels, got: {:?}", resp);
+    assert!(contains_bytes(&resp, b"pubsub:intro:a") && contains_bytes(&resp, b"pubsub:intro:b"));
+
+    send_command(&mut s, &[b!("PUBSUB"), b!("NUMSUB"), b!("pubsub:intro:a"), b!("pubsub:intro:none")]);
+    let resp = read_response(&mut s);
+    assert!(resp.starts_with(b"*4\r\n$14\r\npubsub:intro:a\r\n:"), "Got: {:?}", resp);
+    assert!(resp.ends_with(b"$17\r\npubsub:intro:none\r\n:0\r\n"), "Got: {:?}", resp);
+
+    send_command(&mut s, &[b!("PUBSUB"), b!("NUMPAT")]);
+    let resp = read_response(&mut s);
+    assert!(parse_integer(&resp) >= 1, "Expected a pattern, got: {:?}", resp);
+}
+
+/// A subscriber that hangs up is forgotten: its channels and patterns no
+/// longer count towards PUBLISH or PUBSUB.
+#[test]
+fn test_disconnected_subscriber_is_forgotten() {
+    let mut sub = connect();
+    send_command(&mut sub, &[b!("SUBSCRIBE"), b!("pubsub:depart")]);
+    read_response(&mut sub);
+
+    let mut psub = connect();
+    send_command(&mut psub, &[b!("PSUBSCRIBE"), b!("pubsub:depart*")]);
+    read_response(&mut psub);
+
+    drop(sub);
+    drop(psub);
+    thread::sleep(Duration::from_millis(200));
+
+    let mut s = connect();
+    send_command(&mut s, &[b!("PUBLISH"), b!("pubsub:depart"), b!("hello")]);
+    let resp = read_response(&mut s);
+    assert_eq!(parse_integer(&resp), 0, "Expected no deliveries, got: {:?}", resp);
+
+    let mut s = connect();
+    send_command(&mut s, &[b!("PUBSUB"), b!("NUMSUB"), b!("pubsub:depart")]);
+    let resp = read_response(&mut s);
+    assert_eq!(resp, b"*2\r\n$13\r\npubsub:depart\r\n:0\r\n", "Got: {:?}", resp);
+}
+
+/// With replay buffers on, SUBSCRIBE ... REPLAY id catches up on the messages
+/// published after that sequence ID, then keeps numbering live ones.
+#[test]
+fn test_subscribe_replays_missed_messages() {
+    let mut s = connect();
+    send_command(&mut s, &[b!("CONFIG"), b!("SET"), b!("pubsub-replay-size"), b!("10")]);
+    assert_eq!(read_response(&mut s), b"+OK\r\n");
+
+    for message in ["one", "two", "three"] {
+        send_command(&mut s, &[b!("PUBLISH"), b!("pubsub:replay"), b!(message)]);
+        read_response(&mut s);
+    }
+
+    let mut sub = connect();
+    send_command(&mut sub, &[b!("SUBSCRIBE"), b!("pubsub:replay"), b!("REPLAY"), b!("1")]);
+    let resp = read_until(&mut sub, b"three\r\n:3\r\n");
+    assert!(
+        resp.starts_with(b"*3\r\n$9\r\nsubscribe\r\n$13\r\npubsub:replay\r\n:1\r\n"),
+        "Expected the confirmation first, got: {:?}", resp
+    );
+    assert!(
+        resp.ends_with(
+            b"*4\r\n$7\r\nmessage\r\n$13\r\npubsub:replay\r\n$3\r\ntwo\r\n:2\r\n\
+              *4\r\n$7\r\nmessage\r\n$13\r\npubsub:replay\r\n$5\r\nthree\r\n:3\r\n"
+        ),
+        "Expected messages 2 and 3 replayed, got: {:?}", resp
+    );
+
+    thread::sleep(Duration::from_millis(50));
+    send_command(&mut s, &[b!("PUBLISH"), b!("pubsub:replay"), b!("four")]);
+    assert_eq!(parse_integer(&read_response(&mut s)), 1);
+
+    let resp = read_until(&mut sub, b"four");
+    assert_eq!(
+        resp, b"*4\r\n$7\r\nmessage\r\n$13\r\npubsub:replay\r\n$4\r\nfour\r\n:4\r\n",
+        "Expected the live message numbered, got: {:?}", resp
+    );
+
+    send_command(&mut s, &[b!("SUBSCRIBE"), b!("pubsub:replay"), b!("REPLAY"), b!("later")]);
+    assert!(read_response(&mut s).starts_with(b"-ERR"));
+
+    let mut s = connect();
+    send_command(&mut s, &[b!("CONFIG"), b!("SET"), b!("pubsub-replay-size"), b!("0")]);
+    assert_eq!(read_response(&mut s), b"+OK\r\n");
+}
+
+/// A subscriber that stops reading is disconnected once more than the pubsub
+/// hard limit is waiting for it, without holding up the publisher.
+#[test]
+fn test_slow_subscriber_is_disconnected() {
+    let mut s = connect();
+    send_command(&mut s, &[b!("CONFIG"), b!("SET"), b!("client-output-buffer-limit"), b!("pubsub 256kb 0 0")]);
+    assert_eq!(read_response(&mut s), b"+OK\r\n");
+
+    let mut sub = connect();
+    send_command(&mut sub, &[b!("SUBSCRIBE"), b!("pubsub:slow")]);
+    read_response(&mut sub);
+
+    // Well past what the socket buffers hold, and never read.
+    let payload = vec![b'x'; 64 * 1024];
+    let mut p = connect();
+    for _ in 0..400 {
+        send_command(&mut p, &[b!("PUBLISH"), b!("pubsub:slow"), &payload]);
+        read_response(&mut p);
+    }
+    thread::sleep(Duration::from_millis(200));
+
+    send_command(&mut p, &[b!("PUBSUB"), b!("NUMSUB"), b!("pubsub:slow")]);
+    let resp = read_response(&mut p);
+    assert_eq!(resp, b"*2\r\n$11\r\npubsub:slow\r\n:0\r\n", "Got: {:?}", resp);
+
+    send_command(&mut s, &[b!("CONFIG"), b!("SET"), b!("client-output-buffer-limit"), b!("pubsub 32mb 8mb 60")]);
+    assert_eq!(read_response(&mut s), b"+OK\r\n");
+    drop(sub);
+}
+
+/// Shard channels are subscribed to, counted and published on apart from
+/// global channels of the same name, and their messages arrive as smessage.
+#[test]
+fn test_sharded_pubsub() {
+    let mut sub = connect();
+    send_command(&mut sub, &[b!("SSUBSCRIBE"), b!("pubsub:shard:a"), b!("pubsub:shard:b")]);
+    let resp = read_until(&mut sub, b"pubsub:shard:b\r\n:2\r\n");
+    assert_eq!(
+        resp,
+        b"*3\r\n$10\r\nssubscribe\r\n$14\r\npubsub:shard:a\r\n:1\r\n\
+          *3\r\n$10\r\nssubscribe\r\n$14\r\npubsub:shard:b\r\n:2\r\n"
+    );
+
+    // Its shard subscriptions don't count towards its global ones.
+    send_command(&mut sub, &[b!("SUBSCRIBE"), b!("pubsub:shard:a")]);
+    let resp = read_response(&mut sub);
+    assert_eq!(resp, b"*3\r\n$9\r\nsubscribe\r\n$14\r\npubsub:shard:a\r\n:1\r\n");
+
+    thread::sleep(Duration::from_millis(50));
+
+    let mut s = connect();
+    send_command(&mut s, &[b!("PUBSUB"), b!("SHARDCHANNELS"), b!("pubsub:shard:*")]);
+    let resp = read_response(&mut s);
+    assert!(resp.starts_with(b"*2\r\n"), "Expected 2 shard channels, got: {:?}", resp);
+
+    let mut s = connect();
+    send_command(&mut s, &[b!("PUBSUB"), b!("SHARDNUMSUB"), b!("pubsub:shard:b"), b!("pubsub:shard:c")]);
+    let resp = read_response(&mut s);
+    assert_eq!(resp, b"*4\r\n$14\r\npubsub:shard:b\r\n:1\r\n$14\r\npubsub:shard:c\r\n:0\r\n");
+
+    let mut s = connect();
+    send_command(&mut s, &[b!("SPUBLISH"), b!("pubsub:shard:b"), b!("hello")]);
+    assert_eq!(parse_integer(&read_response(&mut s)), 1);
+
+    let resp = read_until(&mut sub, b"hello");
+    assert_eq!(resp, b"*3\r\n$8\r\nsmessage\r\n$14\r\npubsub:shard:b\r\n$5\r\nhello\r\n");
+
+    // Global subscribers of the same name aren't reached by SPUBLISH, nor
+    // shard subscribers by PUBLISH.
+    let mut s = connect();
+    send_command(&mut s, &[b!("PUBLISH"), b!("pubsub:shard:b"), b!("hello")]);
+    assert_eq!(parse_integer(&read_response(&mut s)), 0);
+
+    send_command(&mut sub, &[b!("SUNSUBSCRIBE")]);
+    let resp = read_until(&mut sub, b":0\r\n");
+    assert_eq!(count_subsequence(&resp, b"sunsubscribe"), 2, "Got: {:?}", resp);
+    assert!(resp.ends_with(b":0\r\n"), "Got: {:?}", resp);
+}
@@ -8,7 +8,19 @@
 // return value.  Expiry is tested by passing a `now` value that is in the
 // future relative to the stored expiry, which simulates the key having expired.
 
-use crate::temple::soul::{Soul, Value};
+use crate::temple::soul::timeseries::{
+    Aggregation, CompactionRule, DuplicatePolicy, LabelFilter, TimeSeries, TimeSeriesOptions,
+};
+use crate::temple::soul::search::{FieldSchema, FieldType, Query};
+use crate::temple::soul::compact::{EncodingLimits, Hash, Listpack, Set};
+use crate::temple::soul::dump::{self, crc64};
+use crate::temple::soul::snapshot;
+use crate::temple::soul::sketch::{CountMinSketch, TopK};
+use crate::temple::soul::notify::{flags_to_string, parse_flags};
+use crate::temple::soul::vector::{self, DistanceMetric, VectorAlgorithm};
+use crate::temple::rite::{self, Relic, Rite};
+use crate::wish::Response;
+use crate::temple::soul::{KnnQuery, ScanOptions, SearchOptions, SortOptions, Soul, Value};
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
@@ -16,7 +28,7 @@ fn soul() -> Soul {
     Soul::new()
 }
 
-/// A `now` value that will never cause expiry in tests that don't want it.
+/// A `now` value, in milliseconds, that will never cause expiry in tests that don't want it.
 const NOW: u64 = 1_000_000;
 
 /// A `now` value far enough in the future that any key set with a short expiry
@@ -49,7 +61,7 @@ fn set_then_get_returns_value() {
 #[test]
 fn get_wrong_type_returns_error() {
     let mut s = soul();
-    s.set(str_key("k"), (Value::List(std::collections::VecDeque::new()), None));
+    s.set(str_key("k"), (Value::List(Default::default()), None));
     assert!(s.get(str_key("k"), NOW).is_err());
 }
 
@@ -83,7 +95,7 @@ fn append_to_existing_key_concatenates() {
 #[test]
 fn append_wrong_type_returns_error() {
     let mut s = soul();
-    s.set(str_key("k"), (Value::List(std::collections::VecDeque::new()), None));
+    s.set(str_key("k"), (Value::List(Default::default()), None));
     assert!(s.append(str_key("k"), str_val("x"), NOW).is_err());
 }
 
@@ -147,7 +159,7 @@ fn strlen_existing_string() {
 #[test]
 fn strlen_wrong_type_returns_error() {
     let mut s = soul();
-    s.set(str_key("k"), (Value::List(std::collections::VecDeque::new()), None));
+    s.set(str_key("k"), (Value::List(Default::default()), None));
     assert!(s.strlen(str_key("k"), NOW).is_err());
 }
 
@@ -527,9 +539,9 @@ fn expire_missing_key_returns_zero() {
 fn ttl_returns_remaining_seconds() {
     use std::time::{SystemTime, Duration};
     let mut s = soul();
-    s.set(str_key("k"), (Value::String(str_val("v")), Some(NOW + 100)));
+    s.set(str_key("k"), (Value::String(str_val("v")), Some(NOW + 100_000)));
     // TTL takes a SystemTime; we use UNIX_EPOCH + NOW as the reference point
-    let at = SystemTime::UNIX_EPOCH + Duration::from_secs(NOW);
+    let at = SystemTime::UNIX_EPOCH + Duration::from_millis(NOW);
     let ttl = s.ttl(str_key("k"), at);
     // Should be ~100 seconds remaining
     assert!(ttl > 0 && ttl <= 100);
@@ -541,3 +553,1600 @@ fn ttl_missing_key_returns_minus_two() {
     let mut s = soul();
     assert_eq!(s.ttl(str_key("nope"), SystemTime::now()), -2);
 }
+
+// ── CMS.* ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn cms_incrby_and_query_track_counts() {
+    let mut s = soul();
+    s.cms_initbydim(str_key("cms"), 2000, 5, NOW).unwrap();
+    assert_eq!(
+        s.cms_incrby(str_key("cms"), vec![(str_val("a"), 3), (str_val("b"), 1)], NOW)
+            .unwrap(),
+        vec![3, 1]
+    );
+    s.cms_incrby(str_key("cms"), vec![(str_val("a"), 2)], NOW).unwrap();
+    assert_eq!(
+        s.cms_query(str_key("cms"), vec![str_val("a"), str_val("b"), str_val("c")], NOW)
+            .unwrap(),
+        vec![5, 1, 0]
+    );
+}
+
+#[test]
+fn cms_initbydim_existing_key_returns_error() {
+    let mut s = soul();
+    s.set(str_key("cms"), (Value::String(str_val("v")), None));
+    assert!(s.cms_initbydim(str_key("cms"), 10, 2, NOW).is_err());
+}
+
+#[test]
+fn cms_query_missing_key_returns_error() {
+    let mut s = soul();
+    assert!(s.cms_query(str_key("nope"), vec![str_val("a")], NOW).is_err());
+}
+
+#[test]
+fn cms_merge_sums_weighted_sources() {
+    let mut s = soul();
+    s.cms_initbydim(str_key("a"), 100, 3, NOW).unwrap();
+    s.cms_initbydim(str_key("b"), 100, 3, NOW).unwrap();
+    s.cms_initbydim(str_key("dest"), 100, 3, NOW).unwrap();
+    s.cms_incrby(str_key("a"), vec![(str_val("x"), 2)], NOW).unwrap();
+    s.cms_incrby(str_key("b"), vec![(str_val("x"), 5)], NOW).unwrap();
+
+    s.cms_merge(str_key("dest"), vec![str_key("a"), str_key("b")], vec![3, 1], NOW)
+        .unwrap();
+    assert_eq!(s.cms_query(str_key("dest"), vec![str_val("x")], NOW).unwrap(), vec![11]);
+
+    s.cms_merge(str_key("dest"), vec![str_key("dest"), str_key("a")], vec![2, 1], NOW)
+        .unwrap();
+    assert_eq!(s.cms_query(str_key("dest"), vec![str_val("x")], NOW).unwrap(), vec![24]);
+}
+
+#[test]
+fn cms_merge_mismatched_dimensions_returns_error() {
+    let mut s = soul();
+    s.cms_initbydim(str_key("a"), 100, 3, NOW).unwrap();
+    s.cms_initbydim(str_key("dest"), 50, 3, NOW).unwrap();
+    assert!(s.cms_merge(str_key("dest"), vec![str_key("a")], vec![], NOW).is_err());
+}
+
+// ── TOPK.* ────────────────────────────────────────────────────────────────────
+
+#[test]
+fn topk_keeps_heaviest_items() {
+    let mut s = soul();
+    s.topk_reserve(str_key("tk"), 2, 50, 4, 0.9, NOW).unwrap();
+
+    for _ in 0..10 {
+        s.topk_add(str_key("tk"), vec![str_val("hot")], NOW).unwrap();
+    }
+    for _ in 0..5 {
+        s.topk_add(str_key("tk"), vec![str_val("warm")], NOW).unwrap();
+    }
+    s.topk_add(str_key("tk"), vec![str_val("cold")], NOW).unwrap();
+
+    let list: Vec<Vec<u8>> = s
+        .topk_list(str_key("tk"), NOW)
+        .unwrap()
+        .into_iter()
+        .map(|(item, _)| item)
+        .collect();
+    assert_eq!(list, vec![str_val("hot"), str_val("warm")]);
+    assert_eq!(s.topk_count(str_key("tk"), vec![str_val("hot")], NOW).unwrap(), vec![10]);
+}
+
+#[test]
+fn topk_add_reports_expelled_item() {
+    let mut s = soul();
+    s.topk_reserve(str_key("tk"), 1, 50, 4, 0.9, NOW).unwrap();
+    s.topk_add(str_key("tk"), vec![str_val("a")], NOW).unwrap();
+
+    let expelled = s
+        .topk_add(str_key("tk"), vec![str_val("b"), str_val("b")], NOW)
+        .unwrap();
+    assert_eq!(expelled, vec![None, Some(str_val("a"))]);
+}
+
+#[test]
+fn topk_wrong_type_returns_error() {
+    let mut s = soul();
+    s.set(str_key("tk"), (Value::String(str_val("v")), None));
+    assert!(s.topk_add(str_key("tk"), vec![str_val("a")], NOW).is_err());
+}
+
+// ── TS.* ──────────────────────────────────────────────────────────────────────
+
+fn ts_options(retention: u64, labels: &[(&str, &str)]) -> TimeSeriesOptions {
+    TimeSeriesOptions {
+        retention: Some(retention),
+        duplicate_policy: None,
+        labels: labels
+            .iter()
+            .map(|(label, value)| (str_val(label), str_val(value)))
+            .collect(),
+    }
+}
+
+#[test]
+fn ts_add_and_range() {
+    let mut s = soul();
+    s.ts_create(str_key("ts"), ts_options(0, &[]), NOW).unwrap();
+    s.ts_add(str_key("ts"), 10, 1.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+    s.ts_add(str_key("ts"), 20, 2.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+    s.ts_add(str_key("ts"), 30, 3.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+
+    assert_eq!(
+        s.ts_range(str_key("ts"), 15, u64::MAX, None, None, false, NOW).unwrap(),
+        vec![(20, 2.0), (30, 3.0)]
+    );
+    assert_eq!(
+        s.ts_range(str_key("ts"), 0, u64::MAX, None, Some(2), true, NOW).unwrap(),
+        vec![(30, 3.0), (20, 2.0)]
+    );
+}
+
+#[test]
+fn ts_create_existing_key_returns_error() {
+    let mut s = soul();
+    s.ts_create(str_key("ts"), TimeSeriesOptions::default(), NOW).unwrap();
+    assert!(s.ts_create(str_key("ts"), TimeSeriesOptions::default(), NOW).is_err());
+}
+
+#[test]
+fn ts_duplicate_policy() {
+    let mut s = soul();
+    s.ts_add(str_key("ts"), 10, 1.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+    assert!(s.ts_add(str_key("ts"), 10, 5.0, TimeSeriesOptions::default(), None, NOW).is_err());
+
+    s.ts_add(str_key("ts"), 10, 5.0, TimeSeriesOptions::default(), Some(DuplicatePolicy::Sum), NOW)
+        .unwrap();
+    assert_eq!(
+        s.ts_range(str_key("ts"), 0, u64::MAX, None, None, false, NOW).unwrap(),
+        vec![(10, 6.0)]
+    );
+}
+
+#[test]
+fn ts_retention_drops_old_samples() {
+    let mut s = soul();
+    s.ts_create(str_key("ts"), ts_options(100, &[]), NOW).unwrap();
+    s.ts_add(str_key("ts"), 1000, 1.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+    s.ts_add(str_key("ts"), 1050, 2.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+    s.ts_add(str_key("ts"), 1150, 3.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+
+    assert_eq!(
+        s.ts_range(str_key("ts"), 0, u64::MAX, None, None, false, NOW).unwrap(),
+        vec![(1050, 2.0), (1150, 3.0)]
+    );
+    assert!(s.ts_add(str_key("ts"), 900, 0.0, TimeSeriesOptions::default(), None, NOW).is_err());
+}
+
+#[test]
+fn ts_range_aggregation() {
+    let mut s = soul();
+    for (timestamp, value) in [(0, 1.0), (5, 3.0), (10, 10.0), (12, 20.0), (25, 7.0)] {
+        s.ts_add(str_key("ts"), timestamp, value, TimeSeriesOptions::default(), None, NOW)
+            .unwrap();
+    }
+
+    let range = |s: &mut Soul, aggregation| {
+        s.ts_range(str_key("ts"), 0, u64::MAX, Some((aggregation, 10)), None, false, NOW)
+            .unwrap()
+    };
+
+    assert_eq!(range(&mut s, Aggregation::Avg), vec![(0, 2.0), (10, 15.0), (20, 7.0)]);
+    assert_eq!(range(&mut s, Aggregation::Sum), vec![(0, 4.0), (10, 30.0), (20, 7.0)]);
+    assert_eq!(range(&mut s, Aggregation::Min), vec![(0, 1.0), (10, 10.0), (20, 7.0)]);
+    assert_eq!(range(&mut s, Aggregation::Max), vec![(0, 3.0), (10, 20.0), (20, 7.0)]);
+    assert_eq!(range(&mut s, Aggregation::Count), vec![(0, 2.0), (10, 2.0), (20, 1.0)]);
+}
+
+#[test]
+fn ts_incrby_accumulates() {
+    let mut s = soul();
+    s.ts_incrby(str_key("ts"), 5.0, 10, TimeSeriesOptions::default(), NOW).unwrap();
+    s.ts_incrby(str_key("ts"), 2.5, 20, TimeSeriesOptions::default(), NOW).unwrap();
+    assert!(s.ts_incrby(str_key("ts"), 1.0, 5, TimeSeriesOptions::default(), NOW).is_err());
+
+    assert_eq!(
+        s.ts_range(str_key("ts"), 0, u64::MAX, None, None, false, NOW).unwrap(),
+        vec![(10, 5.0), (20, 7.5)]
+    );
+}
+
+#[test]
+fn ts_madd_reports_each_sample() {
+    let mut s = soul();
+    s.ts_create(str_key("ts"), TimeSeriesOptions::default(), NOW).unwrap();
+
+    let results = s.ts_madd(
+        vec![
+            (str_key("ts"), 10, 1.0),
+            (str_key("missing"), 10, 1.0),
+            (str_key("ts"), 10, 2.0),
+        ],
+        NOW,
+    );
+    assert!(matches!(results.as_slice(), [Ok(10), Err(_), Err(_)]));
+}
+
+#[test]
+fn ts_mrange_filters_by_labels() {
+    let mut s = soul();
+    s.ts_create(str_key("cpu:1"), ts_options(0, &[("metric", "cpu"), ("host", "a")]), NOW)
+        .unwrap();
+    s.ts_create(str_key("cpu:2"), ts_options(0, &[("metric", "cpu"), ("host", "b")]), NOW)
+        .unwrap();
+    s.ts_create(str_key("mem:1"), ts_options(0, &[("metric", "mem")]), NOW).unwrap();
+
+    for key in ["cpu:1", "cpu:2", "mem:1"] {
+        s.ts_add(str_key(key), 10, 1.0, TimeSeriesOptions::default(), None, NOW).unwrap();
+    }
+
+    let keys = |filters: &[&str]| {
+        s.ts_mrange(
+            0,
+            u64::MAX,
+            None,
+            None,
+            filters
+                .iter()
+                .map(|filter| LabelFilter::parse(filter.as_bytes()).unwrap())
+                .collect(),
+            NOW,
+        )
+        .into_iter()
+        .map(|(key, _, _)| key)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(keys(&["metric=cpu"]), vec![str_key("cpu:1"), str_key("cpu:2")]);
+    assert_eq!(keys(&["metric=cpu", "host!=a"]), vec![str_key("cpu:2")]);
+    assert_eq!(keys(&["host="]), vec![str_key("mem:1")]);
+}
+
+#[test]
+fn ts_compaction_rule_downsamples() {
+    let mut s = soul();
+    s.ts_create(str_key("raw"), TimeSeriesOptions::default(), NOW).unwrap();
+    s.ts_create(str_key("avg"), TimeSeriesOptions::default(), NOW).unwrap();
+    s.ts_createrule(str_key("raw"), str_key("avg"), Aggregation::Avg, 10, NOW)
+        .unwrap();
+    assert!(s
+        .ts_createrule(str_key("raw"), str_key("avg"), Aggregation::Sum, 10, NOW)
+        .is_err());
+
+    for (timestamp, value) in [(1, 2.0), (5, 4.0), (12, 10.0)] {
+        s.ts_add(str_key("raw"), timestamp, value, TimeSeriesOptions::default(), None, NOW)
+            .unwrap();
+    }
+
+    assert_eq!(
+        s.ts_range(str_key("avg"), 0, u64::MAX, None, None, false, NOW).unwrap(),
+        vec![(0, 3.0), (10, 10.0)]
+    );
+
+    s.ts_deleterule(str_key("raw"), str_key("avg"), NOW).unwrap();
+    assert!(s.ts_deleterule(str_key("raw"), str_key("avg"), NOW).is_err());
+}
+
+#[test]
+fn ts_compaction_cascades_and_refuses_loops() {
+    let mut s = soul();
+    for key in ["raw", "minute", "hour", "other"] {
+        s.ts_create(str_key(key), TimeSeriesOptions::default(), NOW).unwrap();
+    }
+    s.ts_createrule(str_key("raw"), str_key("minute"), Aggregation::Sum, 10, NOW)
+        .unwrap();
+    s.ts_createrule(str_key("minute"), str_key("hour"), Aggregation::Sum, 100, NOW)
+        .unwrap();
+
+    assert!(s
+        .ts_createrule(str_key("hour"), str_key("raw"), Aggregation::Sum, 10, NOW)
+        .is_err());
+    assert!(s
+        .ts_createrule(str_key("other"), str_key("hour"), Aggregation::Sum, 10, NOW)
+        .is_err());
+
+    s.set_notify_flags(parse_flags(b"Ed").unwrap());
+    for (timestamp, value) in [(1, 1.0), (15, 2.0)] {
+        s.ts_add(str_key("raw"), timestamp, value, TimeSeriesOptions::default(), None, NOW)
+            .unwrap();
+    }
+
+    assert_eq!(
+        s.ts_range(str_key("hour"), 0, u64::MAX, None, None, false, NOW).unwrap(),
+        vec![(0, 3.0)]
+    );
+    assert!(s
+        .notifications()
+        .contains(&(0, &b"ts.add"[..], str_key("hour"))));
+
+    s.ts_deleterule(str_key("minute"), str_key("hour"), NOW).unwrap();
+    s.ts_createrule(str_key("other"), str_key("hour"), Aggregation::Sum, 10, NOW)
+        .unwrap();
+}
+
+#[test]
+fn ts_wrong_type_returns_error() {
+    let mut s = soul();
+    s.set(str_key("ts"), (Value::String(str_val("v")), None));
+    assert!(s.ts_add(str_key("ts"), 10, 1.0, TimeSeriesOptions::default(), None, NOW).is_err());
+    assert!(s.ts_range(str_key("ts"), 0, u64::MAX, None, None, false, NOW).is_err());
+}
+
+// ── FT.* ──────────────────────────────────────────────────────────────────────
+
+fn products(s: &mut Soul) {
+    s.ft_create(
+        str_key("idx"),
+        vec![str_key("product:")],
+        vec![
+            FieldSchema { name: str_key("name"), field_type: FieldType::Text },
+            FieldSchema { name: str_key("price"), field_type: FieldType::Numeric },
+            FieldSchema { name: str_key("tags"), field_type: FieldType::Tag { separator: b',' } },
+        ],
+    )
+    .unwrap();
+
+    for (key, name, price, tags) in [
+        ("product:1", "Red running shoes", "80", "sport,red"),
+        ("product:2", "Blue running jacket", "120", "sport,blue"),
+        ("product:3", "Red wool scarf", "25", "winter,red"),
+    ] {
+        s.hset(
+            str_key(key),
+            vec![
+                (str_key("name"), str_val(name)),
+                (str_key("price"), str_val(price)),
+                (str_key("tags"), str_val(tags)),
+            ],
+            NOW,
+        )
+        .unwrap();
+    }
+
+    s.hset(str_key("other:1"), vec![(str_key("name"), str_val("Red herring"))], NOW)
+        .unwrap();
+}
+
+fn search_options() -> SearchOptions {
+    SearchOptions { no_content: true, return_fields: None, sort_by: None, limit: (0, 10), knn: None }
+}
+
+fn search(s: &mut Soul, query: &str) -> Vec<Vec<u8>> {
+    s.ft_search(str_key("idx"), Query::parse(query.as_bytes()).unwrap(), search_options(), NOW)
+        .unwrap()
+        .1
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect()
+}
+
+#[test]
+fn ft_search_filters() {
+    let mut s = soul();
+    products(&mut s);
+
+    assert_eq!(search(&mut s, "*").len(), 3);
+    assert_eq!(search(&mut s, "red"), vec![str_key("product:1"), str_key("product:3")]);
+    assert_eq!(search(&mut s, "run*"), vec![str_key("product:1"), str_key("product:2")]);
+    assert_eq!(search(&mut s, "@tags:{blue | winter}"), vec![str_key("product:2"), str_key("product:3")]);
+    assert_eq!(search(&mut s, "@price:[50 (120]"), vec![str_key("product:1")]);
+    assert_eq!(search(&mut s, "@price:[-inf +inf] -red"), vec![str_key("product:2")]);
+    assert_eq!(search(&mut s, "@name:red @tags:{sport} | scarf"), vec![str_key("product:1"), str_key("product:3")]);
+}
+
+#[test]
+fn ft_search_sorts_and_limits() {
+    let mut s = soul();
+    products(&mut s);
+
+    let options = SearchOptions {
+        no_content: false,
+        return_fields: Some(vec![str_key("price")]),
+        sort_by: Some((str_key("price"), false)),
+        limit: (1, 1),
+        knn: None,
+    };
+
+    let (total, documents) = s
+        .ft_search(str_key("idx"), Query::All, options, NOW)
+        .unwrap();
+    assert_eq!(total, 3);
+    assert_eq!(documents, vec![(str_key("product:1"), vec![(str_key("price"), str_val("80"))])]);
+}
+
+#[test]
+fn ft_index_follows_mutations() {
+    let mut s = soul();
+    products(&mut s);
+
+    s.hset(str_key("product:3"), vec![(str_key("tags"), str_val("sport"))], NOW).unwrap();
+    assert_eq!(search(&mut s, "@tags:{sport}").len(), 3);
+
+    s.hdel(str_key("product:1"), vec![str_key("tags")], NOW).unwrap();
+    assert_eq!(search(&mut s, "@tags:{sport}").len(), 2);
+
+    s.del(vec![str_key("product:2")], NOW);
+    assert_eq!(search(&mut s, "@tags:{sport}"), vec![str_key("product:3")]);
+
+    s.set(str_key("product:3"), (Value::String(str_val("v")), None));
+    assert!(search(&mut s, "*").len() == 1);
+
+    s.expire(str_key("product:1"), NOW, NOW);
+    assert!(search(&mut s, "*").len() == 1);
+    assert!(s
+        .ft_search(str_key("idx"), Query::All, search_options(), EXPIRED)
+        .unwrap()
+        .1
+        .is_empty());
+}
+
+#[test]
+fn ft_unknown_index_and_field_return_errors() {
+    let mut s = soul();
+    products(&mut s);
+
+    assert!(s.ft_search(str_key("missing"), Query::All, search_options(), NOW).is_err());
+    assert!(s
+        .ft_search(str_key("idx"), Query::parse(b"@nope:{a}").unwrap(), search_options(), NOW)
+        .is_err());
+    assert!(Query::parse(b"@price:[1").is_none());
+}
+
+#[test]
+fn ft_search_infinite_bounds_on_the_wrong_side_match_nothing() {
+    let mut s = soul();
+    products(&mut s);
+
+    assert!(search(&mut s, "@price:[+inf +inf]").is_empty());
+    assert!(search(&mut s, "@price:[-inf -inf]").is_empty());
+    assert!(search(&mut s, "@price:[inf 100]").is_empty());
+    assert_eq!(search(&mut s, "@price:[-inf 100]").len(), 2);
+}
+
+#[test]
+fn ft_query_nesting_is_bounded() {
+    let deep = |n: usize| format!("{}foo", "-".repeat(n));
+
+    assert!(Query::parse(deep(128).as_bytes()).is_some());
+    assert!(Query::parse(deep(129).as_bytes()).is_none());
+    assert!(Query::parse(deep(200_000).as_bytes()).is_none());
+    assert!(Query::parse(format!("{}foo{}", "(".repeat(129), ")".repeat(129)).as_bytes()).is_none());
+    assert!(Query::parse(format!("{}foo{}", "(-".repeat(64), ")".repeat(64)).as_bytes()).is_some());
+}
+
+#[test]
+fn ft_rebuild_indexes_restores_postings() {
+    let mut s = soul();
+    products(&mut s);
+
+    s.rebuild_indexes();
+    assert_eq!(search(&mut s, "red"), vec![str_key("product:1"), str_key("product:3")]);
+
+    s.set_notify_flags(parse_flags(b"Eg").unwrap());
+    s.ft_dropindex(str_key("idx"), true, NOW).unwrap();
+    assert!(s.ft_list().is_empty());
+    assert_eq!(s.exists(vec![str_key("product:1"), str_key("other:1")], NOW), 1);
+
+    let notifications = s.notifications();
+    assert_eq!(notifications.len(), 3);
+    assert!(notifications.contains(&(0, &b"del"[..], str_key("product:1"))));
+}
+
+// ── FT.SEARCH KNN ────────────────────────────────────────────────────────────
+
+fn blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+#[test]
+fn parse_vector_checks_the_length_without_overflowing() {
+    assert_eq!(vector::parse_vector(&blob(&[1.0, 2.0]), 2), Some(vec![1.0, 2.0]));
+    assert_eq!(vector::parse_vector(&blob(&[1.0]), 2), None);
+    assert_eq!(vector::parse_vector(b"", usize::MAX / 4 + 1), None);
+}
+
+fn points(s: &mut Soul, algorithm: VectorAlgorithm, metric: DistanceMetric) {
+    s.ft_create(
+        str_key("vec"),
+        vec![str_key("point:")],
+        vec![
+            FieldSchema { name: str_key("kind"), field_type: FieldType::Tag { separator: b',' } },
+            FieldSchema {
+                name: str_key("v"),
+                field_type: FieldType::Vector { algorithm, dimension: 2, metric },
+            },
+        ],
+    )
+    .unwrap();
+
+    for i in 0..50 {
+        let kind = if i % 2 == 0 { "even" } else { "odd" };
+        s.hset(
+            format!("point:{i}").into_bytes(),
+            vec![(str_key("kind"), str_val(kind)), (str_key("v"), blob(&[i as f32, 0.0]))],
+            NOW,
+        )
+        .unwrap();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn knn(s: &mut Soul, query: &str, k: usize, target: &[f32]) -> Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)> {
+    let options = SearchOptions {
+        no_content: false,
+        return_fields: Some(vec![str_key("__v_score")]),
+        sort_by: None,
+        limit: (0, 10),
+        knn: Some(Box::new(KnnQuery {
+            k,
+            field: str_key("v"),
+            vector: blob(target),
+            ef_runtime: None,
+            score_field: str_key("__v_score"),
+        })),
+    };
+
+    s.ft_search(str_key("vec"), Query::parse(query.as_bytes()).unwrap(), options, NOW)
+        .unwrap()
+        .1
+}
+
+#[test]
+fn ft_knn_flat_orders_by_distance() {
+    let mut s = soul();
+    points(&mut s, VectorAlgorithm::Flat, DistanceMetric::L2);
+
+    let hits = knn(&mut s, "*", 3, &[10.2, 0.0]);
+    assert_eq!(
+        hits.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>(),
+        vec![str_key("point:10"), str_key("point:11"), str_key("point:9")]
+    );
+    assert_eq!(hits[0].1[0].0, str_key("__v_score"));
+}
+
+#[test]
+fn ft_knn_hnsw_with_prefilter() {
+    let mut s = soul();
+    points(&mut s, VectorAlgorithm::Hnsw { m: 4, ef_construction: 20, ef_runtime: 5 }, DistanceMetric::L2);
+
+    let hits = knn(&mut s, "@kind:{odd}", 2, &[20.0, 0.0]);
+    assert_eq!(
+        hits.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>(),
+        vec![str_key("point:19"), str_key("point:21")]
+    );
+
+    s.del(vec![str_key("point:19")], NOW);
+    let hits = knn(&mut s, "*", 1, &[19.0, 0.0]);
+    assert_eq!(hits[0].1, vec![(str_key("__v_score"), str_val("1"))]);
+}
+
+#[test]
+fn ft_knn_rejects_wrong_dimension() {
+    let mut s = soul();
+    points(&mut s, VectorAlgorithm::Flat, DistanceMetric::Cosine);
+
+    let options = SearchOptions {
+        knn: Some(Box::new(KnnQuery {
+            k: 1,
+            field: str_key("v"),
+            vector: blob(&[1.0, 2.0, 3.0]),
+            ef_runtime: None,
+            score_field: str_key("score"),
+        })),
+        ..search_options()
+    };
+    assert!(s.ft_search(str_key("vec"), Query::All, options, NOW).is_err());
+}
+
+#[test]
+fn distance_metrics() {
+    assert_eq!(DistanceMetric::L2.distance(&[1.0, 2.0], &[4.0, 6.0]), 25.0);
+    assert_eq!(DistanceMetric::InnerProduct.distance(&[1.0, 0.0], &[0.5, 0.0]), 0.5);
+    assert_eq!(DistanceMetric::Cosine.distance(&[1.0, 0.0], &[3.0, 0.0]), 0.0);
+}
+
+// ── CL.THROTTLE ──────────────────────────────────────────────────────────────
+
+#[test]
+fn cl_throttle_allows_burst_then_limits() {
+    let mut s = soul();
+
+    for remaining in (0..3).rev() {
+        let throttle = s.cl_throttle(str_key("rl"), 2, 1, 10_000, 1, NOW).unwrap();
+        assert!(!throttle.is_limited);
+        assert_eq!(throttle.limit, 3);
+        assert_eq!(throttle.remaining, remaining);
+    }
+
+    let throttle = s.cl_throttle(str_key("rl"), 2, 1, 10_000, 1, NOW).unwrap();
+    assert!(throttle.is_limited);
+    assert_eq!(throttle.retry_after, Some(10));
+    assert_eq!(throttle.reset_after, 30);
+
+    let throttle = s.cl_throttle(str_key("rl"), 2, 1, 10_000, 1, NOW + 10_000).unwrap();
+    assert!(!throttle.is_limited);
+    assert_eq!(throttle.remaining, 0);
+}
+
+#[test]
+fn cl_throttle_rejects_oversized_request_and_wrong_type() {
+    let mut s = soul();
+
+    let throttle = s.cl_throttle(str_key("rl"), 2, 1, 10_000, 4, NOW).unwrap();
+    assert!(throttle.is_limited);
+    assert_eq!(throttle.retry_after, None);
+    assert_eq!(throttle.remaining, 3);
+
+    s.set(str_key("s"), (Value::String(str_val("v")), None));
+    assert!(s.cl_throttle(str_key("s"), 2, 1, 10_000, 1, NOW).is_err());
+}
+
+#[test]
+fn cl_throttle_key_expires_the_millisecond_it_refills() {
+    let mut s = soul();
+    s.cl_throttle(str_key("rl"), 0, 1, 2_500, 1, NOW + 200).unwrap();
+
+    assert_eq!(s.exists(vec![str_key("rl")], NOW + 2_700), 1);
+    assert_eq!(s.exists(vec![str_key("rl")], NOW + 2_701), 0);
+}
+
+#[test]
+fn cl_throttle_limited_on_a_missing_key_stores_nothing() {
+    let mut s = soul();
+    s.set_notify_flags(parse_flags(b"Edn").unwrap());
+
+    let throttle = s.cl_throttle(str_key("rl"), 2, 1, 10_000, 4, NOW).unwrap();
+    assert!(throttle.is_limited);
+    assert_eq!(s.exists(vec![str_key("rl")], NOW), 0);
+    assert!(s.notifications().is_empty());
+}
+
+// ── SCAN / HSCAN / SSCAN / KEYS ──────────────────────────────────────────────
+
+fn scan_all(s: &mut Soul, options: &ScanOptions) -> Vec<Vec<u8>> {
+    let (mut cursor, mut keys) = s.scan(0, options, NOW);
+
+    while cursor != 0 {
+        let (next, page) = s.scan(cursor, options, NOW);
+        cursor = next;
+        keys.extend(page);
+    }
+
+    keys.sort();
+    keys
+}
+
+#[test]
+fn scan_survives_mutations_between_calls() {
+    let mut s = soul();
+
+    for i in 0..100 {
+        s.set(format!("key:{i}").into_bytes(), (Value::String(str_val("v")), None));
+    }
+
+    let options = ScanOptions { count: 7, ..ScanOptions::default() };
+    let (mut cursor, mut seen) = s.scan(0, &options, NOW);
+
+    for i in 0..50 {
+        s.set(format!("new:{i}").into_bytes(), (Value::String(str_val("v")), None));
+    }
+    s.del(vec![str_key("key:0")], NOW);
+
+    while cursor != 0 {
+        let (next, page) = s.scan(cursor, &options, NOW);
+        cursor = next;
+        seen.extend(page);
+    }
+
+    for i in 1..100 {
+        assert_eq!(seen.iter().filter(|key| **key == format!("key:{i}").into_bytes()).count(), 1);
+    }
+}
+
+#[test]
+fn scan_filters_by_match_type_and_expiry() {
+    let mut s = soul();
+    s.set(str_key("user:1"), (Value::String(str_val("v")), None));
+    s.set(str_key("user:2"), (Value::String(str_val("v")), Some(NOW - 1)));
+    s.hset(str_key("user:3"), vec![(str_key("f"), str_val("v"))], NOW).unwrap();
+    s.set(str_key("other"), (Value::String(str_val("v")), None));
+
+    let options = ScanOptions { pattern: Some(str_key("user:*")), ..ScanOptions::default() };
+    assert_eq!(scan_all(&mut s, &options), vec![str_key("user:1"), str_key("user:3")]);
+
+    let options = ScanOptions { type_name: Some(str_key("HASH")), ..ScanOptions::default() };
+    assert_eq!(scan_all(&mut s, &options), vec![str_key("user:3")]);
+}
+
+#[test]
+fn hscan_and_sscan_page_through_members() {
+    let mut s = soul();
+    let fields = (0..30).map(|i| (format!("f{i}").into_bytes(), str_val("v"))).collect();
+    s.hset(str_key("h"), fields, NOW).unwrap();
+    s.sadd(str_key("s"), (0..30).map(|i| format!("m{i}").into_bytes()).collect(), NOW).unwrap();
+
+    let options = ScanOptions { count: 4, no_values: true, ..ScanOptions::default() };
+    let (mut cursor, mut fields) = s.hscan(str_key("h"), 0, &options, NOW).unwrap();
+    while cursor != 0 {
+        let (next, page) = s.hscan(str_key("h"), cursor, &options, NOW).unwrap();
+        cursor = next;
+        fields.extend(page);
+    }
+    assert_eq!(fields.len(), 30);
+
+    let options = ScanOptions { pattern: Some(str_key("m1?")), count: 100, ..ScanOptions::default() };
+    let (cursor, members) = s.sscan(str_key("s"), 0, &options, NOW).unwrap();
+    assert_eq!(cursor, 0);
+    assert_eq!(members.len(), 10);
+
+    assert_eq!(s.sscan(str_key("missing"), 0, &options, NOW).unwrap(), (0, vec![]));
+    assert!(s.sscan(str_key("h"), 0, &options, NOW).is_err());
+}
+
+#[test]
+fn keys_matches_glob_patterns() {
+    let mut s = soul();
+    for key in ["hello", "hallo", "hxllo", "hllo", "heeeello", "h*llo"] {
+        s.set(str_key(key), (Value::String(str_val("v")), None));
+    }
+
+    assert_eq!(s.keys(b"h?llo", NOW), vec![str_key("h*llo"), str_key("hallo"), str_key("hello"), str_key("hxllo")]);
+    assert_eq!(s.keys(b"h*llo", NOW).len(), 6);
+    assert_eq!(s.keys(b"h[ae]llo", NOW), vec![str_key("hallo"), str_key("hello")]);
+    assert_eq!(s.keys(b"h[^e]llo", NOW), vec![str_key("h*llo"), str_key("hallo"), str_key("hxllo")]);
+    assert_eq!(s.keys(b"h[a-b]llo", NOW), vec![str_key("hallo")]);
+    assert_eq!(s.keys(b"h\\*llo", NOW), vec![str_key("h*llo")]);
+}
+
+// ── TYPE / RENAME / COPY / RANDOMKEY / DBSIZE / TOUCH / UNLINK ────────────────
+
+#[test]
+fn key_type_names_every_value() {
+    let mut s = soul();
+    s.set(str_key("s"), (Value::String(str_val("v")), None));
+    s.hset(str_key("h"), vec![(str_key("f"), str_val("v"))], NOW).unwrap();
+    s.set(str_key("gone"), (Value::String(str_val("v")), Some(NOW - 1)));
+
+    assert_eq!(s.key_type(str_key("s"), NOW), b"string");
+    assert_eq!(s.key_type(str_key("h"), NOW), b"hash");
+    assert_eq!(s.key_type(str_key("gone"), NOW), b"none");
+}
+
+#[test]
+fn rename_moves_value_and_expiry() {
+    let mut s = soul();
+    s.set(str_key("a"), (Value::String(str_val("1")), Some(NOW + 100)));
+    s.set(str_key("b"), (Value::String(str_val("2")), None));
+
+    s.rename(str_key("a"), str_key("b"), NOW).unwrap();
+    assert_eq!(s.exists(vec![str_key("a")], NOW), 0);
+    assert_eq!(s.get(str_key("b"), NOW).unwrap(), Some(str_val("1")));
+    assert_eq!(s.exists(vec![str_key("b")], NOW + 101), 0);
+
+    assert!(s.rename(str_key("missing"), str_key("c"), NOW).is_err());
+}
+
+#[test]
+fn renamenx_keeps_existing_destination() {
+    let mut s = soul();
+    s.set(str_key("a"), (Value::String(str_val("1")), None));
+    s.set(str_key("b"), (Value::String(str_val("2")), None));
+
+    assert!(!s.renamenx(str_key("a"), str_key("b"), NOW).unwrap());
+    assert!(s.renamenx(str_key("a"), str_key("c"), NOW).unwrap());
+    assert_eq!(s.get(str_key("c"), NOW).unwrap(), Some(str_val("1")));
+}
+
+#[test]
+fn copy_respects_replace() {
+    let mut s = soul();
+    s.set(str_key("a"), (Value::String(str_val("1")), None));
+    s.set(str_key("b"), (Value::String(str_val("2")), None));
+
+    assert!(!s.copy(str_key("a"), str_key("b"), false, NOW).unwrap());
+    assert!(s.copy(str_key("a"), str_key("b"), true, NOW).unwrap());
+    assert_eq!(s.get(str_key("b"), NOW).unwrap(), Some(str_val("1")));
+    assert_eq!(s.get(str_key("a"), NOW).unwrap(), Some(str_val("1")));
+
+    assert!(!s.copy(str_key("missing"), str_key("c"), false, NOW).unwrap());
+    assert!(s.copy(str_key("a"), str_key("a"), false, NOW).is_err());
+}
+
+#[test]
+fn rename_and_copy_follow_search_indexes() {
+    let mut s = soul();
+    products(&mut s);
+
+    s.rename(str_key("product:1"), str_key("moved:1"), NOW).unwrap();
+    assert_eq!(search(&mut s, "red"), vec![str_key("product:3")]);
+
+    s.copy(str_key("product:3"), str_key("product:4"), false, NOW).unwrap();
+    assert_eq!(search(&mut s, "red"), vec![str_key("product:3"), str_key("product:4")]);
+}
+
+#[test]
+fn randomkey_dbsize_and_touch() {
+    let mut s = soul();
+    assert_eq!(s.randomkey(NOW), None);
+
+    s.set(str_key("a"), (Value::String(str_val("1")), None));
+    s.set(str_key("b"), (Value::String(str_val("2")), None));
+
+    assert!(matches!(s.randomkey(NOW), Some(key) if key == str_key("a") || key == str_key("b")));
+    assert_eq!(s.dbsize(), 2);
+    assert_eq!(s.touch(vec![str_key("a"), str_key("b"), str_key("c")], NOW), 2);
+}
+
+#[test]
+fn unlink_returns_only_large_values() {
+    let mut s = soul();
+    s.set(str_key("small"), (Value::String(str_val("v")), None));
+    s.sadd(str_key("big"), (0..100).map(|i| format!("m{i}").into_bytes()).collect(), NOW).unwrap();
+
+    let (unlinked, large_values) = s.unlink(vec![str_key("small"), str_key("big"), str_key("missing")], NOW);
+    assert_eq!(unlinked, 2);
+    assert_eq!(large_values.len(), 1);
+    assert_eq!(s.dbsize(), 0);
+}
+
+// ── SELECT / MOVE / SWAPDB / FLUSHDB / FLUSHALL ──────────────────────────────
+
+#[test]
+fn databases_are_isolated() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("zero")), None));
+
+    s.select(15);
+    assert_eq!(s.get(str_key("k"), NOW).unwrap(), None);
+    s.set(str_key("k"), (Value::String(str_val("fifteen")), None));
+
+    s.select(0);
+    assert_eq!(s.get(str_key("k"), NOW).unwrap(), Some(str_val("zero")));
+    assert_eq!(s.database_count(), 16);
+}
+
+#[test]
+fn move_key_between_databases() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), Some(NOW + 100)));
+
+    assert!(s.move_key(str_key("k"), 0, NOW).is_err());
+    assert!(s.move_key(str_key("k"), 16, NOW).is_err());
+    assert!(s.move_key(str_key("k"), 1, NOW).unwrap());
+    assert!(!s.move_key(str_key("k"), 1, NOW).unwrap());
+    assert_eq!(s.dbsize(), 0);
+
+    s.select(1);
+    assert_eq!(s.get(str_key("k"), NOW).unwrap(), Some(str_val("v")));
+    assert_eq!(s.get(str_key("k"), NOW + 101).unwrap(), None);
+
+    s.set(str_key("k"), (Value::String(str_val("taken")), None));
+    s.select(0);
+    s.set(str_key("k"), (Value::String(str_val("other")), None));
+    assert!(!s.move_key(str_key("k"), 1, NOW).unwrap());
+}
+
+#[test]
+fn swapdb_exchanges_contents() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("zero")), None));
+
+    s.swapdb(0, 3).unwrap();
+    assert_eq!(s.dbsize(), 0);
+
+    s.select(3);
+    assert_eq!(s.get(str_key("k"), NOW).unwrap(), Some(str_val("zero")));
+    assert!(s.swapdb(0, 16).is_err());
+}
+
+#[test]
+fn flushdb_keeps_index_definitions() {
+    let mut s = soul();
+    products(&mut s);
+
+    assert_eq!(s.flushdb().len(), 4);
+    assert_eq!(s.dbsize(), 0);
+    assert!(search(&mut s, "red").is_empty());
+
+    s.hset(str_key("product:9"), vec![(str_key("name"), str_val("Red hat"))], NOW)
+        .unwrap();
+    assert_eq!(search(&mut s, "red"), vec![str_key("product:9")]);
+}
+
+#[test]
+fn flushall_empties_every_database() {
+    let mut s = soul();
+    s.set(str_key("a"), (Value::String(str_val("1")), None));
+    s.select(7);
+    s.set(str_key("b"), (Value::String(str_val("2")), None));
+
+    assert_eq!(s.flushall().iter().map(|keyspace| keyspace.len()).sum::<usize>(), 2);
+    assert_eq!(s.dbsize(), 0);
+
+    s.select(0);
+    assert_eq!(s.dbsize(), 0);
+}
+
+#[test]
+fn snapshot_keeps_every_database() {
+    let mut s = soul();
+    s.set(str_key("a"), (Value::String(str_val("1")), None));
+    s.select(5);
+    s.set(str_key("b"), (Value::String(str_val("2")), None));
+
+    let path = std::env::temp_dir().join(format!("jerusalem-databases-{}.rdb", std::process::id()));
+    assert!(s.save(path.clone()).is_ok());
+
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut loaded = snapshot::decode(&bytes).ok().unwrap();
+
+    assert_eq!(loaded.database_count(), 16);
+    assert_eq!(loaded.get(str_key("a"), NOW).unwrap(), Some(str_val("1")));
+
+    loaded.select(5);
+    assert_eq!(loaded.get(str_key("b"), NOW).unwrap(), Some(str_val("2")));
+}
+
+// The layout dump.rdb had before snapshots carried a version.
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+enum LegacyValue {
+    String(Vec<u8>),
+    List(std::collections::VecDeque<Vec<u8>>),
+    Hash(std::collections::HashMap<Vec<u8>, Vec<u8>>),
+    Set(std::collections::HashSet<Vec<u8>>),
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct LegacySoul(std::collections::HashMap<Vec<u8>, (LegacyValue, Option<u64>)>);
+
+#[test]
+fn snapshot_migrates_the_unversioned_layout() {
+    let legacy = LegacySoul(
+        [
+            // Kept in seconds then.
+            (str_key("s"), (LegacyValue::String(str_val("v")), Some(NOW / 1000 + 10))),
+            (str_key("l"), (LegacyValue::List([str_val("a"), str_val("b")].into()), None)),
+            (str_key("h"), (LegacyValue::Hash([(str_key("f"), str_val("1"))].into()), None)),
+            (str_key("z"), (LegacyValue::Set([str_val("7")].into()), None)),
+        ]
+        .into(),
+    );
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&legacy).unwrap();
+
+    let mut loaded = snapshot::decode(&bytes).ok().unwrap();
+
+    assert_eq!(loaded.get(str_key("s"), NOW + 10_000).unwrap(), Some(str_val("v")));
+    assert_eq!(loaded.get(str_key("s"), NOW + 10_001).unwrap(), None);
+    assert_eq!(
+        loaded.lrange(str_key("l"), 0, -1, NOW).unwrap(),
+        Some(vec![Some(str_val("a")), Some(str_val("b"))])
+    );
+    assert_eq!(loaded.hget(str_key("h"), str_key("f"), NOW).unwrap(), Some(str_val("1")));
+    assert_eq!(loaded.sismember(str_key("z"), str_val("7"), NOW).unwrap(), 1);
+    assert_eq!(loaded.object_encoding(str_key("z"), NOW), Some(&b"intset"[..]));
+}
+
+#[test]
+fn snapshot_refuses_what_it_cannot_read() {
+    let s = soul();
+    let path = std::env::temp_dir().join(format!("jerusalem-version-{}.rdb", std::process::id()));
+    assert!(s.save(path.clone()).is_ok());
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(snapshot::decode(&bytes).is_ok());
+    bytes[8] = 0xff;
+    assert!(snapshot::decode(&bytes).is_err());
+    assert!(snapshot::decode(b"junk").is_err());
+    assert!(snapshot::decode(b"").is_err());
+}
+
+// ── DUMP / RESTORE ───────────────────────────────────────────────────────────
+
+#[test]
+fn dump_checksum_is_crc64_jones() {
+    assert_eq!(crc64(b"123456789"), 0xe9c6_d914_c4b8_d9ca);
+}
+
+#[test]
+fn dump_then_restore_roundtrips_value() {
+    let mut s = soul();
+    assert_eq!(s.dump(str_key("missing"), NOW), None);
+
+    s.rpush(str_key("list"), vec![str_val("a"), str_val("b")], NOW).unwrap();
+    let payload = s.dump(str_key("list"), NOW).unwrap();
+
+    s.restore(str_key("copy"), payload.clone(), Some(NOW + 10), false, NOW).unwrap();
+    assert_eq!(
+        s.lrange(str_key("copy"), 0, -1, NOW).unwrap(),
+        Some(vec![Some(str_val("a")), Some(str_val("b"))])
+    );
+    assert_eq!(s.llen(str_key("copy"), NOW + 11).unwrap(), 0);
+
+    assert!(s.restore(str_key("list"), payload.clone(), None, false, NOW).is_err());
+    assert!(s.restore(str_key("list"), payload, None, true, NOW).is_ok());
+}
+
+#[test]
+fn restore_rejects_corrupted_payload() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+
+    let mut payload = s.dump(str_key("k"), NOW).unwrap();
+    payload[0] ^= 1;
+
+    assert!(s.restore(str_key("other"), payload, None, false, NOW).is_err());
+    assert!(s.restore(str_key("other"), str_val("junk"), None, false, NOW).is_err());
+    assert_eq!(s.dbsize(), 1);
+}
+
+#[test]
+fn restore_rejects_values_that_break_their_invariants() {
+    let mut s = soul();
+
+    let mut series = TimeSeries::new(TimeSeriesOptions::default());
+    series.rules.push(CompactionRule {
+        destination: str_key("dst"),
+        aggregation: Aggregation::Avg,
+        bucket_duration: 0,
+    });
+
+    let mut odd = Listpack::default();
+    odd.push_back(b"field");
+
+    let mut fields = Listpack::default();
+    for entry in [b"f", b"1", b"f", b"2"] {
+        fields.push_back(entry);
+    }
+
+    let mut repeated = Listpack::default();
+    repeated.push_back(b"a");
+    repeated.push_back(b"a");
+
+    for value in [
+        Value::CountMinSketch(CountMinSketch::new(0, 5)),
+        Value::TopK(TopK::new(0, 8, 7, 0.9)),
+        Value::TimeSeries(series),
+        Value::Set(Set::Intset(vec![3, 1])),
+        Value::Set(Set::Intset(vec![1, 1])),
+        Value::Set(Set::Listpack(repeated)),
+        Value::Hash(Hash::Listpack(odd)),
+        Value::Hash(Hash::Listpack(fields)),
+    ] {
+        let payload = dump::encode(&value).unwrap();
+        assert!(s.restore(str_key("k"), payload, None, true, NOW).is_err());
+    }
+
+    assert_eq!(s.dbsize(), 0);
+
+    let payload = dump::encode(&Value::Set(Set::Intset(vec![1, 3]))).unwrap();
+    assert!(s.restore(str_key("k"), payload, None, true, NOW).is_ok());
+}
+
+#[test]
+fn restore_updates_search_indexes() {
+    let mut s = soul();
+    products(&mut s);
+
+    let payload = s.dump(str_key("product:3"), NOW).unwrap();
+    s.restore(str_key("product:4"), payload, None, false, NOW).unwrap();
+
+    assert_eq!(search(&mut s, "scarf"), vec![str_key("product:3"), str_key("product:4")]);
+}
+
+// ── Keyspace notifications ───────────────────────────────────────────────────
+
+#[test]
+fn notify_flags_parse_and_print() {
+    assert_eq!(parse_flags(b"KEA").map(flags_to_string), Some(str_val("AKE")));
+    assert_eq!(parse_flags(b"Eg$x").map(flags_to_string), Some(str_val("g$xE")));
+    assert_eq!(parse_flags(b"").map(flags_to_string), Some(vec![]));
+    assert_eq!(parse_flags(b"Kq"), None);
+}
+
+#[test]
+fn notifications_are_off_by_default() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+    assert!(s.notifications().is_empty());
+}
+
+#[test]
+fn notifications_follow_event_classes() {
+    let mut s = soul();
+    s.set_notify_flags(parse_flags(b"KEg$").unwrap());
+
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+    s.sadd(str_key("set"), vec![str_val("m")], NOW).unwrap();
+    s.del(vec![str_key("k"), str_key("missing")], NOW);
+
+    assert_eq!(
+        s.notifications(),
+        vec![(0, &b"set"[..], str_key("k")), (0, &b"del"[..], str_key("k"))]
+    );
+    assert!(s.notifications().is_empty());
+}
+
+#[test]
+fn notifications_cover_expiry_new_keys_and_misses() {
+    let mut s = soul();
+    s.set_notify_flags(parse_flags(b"Exnml").unwrap());
+
+    s.rpush(str_key("list"), vec![str_val("a")], NOW).unwrap();
+    s.expire(str_key("list"), NOW + 1, NOW);
+    assert_eq!(s.llen(str_key("list"), EXPIRED).unwrap(), 0);
+
+    assert_eq!(
+        s.notifications(),
+        vec![
+            (0, &b"new"[..], str_key("list")),
+            (0, &b"rpush"[..], str_key("list")),
+            (0, &b"expired"[..], str_key("list")),
+            (0, &b"keymiss"[..], str_key("list")),
+        ]
+    );
+}
+
+#[test]
+fn notifications_name_the_database() {
+    let mut s = soul();
+    s.set_notify_flags(parse_flags(b"Eg").unwrap());
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+
+    s.move_key(str_key("k"), 3, NOW).unwrap();
+
+    assert_eq!(
+        s.notifications(),
+        vec![(0, &b"move_from"[..], str_key("k")), (3, &b"move_to"[..], str_key("k"))]
+    );
+}
+
+// ── SORT ──────────────────────────────────────────────────────────────────────
+
+fn sorted(s: &mut Soul, key: &str, options: &SortOptions) -> Vec<Option<Vec<u8>>> {
+    s.sort(str_key(key), options, NOW).unwrap()
+}
+
+fn some_vals(values: &[&str]) -> Vec<Option<Vec<u8>>> {
+    values.iter().map(|v| Some(str_val(v))).collect()
+}
+
+#[test]
+fn sort_list_numerically() {
+    let mut s = soul();
+    s.rpush(str_key("l"), vec![str_val("10"), str_val("2"), str_val("-3.5")], NOW).unwrap();
+
+    assert_eq!(sorted(&mut s, "l", &SortOptions::default()), some_vals(&["-3.5", "2", "10"]));
+
+    let desc = SortOptions { descending: true, ..Default::default() };
+    assert_eq!(sorted(&mut s, "l", &desc), some_vals(&["10", "2", "-3.5"]));
+}
+
+#[test]
+fn sort_non_numeric_without_alpha_returns_error() {
+    let mut s = soul();
+    s.rpush(str_key("l"), vec![str_val("b"), str_val("1")], NOW).unwrap();
+
+    assert!(s.sort(str_key("l"), &SortOptions::default(), NOW).is_err());
+
+    let alpha = SortOptions { alpha: true, ..Default::default() };
+    assert_eq!(sorted(&mut s, "l", &alpha), some_vals(&["1", "b"]));
+}
+
+#[test]
+fn sort_set_with_limit() {
+    let mut s = soul();
+    s.sadd(str_key("s"), vec![str_val("c"), str_val("a"), str_val("d"), str_val("b")], NOW).unwrap();
+
+    let options = SortOptions { alpha: true, limit: Some((1, 2)), ..Default::default() };
+    assert_eq!(sorted(&mut s, "s", &options), some_vals(&["b", "c"]));
+
+    let rest = SortOptions { alpha: true, limit: Some((2, -1)), ..Default::default() };
+    assert_eq!(sorted(&mut s, "s", &rest), some_vals(&["c", "d"]));
+}
+
+#[test]
+fn sort_by_external_keys_and_hash_fields() {
+    let mut s = soul();
+    s.rpush(str_key("ids"), vec![str_val("1"), str_val("2"), str_val("3")], NOW).unwrap();
+    s.set(str_key("weight_1"), (Value::String(str_val("30")), None));
+    s.set(str_key("weight_2"), (Value::String(str_val("10")), None));
+    s.hset(str_key("obj_1"), vec![(str_val("name"), str_val("one"))], NOW).unwrap();
+    s.hset(str_key("obj_2"), vec![(str_val("name"), str_val("two"))], NOW).unwrap();
+
+    // 3 has no weight, so it counts as 0.
+    let by = SortOptions { by: Some(str_val("weight_*")), ..Default::default() };
+    assert_eq!(sorted(&mut s, "ids", &by), some_vals(&["3", "2", "1"]));
+
+    let get = SortOptions {
+        by: Some(str_val("weight_*")),
+        get: vec![str_val("#"), str_val("obj_*->name")],
+        ..Default::default()
+    };
+    assert_eq!(
+        sorted(&mut s, "ids", &get),
+        vec![
+            Some(str_val("3")),
+            None,
+            Some(str_val("2")),
+            Some(str_val("two")),
+            Some(str_val("1")),
+            Some(str_val("one")),
+        ]
+    );
+}
+
+#[test]
+fn sort_by_pattern_without_star_keeps_order() {
+    let mut s = soul();
+    s.rpush(str_key("l"), vec![str_val("3"), str_val("1"), str_val("2")], NOW).unwrap();
+
+    let options = SortOptions { by: Some(str_val("nosort")), ..Default::default() };
+    assert_eq!(sorted(&mut s, "l", &options), some_vals(&["3", "1", "2"]));
+}
+
+#[test]
+fn sort_wrong_type_returns_error() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+
+    assert!(s.sort(str_key("k"), &SortOptions::default(), NOW).is_err());
+}
+
+#[test]
+fn sort_store_replaces_destination_with_list() {
+    let mut s = soul();
+    s.rpush(str_key("l"), vec![str_val("2"), str_val("1")], NOW).unwrap();
+    s.set(str_key("dst"), (Value::String(str_val("old")), None));
+
+    let options = SortOptions { get: vec![str_val("#"), str_val("missing_*")], ..Default::default() };
+    assert_eq!(s.sort_store(str_key("l"), &options, str_key("dst"), NOW).unwrap(), 4);
+    assert_eq!(
+        s.lrange(str_key("dst"), 0, -1, NOW).unwrap(),
+        Some(vec![Some(str_val("1")), Some(Vec::new()), Some(str_val("2")), Some(Vec::new())])
+    );
+
+    assert_eq!(s.sort_store(str_key("none"), &options, str_key("dst"), NOW).unwrap(), 0);
+    assert_eq!(s.exists(vec![str_key("dst")], NOW), 0);
+}
+
+// ── OBJECT / MEMORY ──────────────────────────────────────────────────────────
+
+#[test]
+fn object_encoding_follows_the_value() {
+    let mut s = soul();
+    s.set(str_key("int"), (Value::String(str_val("12345")), None));
+    s.set(str_key("short"), (Value::String(str_val("hello")), None));
+    s.set(str_key("long"), (Value::String(vec![b'x'; 100]), None));
+    s.rpush(str_key("list"), vec![str_val("a")], NOW).unwrap();
+    s.sadd(str_key("set"), vec![str_val("a")], NOW).unwrap();
+
+    assert_eq!(s.object_encoding(str_key("int"), NOW), Some(&b"int"[..]));
+    assert_eq!(s.object_encoding(str_key("short"), NOW), Some(&b"embstr"[..]));
+    assert_eq!(s.object_encoding(str_key("long"), NOW), Some(&b"raw"[..]));
+    assert_eq!(s.object_encoding(str_key("list"), NOW), Some(&b"listpack"[..]));
+    assert_eq!(s.object_encoding(str_key("set"), NOW), Some(&b"listpack"[..]));
+    assert_eq!(s.object_encoding(str_key("missing"), NOW), None);
+}
+
+#[test]
+fn object_idletime_counts_from_last_use_and_does_not_reset_itself() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+    s.get(str_key("k"), NOW).unwrap();
+
+    assert_eq!(s.object_idletime(str_key("k"), NOW + 30_000), Some(30));
+    assert_eq!(s.object_idletime(str_key("k"), NOW + 40_999), Some(40));
+
+    s.get(str_key("k"), NOW + 50_000).unwrap();
+    assert_eq!(s.object_idletime(str_key("k"), NOW + 55_000), Some(5));
+    assert_eq!(s.object_idletime(str_key("missing"), NOW), None);
+}
+
+#[test]
+fn object_freq_starts_warm_and_decays_per_minute() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+    assert_eq!(s.object_freq(str_key("k"), NOW), Some(5));
+
+    // Up to the initial value every use counts.
+    s.get(str_key("k"), NOW).unwrap();
+    assert_eq!(s.object_freq(str_key("k"), NOW), Some(6));
+    assert_eq!(s.object_freq(str_key("k"), NOW + 120_000), Some(4));
+}
+
+#[test]
+fn object_freq_resets_when_key_is_recreated() {
+    let mut s = soul();
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+    s.get(str_key("k"), NOW).unwrap();
+    s.del(vec![str_key("k")], NOW);
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+
+    assert_eq!(s.object_freq(str_key("k"), NOW), Some(5));
+    assert_eq!(s.object_refcount(str_key("k"), NOW), Some(1));
+}
+
+#[test]
+fn memory_usage_grows_with_the_value() {
+    let mut s = soul();
+    s.set(str_key("small"), (Value::String(str_val("v")), None));
+    s.set(str_key("large"), (Value::String(vec![b'x'; 1000]), None));
+
+    let small = s.memory_usage(str_key("small"), 5, NOW).unwrap();
+    let big = s.memory_usage(str_key("large"), 5, NOW).unwrap();
+
+    assert!(big >= small + 999);
+    assert_eq!(s.memory_usage(str_key("missing"), 5, NOW), None);
+}
+
+#[test]
+fn memory_usage_extrapolates_from_samples() {
+    let mut s = soul();
+    // Too big for a listpack, so the elements are sized one by one.
+    let mut elements = vec![vec![b'x'; 10_000]];
+    elements.extend((0..9).map(|i| str_val(&i.to_string())));
+    s.rpush(str_key("l"), elements, NOW).unwrap();
+
+    let sampled = s.memory_usage(str_key("l"), 1, NOW).unwrap();
+    let exact = s.memory_usage(str_key("l"), 0, NOW).unwrap();
+
+    // The one big element sampled stands in for all ten.
+    assert!(sampled > exact + 80_000);
+}
+
+// ── Compact encodings ────────────────────────────────────────────────────────
+
+fn encoding(s: &mut Soul, key: &str) -> &'static [u8] {
+    s.object_encoding(str_key(key), NOW).unwrap()
+}
+
+#[test]
+fn small_hash_is_a_listpack_until_it_grows() {
+    let mut s = soul();
+    s.hset(str_key("h"), vec![(str_val("f1"), str_val("v1")), (str_val("f2"), str_val("v2"))], NOW).unwrap();
+    assert_eq!(encoding(&mut s, "h"), b"listpack");
+
+    // Overwriting and deleting work in place.
+    assert_eq!(s.hset(str_key("h"), vec![(str_val("f1"), str_val("new"))], NOW).unwrap(), 0);
+    assert_eq!(s.hdel(str_key("h"), vec![str_val("f2")], NOW).unwrap(), 1);
+    assert_eq!(s.hget(str_key("h"), str_val("f1"), NOW).unwrap(), Some(str_val("new")));
+    assert_eq!(s.hlen(str_key("h"), NOW).unwrap(), 1);
+
+    let pairs = (0..200).map(|i| (str_val(&format!("f{i}")), str_val("v"))).collect();
+    s.hset(str_key("h"), pairs, NOW).unwrap();
+
+    assert_eq!(encoding(&mut s, "h"), b"hashtable");
+    assert_eq!(s.hlen(str_key("h"), NOW).unwrap(), 200);
+    assert_eq!(s.hget(str_key("h"), str_val("f1"), NOW).unwrap(), Some(str_val("v")));
+}
+
+#[test]
+fn long_hash_value_converts_to_hashtable() {
+    let mut s = soul();
+    s.hset(str_key("h"), vec![(str_val("f"), str_val("v"))], NOW).unwrap();
+    s.hset(str_key("h"), vec![(str_val("big"), vec![b'x'; 65])], NOW).unwrap();
+
+    assert_eq!(encoding(&mut s, "h"), b"hashtable");
+    assert_eq!(s.hget(str_key("h"), str_val("f"), NOW).unwrap(), Some(str_val("v")));
+}
+
+#[test]
+fn integer_set_is_an_intset_until_a_string_arrives() {
+    let mut s = soul();
+    s.sadd(str_key("s"), vec![str_val("3"), str_val("-1"), str_val("2")], NOW).unwrap();
+    assert_eq!(encoding(&mut s, "s"), b"intset");
+    assert_eq!(s.sismember(str_key("s"), str_val("2"), NOW).unwrap(), 1);
+
+    // "07" isn't how 7 prints, so it can't be stored as one.
+    s.sadd(str_key("s"), vec![str_val("07")], NOW).unwrap();
+    assert_eq!(encoding(&mut s, "s"), b"listpack");
+
+    let mut members = s.smembers(str_key("s"), NOW).unwrap().unwrap();
+    members.sort();
+    assert_eq!(members, vec![Some(str_val("-1")), Some(str_val("07")), Some(str_val("2")), Some(str_val("3"))]);
+
+    assert_eq!(s.srem(str_key("s"), vec![str_val("-1")], NOW).unwrap(), 1);
+    assert_eq!(s.sismember(str_key("s"), str_val("-1"), NOW).unwrap(), 0);
+}
+
+#[test]
+fn large_integer_set_converts_to_hashtable() {
+    let mut s = soul();
+    let members = (0..600).map(|i| str_val(&i.to_string())).collect();
+    s.sadd(str_key("s"), members, NOW).unwrap();
+
+    assert_eq!(encoding(&mut s, "s"), b"hashtable");
+    assert_eq!(s.sismember(str_key("s"), str_val("599"), NOW).unwrap(), 1);
+}
+
+#[test]
+fn list_listpack_supports_every_list_operation() {
+    let mut s = soul();
+    s.rpush(str_key("l"), vec![str_val("b"), str_val("c"), str_val("b")], NOW).unwrap();
+    s.lpush(str_key("l"), vec![str_val("a")], NOW).unwrap();
+    assert_eq!(encoding(&mut s, "l"), b"listpack");
+
+    s.lset(str_key("l"), -1, str_val("d"), NOW).unwrap();
+    assert_eq!(s.lindex(str_key("l"), 3, NOW).unwrap(), Some(str_val("d")));
+    assert_eq!(s.lrem(str_key("l"), 0, str_val("b"), NOW).unwrap(), 1);
+    assert_eq!(s.rpop(str_key("l"), NOW).unwrap(), Some(str_val("d")));
+    assert_eq!(s.lpop(str_key("l"), NOW).unwrap(), Some(str_val("a")));
+    assert_eq!(
+        s.lrange(str_key("l"), 0, -1, NOW).unwrap(),
+        Some(vec![Some(str_val("c"))])
+    );
+}
+
+#[test]
+fn list_outgrowing_its_byte_budget_becomes_a_quicklist() {
+    let mut s = soul();
+    let elements = (0..10).map(|_| vec![b'x'; 1000]).collect();
+    s.rpush(str_key("l"), elements, NOW).unwrap();
+
+    assert_eq!(encoding(&mut s, "l"), b"quicklist");
+    assert_eq!(s.llen(str_key("l"), NOW).unwrap(), 10);
+}
+
+#[test]
+fn encoding_limits_are_configurable() {
+    let mut s = soul();
+    let mut limits = EncodingLimits::default();
+    assert!(limits.set(b"hash-max-listpack-entries", b"1"));
+    assert!(limits.set(b"list-max-listpack-size", b"2"));
+    assert!(!limits.set(b"list-max-listpack-size", b"-6"));
+    assert!(!limits.set(b"no-such-limit", b"1"));
+    s.set_encoding_limits(limits);
+
+    s.hset(str_key("h"), vec![(str_val("f1"), str_val("v1")), (str_val("f2"), str_val("v2"))], NOW).unwrap();
+    s.rpush(str_key("l"), vec![str_val("a"), str_val("b"), str_val("c")], NOW).unwrap();
+
+    assert_eq!(encoding(&mut s, "h"), b"hashtable");
+    assert_eq!(encoding(&mut s, "l"), b"quicklist");
+}
+
+// ── WATCH ─────────────────────────────────────────────────────────────────────
+
+#[test]
+fn touched_keys_are_only_kept_while_watching() {
+    let mut s = soul();
+    s.set(str_key("before"), (Value::String(str_val("v")), None));
+    assert!(s.touched().is_empty());
+
+    s.set_watching(true);
+    s.set(str_key("k"), (Value::String(str_val("v")), None));
+    s.move_key(str_key("k"), 2, NOW).unwrap();
+
+    let mut touched = s.touched();
+    touched.dedup();
+    assert_eq!(touched, vec![(0, str_key("k")), (2, str_key("k"))]);
+    assert!(s.touched().is_empty());
+}
+
+#[test]
+fn touched_keys_include_expiry_but_not_misses() {
+    let mut s = soul();
+    s.rpush(str_key("list"), vec![str_val("a")], NOW).unwrap();
+    s.expire(str_key("list"), NOW + 1, NOW);
+    s.set_watching(true);
+
+    s.expire_if_due(&str_key("list"), NOW);
+    assert!(s.touched().is_empty());
+
+    s.expire_if_due(&str_key("list"), EXPIRED);
+    assert_eq!(s.llen(str_key("list"), EXPIRED).unwrap(), 0);
+    assert_eq!(s.touched(), vec![(0, str_key("list"))]);
+}
+
+// ── Rites and relics ─────────────────────────────────────────────────────────
+
+struct Tally(u64);
+
+impl Relic for Tally {
+    const TYPE_NAME: &'static [u8] = b"tally";
+
+    fn inscribe(&self) -> Vec<u8> {
+        self.0.to_le_bytes().to_vec()
+    }
+
+    fn decipher(bytes: &[u8]) -> Option<Self> {
+        Some(Tally(u64::from_le_bytes(bytes.try_into().ok()?)))
+    }
+}
+
+struct TallyIncr;
+
+impl Rite for TallyIncr {
+    fn name(&self) -> &'static [u8] {
+        b"TALLY.INCR"
+    }
+
+    fn arity(&self) -> i64 {
+        2
+    }
+
+    fn flags(&self) -> &'static [&'static [u8]] {
+        &[rite::WRITE]
+    }
+
+    fn perform(&self, terms: Vec<Vec<u8>>, soul: &mut Soul, now: u64) -> Response {
+        let key = terms[1].clone();
+
+        match soul.relic::<Tally>(&key, now) {
+            Ok(tally) => {
+                let tally = Tally(tally.map_or(0, |tally| tally.0) + 1);
+                soul.set_relic(key, &tally, None);
+                Response::Number(tally.0 as i64)
+            }
+            Err(sacrilege) => Response::Error(sacrilege),
+        }
+    }
+}
+
+#[test]
+fn relic_roundtrips_through_its_bytes() {
+    let mut s = soul();
+    assert!(s.relic::<Tally>(&str_key("t"), NOW).unwrap().is_none());
+
+    s.set_relic(str_key("t"), &Tally(41), None);
+    assert_eq!(s.relic::<Tally>(&str_key("t"), NOW).unwrap().unwrap().0, 41);
+
+    s.set(str_key("s"), (Value::String(str_val("v")), None));
+    assert!(s.relic::<Tally>(&str_key("s"), NOW).is_err());
+    assert!(s.get(str_key("t"), NOW).is_err());
+
+    let payload = s.dump(str_key("t"), NOW).unwrap();
+    s.restore(str_key("copy"), payload, None, false, NOW).unwrap();
+    assert_eq!(s.relic::<Tally>(&str_key("copy"), NOW).unwrap().unwrap().0, 41);
+}
+
+#[test]
+fn relic_type_is_named_once_enshrined() {
+    struct Unnamed;
+
+    impl Relic for Unnamed {
+        const TYPE_NAME: &'static [u8] = b"unnamed";
+
+        fn inscribe(&self) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn decipher(_: &[u8]) -> Option<Self> {
+            Some(Unnamed)
+        }
+    }
+
+    let mut s = soul();
+    s.set_relic(str_key("u"), &Unnamed, None);
+    assert_eq!(s.key_type(str_key("u"), NOW), b"relic");
+
+    rite::enshrine::<Unnamed>();
+    assert_eq!(s.key_type(str_key("u"), NOW), b"unnamed");
+}
+
+#[test]
+fn consecrated_rite_is_found_and_performed() {
+    assert!(rite::consecrate(TallyIncr).is_ok());
+    assert!(rite::consecrate(TallyIncr).is_err());
+
+    let tally_incr = rite::find(b"tally.incr").unwrap();
+    assert!(rite::fits(tally_incr.as_ref(), 2));
+    assert!(!rite::fits(tally_incr.as_ref(), 3));
+    assert!(rite::find(b"tally.decr").is_none());
+
+    let mut s = soul();
+    for expected in 1..=3 {
+        match tally_incr.perform(vec![str_key("TALLY.INCR"), str_key("t")], &mut s, NOW) {
+            Response::Number(n) => assert_eq!(n, expected),
+            _ => panic!("expected a number"),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     temple::Temple,
-    wish::{grant::Decree, util::bytes_to_usize},
+    wish::{covenant::Covenant, grant::Decree, util::bytes_to_usize},
 };
 use mio::{Token, net::TcpStream};
 use std::{io::Read, sync::mpsc::Sender};
@@ -108,6 +108,64 @@ pub enum Command {
     HGETALL,
     SMEMBERS,
     CONFIG,
+    CMSINITBYDIM,
+    CMSINCRBY,
+    CMSQUERY,
+    CMSMERGE,
+    TOPKRESERVE,
+    TOPKADD,
+    TOPKLIST,
+    TOPKCOUNT,
+    TSCREATE,
+    TSADD,
+    TSMADD,
+    TSINCRBY,
+    TSRANGE,
+    TSREVRANGE,
+    TSMRANGE,
+    TSCREATERULE,
+    TSDELETERULE,
+    FTCREATE,
+    FTSEARCH,
+    FTDROPINDEX,
+    FTLIST,
+    CLTHROTTLE,
+    SCAN,
+    HSCAN,
+    SSCAN,
+    KEYS,
+    TYPE,
+    RENAME,
+    RENAMENX,
+    COPY,
+    RANDOMKEY,
+    DBSIZE,
+    TOUCH,
+    UNLINK,
+    SELECT,
+    MOVE,
+    SWAPDB,
+    FLUSHDB,
+    FLUSHALL,
+    DUMP,
+    RESTORE,
+    SORT,
+    SORTRO,
+    OBJECT,
+    MEMORY,
+    MULTI,
+    EXEC,
+    DISCARD,
+    WATCH,
+    UNWATCH,
+    EVAL,
+    EVALSHA,
+    SCRIPT,
+    FCALL,
+    FCALLRO,
+    FUNCTION,
+    COMMAND,
+    PUBSUB,
 }
 
 #[derive(Debug)]
@@ -116,11 +174,37 @@ pub enum Sacrilege {
     IncorrectUsage(Command),
     UnknownCommand,
     SubscriberOnlyMode,
+    KeyAlreadyExists(Command),
+    KeyDoesNotExist(Command),
+    InvalidArgument(Command),
+    DimensionMismatch,
+    InvalidScore,
+    DuplicateSample,
+    InvalidTimestamp(Command),
+    TransactionAborted,
+    NegativeNumberOfKeys,
+    // An error reply a script returned or raised, with its code.
+    Script(Vec<u8>),
+    NoScript,
+    NotBusy,
+    Unkillable,
+    Busy,
+    // An error reply a rite returned, with its code.
+    Rite(Vec<u8>),
+    // The command table's arity check failed, with the command's name.
+    WrongNumberOfArguments(&'static [u8]),
+    InvalidCommand,
+    InvalidCommandArity,
+    NoKeyArguments,
 }
 
 pub enum InfoType {
     Ok,
     Pong,
+    Queued,
+    Type(&'static [u8]),
+    // A status reply a script returned.
+    Status(Vec<u8>),
 }
 
 pub enum Response {
@@ -131,13 +215,19 @@ pub enum Response {
     Amount(u32),
     Number(i64),
     Length(usize),
-    SubscribedChannels(Vec<(Vec<u8>, usize)>),
-    UnsubscribedChannels(Option<Vec<(Vec<u8>, usize)>>),
+    // A subscribe or psubscribe reply for each channel or pattern, with how
+    // many the client is subscribed to after it.
+    Subscribed(&'static [u8], Vec<(Vec<u8>, usize)>),
+    // Likewise for unsubscribe or punsubscribe. A client that had nothing to
+    // leave gets one reply without a name.
+    Unsubscribed(&'static [u8], Vec<(Option<Vec<u8>>, usize)>),
+    Array(Vec<Response>),
 }
 
 pub struct Pilgrim {
     pub stream: TcpStream,
     pub virtue: Option<Virtue>,
+    pub covenant: Option<Covenant>,
     pub tx: Sender<Decree>,
 }
 
@@ -149,6 +239,7 @@ pub enum Sin {
     Blasphemy,
 }
 
+pub mod covenant;
 pub mod grant;
 pub mod util;
 
@@ -226,7 +317,13 @@ pub fn wish(pilgrim: &mut Pilgrim, mut temple: Temple, token: Token) -> Result<(
                     if virtue.terms.len() == virtue.expected_terms {
                         let terms = std::mem::take(&mut virtue.terms);
 
-                        grant::grant(terms, &mut temple, pilgrim.tx.clone(), token);
+                        covenant::keep(
+                            terms,
+                            &mut pilgrim.covenant,
+                            &mut temple,
+                            pilgrim.tx.clone(),
+                            token,
+                        );
 
                         virtue.phase = Phase::Idle;
                     }
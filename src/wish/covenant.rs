@@ -0,0 +1,197 @@
+use std::{
+    sync::mpsc::{self, Sender},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, InfoType, Response, Sacrilege,
+        grant::{self, Decree, Gift},
+    },
+};
+
+// The commands a connection queued after MULTI. A command that fails before
+// it is queued breaks the covenant and EXEC discards all of them.
+#[derive(Default)]
+pub struct Covenant {
+    queued: Vec<Vec<Vec<u8>>>,
+    broken: bool,
+}
+
+pub fn keep(
+    terms: Vec<Vec<u8>>,
+    covenant: &mut Option<Covenant>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+) {
+    let cmd = &terms[0];
+
+    // A long script holds the temple, nothing but SCRIPT KILL and FUNCTION
+    // KILL is let through.
+    if temple.busy()
+        && !((cmd.eq_ignore_ascii_case(b"SCRIPT") || cmd.eq_ignore_ascii_case(b"FUNCTION"))
+            && terms
+                .get(1)
+                .is_some_and(|sub| sub.eq_ignore_ascii_case(b"KILL")))
+    {
+        deliver(&tx, Response::Error(Sacrilege::Busy), token);
+    } else if cmd.eq_ignore_ascii_case(b"MULTI") {
+        multi(terms, covenant, tx, token);
+    } else if cmd.eq_ignore_ascii_case(b"EXEC") {
+        exec(terms, covenant, temple, tx, token);
+    } else if cmd.eq_ignore_ascii_case(b"DISCARD") {
+        discard(terms, covenant, temple, tx, token);
+    } else if let Some(covenant) = covenant {
+        queue(terms, covenant, tx, token);
+    } else {
+        grant::grant(terms, temple, tx, token);
+    }
+}
+
+fn multi(terms: Vec<Vec<u8>>, covenant: &mut Option<Covenant>, tx: Sender<Decree>, token: Token) {
+    let response = if terms.len() != 1 {
+        refuse(
+            covenant,
+            Sacrilege::IncorrectNumberOfArguments(Command::MULTI),
+        )
+    } else if covenant.is_some() {
+        Response::Error(Sacrilege::IncorrectUsage(Command::MULTI))
+    } else {
+        *covenant = Some(Covenant::default());
+
+        Response::Info(InfoType::Ok)
+    };
+
+    deliver(&tx, response, token);
+}
+
+fn exec(
+    terms: Vec<Vec<u8>>,
+    covenant: &mut Option<Covenant>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+) {
+    if terms.len() != 1 {
+        let response = refuse(
+            covenant,
+            Sacrilege::IncorrectNumberOfArguments(Command::EXEC),
+        );
+        deliver(&tx, response, token);
+        return;
+    }
+
+    let Some(Covenant { queued, broken }) = covenant.take() else {
+        deliver(
+            &tx,
+            Response::Error(Sacrilege::IncorrectUsage(Command::EXEC)),
+            token,
+        );
+        return;
+    };
+
+    if broken {
+        temple.abort(tx, token);
+        return;
+    }
+
+    // Every command is granted against a recorder, each with its own reply
+    // channel, so the temple can answer them all in one array.
+    let (mut recorder, recorded) = Temple::recorder();
+    let mut replies = Vec::with_capacity(queued.len());
+
+    for terms in queued {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        grant::grant(terms, &mut recorder, reply_tx, token);
+        replies.push(reply_rx);
+    }
+
+    temple.exec(
+        tx,
+        recorded.try_iter().collect(),
+        replies,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
+
+fn discard(
+    terms: Vec<Vec<u8>>,
+    covenant: &mut Option<Covenant>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+) {
+    let response = if terms.len() != 1 {
+        refuse(
+            covenant,
+            Sacrilege::IncorrectNumberOfArguments(Command::DISCARD),
+        )
+    } else if covenant.take().is_some() {
+        temple.unwatch(tx, token);
+        return;
+    } else {
+        Response::Error(Sacrilege::IncorrectUsage(Command::DISCARD))
+    };
+
+    deliver(&tx, response, token);
+}
+
+// Grants the command against a recorder to see whether it would be refused
+// outright. Nothing reaches the temple until EXEC.
+fn queue(terms: Vec<Vec<u8>>, covenant: &mut Covenant, tx: Sender<Decree>, token: Token) {
+    // Keys have to be watched before MULTI for EXEC to check them.
+    if terms[0].eq_ignore_ascii_case(b"WATCH") {
+        covenant.broken = true;
+        deliver(
+            &tx,
+            Response::Error(Sacrilege::IncorrectUsage(Command::WATCH)),
+            token,
+        );
+        return;
+    }
+
+    let (mut recorder, _recorded) = Temple::recorder();
+    let (reply_tx, reply_rx) = mpsc::channel();
+
+    grant::grant(terms.clone(), &mut recorder, reply_tx, token);
+
+    let response = match reply_rx.try_recv() {
+        Ok(Decree::Deliver(Gift {
+            response: Response::Error(sacrilege),
+            ..
+        })) => {
+            covenant.broken = true;
+
+            Response::Error(sacrilege)
+        }
+        _ => {
+            covenant.queued.push(terms);
+
+            Response::Info(InfoType::Queued)
+        }
+    };
+
+    deliver(&tx, response, token);
+}
+
+fn refuse(covenant: &mut Option<Covenant>, sacrilege: Sacrilege) -> Response {
+    if let Some(covenant) = covenant {
+        covenant.broken = true;
+    }
+
+    Response::Error(sacrilege)
+}
+
+fn deliver(tx: &Sender<Decree>, response: Response, token: Token) {
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
+        eprintln!("angel panicked");
+    }
+}
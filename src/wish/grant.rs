@@ -1,18 +1,37 @@
 use mio::Token;
 
 use crate::{
+    egress::outbox::OutputLimits,
     temple::Temple,
-    wish::{InfoType, Response, Sacrilege},
+    wish::{Response, Sacrilege},
 };
 
 use std::sync::mpsc::Sender;
 
 mod append;
+pub mod canon;
+mod cl_throttle;
+mod cms_incrby;
+mod cms_initbydim;
+mod cms_merge;
+mod cms_query;
+mod command;
 mod config;
+mod copy;
+mod dbsize;
 mod decr;
 mod del;
+mod dump;
+mod eval;
 mod exists;
 mod expire;
+mod flushall;
+mod flushdb;
+mod ft_create;
+mod ft_dropindex;
+mod ft_list;
+mod ft_search;
+mod function;
 mod get;
 mod hdel;
 mod hexists;
@@ -20,8 +39,11 @@ mod hget;
 mod hgetall;
 mod hlen;
 mod hmget;
+mod hscan;
 mod hset;
 mod incr;
+mod key_type;
+mod keys;
 mod lindex;
 mod llen;
 mod lpop;
@@ -29,21 +51,52 @@ mod lpush;
 mod lrange;
 mod lrem;
 mod lset;
+mod memory;
 mod mget;
+mod move_key;
 mod mset;
+mod object;
 mod ping;
 mod publish;
+mod pubsub;
+mod randomkey;
+mod rename;
+mod renamenx;
+mod restore;
+mod rite;
 mod rpop;
 mod rpush;
 mod sadd;
+mod scan;
+mod script;
+mod select;
 mod set;
 mod sismember;
 mod smembers;
+mod sort;
 mod srem;
+mod sscan;
 mod strlen;
 mod subscribe;
+mod swapdb;
+mod topk_add;
+mod topk_count;
+mod topk_list;
+mod topk_reserve;
+mod touch;
+mod ts_add;
+mod ts_create;
+mod ts_createrule;
+mod ts_deleterule;
+mod ts_incrby;
+mod ts_madd;
+mod ts_mrange;
+mod ts_range;
 mod ttl;
+mod unlink;
 mod unsubscribe;
+mod unwatch;
+mod watch;
 
 pub struct Gift {
     pub token: mio::Token,
@@ -52,104 +105,82 @@ pub struct Gift {
 
 pub enum Decree {
     Welcome(Token, mio::net::TcpStream),
+    // The client hung up, its half of the socket is closed too.
+    Farewell(Token),
+    // client-output-buffer-limit was changed.
+    Limits(OutputLimits),
     Deliver(Gift),
-    Broadcast(Token, Vec<u8>, Vec<u8>, Vec<Token>),
+    // The publisher, if there is one, is told how many clients received it.
+    Broadcast(Option<Token>, Vec<u8>, Vec<u8>, Congregation),
+}
+
+// Who a message published on a channel goes to: the channel's subscribers,
+// and the subscribers of each pattern matching it, who are told the pattern.
+// Subscribers replaying the channel are readers, told the message's sequence
+// ID as well. A message on a shard channel goes to its clients as smessage.
+#[derive(Default)]
+pub struct Congregation {
+    pub clients: Vec<Token>,
+    pub patterns: Vec<(Vec<u8>, Vec<Token>)>,
+    pub readers: Vec<Token>,
+    pub sequence: Option<u64>,
+    pub shard: bool,
+}
+
+impl Congregation {
+    // Sets the readers among the channel's subscribers apart, once the
+    // message has a sequence ID.
+    pub fn chronicle(&mut self, sequence: u64, is_reader: impl Fn(&Token) -> bool) {
+        let (readers, clients) = std::mem::take(&mut self.clients)
+            .into_iter()
+            .partition(is_reader);
+
+        self.clients = clients;
+        self.readers = readers;
+        self.sequence = Some(sequence);
+    }
+
+    // How many deliveries it takes, a client matching several patterns
+    // counting once for each.
+    pub fn len(&self) -> usize {
+        self.clients.len()
+            + self.readers.len()
+            + self
+                .patterns
+                .iter()
+                .map(|(_, clients)| clients.len())
+                .sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 pub fn grant(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
     let cmd = &terms[0];
 
-    if cmd.eq_ignore_ascii_case(b"SET") {
-        set::set(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"GET") {
-        get::get(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"PING") {
-        ping::ping(terms, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"DEL") {
-        del::del(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"EXISTS") {
-        exists::exists(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"INCR") {
-        incr::incr(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"DECR") {
-        decr::decr(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"APPEND") {
-        append::append(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HSET") {
-        hset::hset(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HGET") {
-        hget::hget(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HMGET") {
-        hmget::hmget(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"STRLEN") {
-        strlen::strlen(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HDEL") {
-        hdel::hdel(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HEXISTS") {
-        hexists::hexists(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HLEN") {
-        hlen::hlen(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LPUSH") {
-        lpush::lpush(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LPOP") {
-        lpop::lpop(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"RPUSH") {
-        rpush::rpush(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"RPOP") {
-        rpop::rpop(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LLEN") {
-        llen::llen(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LRANGE") {
-        lrange::lrange(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LINDEX") {
-        lindex::lindex(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LSET") {
-        lset::lset(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"LREM") {
-        lrem::lrem(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"EXPIRE") {
-        expire::expire(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"TTL") {
-        ttl::ttl(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"SUBSCRIBE") {
-        subscribe::subscribe(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"PUBLISH") {
-        publish::publish(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"MSET") {
-        mset::mset(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"MGET") {
-        mget::mget(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"SADD") {
-        sadd::sadd(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"SREM") {
-        srem::srem(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"SISMEMBER") {
-        sismember::sismember(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"HGETALL") {
-        hgetall::hgetall(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"SMEMBERS") {
-        smembers::smembers(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"UNSUBSCRIBE") {
-        unsubscribe::unsubscribe(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"CONFIG") {
-        config::config(terms, temple, tx, token);
-    } else if cmd.eq_ignore_ascii_case(b"COMMAND") {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Info(InfoType::Ok),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        };
-    } else if tx
-        .send(Decree::Deliver(Gift {
-            token,
-            response: Response::Error(Sacrilege::UnknownCommand),
-        }))
-        .is_err()
-    {
+    let response = if let Some(canon) = canon::find(cmd) {
+        if !canon::fits(canon.arity, terms.len()) {
+            Response::Error(Sacrilege::WrongNumberOfArguments(canon.name))
+        } else if let Some(grant) = canon.grant {
+            grant(terms, temple, tx, token);
+            return;
+        } else {
+            Response::Error(Sacrilege::UnknownCommand)
+        }
+    } else if let Some(consecrated) = crate::temple::rite::find(cmd) {
+        if !crate::temple::rite::fits(consecrated.as_ref(), terms.len()) {
+            Response::Error(Sacrilege::WrongNumberOfArguments(consecrated.name()))
+        } else {
+            rite::rite(consecrated, terms, temple, tx, token);
+            return;
+        }
+    } else {
+        Response::Error(Sacrilege::UnknownCommand)
+    };
+
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
         eprintln!("angel panicked");
     }
 }
@@ -14,20 +14,6 @@ use crate::{
 };
 
 pub fn append(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
-    if terms.len() != 3 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::APPEND)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-
-        return;
-    }
-
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
@@ -39,7 +25,7 @@ pub fn append(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, toke
             token,
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs())
+                .map(|d| d.as_millis() as u64)
                 .unwrap_or(0),
         );
     } else if tx
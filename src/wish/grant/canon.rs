@@ -0,0 +1,1134 @@
+use std::{collections::HashMap, sync::LazyLock, sync::mpsc::Sender};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, rite},
+    wish::{grant::Decree, util::bytes_to_i64},
+};
+
+use super::*;
+
+/// The command changes the dataset.
+pub const WRITE: &[u8] = b"write";
+/// The command only reads the dataset.
+pub const READONLY: &[u8] = b"readonly";
+/// The command may grow the dataset.
+pub const DENYOOM: &[u8] = b"denyoom";
+/// The command is part of pub/sub.
+pub const PUBSUB: &[u8] = b"pubsub";
+/// The command administers the server.
+pub const ADMIN: &[u8] = b"admin";
+/// The command runs in constant or logarithmic time.
+pub const FAST: &[u8] = b"fast";
+/// Scripts can't call the command.
+pub const NOSCRIPT: &[u8] = b"noscript";
+/// Reported for commands whose keys have to be found by parsing the terms.
+pub const MOVABLEKEYS: &[u8] = b"movablekeys";
+
+pub type Grant = fn(Vec<Vec<u8>>, &mut Temple, Sender<Decree>, Token);
+
+/// Which terms are keys.
+#[derive(Clone, Copy)]
+pub enum Keys {
+    None,
+    /// Every `step`th term from `first` to `last`, a negative `last` counting
+    /// back from the final term.
+    Range { first: usize, last: i64, step: usize },
+    /// The term at `numkeys` says how many keys follow it.
+    Counted { numkeys: usize },
+}
+
+const NONE: Keys = Keys::None;
+const KEY: Keys = Keys::Range {
+    first: 1,
+    last: 1,
+    step: 1,
+};
+const SECOND: Keys = Keys::Range {
+    first: 2,
+    last: 2,
+    step: 1,
+};
+const TWO: Keys = Keys::Range {
+    first: 1,
+    last: 2,
+    step: 1,
+};
+const ALL: Keys = Keys::Range {
+    first: 1,
+    last: -1,
+    step: 1,
+};
+const PAIRS: Keys = Keys::Range {
+    first: 1,
+    last: -1,
+    step: 2,
+};
+const TRIPLES: Keys = Keys::Range {
+    first: 1,
+    last: -1,
+    step: 3,
+};
+const NUMKEYS: Keys = Keys::Counted { numkeys: 2 };
+
+#[derive(Clone, Copy)]
+pub struct Canon {
+    /// Uppercase, as clients are matched against it case insensitively.
+    pub name: &'static [u8],
+    /// How many terms it takes, the name included. A negative arity means at
+    /// least that many.
+    pub arity: i64,
+    pub flags: &'static [&'static [u8]],
+    pub keys: Keys,
+    /// What COMMAND DOCS files it under.
+    pub group: &'static [u8],
+    /// ACL categories besides the ones its group and flags imply.
+    pub categories: &'static [&'static [u8]],
+    /// None for the commands a connection keeps itself, MULTI, EXEC and
+    /// DISCARD.
+    pub grant: Option<Grant>,
+}
+
+static INDEX: LazyLock<HashMap<&'static [u8], &'static Canon>> =
+    LazyLock::new(|| CANON.iter().map(|canon| (canon.name, canon)).collect());
+
+/// The built-in command `name` refers to.
+pub fn find(name: &[u8]) -> Option<&'static Canon> {
+    INDEX.get(name.to_ascii_uppercase().as_slice()).copied()
+}
+
+/// The built-in or consecrated command `name` refers to. A rite is described
+/// by its own metadata and left without a grant, it's dispatched by
+/// `rite::rite`.
+pub fn describe(name: &[u8]) -> Option<Canon> {
+    find(name).copied().or_else(|| rite::find(name).map(|rite| of_rite(rite.as_ref())))
+}
+
+/// Every command, built-in ones first.
+pub fn all() -> Vec<Canon> {
+    CANON
+        .iter()
+        .copied()
+        .chain(rite::rites().iter().map(|rite| of_rite(rite.as_ref())))
+        .collect()
+}
+
+/// Whether `name` is a command carrying `flag`.
+pub fn flagged(name: &[u8], flag: &[u8]) -> bool {
+    describe(name).is_some_and(|canon| canon.flags.contains(&flag))
+}
+
+/// Whether `terms` is a count `arity` takes.
+pub fn fits(arity: i64, terms: usize) -> bool {
+    if arity < 0 {
+        terms as u64 >= arity.unsigned_abs()
+    } else {
+        terms as u64 == arity as u64
+    }
+}
+
+fn of_rite(rite: &dyn rite::Rite) -> Canon {
+    Canon {
+        name: rite.name(),
+        arity: rite.arity(),
+        flags: rite.flags(),
+        keys: rite.keys(),
+        group: b"module",
+        categories: &[],
+        grant: None,
+    }
+}
+
+impl Canon {
+    /// The keys among `terms`, which the arity has already been checked
+    /// against.
+    pub fn keys<'a>(&self, terms: &'a [Vec<u8>]) -> Vec<&'a [u8]> {
+        match self.keys {
+            Keys::None => Vec::new(),
+            Keys::Range { first, last, step } => {
+                let last = if last < 0 {
+                    terms.len() as i64 + last
+                } else {
+                    last.min(terms.len() as i64 - 1)
+                };
+
+                (first..)
+                    .step_by(step)
+                    .take_while(|&index| index as i64 <= last)
+                    .map(|index| terms[index].as_slice())
+                    .collect()
+            }
+            Keys::Counted { numkeys } => {
+                let count = terms
+                    .get(numkeys)
+                    .and_then(|count| bytes_to_i64(count).ok())
+                    .and_then(|count| usize::try_from(count).ok())
+                    .unwrap_or(0);
+
+                terms
+                    .iter()
+                    .skip(numkeys + 1)
+                    .take(count)
+                    .map(Vec::as_slice)
+                    .collect()
+            }
+        }
+    }
+
+    /// The categories ACL rules would match it by: its own, its group's and
+    /// the ones its flags imply.
+    pub fn acl_categories(&self) -> Vec<&'static [u8]> {
+        let mut categories = Vec::new();
+
+        let implied: &[(&[u8], &[u8])] = &[
+            (WRITE, b"@write"),
+            (READONLY, b"@read"),
+            (PUBSUB, b"@pubsub"),
+            (ADMIN, b"@admin"),
+            (ADMIN, b"@dangerous"),
+        ];
+
+        for (flag, category) in implied {
+            if self.flags.contains(flag) {
+                categories.push(*category);
+            }
+        }
+
+        categories.push(if self.flags.contains(&FAST) {
+            b"@fast"
+        } else {
+            b"@slow"
+        });
+
+        let group: &'static [u8] = match self.group {
+            b"string" => b"@string",
+            b"hash" => b"@hash",
+            b"list" => b"@list",
+            b"set" => b"@set",
+            b"generic" => b"@keyspace",
+            b"connection" => b"@connection",
+            b"transactions" => b"@transaction",
+            b"scripting" => b"@scripting",
+            b"cms" => b"@cms",
+            b"topk" => b"@topk",
+            b"timeseries" => b"@timeseries",
+            b"search" => b"@search",
+            _ => b"",
+        };
+
+        for category in [group].into_iter().chain(self.categories.iter().copied()) {
+            if !category.is_empty() && !categories.contains(&category) {
+                categories.push(category);
+            }
+        }
+
+        categories
+    }
+}
+
+// Every built-in command. Dispatch, the arity check, COMMAND and what scripts
+// may call all read from here.
+static CANON: &[Canon] = &[
+    Canon {
+        name: b"SET",
+        arity: -3,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"string",
+        categories: &[],
+        grant: Some(set::set),
+    },
+    Canon {
+        name: b"GET",
+        arity: 2,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"string",
+        categories: &[],
+        grant: Some(get::get),
+    },
+    Canon {
+        name: b"PING",
+        arity: -1,
+        flags: &[FAST],
+        keys: NONE,
+        group: b"connection",
+        categories: &[],
+        grant: Some(|terms, _, tx, token| ping::ping(terms, tx, token)),
+    },
+    Canon {
+        name: b"DEL",
+        arity: -2,
+        flags: &[WRITE],
+        keys: ALL,
+        group: b"generic",
+        categories: &[],
+        grant: Some(del::del),
+    },
+    Canon {
+        name: b"EXISTS",
+        arity: -2,
+        flags: &[READONLY, FAST],
+        keys: ALL,
+        group: b"generic",
+        categories: &[],
+        grant: Some(exists::exists),
+    },
+    Canon {
+        name: b"INCR",
+        arity: 2,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"string",
+        categories: &[],
+        grant: Some(incr::incr),
+    },
+    Canon {
+        name: b"DECR",
+        arity: 2,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"string",
+        categories: &[],
+        grant: Some(decr::decr),
+    },
+    Canon {
+        name: b"APPEND",
+        arity: 3,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"string",
+        categories: &[],
+        grant: Some(append::append),
+    },
+    Canon {
+        name: b"HSET",
+        arity: -4,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hset::hset),
+    },
+    Canon {
+        name: b"HGET",
+        arity: 3,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hget::hget),
+    },
+    Canon {
+        name: b"HMGET",
+        arity: -3,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hmget::hmget),
+    },
+    Canon {
+        name: b"STRLEN",
+        arity: 2,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"string",
+        categories: &[],
+        grant: Some(strlen::strlen),
+    },
+    Canon {
+        name: b"HDEL",
+        arity: -3,
+        flags: &[WRITE, FAST],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hdel::hdel),
+    },
+    Canon {
+        name: b"HEXISTS",
+        arity: 3,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hexists::hexists),
+    },
+    Canon {
+        name: b"HLEN",
+        arity: 2,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hlen::hlen),
+    },
+    Canon {
+        name: b"LPUSH",
+        arity: -3,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(lpush::lpush),
+    },
+    Canon {
+        name: b"LPOP",
+        arity: -2,
+        flags: &[WRITE, FAST],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(lpop::lpop),
+    },
+    Canon {
+        name: b"RPUSH",
+        arity: -3,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(rpush::rpush),
+    },
+    Canon {
+        name: b"RPOP",
+        arity: -2,
+        flags: &[WRITE, FAST],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(rpop::rpop),
+    },
+    Canon {
+        name: b"LLEN",
+        arity: 2,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(llen::llen),
+    },
+    Canon {
+        name: b"LRANGE",
+        arity: 4,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(lrange::lrange),
+    },
+    Canon {
+        name: b"LINDEX",
+        arity: 3,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(lindex::lindex),
+    },
+    Canon {
+        name: b"LSET",
+        arity: 4,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(lset::lset),
+    },
+    Canon {
+        name: b"LREM",
+        arity: 4,
+        flags: &[WRITE],
+        keys: KEY,
+        group: b"list",
+        categories: &[],
+        grant: Some(lrem::lrem),
+    },
+    Canon {
+        name: b"EXPIRE",
+        arity: -3,
+        flags: &[WRITE, FAST],
+        keys: KEY,
+        group: b"generic",
+        categories: &[],
+        grant: Some(expire::expire),
+    },
+    Canon {
+        name: b"TTL",
+        arity: 2,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"generic",
+        categories: &[],
+        grant: Some(ttl::ttl),
+    },
+    Canon {
+        name: b"SUBSCRIBE",
+        arity: -2,
+        flags: &[PUBSUB, NOSCRIPT],
+        keys: NONE,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(subscribe::subscribe),
+    },
+    Canon {
+        name: b"UNSUBSCRIBE",
+        arity: -1,
+        flags: &[PUBSUB, NOSCRIPT],
+        keys: NONE,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(unsubscribe::unsubscribe),
+    },
+    Canon {
+        name: b"PSUBSCRIBE",
+        arity: -2,
+        flags: &[PUBSUB, NOSCRIPT],
+        keys: NONE,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(subscribe::psubscribe),
+    },
+    Canon {
+        name: b"PUNSUBSCRIBE",
+        arity: -1,
+        flags: &[PUBSUB, NOSCRIPT],
+        keys: NONE,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(unsubscribe::punsubscribe),
+    },
+    Canon {
+        name: b"PUBLISH",
+        arity: 3,
+        flags: &[PUBSUB, FAST],
+        keys: NONE,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(publish::publish),
+    },
+    // Shard channels are given as keys, to be hashed to slots like them.
+    Canon {
+        name: b"SSUBSCRIBE",
+        arity: -2,
+        flags: &[PUBSUB, NOSCRIPT],
+        keys: ALL,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(subscribe::ssubscribe),
+    },
+    Canon {
+        name: b"SUNSUBSCRIBE",
+        arity: -1,
+        flags: &[PUBSUB, NOSCRIPT],
+        keys: ALL,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(unsubscribe::sunsubscribe),
+    },
+    Canon {
+        name: b"SPUBLISH",
+        arity: 3,
+        flags: &[PUBSUB, FAST],
+        keys: KEY,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(publish::spublish),
+    },
+    Canon {
+        name: b"PUBSUB",
+        arity: -2,
+        flags: &[PUBSUB],
+        keys: NONE,
+        group: b"pubsub",
+        categories: &[],
+        grant: Some(pubsub::pubsub),
+    },
+    Canon {
+        name: b"MSET",
+        arity: -3,
+        flags: &[WRITE, DENYOOM],
+        keys: PAIRS,
+        group: b"string",
+        categories: &[],
+        grant: Some(mset::mset),
+    },
+    Canon {
+        name: b"MGET",
+        arity: -2,
+        flags: &[READONLY, FAST],
+        keys: ALL,
+        group: b"string",
+        categories: &[],
+        grant: Some(mget::mget),
+    },
+    Canon {
+        name: b"SADD",
+        arity: -3,
+        flags: &[WRITE, DENYOOM, FAST],
+        keys: KEY,
+        group: b"set",
+        categories: &[],
+        grant: Some(sadd::sadd),
+    },
+    Canon {
+        name: b"SREM",
+        arity: -3,
+        flags: &[WRITE, FAST],
+        keys: KEY,
+        group: b"set",
+        categories: &[],
+        grant: Some(srem::srem),
+    },
+    Canon {
+        name: b"SISMEMBER",
+        arity: 3,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"set",
+        categories: &[],
+        grant: Some(sismember::sismember),
+    },
+    Canon {
+        name: b"HGETALL",
+        arity: 2,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hgetall::hgetall),
+    },
+    Canon {
+        name: b"SMEMBERS",
+        arity: 2,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"set",
+        categories: &[],
+        grant: Some(smembers::smembers),
+    },
+    Canon {
+        name: b"CONFIG",
+        arity: -2,
+        flags: &[ADMIN],
+        keys: NONE,
+        group: b"server",
+        categories: &[],
+        grant: Some(config::config),
+    },
+    Canon {
+        name: b"CMS.INITBYDIM",
+        arity: 4,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"cms",
+        categories: &[],
+        grant: Some(cms_initbydim::cms_initbydim),
+    },
+    Canon {
+        name: b"CMS.INCRBY",
+        arity: -4,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"cms",
+        categories: &[],
+        grant: Some(cms_incrby::cms_incrby),
+    },
+    Canon {
+        name: b"CMS.QUERY",
+        arity: -3,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"cms",
+        categories: &[],
+        grant: Some(cms_query::cms_query),
+    },
+    Canon {
+        name: b"CMS.MERGE",
+        arity: -4,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"cms",
+        categories: &[],
+        grant: Some(cms_merge::cms_merge),
+    },
+    Canon {
+        name: b"TOPK.RESERVE",
+        arity: -3,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"topk",
+        categories: &[],
+        grant: Some(topk_reserve::topk_reserve),
+    },
+    Canon {
+        name: b"TOPK.ADD",
+        arity: -3,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"topk",
+        categories: &[],
+        grant: Some(topk_add::topk_add),
+    },
+    Canon {
+        name: b"TOPK.LIST",
+        arity: -2,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"topk",
+        categories: &[],
+        grant: Some(topk_list::topk_list),
+    },
+    Canon {
+        name: b"TOPK.COUNT",
+        arity: -3,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"topk",
+        categories: &[],
+        grant: Some(topk_count::topk_count),
+    },
+    Canon {
+        name: b"TS.CREATE",
+        arity: -2,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_create::ts_create),
+    },
+    Canon {
+        name: b"TS.ADD",
+        arity: -4,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_add::ts_add),
+    },
+    Canon {
+        name: b"TS.MADD",
+        arity: -4,
+        flags: &[WRITE, DENYOOM],
+        keys: TRIPLES,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_madd::ts_madd),
+    },
+    Canon {
+        name: b"TS.INCRBY",
+        arity: -3,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_incrby::ts_incrby),
+    },
+    Canon {
+        name: b"TS.RANGE",
+        arity: -4,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_range::ts_range),
+    },
+    Canon {
+        name: b"TS.REVRANGE",
+        arity: -4,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_range::ts_revrange),
+    },
+    Canon {
+        name: b"TS.MRANGE",
+        arity: -5,
+        flags: &[READONLY],
+        keys: NONE,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_mrange::ts_mrange),
+    },
+    Canon {
+        name: b"TS.CREATERULE",
+        arity: 6,
+        flags: &[WRITE],
+        keys: TWO,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_createrule::ts_createrule),
+    },
+    Canon {
+        name: b"TS.DELETERULE",
+        arity: 3,
+        flags: &[WRITE],
+        keys: TWO,
+        group: b"timeseries",
+        categories: &[],
+        grant: Some(ts_deleterule::ts_deleterule),
+    },
+    Canon {
+        name: b"FT.CREATE",
+        arity: -5,
+        flags: &[WRITE, DENYOOM],
+        keys: NONE,
+        group: b"search",
+        categories: &[],
+        grant: Some(ft_create::ft_create),
+    },
+    Canon {
+        name: b"FT.SEARCH",
+        arity: -3,
+        flags: &[READONLY],
+        keys: NONE,
+        group: b"search",
+        categories: &[],
+        grant: Some(ft_search::ft_search),
+    },
+    Canon {
+        name: b"FT.DROPINDEX",
+        arity: -2,
+        flags: &[WRITE],
+        keys: NONE,
+        group: b"search",
+        categories: &[],
+        grant: Some(ft_dropindex::ft_dropindex),
+    },
+    Canon {
+        name: b"FT._LIST",
+        arity: 1,
+        flags: &[READONLY],
+        keys: NONE,
+        group: b"search",
+        categories: &[],
+        grant: Some(ft_list::ft_list),
+    },
+    Canon {
+        name: b"CL.THROTTLE",
+        arity: -5,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"cell",
+        categories: &[],
+        grant: Some(cl_throttle::cl_throttle),
+    },
+    Canon {
+        name: b"SCAN",
+        arity: -2,
+        flags: &[READONLY],
+        keys: NONE,
+        group: b"generic",
+        categories: &[],
+        grant: Some(scan::scan),
+    },
+    Canon {
+        name: b"HSCAN",
+        arity: -3,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"hash",
+        categories: &[],
+        grant: Some(hscan::hscan),
+    },
+    Canon {
+        name: b"SSCAN",
+        arity: -3,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"set",
+        categories: &[],
+        grant: Some(sscan::sscan),
+    },
+    Canon {
+        name: b"KEYS",
+        arity: 2,
+        flags: &[READONLY],
+        keys: NONE,
+        group: b"generic",
+        categories: &[b"@dangerous"],
+        grant: Some(keys::keys),
+    },
+    Canon {
+        name: b"TYPE",
+        arity: 2,
+        flags: &[READONLY, FAST],
+        keys: KEY,
+        group: b"generic",
+        categories: &[],
+        grant: Some(key_type::key_type),
+    },
+    Canon {
+        name: b"RENAME",
+        arity: 3,
+        flags: &[WRITE],
+        keys: TWO,
+        group: b"generic",
+        categories: &[],
+        grant: Some(rename::rename),
+    },
+    Canon {
+        name: b"RENAMENX",
+        arity: 3,
+        flags: &[WRITE, FAST],
+        keys: TWO,
+        group: b"generic",
+        categories: &[],
+        grant: Some(renamenx::renamenx),
+    },
+    Canon {
+        name: b"COPY",
+        arity: -3,
+        flags: &[WRITE, DENYOOM],
+        keys: TWO,
+        group: b"generic",
+        categories: &[],
+        grant: Some(copy::copy),
+    },
+    Canon {
+        name: b"RANDOMKEY",
+        arity: 1,
+        flags: &[READONLY],
+        keys: NONE,
+        group: b"generic",
+        categories: &[],
+        grant: Some(randomkey::randomkey),
+    },
+    Canon {
+        name: b"DBSIZE",
+        arity: 1,
+        flags: &[READONLY, FAST],
+        keys: NONE,
+        group: b"server",
+        categories: &[b"@keyspace"],
+        grant: Some(dbsize::dbsize),
+    },
+    Canon {
+        name: b"TOUCH",
+        arity: -2,
+        flags: &[READONLY, FAST],
+        keys: ALL,
+        group: b"generic",
+        categories: &[],
+        grant: Some(touch::touch),
+    },
+    Canon {
+        name: b"UNLINK",
+        arity: -2,
+        flags: &[WRITE, FAST],
+        keys: ALL,
+        group: b"generic",
+        categories: &[],
+        grant: Some(unlink::unlink),
+    },
+    Canon {
+        name: b"SELECT",
+        arity: 2,
+        flags: &[FAST],
+        keys: NONE,
+        group: b"connection",
+        categories: &[],
+        grant: Some(select::select),
+    },
+    Canon {
+        name: b"MOVE",
+        arity: 3,
+        flags: &[WRITE, FAST],
+        keys: KEY,
+        group: b"generic",
+        categories: &[],
+        grant: Some(move_key::move_key),
+    },
+    Canon {
+        name: b"SWAPDB",
+        arity: 3,
+        flags: &[WRITE, FAST],
+        keys: NONE,
+        group: b"server",
+        categories: &[b"@keyspace", b"@dangerous"],
+        grant: Some(swapdb::swapdb),
+    },
+    Canon {
+        name: b"FLUSHDB",
+        arity: -1,
+        flags: &[WRITE],
+        keys: NONE,
+        group: b"server",
+        categories: &[b"@keyspace", b"@dangerous"],
+        grant: Some(flushdb::flushdb),
+    },
+    Canon {
+        name: b"FLUSHALL",
+        arity: -1,
+        flags: &[WRITE],
+        keys: NONE,
+        group: b"server",
+        categories: &[b"@keyspace", b"@dangerous"],
+        grant: Some(flushall::flushall),
+    },
+    Canon {
+        name: b"DUMP",
+        arity: 2,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"generic",
+        categories: &[],
+        grant: Some(dump::dump),
+    },
+    Canon {
+        name: b"RESTORE",
+        arity: -4,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"generic",
+        categories: &[b"@dangerous"],
+        grant: Some(restore::restore),
+    },
+    Canon {
+        name: b"SORT",
+        arity: -2,
+        flags: &[WRITE, DENYOOM],
+        keys: KEY,
+        group: b"generic",
+        categories: &[b"@set", b"@list", b"@dangerous"],
+        grant: Some(sort::sort),
+    },
+    Canon {
+        name: b"SORT_RO",
+        arity: -2,
+        flags: &[READONLY],
+        keys: KEY,
+        group: b"generic",
+        categories: &[b"@set", b"@list", b"@dangerous"],
+        grant: Some(sort::sort_ro),
+    },
+    Canon {
+        name: b"OBJECT",
+        arity: -2,
+        flags: &[READONLY],
+        keys: SECOND,
+        group: b"generic",
+        categories: &[],
+        grant: Some(object::object),
+    },
+    Canon {
+        name: b"MEMORY",
+        arity: -2,
+        flags: &[READONLY],
+        keys: SECOND,
+        group: b"server",
+        categories: &[],
+        grant: Some(memory::memory),
+    },
+    Canon {
+        name: b"MULTI",
+        arity: 1,
+        flags: &[NOSCRIPT, FAST],
+        keys: NONE,
+        group: b"transactions",
+        categories: &[],
+        grant: None,
+    },
+    Canon {
+        name: b"EXEC",
+        arity: 1,
+        flags: &[NOSCRIPT],
+        keys: NONE,
+        group: b"transactions",
+        categories: &[],
+        grant: None,
+    },
+    Canon {
+        name: b"DISCARD",
+        arity: 1,
+        flags: &[NOSCRIPT, FAST],
+        keys: NONE,
+        group: b"transactions",
+        categories: &[],
+        grant: None,
+    },
+    Canon {
+        name: b"WATCH",
+        arity: -2,
+        flags: &[NOSCRIPT, FAST],
+        keys: ALL,
+        group: b"transactions",
+        categories: &[],
+        grant: Some(watch::watch),
+    },
+    Canon {
+        name: b"UNWATCH",
+        arity: 1,
+        flags: &[NOSCRIPT, FAST],
+        keys: NONE,
+        group: b"transactions",
+        categories: &[],
+        grant: Some(unwatch::unwatch),
+    },
+    Canon {
+        name: b"EVAL",
+        arity: -3,
+        flags: &[NOSCRIPT],
+        keys: NUMKEYS,
+        group: b"scripting",
+        categories: &[],
+        grant: Some(eval::eval),
+    },
+    Canon {
+        name: b"EVALSHA",
+        arity: -3,
+        flags: &[NOSCRIPT],
+        keys: NUMKEYS,
+        group: b"scripting",
+        categories: &[],
+        grant: Some(eval::evalsha),
+    },
+    Canon {
+        name: b"SCRIPT",
+        arity: -2,
+        flags: &[NOSCRIPT],
+        keys: NONE,
+        group: b"scripting",
+        categories: &[],
+        grant: Some(script::script),
+    },
+    Canon {
+        name: b"FCALL",
+        arity: -3,
+        flags: &[NOSCRIPT],
+        keys: NUMKEYS,
+        group: b"scripting",
+        categories: &[],
+        grant: Some(eval::fcall),
+    },
+    Canon {
+        name: b"FCALL_RO",
+        arity: -3,
+        flags: &[NOSCRIPT, READONLY],
+        keys: NUMKEYS,
+        group: b"scripting",
+        categories: &[],
+        grant: Some(eval::fcall_ro),
+    },
+    Canon {
+        name: b"FUNCTION",
+        arity: -2,
+        flags: &[NOSCRIPT],
+        keys: NONE,
+        group: b"scripting",
+        categories: &[],
+        grant: Some(function::function),
+    },
+    Canon {
+        name: b"COMMAND",
+        arity: -1,
+        flags: &[],
+        keys: NONE,
+        group: b"server",
+        categories: &[b"@connection"],
+        grant: Some(|terms, _, tx, token| command::command(terms, tx, token)),
+    },
+];
@@ -0,0 +1,82 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_u64,
+    },
+};
+
+pub fn cl_throttle(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    if terms.len() != 5 && terms.len() != 6 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                    Command::CLTHROTTLE,
+                )),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(max_burst), Some(count), Some(period)) = (
+        terms_iter.next(),
+        terms_iter.next(),
+        terms_iter.next(),
+        terms_iter.next(),
+    ) else {
+        return;
+    };
+
+    let quantity = terms_iter
+        .next()
+        .map_or(Ok(1), |quantity| bytes_to_u64(&quantity));
+
+    let (Ok(max_burst), Ok(count @ 1..), Ok(period @ 1..), Ok(quantity)) = (
+        bytes_to_u64(&max_burst),
+        bytes_to_u64(&count),
+        bytes_to_u64(&period),
+        quantity,
+    ) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::CLTHROTTLE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.cl_throttle(
+        tx,
+        key,
+        max_burst,
+        count,
+        period.saturating_mul(1000),
+        quantity,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_u64,
+    },
+};
+
+pub fn cms_incrby(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let terms_len = terms.len();
+
+    if terms_len < 4 || !terms_len.is_multiple_of(2) {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                    Command::CMSINCRBY,
+                )),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    if let Some(key) = terms_iter.next() {
+        let mut increments = Vec::new();
+
+        while let (Some(item), Some(increment)) = (terms_iter.next(), terms_iter.next()) {
+            let Ok(increment) = bytes_to_u64(&increment) else {
+                if tx
+                    .send(Decree::Deliver(Gift {
+                        token,
+                        response: Response::Error(Sacrilege::InvalidArgument(Command::CMSINCRBY)),
+                    }))
+                    .is_err()
+                {
+                    eprintln!("angel panicked");
+                }
+
+                return;
+            };
+
+            increments.push((item, increment));
+        }
+
+        temple.cms_incrby(
+            tx,
+            key,
+            increments,
+            token,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        );
+    }
+}
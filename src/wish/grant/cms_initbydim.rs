@@ -0,0 +1,81 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::sketch::MAX_COUNTERS},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_usize,
+    },
+};
+
+pub fn cms_initbydim(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(width), Some(depth)) =
+        (terms_iter.next(), terms_iter.next(), terms_iter.next())
+    else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                    Command::CMSINITBYDIM,
+                )),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    let (Ok(width), Ok(depth)) = (bytes_to_usize(&width), bytes_to_usize(&depth)) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::CMSINITBYDIM)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    if width
+        .checked_mul(depth)
+        .is_none_or(|counters| counters == 0 || counters > MAX_COUNTERS)
+    {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::CMSINITBYDIM)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    temple.cms_initbydim(
+        tx,
+        key,
+        width,
+        depth,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,107 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_u64, bytes_to_usize},
+    },
+};
+
+pub fn cms_merge(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(destination), Some(number_of_keys)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let Ok(number_of_keys) = bytes_to_usize(&number_of_keys) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::CMSMERGE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    let sources: Vec<Vec<u8>> = terms_iter.by_ref().take(number_of_keys).collect();
+    let mut weights = Vec::new();
+
+    if let Some(weights_keyword) = terms_iter.next() {
+        if weights_keyword.eq_ignore_ascii_case(b"WEIGHTS") {
+            for weight in terms_iter.by_ref() {
+                let Ok(weight) = bytes_to_u64(&weight) else {
+                    if tx
+                        .send(Decree::Deliver(Gift {
+                            token,
+                            response: Response::Error(Sacrilege::InvalidArgument(
+                                Command::CMSMERGE,
+                            )),
+                        }))
+                        .is_err()
+                    {
+                        eprintln!("angel panicked");
+                    }
+
+                    return;
+                };
+
+                weights.push(weight);
+            }
+        }
+
+        if weights.len() != number_of_keys {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                        Command::CMSMERGE,
+                    )),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        }
+    }
+
+    if sources.len() != number_of_keys || number_of_keys == 0 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::CMSMERGE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    temple.cms_merge(
+        tx,
+        destination,
+        sources,
+        weights,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,29 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::grant::Decree,
+};
+
+pub fn cms_query(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    if let Some(key) = terms_iter.next() {
+        temple.cms_query(
+            tx,
+            key,
+            terms_iter.collect(),
+            token,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::wish::{
+    Command, InfoType, Response, Sacrilege,
+    grant::{
+        Decree, Gift,
+        canon::{self, Canon, Keys, MOVABLEKEYS, PUBSUB, WRITE},
+    },
+    util::glob_match,
+};
+
+const HELP: [&[u8]; 8] = [
+    b"COMMAND <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    b"(no subcommand)",
+    b"COUNT",
+    b"DOCS [<command-name> ...]",
+    b"GETKEYS <full-command>",
+    b"INFO [<command-name> ...]",
+    b"LIST [FILTERBY (ACLCAT <category>|PATTERN <pattern>)]",
+    b"HELP",
+];
+
+// COMMAND | COMMAND COUNT | COMMAND DOCS [name ...] | COMMAND GETKEYS command [arg ...]
+// | COMMAND INFO [name ...] | COMMAND LIST [FILTERBY ACLCAT category|PATTERN pattern]
+pub fn command(terms: Vec<Vec<u8>>, tx: Sender<Decree>, token: Token) {
+    let response = match terms.get(1) {
+        None => Response::Array(canon::all().iter().map(info).collect()),
+        Some(subcommand) => {
+            let arguments = &terms[2..];
+
+            if subcommand.eq_ignore_ascii_case(b"COUNT") && arguments.is_empty() {
+                Response::Length(canon::all().len())
+            } else if subcommand.eq_ignore_ascii_case(b"INFO") {
+                if arguments.is_empty() {
+                    Response::Array(canon::all().iter().map(info).collect())
+                } else {
+                    Response::Array(
+                        arguments
+                            .iter()
+                            .map(|name| {
+                                canon::describe(name)
+                                    .map_or(Response::BulkString(None), |canon| info(&canon))
+                            })
+                            .collect(),
+                    )
+                }
+            } else if subcommand.eq_ignore_ascii_case(b"DOCS") {
+                let canons = if arguments.is_empty() {
+                    canon::all()
+                } else {
+                    arguments
+                        .iter()
+                        .filter_map(|name| canon::describe(name))
+                        .collect()
+                };
+
+                Response::Array(canons.iter().flat_map(docs).collect())
+            } else if subcommand.eq_ignore_ascii_case(b"GETKEYS") && !arguments.is_empty() {
+                getkeys(arguments)
+            } else if subcommand.eq_ignore_ascii_case(b"LIST") {
+                list(arguments)
+            } else if subcommand.eq_ignore_ascii_case(b"HELP") && arguments.is_empty() {
+                Response::BulkStringArray(Some(HELP.iter().map(|line| Some(line.to_vec())).collect()))
+            } else {
+                Response::Error(Sacrilege::IncorrectUsage(Command::COMMAND))
+            }
+        }
+    };
+
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
+        eprintln!("angel panicked");
+    }
+}
+
+// name, arity, flags, first key, last key, step, ACL categories, tips, key
+// specs and subcommands, in the order clients expect them.
+fn info(canon: &Canon) -> Response {
+    let mut flags: Vec<Response> = canon.flags.iter().map(|flag| status(flag)).collect();
+
+    let (first, last, step) = match canon.keys {
+        Keys::None => (0, 0, 0),
+        Keys::Range { first, last, step } => (first as i64, last, step as i64),
+        Keys::Counted { .. } => {
+            flags.push(status(MOVABLEKEYS));
+            (0, 0, 0)
+        }
+    };
+
+    Response::Array(vec![
+        bulk(&canon.name.to_ascii_lowercase()),
+        Response::Number(canon.arity),
+        Response::Array(flags),
+        Response::Number(first),
+        Response::Number(last),
+        Response::Number(step),
+        Response::Array(
+            canon
+                .acl_categories()
+                .into_iter()
+                .map(status)
+                .collect(),
+        ),
+        Response::Array(Vec::new()),
+        Response::Array(key_specs(canon)),
+        Response::Array(Vec::new()),
+    ])
+}
+
+fn key_specs(canon: &Canon) -> Vec<Response> {
+    // Shard channels are named like keys without being any.
+    let access = if canon.flags.contains(&PUBSUB) {
+        b"not_key".as_slice()
+    } else if canon.flags.contains(&WRITE) {
+        b"RW"
+    } else {
+        b"RO"
+    };
+
+    let (begin_search, find_keys) = match canon.keys {
+        Keys::None => return Vec::new(),
+        // The last key is given relative to the first, unless it counts back
+        // from the end.
+        Keys::Range { first, last, step } => (
+            [bulk(b"index"), Response::Number(first as i64)],
+            vec![
+                bulk(b"type"),
+                bulk(b"range"),
+                bulk(b"spec"),
+                Response::Array(vec![
+                    bulk(b"lastkey"),
+                    Response::Number(if last < 0 { last } else { last - first as i64 }),
+                    bulk(b"keystep"),
+                    Response::Number(step as i64),
+                    bulk(b"limit"),
+                    Response::Number(0),
+                ]),
+            ],
+        ),
+        Keys::Counted { numkeys } => (
+            [bulk(b"index"), Response::Number(numkeys as i64)],
+            vec![
+                bulk(b"type"),
+                bulk(b"keynum"),
+                bulk(b"spec"),
+                Response::Array(vec![
+                    bulk(b"keynumidx"),
+                    Response::Number(0),
+                    bulk(b"firstkey"),
+                    Response::Number(1),
+                    bulk(b"keystep"),
+                    Response::Number(1),
+                ]),
+            ],
+        ),
+    };
+
+    vec![Response::Array(vec![
+        bulk(b"flags"),
+        Response::Array(vec![status(access)]),
+        bulk(b"begin_search"),
+        Response::Array(vec![
+            bulk(b"type"),
+            bulk(b"index"),
+            bulk(b"spec"),
+            Response::Array(begin_search.into()),
+        ]),
+        bulk(b"find_keys"),
+        Response::Array(find_keys),
+    ])]
+}
+
+// A name and its documentation, two entries of the map COMMAND DOCS replies
+// with.
+fn docs(canon: &Canon) -> [Response; 2] {
+    [
+        bulk(&canon.name.to_ascii_lowercase()),
+        Response::Array(vec![bulk(b"group"), bulk(canon.group)]),
+    ]
+}
+
+fn getkeys(command: &[Vec<u8>]) -> Response {
+    let Some(canon) = canon::describe(&command[0]) else {
+        return Response::Error(Sacrilege::InvalidCommand);
+    };
+
+    if !canon::fits(canon.arity, command.len()) {
+        return Response::Error(Sacrilege::InvalidCommandArity);
+    }
+
+    let keys = canon.keys(command);
+
+    if keys.is_empty() {
+        return Response::Error(Sacrilege::NoKeyArguments);
+    }
+
+    Response::Array(keys.into_iter().map(bulk).collect())
+}
+
+fn list(arguments: &[Vec<u8>]) -> Response {
+    let canons = canon::all();
+
+    let names = match arguments {
+        [] => canons.iter().collect::<Vec<_>>(),
+        [filterby, kind, value] if filterby.eq_ignore_ascii_case(b"FILTERBY") => {
+            if kind.eq_ignore_ascii_case(b"ACLCAT") {
+                let category = [b"@".as_slice(), &value.to_ascii_lowercase()].concat();
+
+                canons
+                    .iter()
+                    .filter(|canon| canon.acl_categories().contains(&category.as_slice()))
+                    .collect()
+            } else if kind.eq_ignore_ascii_case(b"PATTERN") {
+                canons
+                    .iter()
+                    .filter(|canon| glob_match(value, &canon.name.to_ascii_lowercase()))
+                    .collect()
+            } else {
+                return Response::Error(Sacrilege::InvalidArgument(Command::COMMAND));
+            }
+        }
+        _ => return Response::Error(Sacrilege::InvalidArgument(Command::COMMAND)),
+    };
+
+    Response::BulkStringArray(Some(
+        names
+            .into_iter()
+            .map(|canon| Some(canon.name.to_ascii_lowercase()))
+            .collect(),
+    ))
+}
+
+fn bulk(bytes: &[u8]) -> Response {
+    Response::BulkString(Some(bytes.to_vec()))
+}
+
+fn status(bytes: &[u8]) -> Response {
+    Response::Info(InfoType::Status(bytes.to_vec()))
+}
@@ -42,6 +42,30 @@ pub fn config(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, toke
         return;
     };
 
+    if command.eq_ignore_ascii_case(b"SET") {
+        let (Some(property), Some(value), None) =
+            (terms_iter.next(), terms_iter.next(), terms_iter.next())
+        else {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                        Command::CONFIG,
+                    )),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        };
+
+        temple.config_set(tx, token, property, value);
+
+        return;
+    }
+
     if !command.eq_ignore_ascii_case(b"GET") {
         if tx
             .send(Decree::Deliver(Gift {
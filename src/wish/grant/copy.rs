@@ -0,0 +1,68 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+// COPY source destination [REPLACE]
+pub fn copy(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    if terms.len() != 3 && terms.len() != 4 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::COPY)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(source), Some(destination)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let replace = match terms_iter.next() {
+        Some(option) if option.eq_ignore_ascii_case(b"REPLACE") => true,
+        Some(_) => {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::COPY)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        }
+        None => false,
+    };
+
+    temple.copy(
+        tx,
+        source,
+        destination,
+        replace,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,12 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::grant::Decree,
+};
+
+pub fn dbsize(_terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    temple.dbsize(tx, token);
+}
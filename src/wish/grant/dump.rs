@@ -0,0 +1,30 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::grant::Decree,
+};
+
+pub fn dump(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(key) = terms_iter.next() else {
+        return;
+    };
+
+    temple.dump(
+        tx,
+        key,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
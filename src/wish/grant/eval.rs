@@ -0,0 +1,109 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::{
+    temple::{Source, Temple},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_i64,
+    },
+};
+
+// EVAL script numkeys [key [key ...]] [arg [arg ...]]
+pub fn eval(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    petition(terms, temple, tx, token, Command::EVAL);
+}
+
+// EVALSHA sha1 numkeys [key [key ...]] [arg [arg ...]]
+pub fn evalsha(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    petition(terms, temple, tx, token, Command::EVALSHA);
+}
+
+// FCALL function numkeys [key [key ...]] [arg [arg ...]]
+pub fn fcall(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    petition(terms, temple, tx, token, Command::FCALL);
+}
+
+// FCALL_RO function numkeys [key [key ...]] [arg [arg ...]]
+pub fn fcall_ro(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    petition(terms, temple, tx, token, Command::FCALLRO);
+}
+
+fn petition(
+    terms: Vec<Vec<u8>>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+    command: Command,
+) {
+    if terms.len() < 3 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(command)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let function = match command {
+        Command::FCALL => Some(false),
+        Command::FCALLRO => Some(true),
+        _ => None,
+    };
+    let by_sha = matches!(command, Command::EVALSHA);
+
+    // KEYS gets the first numkeys arguments, ARGV the rest.
+    let sacrilege = match bytes_to_i64(&terms[2]) {
+        Err(_) => Some(Sacrilege::InvalidArgument(command)),
+        Ok(numkeys) if numkeys < 0 => Some(Sacrilege::NegativeNumberOfKeys),
+        Ok(numkeys) if numkeys as u64 > (terms.len() - 3) as u64 => {
+            Some(Sacrilege::IncorrectUsage(command))
+        }
+        Ok(_) => None,
+    };
+
+    if let Some(sacrilege) = sacrilege {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(sacrilege),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(script), Some(numkeys)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+    let Ok(numkeys) = bytes_to_i64(&numkeys) else {
+        return;
+    };
+
+    let keys: Vec<Vec<u8>> = terms_iter.by_ref().take(numkeys as usize).collect();
+    let args: Vec<Vec<u8>> = terms_iter.collect();
+
+    let source = match function {
+        Some(read_only) => Source::Function {
+            name: script,
+            read_only,
+        },
+        None if by_sha => Source::Sha(script),
+        None => Source::Body(script),
+    };
+
+    temple.eval(tx, source, keys, args, token);
+}
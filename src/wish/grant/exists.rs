@@ -2,27 +2,11 @@ use mio::Token;
 
 use crate::{
     temple::Temple,
-    wish::{
-        Command, Response, Sacrilege,
-        grant::{Decree, Gift},
-    },
+    wish::grant::Decree,
 };
 use std::{sync::mpsc::Sender, time::{SystemTime, UNIX_EPOCH}};
 
 pub fn exists(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
-    if terms.len() < 2 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::EXISTS)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        };
-        return;
-    }
-
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
@@ -32,7 +16,7 @@ pub fn exists(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, toke
         token,
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
+            .map(|d| d.as_millis() as u64)
             .unwrap_or(0),
     );
 }
@@ -62,13 +62,13 @@ pub fn expire(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, toke
 
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
+        .map(|d| d.as_millis() as u64)
         .unwrap_or(0);
 
     temple.expire(
         tx,
         key,
-        now + expiry,
+        now.saturating_add(expiry.saturating_mul(1000)),
         token,
         now,
     );
@@ -0,0 +1,49 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+// FLUSHDB [ASYNC | SYNC]
+pub fn flushdb(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    if terms.len() > 2 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::FLUSHDB)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let asynchronous = match terms.get(1) {
+        Some(mode) if mode.eq_ignore_ascii_case(b"ASYNC") => true,
+        Some(mode) if mode.eq_ignore_ascii_case(b"SYNC") => false,
+        Some(_) => {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::FLUSHDB)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        }
+        None => false,
+    };
+
+    temple.flushdb(tx, asynchronous, token);
+}
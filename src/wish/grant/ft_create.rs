@@ -0,0 +1,185 @@
+use std::{sync::mpsc::Sender, vec::IntoIter};
+
+use mio::Token;
+
+use crate::{
+    temple::{
+        Temple,
+        soul::{
+            search::{FieldSchema, FieldType},
+            vector::{DistanceMetric, MAX_DIMENSION, VectorAlgorithm},
+        },
+    },
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_usize,
+    },
+};
+
+pub fn ft_create(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(index) = terms_iter.next() else {
+        return;
+    };
+
+    let Some((prefixes, schema)) = parse_definition(terms_iter) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::FTCREATE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.ft_create(tx, index, prefixes, schema, token);
+}
+
+// [ON HASH] [PREFIX count prefix ...] SCHEMA field type [SEPARATOR sep] [SORTABLE] ...
+fn parse_definition(mut terms_iter: IntoIter<Vec<u8>>) -> Option<(Vec<Vec<u8>>, Vec<FieldSchema>)> {
+    let mut prefixes = Vec::new();
+
+    loop {
+        let keyword = terms_iter.next()?;
+
+        if keyword.eq_ignore_ascii_case(b"ON") {
+            if !terms_iter.next()?.eq_ignore_ascii_case(b"HASH") {
+                return None;
+            }
+        } else if keyword.eq_ignore_ascii_case(b"PREFIX") {
+            let count = bytes_to_usize(&terms_iter.next()?).ok()?;
+
+            for _ in 0..count {
+                prefixes.push(terms_iter.next()?);
+            }
+        } else if keyword.eq_ignore_ascii_case(b"SCHEMA") {
+            break;
+        } else {
+            return None;
+        }
+    }
+
+    let mut schema: Vec<FieldSchema> = Vec::new();
+    let mut terms_iter = terms_iter.peekable();
+
+    while let Some(name) = terms_iter.next() {
+        let field_type = terms_iter.next()?;
+
+        let mut field_type = if field_type.eq_ignore_ascii_case(b"TAG") {
+            FieldType::Tag { separator: b',' }
+        } else if field_type.eq_ignore_ascii_case(b"NUMERIC") {
+            FieldType::Numeric
+        } else if field_type.eq_ignore_ascii_case(b"TEXT") {
+            FieldType::Text
+        } else if field_type.eq_ignore_ascii_case(b"VECTOR") {
+            parse_vector_field(&mut terms_iter)?
+        } else {
+            return None;
+        };
+
+        // Every field can be sorted on, SORTABLE is accepted for compatibility.
+        while let Some(option) = terms_iter.next_if(|option| {
+            option.eq_ignore_ascii_case(b"SEPARATOR") || option.eq_ignore_ascii_case(b"SORTABLE")
+        }) {
+            if option.eq_ignore_ascii_case(b"SEPARATOR") {
+                let FieldType::Tag { separator } = &mut field_type else {
+                    return None;
+                };
+
+                let [byte] = terms_iter.next()?[..] else {
+                    return None;
+                };
+
+                *separator = byte;
+            }
+        }
+
+        if schema.iter().any(|field| field.name == name) {
+            return None;
+        }
+
+        schema.push(FieldSchema { name, field_type });
+    }
+
+    if schema.is_empty() {
+        None
+    } else {
+        Some((prefixes, schema))
+    }
+}
+
+// FLAT|HNSW count TYPE FLOAT32 DIM dim DISTANCE_METRIC L2|IP|COSINE
+// [M m] [EF_CONSTRUCTION ef] [EF_RUNTIME ef] [INITIAL_CAP cap] [BLOCK_SIZE size] [EPSILON e]
+fn parse_vector_field(terms_iter: &mut impl Iterator<Item = Vec<u8>>) -> Option<FieldType> {
+    let algorithm = terms_iter.next()?;
+    let is_hnsw = if algorithm.eq_ignore_ascii_case(b"HNSW") {
+        true
+    } else if algorithm.eq_ignore_ascii_case(b"FLAT") {
+        false
+    } else {
+        return None;
+    };
+
+    let count = bytes_to_usize(&terms_iter.next()?).ok()?;
+
+    if count % 2 != 0 {
+        return None;
+    }
+
+    let (mut dimension, mut metric) = (None, None);
+    let (mut m, mut ef_construction, mut ef_runtime) = (16, 200, 10);
+
+    for _ in 0..count / 2 {
+        let (attribute, value) = (terms_iter.next()?, terms_iter.next()?);
+
+        if attribute.eq_ignore_ascii_case(b"TYPE") {
+            if !value.eq_ignore_ascii_case(b"FLOAT32") {
+                return None;
+            }
+        } else if attribute.eq_ignore_ascii_case(b"DIM") {
+            dimension = Some(
+                bytes_to_usize(&value)
+                    .ok()
+                    .filter(|dim| (1..=MAX_DIMENSION).contains(dim))?,
+            );
+        } else if attribute.eq_ignore_ascii_case(b"DISTANCE_METRIC") {
+            metric = Some(DistanceMetric::parse(&value)?);
+        } else if is_hnsw && attribute.eq_ignore_ascii_case(b"M") {
+            m = bytes_to_usize(&value).ok().filter(|&m| m > 1)?;
+        } else if is_hnsw && attribute.eq_ignore_ascii_case(b"EF_CONSTRUCTION") {
+            ef_construction = bytes_to_usize(&value).ok().filter(|&ef| ef > 0)?;
+        } else if is_hnsw && attribute.eq_ignore_ascii_case(b"EF_RUNTIME") {
+            ef_runtime = bytes_to_usize(&value).ok().filter(|&ef| ef > 0)?;
+        } else if attribute.eq_ignore_ascii_case(b"INITIAL_CAP")
+            || attribute.eq_ignore_ascii_case(b"BLOCK_SIZE")
+            || (is_hnsw && attribute.eq_ignore_ascii_case(b"EPSILON"))
+        {
+            // Sizing hints only matter to preallocating implementations.
+        } else {
+            return None;
+        }
+    }
+
+    let algorithm = if is_hnsw {
+        VectorAlgorithm::Hnsw {
+            m,
+            ef_construction,
+            ef_runtime,
+        }
+    } else {
+        VectorAlgorithm::Flat
+    };
+
+    Some(FieldType::Vector {
+        algorithm,
+        dimension: dimension?,
+        metric: metric?,
+    })
+}
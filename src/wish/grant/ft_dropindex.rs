@@ -0,0 +1,70 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+pub fn ft_dropindex(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let terms_len = terms.len();
+
+    if terms_len != 2 && terms_len != 3 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                    Command::FTDROPINDEX,
+                )),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(index) = terms_iter.next() else {
+        return;
+    };
+
+    let delete_documents = match terms_iter.next() {
+        Some(option) if option.eq_ignore_ascii_case(b"DD") => true,
+        Some(_) => {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::FTDROPINDEX)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        }
+        None => false,
+    };
+
+    temple.ft_dropindex(
+        tx,
+        index,
+        delete_documents,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
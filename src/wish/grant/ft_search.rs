@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+    vec::IntoIter,
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{
+        Temple,
+        soul::{KnnQuery, SearchOptions, search::Query},
+    },
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_usize,
+    },
+};
+
+pub fn ft_search(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(index), Some(query)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let Some(query) = parse_query(&query, terms_iter) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::FTSEARCH)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    let (query, options) = query;
+
+    temple.ft_search(
+        tx,
+        index,
+        query,
+        options,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
+
+// filter [=>[KNN k @field $param [EF_RUNTIME n] [AS alias]]]
+fn parse_query(query: &[u8], terms_iter: IntoIter<Vec<u8>>) -> Option<(Query, SearchOptions)> {
+    let (mut options, params) = parse_options(terms_iter)?;
+
+    let Some(arrow) = query.windows(2).position(|window| window == b"=>") else {
+        return Some((Query::parse(query)?, options));
+    };
+
+    let clause = query[arrow + 2..].trim_ascii();
+    let clause = clause.strip_prefix(b"[")?.strip_suffix(b"]")?;
+
+    let resolve = |word: &[u8]| match word.strip_prefix(b"$") {
+        Some(name) => params.get(name).cloned(),
+        None => Some(word.to_vec()),
+    };
+
+    let mut words = clause
+        .split(|byte| byte.is_ascii_whitespace())
+        .filter(|word| !word.is_empty());
+
+    if !words.next()?.eq_ignore_ascii_case(b"KNN") {
+        return None;
+    }
+
+    let k = bytes_to_usize(&resolve(words.next()?)?).ok()?;
+    let field = words.next()?.strip_prefix(b"@")?.to_vec();
+    let vector = resolve(words.next()?)?;
+
+    let mut ef_runtime = None;
+    let mut score_field = [b"__".as_slice(), &field, b"_score"].concat();
+
+    while let Some(keyword) = words.next() {
+        if keyword.eq_ignore_ascii_case(b"EF_RUNTIME") {
+            ef_runtime = Some(bytes_to_usize(&resolve(words.next()?)?).ok()?);
+        } else if keyword.eq_ignore_ascii_case(b"AS") {
+            score_field = words.next()?.to_vec();
+        } else {
+            return None;
+        }
+    }
+
+    options.knn = Some(Box::new(KnnQuery {
+        k,
+        field,
+        vector,
+        ef_runtime,
+        score_field,
+    }));
+
+    Some((Query::parse(&query[..arrow])?, options))
+}
+
+// [NOCONTENT] [RETURN count field ...] [SORTBY field [ASC|DESC]] [LIMIT offset num]
+// [PARAMS count name value ...] [DIALECT dialect]
+#[allow(clippy::type_complexity)]
+fn parse_options(
+    terms_iter: IntoIter<Vec<u8>>,
+) -> Option<(SearchOptions, HashMap<Vec<u8>, Vec<u8>>)> {
+    let mut options = SearchOptions {
+        no_content: false,
+        return_fields: None,
+        sort_by: None,
+        limit: (0, 10),
+        knn: None,
+    };
+    let mut params = HashMap::new();
+
+    let mut terms_iter = terms_iter.peekable();
+
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"NOCONTENT") {
+            options.no_content = true;
+        } else if keyword.eq_ignore_ascii_case(b"RETURN") {
+            let count = bytes_to_usize(&terms_iter.next()?).ok()?;
+            let mut fields = Vec::with_capacity(count);
+
+            for _ in 0..count {
+                fields.push(terms_iter.next()?);
+            }
+
+            options.return_fields = Some(fields);
+        } else if keyword.eq_ignore_ascii_case(b"SORTBY") {
+            let field = terms_iter.next()?;
+            let mut ascending = true;
+
+            if let Some(order) = terms_iter.next_if(|order| {
+                order.eq_ignore_ascii_case(b"ASC") || order.eq_ignore_ascii_case(b"DESC")
+            }) {
+                ascending = order.eq_ignore_ascii_case(b"ASC");
+            }
+
+            options.sort_by = Some((field, ascending));
+        } else if keyword.eq_ignore_ascii_case(b"LIMIT") {
+            let offset = bytes_to_usize(&terms_iter.next()?).ok()?;
+            let count = bytes_to_usize(&terms_iter.next()?).ok()?;
+
+            options.limit = (offset, count);
+        } else if keyword.eq_ignore_ascii_case(b"PARAMS") {
+            let count = bytes_to_usize(&terms_iter.next()?).ok()?;
+
+            if count % 2 != 0 {
+                return None;
+            }
+
+            for _ in 0..count / 2 {
+                params.insert(terms_iter.next()?, terms_iter.next()?);
+            }
+        } else if keyword.eq_ignore_ascii_case(b"DIALECT") {
+            bytes_to_usize(&terms_iter.next()?).ok()?;
+        } else {
+            return None;
+        }
+    }
+
+    Some((options, params))
+}
@@ -0,0 +1,132 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::{
+    temple::{FunctionCommand, RestorePolicy, ScriptCommand, Temple},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+const HELP: [&[u8]; 9] = [
+    b"FUNCTION <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    b"LOAD [REPLACE] <function code>",
+    b"DELETE <library name>",
+    b"LIST [LIBRARYNAME PATTERN] [WITHCODE]",
+    b"FLUSH [ASYNC|SYNC]",
+    b"DUMP",
+    b"RESTORE <payload> [FLUSH|APPEND|REPLACE]",
+    b"KILL",
+    b"HELP",
+];
+
+// FUNCTION LOAD [REPLACE] code | FUNCTION DELETE library | FUNCTION FLUSH [ASYNC|SYNC]
+// | FUNCTION LIST [LIBRARYNAME pattern] [WITHCODE] | FUNCTION DUMP
+// | FUNCTION RESTORE payload [FLUSH|APPEND|REPLACE] | FUNCTION KILL
+pub fn function(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(subcommand) = terms_iter.next() else {
+        return;
+    };
+    let arguments: Vec<Vec<u8>> = terms_iter.collect();
+
+    let function_command = if subcommand.eq_ignore_ascii_case(b"LOAD") {
+        match arguments.as_slice() {
+            [code] => Some(FunctionCommand::Load {
+                code: code.clone(),
+                replace: false,
+            }),
+            [replace, code] if replace.eq_ignore_ascii_case(b"REPLACE") => {
+                Some(FunctionCommand::Load {
+                    code: code.clone(),
+                    replace: true,
+                })
+            }
+            _ => None,
+        }
+    } else if subcommand.eq_ignore_ascii_case(b"DELETE") {
+        match arguments.as_slice() {
+            [library] => Some(FunctionCommand::Delete {
+                library: library.clone(),
+            }),
+            _ => None,
+        }
+    } else if subcommand.eq_ignore_ascii_case(b"FLUSH") {
+        match arguments.as_slice() {
+            [] => Some(FunctionCommand::Flush),
+            [mode] if mode.eq_ignore_ascii_case(b"ASYNC") || mode.eq_ignore_ascii_case(b"SYNC") => {
+                Some(FunctionCommand::Flush)
+            }
+            _ => None,
+        }
+    } else if subcommand.eq_ignore_ascii_case(b"LIST") {
+        list(&arguments)
+    } else if subcommand.eq_ignore_ascii_case(b"DUMP") && arguments.is_empty() {
+        Some(FunctionCommand::Dump)
+    } else if subcommand.eq_ignore_ascii_case(b"RESTORE") {
+        match arguments.as_slice() {
+            [payload] => Some(FunctionCommand::Restore {
+                payload: payload.clone(),
+                policy: RestorePolicy::Append,
+            }),
+            [payload, policy] => {
+                let policy = if policy.eq_ignore_ascii_case(b"FLUSH") {
+                    Some(RestorePolicy::Flush)
+                } else if policy.eq_ignore_ascii_case(b"APPEND") {
+                    Some(RestorePolicy::Append)
+                } else if policy.eq_ignore_ascii_case(b"REPLACE") {
+                    Some(RestorePolicy::Replace)
+                } else {
+                    None
+                };
+
+                policy.map(|policy| FunctionCommand::Restore {
+                    payload: payload.clone(),
+                    policy,
+                })
+            }
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    if let Some(function_command) = function_command {
+        temple.script(tx, ScriptCommand::Function(function_command), token);
+        return;
+    }
+
+    let response = if subcommand.eq_ignore_ascii_case(b"KILL") && arguments.is_empty() {
+        temple.script_kill()
+    } else if subcommand.eq_ignore_ascii_case(b"HELP") && arguments.is_empty() {
+        Response::BulkStringArray(Some(HELP.iter().map(|line| Some(line.to_vec())).collect()))
+    } else {
+        Response::Error(Sacrilege::IncorrectUsage(Command::FUNCTION))
+    };
+
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
+        eprintln!("angel panicked");
+    }
+}
+
+fn list(arguments: &[Vec<u8>]) -> Option<FunctionCommand> {
+    let mut pattern = None;
+    let mut with_code = false;
+    let mut arguments_iter = arguments.iter();
+
+    while let Some(argument) = arguments_iter.next() {
+        if argument.eq_ignore_ascii_case(b"WITHCODE") && !with_code {
+            with_code = true;
+        } else if argument.eq_ignore_ascii_case(b"LIBRARYNAME") && pattern.is_none() {
+            pattern = Some(arguments_iter.next()?.clone());
+        } else {
+            return None;
+        }
+    }
+
+    Some(FunctionCommand::List { pattern, with_code })
+}
@@ -11,20 +11,6 @@ use crate::{
 };
 
 pub fn hexists(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
-    if terms.len() != 3 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::HEXISTS)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-
-        return;
-    }
-
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
@@ -36,7 +22,7 @@ pub fn hexists(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, tok
             token,
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs())
+                .map(|d| d.as_millis() as u64)
                 .unwrap_or(0),
         );
     } else if tx
@@ -0,0 +1,65 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::ScanOptions},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_cursor, parse_scan_option},
+    },
+};
+
+// HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+pub fn hscan(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(cursor)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let mut options = ScanOptions::default();
+    let mut is_valid = true;
+
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"NOVALUES") {
+            options.no_values = true;
+        } else {
+            is_valid &= matches!(
+                parse_scan_option(&keyword, &mut terms_iter, &mut options),
+                Ok(true)
+            );
+        }
+    }
+
+    let (Ok(cursor), true) = (bytes_to_cursor(&cursor), is_valid) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::HSCAN)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.hscan(
+        tx,
+        key,
+        cursor,
+        options,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
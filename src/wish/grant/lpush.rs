@@ -1,28 +1,11 @@
 use crate::{
     temple::Temple,
-    wish::{
-        Command, Response, Sacrilege,
-        grant::{Decree, Gift},
-    },
+    wish::grant::Decree,
 };
 use mio::Token;
 use std::{sync::mpsc::Sender, time::{SystemTime, UNIX_EPOCH}};
 
 pub fn lpush(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
-    if terms.len() < 3 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::LPUSH)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        };
-
-        return;
-    }
-
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
@@ -34,7 +17,7 @@ pub fn lpush(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token
             token,
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs())
+                .map(|d| d.as_millis() as u64)
                 .unwrap_or(0),
         );
     }
@@ -12,20 +12,6 @@ use crate::{
 };
 
 pub fn lrem(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
-    if terms.len() != 4 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::LREM)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        };
-
-        return;
-    }
-
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
@@ -67,7 +53,7 @@ pub fn lrem(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token:
         token,
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
+            .map(|d| d.as_millis() as u64)
             .unwrap_or(0),
     );
 }
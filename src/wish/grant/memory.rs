@@ -0,0 +1,99 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_i64,
+    },
+};
+
+// How many elements of a collection MEMORY USAGE looks at unless told.
+const DEFAULT_SAMPLES: usize = 5;
+
+const HELP: [&[u8]; 4] = [
+    b"MEMORY <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    b"USAGE <key> [SAMPLES <count>]",
+    b"STATS",
+    b"PURGE",
+];
+
+// MEMORY USAGE key [SAMPLES count] | MEMORY STATS | MEMORY PURGE
+pub fn memory(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(subcommand) = terms_iter.next() else {
+        return;
+    };
+    let arguments: Vec<Vec<u8>> = terms_iter.collect();
+
+    if subcommand.eq_ignore_ascii_case(b"USAGE") && !arguments.is_empty() {
+        let samples = match &arguments[1..] {
+            [] => Some(DEFAULT_SAMPLES),
+            [keyword, count] if keyword.eq_ignore_ascii_case(b"SAMPLES") => bytes_to_i64(count)
+                .ok()
+                .and_then(|count| usize::try_from(count).ok()),
+            _ => None,
+        };
+
+        let Some(samples) = samples else {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::MEMORY)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        };
+
+        let Some(key) = arguments.into_iter().next() else {
+            return;
+        };
+
+        temple.memory_usage(
+            tx,
+            key,
+            samples,
+            token,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        );
+    } else if subcommand.eq_ignore_ascii_case(b"STATS") && arguments.is_empty() {
+        temple.memory_stats(tx, token);
+    } else if subcommand.eq_ignore_ascii_case(b"PURGE") && arguments.is_empty() {
+        temple.memory_purge(tx, token);
+    } else if subcommand.eq_ignore_ascii_case(b"HELP") && arguments.is_empty() {
+        let help = HELP.iter().map(|line| Some(line.to_vec())).collect();
+
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::BulkStringArray(Some(help)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+    } else if tx
+        .send(Decree::Deliver(Gift {
+            token,
+            response: Response::Error(Sacrilege::IncorrectUsage(Command::MEMORY)),
+        }))
+        .is_err()
+    {
+        eprintln!("angel panicked");
+    }
+}
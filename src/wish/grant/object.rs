@@ -0,0 +1,95 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{ObjectField, Temple},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+const HELP: [&[u8]; 5] = [
+    b"OBJECT <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    b"ENCODING <key>",
+    b"FREQ <key>",
+    b"IDLETIME <key>",
+    b"REFCOUNT <key>",
+];
+
+// OBJECT ENCODING|FREQ|IDLETIME|REFCOUNT key
+pub fn object(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    if terms.len() == 2 && terms[1].eq_ignore_ascii_case(b"HELP") {
+        let help = HELP.iter().map(|line| Some(line.to_vec())).collect();
+
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::BulkStringArray(Some(help)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    if terms.len() != 3 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::OBJECT)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(subcommand), Some(key)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let field = if subcommand.eq_ignore_ascii_case(b"ENCODING") {
+        ObjectField::Encoding
+    } else if subcommand.eq_ignore_ascii_case(b"IDLETIME") {
+        ObjectField::IdleTime
+    } else if subcommand.eq_ignore_ascii_case(b"FREQ") {
+        ObjectField::Freq
+    } else if subcommand.eq_ignore_ascii_case(b"REFCOUNT") {
+        ObjectField::RefCount
+    } else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectUsage(Command::OBJECT)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.object(
+        tx,
+        key,
+        field,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
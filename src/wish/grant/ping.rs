@@ -8,26 +8,15 @@ use crate::wish::{
     grant::{Decree, Gift},
 };
 
+// PING [message]
 pub fn ping(terms: Vec<Vec<u8>>, tx: Sender<Decree>, token: Token) {
-    if terms.len() != 1
-        && tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::PING)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-            return;
-        }
+    let response = match terms.len() {
+        1 => Response::Info(InfoType::Pong),
+        2 => Response::BulkString(terms.into_iter().nth(1)),
+        _ => Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::PING)),
+    };
 
-    if tx
-        .send(Decree::Deliver(Gift {
-            token,
-            response: Response::Info(InfoType::Pong),
-        }))
-        .is_err()
-    {
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
         eprintln!("angel panicked");
     };
 }
@@ -11,20 +11,6 @@ use crate::{
 };
 
 pub fn publish(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
-    if terms.len() != 3 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::PUBLISH)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
-        }
-
-        return;
-    }
-
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
@@ -40,3 +26,13 @@ pub fn publish(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, tok
         eprintln!("angel panicked");
     }
 }
+
+// SPUBLISH shardchannel message
+pub fn spublish(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    if let (Some(channel), Some(message)) = (terms_iter.next(), terms_iter.next()) {
+        temple.spublish(tx, channel, message, token);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::{
+    temple::{PubsubQuery, Temple},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+const HELP: [&[u8]; 7] = [
+    b"PUBSUB <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    b"CHANNELS [<pattern>]",
+    b"NUMPAT",
+    b"NUMSUB [<channel> ...]",
+    b"SHARDCHANNELS [<pattern>]",
+    b"SHARDNUMSUB [<shardchannel> ...]",
+    b"HELP",
+];
+
+// PUBSUB CHANNELS [pattern] | PUBSUB NUMSUB [channel ...] | PUBSUB NUMPAT
+// | PUBSUB SHARDCHANNELS [pattern] | PUBSUB SHARDNUMSUB [shardchannel ...]
+pub fn pubsub(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(subcommand) = terms_iter.next() else {
+        return;
+    };
+    let mut arguments: Vec<Vec<u8>> = terms_iter.collect();
+    let help = subcommand.eq_ignore_ascii_case(b"HELP") && arguments.is_empty();
+
+    let query = if subcommand.eq_ignore_ascii_case(b"CHANNELS") && arguments.len() <= 1 {
+        Some(PubsubQuery::Channels {
+            pattern: arguments.pop(),
+        })
+    } else if subcommand.eq_ignore_ascii_case(b"NUMSUB") {
+        Some(PubsubQuery::Numsub {
+            channels: arguments,
+        })
+    } else if subcommand.eq_ignore_ascii_case(b"NUMPAT") && arguments.is_empty() {
+        Some(PubsubQuery::Numpat)
+    } else if subcommand.eq_ignore_ascii_case(b"SHARDCHANNELS") && arguments.len() <= 1 {
+        Some(PubsubQuery::ShardChannels {
+            pattern: arguments.pop(),
+        })
+    } else if subcommand.eq_ignore_ascii_case(b"SHARDNUMSUB") {
+        Some(PubsubQuery::ShardNumsub {
+            channels: arguments,
+        })
+    } else {
+        None
+    };
+
+    if let Some(query) = query {
+        temple.pubsub(tx, query, token);
+        return;
+    }
+
+    let response = if help {
+        Response::BulkStringArray(Some(HELP.iter().map(|line| Some(line.to_vec())).collect()))
+    } else {
+        Response::Error(Sacrilege::IncorrectUsage(Command::PUBSUB))
+    };
+
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
+        eprintln!("angel panicked");
+    }
+}
@@ -0,0 +1,32 @@
+use std::{
+    sync::{Arc, mpsc::Sender},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, rite::Rite},
+    wish::grant::Decree,
+};
+
+// Any command a rite was consecrated under, its arity already checked and
+// the rest left to the rite itself.
+pub fn rite(
+    rite: Arc<dyn Rite>,
+    terms: Vec<Vec<u8>>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+) {
+    temple.rite(
+        tx,
+        rite,
+        terms,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -39,7 +39,7 @@ pub fn rpop(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token:
                     token,
                     SystemTime::now()
                         .duration_since(UNIX_EPOCH)
-                        .map(|d| d.as_secs())
+                        .map(|d| d.as_millis() as u64)
                         .unwrap_or(0),
                 );
 
@@ -65,7 +65,7 @@ pub fn rpop(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token:
             token,
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs())
+                .map(|d| d.as_millis() as u64)
                 .unwrap_or(0),
         );
     } else if tx
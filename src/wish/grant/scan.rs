@@ -0,0 +1,65 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::ScanOptions},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_cursor, parse_scan_option},
+    },
+};
+
+// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+pub fn scan(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(cursor) = terms_iter.next() else {
+        return;
+    };
+
+    let mut options = ScanOptions::default();
+    let mut is_valid = true;
+
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"TYPE") {
+            options.type_name = terms_iter.next();
+            is_valid &= options.type_name.is_some();
+        } else {
+            is_valid &= matches!(
+                parse_scan_option(&keyword, &mut terms_iter, &mut options),
+                Ok(true)
+            );
+        }
+    }
+
+    let (Ok(cursor), true) = (bytes_to_cursor(&cursor), is_valid) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::SCAN)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.scan(
+        tx,
+        cursor,
+        options,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,62 @@
+use std::sync::mpsc::Sender;
+
+use mio::Token;
+
+use crate::{
+    temple::{ScriptCommand, Temple},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+    },
+};
+
+const HELP: [&[u8]; 6] = [
+    b"SCRIPT <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    b"EXISTS <sha1> [<sha1> ...]",
+    b"FLUSH [ASYNC|SYNC]",
+    b"KILL",
+    b"LOAD <script>",
+    b"HELP",
+];
+
+// SCRIPT LOAD script | SCRIPT EXISTS sha1 [sha1 ...] | SCRIPT FLUSH [ASYNC|SYNC] | SCRIPT KILL
+pub fn script(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(subcommand) = terms_iter.next() else {
+        return;
+    };
+    let mut arguments: Vec<Vec<u8>> = terms_iter.collect();
+
+    let response = if subcommand.eq_ignore_ascii_case(b"LOAD") && arguments.len() == 1 {
+        let Some(body) = arguments.pop() else {
+            return;
+        };
+
+        temple.script(tx, ScriptCommand::Load { body }, token);
+        return;
+    } else if subcommand.eq_ignore_ascii_case(b"EXISTS") && !arguments.is_empty() {
+        temple.script(tx, ScriptCommand::Exists { shas: arguments }, token);
+        return;
+    } else if subcommand.eq_ignore_ascii_case(b"FLUSH")
+        && match arguments.as_slice() {
+            [] => true,
+            [mode] => mode.eq_ignore_ascii_case(b"ASYNC") || mode.eq_ignore_ascii_case(b"SYNC"),
+            _ => false,
+        }
+    {
+        temple.script(tx, ScriptCommand::Flush, token);
+        return;
+    } else if subcommand.eq_ignore_ascii_case(b"KILL") && arguments.is_empty() {
+        temple.script_kill()
+    } else if subcommand.eq_ignore_ascii_case(b"HELP") && arguments.is_empty() {
+        Response::BulkStringArray(Some(HELP.iter().map(|line| Some(line.to_vec())).collect()))
+    } else {
+        Response::Error(Sacrilege::IncorrectUsage(Command::SCRIPT))
+    };
+
+    if tx.send(Decree::Deliver(Gift { token, response })).is_err() {
+        eprintln!("angel panicked");
+    }
+}
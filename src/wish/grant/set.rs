@@ -66,10 +66,10 @@ pub fn set(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token:
 
                     let now = SystemTime::now()
                         .duration_since(UNIX_EPOCH)
-                        .map(|d| d.as_secs())
+                        .map(|d| d.as_millis() as u64)
                         .unwrap_or(0);
 
-                    temple.set(key, (Value::String(value), Some(now + expiry)), tx, token);
+                    temple.set(key, (Value::String(value), Some(now.saturating_add(expiry.saturating_mul(1000)))), tx, token);
                 } else if tx
                     .send(Decree::Deliver(Gift {
                         token,
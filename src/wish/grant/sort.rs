@@ -0,0 +1,116 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+    vec::IntoIter,
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::SortOptions},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_i64,
+    },
+};
+
+// SORT key [BY pattern] [LIMIT offset count] [GET pattern ...] [ASC | DESC] [ALPHA]
+//     [STORE destination]
+pub fn sort(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    sort_with(terms, temple, tx, token, Command::SORT);
+}
+
+// SORT_RO is SORT without STORE.
+pub fn sort_ro(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    sort_with(terms, temple, tx, token, Command::SORTRO);
+}
+
+fn sort_with(
+    terms: Vec<Vec<u8>>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+    command: Command,
+) {
+    if terms.len() < 2 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(command)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(key) = terms_iter.next() else {
+        return;
+    };
+
+    let read_only = matches!(command, Command::SORTRO);
+
+    let Some((options, store)) = parse_options(terms_iter, read_only) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(command)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.sort(
+        tx,
+        key,
+        options,
+        store,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
+
+fn parse_options(
+    mut terms_iter: IntoIter<Vec<u8>>,
+    read_only: bool,
+) -> Option<(SortOptions, Option<Vec<u8>>)> {
+    let mut options = SortOptions::default();
+    let mut store = None;
+
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"BY") {
+            options.by = Some(terms_iter.next()?);
+        } else if keyword.eq_ignore_ascii_case(b"LIMIT") {
+            let offset = bytes_to_i64(&terms_iter.next()?).ok()?;
+            let count = bytes_to_i64(&terms_iter.next()?).ok()?;
+            options.limit = Some((offset, count));
+        } else if keyword.eq_ignore_ascii_case(b"GET") {
+            options.get.push(terms_iter.next()?);
+        } else if keyword.eq_ignore_ascii_case(b"ASC") {
+            options.descending = false;
+        } else if keyword.eq_ignore_ascii_case(b"DESC") {
+            options.descending = true;
+        } else if keyword.eq_ignore_ascii_case(b"ALPHA") {
+            options.alpha = true;
+        } else if keyword.eq_ignore_ascii_case(b"STORE") && !read_only {
+            store = Some(terms_iter.next()?);
+        } else {
+            return None;
+        }
+    }
+
+    Some((options, store))
+}
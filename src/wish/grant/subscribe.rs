@@ -1,38 +1,61 @@
 use mio::Token;
 
 use crate::{
-    temple::Temple,
+    temple::{Subscription, Temple},
     wish::{
         Command, Response, Sacrilege,
         grant::{Decree, Gift},
+        util::bytes_to_u64,
     },
 };
 use std::sync::mpsc::Sender;
 
+// SUBSCRIBE channel [channel ...] [REPLAY id]
 pub fn subscribe(
-    terms: Vec<Vec<u8>>,
+    mut terms: Vec<Vec<u8>>,
     temple: &mut Temple,
     tx: Sender<Decree>,
     token: Token,
-)  {
-    if terms.len() < 2 {
-        if tx
-            .send(Decree::Deliver(Gift {
-                token,
-                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::SUBSCRIBE)),
-            }))
-            .is_err()
-        {
-            eprintln!("angel panicked");
+) {
+    let replay = if terms.len() >= 4 && terms[terms.len() - 2].eq_ignore_ascii_case(b"REPLAY") {
+        let Ok(after) = bytes_to_u64(&terms[terms.len() - 1]) else {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::SUBSCRIBE)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
         };
 
-        return ;
-    }
+        terms.truncate(terms.len() - 2);
+        Some(after)
+    } else {
+        None
+    };
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    temple.subscribe(tx, Subscription::Channel, terms_iter.collect(), replay, token);
+}
 
+// PSUBSCRIBE pattern [pattern ...]
+pub fn psubscribe(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
-    temple.subscribe(tx, terms_iter.collect(), token);
+    temple.subscribe(tx, Subscription::Pattern, terms_iter.collect(), None, token);
+}
+
+// SSUBSCRIBE shardchannel [shardchannel ...]
+pub fn ssubscribe(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
 
-    
+    temple.subscribe(tx, Subscription::Shard, terms_iter.collect(), None, token);
 }
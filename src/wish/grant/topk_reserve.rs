@@ -0,0 +1,105 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{
+        Temple,
+        soul::sketch::{MAX_COUNTERS, MAX_TOPK},
+    },
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_f64, bytes_to_usize},
+    },
+};
+
+pub fn topk_reserve(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let terms_len = terms.len();
+
+    if terms_len != 3 && terms_len != 6 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(
+                    Command::TOPKRESERVE,
+                )),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(k)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let parameters = match (terms_iter.next(), terms_iter.next(), terms_iter.next()) {
+        (Some(width), Some(depth), Some(decay)) => (
+            bytes_to_usize(&k),
+            bytes_to_usize(&width),
+            bytes_to_usize(&depth),
+            bytes_to_f64(&decay),
+        ),
+        _ => (bytes_to_usize(&k), Ok(8), Ok(7), Ok(0.9)),
+    };
+
+    let (Ok(k), Ok(width), Ok(depth), Ok(decay)) = parameters else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::TOPKRESERVE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    if k == 0
+        || k > MAX_TOPK
+        || width
+            .checked_mul(depth)
+            .is_none_or(|buckets| buckets == 0 || buckets > MAX_COUNTERS)
+        || decay.is_nan()
+        || decay <= 0.0
+        || decay > 1.0
+    {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::TOPKRESERVE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    temple.topk_reserve(
+        tx,
+        key,
+        k,
+        width,
+        depth,
+        decay,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,82 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{
+        Temple,
+        soul::timeseries::{DuplicatePolicy, TimeSeriesOptions},
+    },
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_f64, bytes_to_u64, parse_ts_option},
+    },
+};
+
+pub fn ts_add(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(timestamp), Some(value)) =
+        (terms_iter.next(), terms_iter.next(), terms_iter.next())
+    else {
+        return;
+    };
+
+    let timestamp = if timestamp == b"*" {
+        Ok(now.as_millis() as u64)
+    } else {
+        bytes_to_u64(&timestamp)
+    };
+
+    let mut options = TimeSeriesOptions::default();
+    let mut on_duplicate = None;
+    let mut is_valid = true;
+
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"ON_DUPLICATE") {
+            on_duplicate = terms_iter
+                .next()
+                .and_then(|policy| DuplicatePolicy::parse(&policy));
+            is_valid &= on_duplicate.is_some();
+        } else {
+            is_valid &= matches!(
+                parse_ts_option(&keyword, &mut terms_iter, &mut options),
+                Ok(true)
+            );
+        }
+    }
+
+    let (Ok(timestamp), Ok(value), true) = (timestamp, bytes_to_f64(&value), is_valid) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::TSADD)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.ts_add(
+        tx,
+        key,
+        timestamp,
+        value,
+        options,
+        on_duplicate,
+        token,
+        now.as_millis() as u64,
+    );
+}
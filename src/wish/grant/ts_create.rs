@@ -0,0 +1,56 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::timeseries::TimeSeriesOptions},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::parse_ts_option,
+    },
+};
+
+pub fn ts_create(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let Some(key) = terms_iter.next() else {
+        return;
+    };
+
+    let mut options = TimeSeriesOptions::default();
+
+    while let Some(keyword) = terms_iter.next() {
+        if !matches!(
+            parse_ts_option(&keyword, &mut terms_iter, &mut options),
+            Ok(true)
+        ) {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::TSCREATE)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        }
+    }
+
+    temple.ts_create(
+        tx,
+        key,
+        options,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,61 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::timeseries::Aggregation},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::bytes_to_u64,
+    },
+};
+
+pub fn ts_createrule(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(source), Some(destination), Some(keyword), Some(aggregation), Some(bucket_duration)) = (
+        terms_iter.next(),
+        terms_iter.next(),
+        terms_iter.next(),
+        terms_iter.next(),
+        terms_iter.next(),
+    ) else {
+        return;
+    };
+
+    let (true, Some(aggregation), Ok(bucket_duration @ 1..)) = (
+        keyword.eq_ignore_ascii_case(b"AGGREGATION"),
+        Aggregation::parse(&aggregation),
+        bytes_to_u64(&bucket_duration),
+    ) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::TSCREATERULE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.ts_createrule(
+        tx,
+        source,
+        destination,
+        aggregation,
+        bucket_duration,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,67 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::timeseries::TimeSeriesOptions},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_f64, bytes_to_u64, parse_ts_option},
+    },
+};
+
+pub fn ts_incrby(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(increment)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let mut timestamp = Ok(now.as_millis() as u64);
+    let mut options = TimeSeriesOptions::default();
+    let mut is_valid = true;
+
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"TIMESTAMP") {
+            timestamp = match terms_iter.next() {
+                Some(timestamp) if timestamp == b"*" => Ok(now.as_millis() as u64),
+                Some(timestamp) => bytes_to_u64(&timestamp),
+                None => {
+                    is_valid = false;
+                    break;
+                }
+            };
+        } else {
+            is_valid &= matches!(
+                parse_ts_option(&keyword, &mut terms_iter, &mut options),
+                Ok(true)
+            );
+        }
+    }
+
+    let (Ok(timestamp), Ok(increment), true) = (timestamp, bytes_to_f64(&increment), is_valid)
+    else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::TSINCRBY)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.ts_incrby(tx, key, increment, timestamp, options, token, now.as_millis() as u64);
+}
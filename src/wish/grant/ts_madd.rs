@@ -0,0 +1,70 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_f64, bytes_to_u64},
+    },
+};
+
+pub fn ts_madd(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let terms_len = terms.len();
+
+    if terms_len < 4 || !(terms_len - 1).is_multiple_of(3) {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(Command::TSMADD)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let mut samples = Vec::new();
+
+    while let (Some(key), Some(timestamp), Some(value)) =
+        (terms_iter.next(), terms_iter.next(), terms_iter.next())
+    {
+        let timestamp = if timestamp == b"*" {
+            Ok(now.as_millis() as u64)
+        } else {
+            bytes_to_u64(&timestamp)
+        };
+
+        let (Ok(timestamp), Ok(value)) = (timestamp, bytes_to_f64(&value)) else {
+            if tx
+                .send(Decree::Deliver(Gift {
+                    token,
+                    response: Response::Error(Sacrilege::InvalidArgument(Command::TSMADD)),
+                }))
+                .is_err()
+            {
+                eprintln!("angel panicked");
+            }
+
+            return;
+        };
+
+        samples.push((key, timestamp, value));
+    }
+
+    temple.ts_madd(tx, samples, token, now.as_millis() as u64);
+}
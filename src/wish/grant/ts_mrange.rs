@@ -0,0 +1,79 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::{Temple, soul::timeseries::LabelFilter},
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_ts_bound, parse_ts_range_option},
+    },
+};
+
+pub fn ts_mrange(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(from), Some(to)) = (terms_iter.next(), terms_iter.next()) else {
+        return;
+    };
+
+    let mut aggregation = None;
+    let mut count = None;
+    let mut filters = Vec::new();
+    let mut is_valid = true;
+
+    // FILTER takes every remaining term, as in TS.MRANGE.
+    while let Some(keyword) = terms_iter.next() {
+        if keyword.eq_ignore_ascii_case(b"FILTER") {
+            for filter in terms_iter.by_ref() {
+                match LabelFilter::parse(&filter) {
+                    Some(filter) => filters.push(filter),
+                    None => is_valid = false,
+                }
+            }
+        } else {
+            is_valid &= matches!(
+                parse_ts_range_option(&keyword, &mut terms_iter, &mut aggregation, &mut count),
+                Ok(true)
+            );
+        }
+    }
+
+    let (Ok(from), Ok(to), true, false) = (
+        bytes_to_ts_bound(&from),
+        bytes_to_ts_bound(&to),
+        is_valid,
+        filters.is_empty(),
+    ) else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(Command::TSMRANGE)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.ts_mrange(
+        tx,
+        from,
+        to,
+        aggregation,
+        count,
+        filters,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -0,0 +1,101 @@
+use std::{
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mio::Token;
+
+use crate::{
+    temple::Temple,
+    wish::{
+        Command, Response, Sacrilege,
+        grant::{Decree, Gift},
+        util::{bytes_to_ts_bound, parse_ts_range_option},
+    },
+};
+
+pub fn ts_range(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    range(terms, temple, tx, token, false);
+}
+
+pub fn ts_revrange(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    range(terms, temple, tx, token, true);
+}
+
+fn range(
+    terms: Vec<Vec<u8>>,
+    temple: &mut Temple,
+    tx: Sender<Decree>,
+    token: Token,
+    reverse: bool,
+) {
+    let command = if reverse {
+        Command::TSREVRANGE
+    } else {
+        Command::TSRANGE
+    };
+
+    if terms.len() < 4 {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::IncorrectNumberOfArguments(command)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    }
+
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    let (Some(key), Some(from), Some(to)) =
+        (terms_iter.next(), terms_iter.next(), terms_iter.next())
+    else {
+        return;
+    };
+
+    let mut aggregation = None;
+    let mut count = None;
+    let mut is_valid = true;
+
+    while let Some(keyword) = terms_iter.next() {
+        is_valid &= matches!(
+            parse_ts_range_option(&keyword, &mut terms_iter, &mut aggregation, &mut count),
+            Ok(true)
+        );
+    }
+
+    let (Ok(from), Ok(to), true) = (bytes_to_ts_bound(&from), bytes_to_ts_bound(&to), is_valid)
+    else {
+        if tx
+            .send(Decree::Deliver(Gift {
+                token,
+                response: Response::Error(Sacrilege::InvalidArgument(command)),
+            }))
+            .is_err()
+        {
+            eprintln!("angel panicked");
+        }
+
+        return;
+    };
+
+    temple.ts_range(
+        tx,
+        key,
+        from,
+        to,
+        aggregation,
+        count,
+        reverse,
+        token,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    );
+}
@@ -3,7 +3,7 @@ use std::sync::mpsc::Sender;
 use mio::Token;
 
 use crate::{
-    temple::Temple,
+    temple::{Subscription, Temple},
     wish::grant::Decree,
 };
 
@@ -11,5 +11,21 @@ pub fn unsubscribe(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>,
     let mut terms_iter = terms.into_iter();
     terms_iter.next();
 
-    temple.unsubscribe(tx, token, terms_iter.collect());
+    temple.unsubscribe(tx, token, Subscription::Channel, terms_iter.collect());
+}
+
+// PUNSUBSCRIBE [pattern ...]
+pub fn punsubscribe(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    temple.unsubscribe(tx, token, Subscription::Pattern, terms_iter.collect());
+}
+
+// SUNSUBSCRIBE [shardchannel ...]
+pub fn sunsubscribe(terms: Vec<Vec<u8>>, temple: &mut Temple, tx: Sender<Decree>, token: Token) {
+    let mut terms_iter = terms.into_iter();
+    terms_iter.next();
+
+    temple.unsubscribe(tx, token, Subscription::Shard, terms_iter.collect());
 }
@@ -1,3 +1,7 @@
+use std::vec::IntoIter;
+
+use crate::temple::soul::ScanOptions;
+use crate::temple::soul::timeseries::{Aggregation, DuplicatePolicy, TimeSeriesOptions};
 use crate::wish::Sin;
 
 pub fn find_crlf(buffer: &[u8]) -> Option<usize> {
@@ -103,3 +107,187 @@ pub fn bytes_to_usize(bytes: &[u8]) -> Result<usize, Sin> {
 
     Ok(result)
 }
+
+pub fn bytes_to_f64(bytes: &[u8]) -> Result<f64, Sin> {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return Err(Sin::Utf8Error);
+    };
+
+    text.parse::<f64>().map_err(|_| Sin::ParseError)
+}
+
+/// Parses a TS.RANGE bound, where `-` and `+` stand for the oldest and newest sample.
+pub fn bytes_to_ts_bound(bytes: &[u8]) -> Result<u64, Sin> {
+    match bytes {
+        b"-" => Ok(0),
+        b"+" => Ok(u64::MAX),
+        _ => bytes_to_u64(bytes),
+    }
+}
+
+/// Consumes one of the RETENTION / DUPLICATE_POLICY / LABELS options shared by
+/// the time-series write commands. Returns false if `keyword` isn't one of them.
+/// LABELS swallows every remaining term, so it has to come last.
+pub fn parse_ts_option(
+    keyword: &[u8],
+    terms_iter: &mut IntoIter<Vec<u8>>,
+    options: &mut TimeSeriesOptions,
+) -> Result<bool, Sin> {
+    if keyword.eq_ignore_ascii_case(b"RETENTION") {
+        let retention = terms_iter.next().ok_or(Sin::ParseError)?;
+        options.retention = Some(bytes_to_u64(&retention)?);
+    } else if keyword.eq_ignore_ascii_case(b"DUPLICATE_POLICY") {
+        let policy = terms_iter.next().ok_or(Sin::ParseError)?;
+        options.duplicate_policy = Some(DuplicatePolicy::parse(&policy).ok_or(Sin::ParseError)?);
+    } else if keyword.eq_ignore_ascii_case(b"LABELS") {
+        while let Some(label) = terms_iter.next() {
+            let value = terms_iter.next().ok_or(Sin::ParseError)?;
+            options.labels.push((label, value));
+        }
+
+        if options.labels.is_empty() {
+            return Err(Sin::ParseError);
+        }
+    } else {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Consumes one of the AGGREGATION / COUNT options shared by the time-series
+/// range commands. Returns false if `keyword` isn't one of them.
+pub fn parse_ts_range_option(
+    keyword: &[u8],
+    terms_iter: &mut IntoIter<Vec<u8>>,
+    aggregation: &mut Option<(Aggregation, u64)>,
+    count: &mut Option<usize>,
+) -> Result<bool, Sin> {
+    if keyword.eq_ignore_ascii_case(b"AGGREGATION") {
+        let (Some(aggregator), Some(bucket_duration)) = (terms_iter.next(), terms_iter.next())
+        else {
+            return Err(Sin::ParseError);
+        };
+
+        let aggregator = Aggregation::parse(&aggregator).ok_or(Sin::ParseError)?;
+        let bucket_duration = bytes_to_u64(&bucket_duration)?;
+
+        if bucket_duration == 0 {
+            return Err(Sin::ParseError);
+        }
+
+        *aggregation = Some((aggregator, bucket_duration));
+    } else if keyword.eq_ignore_ascii_case(b"COUNT") {
+        let limit = terms_iter.next().ok_or(Sin::ParseError)?;
+        *count = Some(bytes_to_usize(&limit)?);
+    } else {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Parses a SCAN cursor. Cursors span the whole u64 range, so unlike
+/// `bytes_to_u64` this has to reject overflow rather than wrap.
+pub fn bytes_to_cursor(bytes: &[u8]) -> Result<u64, Sin> {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return Err(Sin::Utf8Error);
+    };
+
+    text.parse::<u64>().map_err(|_| Sin::ParseError)
+}
+
+/// Consumes one of the MATCH / COUNT options shared by the SCAN family.
+/// Returns false if `keyword` isn't one of them.
+pub fn parse_scan_option(
+    keyword: &[u8],
+    terms_iter: &mut IntoIter<Vec<u8>>,
+    options: &mut ScanOptions,
+) -> Result<bool, Sin> {
+    if keyword.eq_ignore_ascii_case(b"MATCH") {
+        options.pattern = Some(terms_iter.next().ok_or(Sin::ParseError)?);
+    } else if keyword.eq_ignore_ascii_case(b"COUNT") {
+        let count = terms_iter.next().ok_or(Sin::ParseError)?;
+        options.count = bytes_to_usize(&count)?;
+
+        if options.count == 0 {
+            return Err(Sin::ParseError);
+        }
+    } else {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Glob-style matching as in KEYS and SCAN's MATCH: `*` matches any run of
+/// bytes, `?` any single byte, `[abc]`, `[^abc]` and `[a-z]` a class of bytes,
+/// and `\` escapes the next byte.
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Where to resume if the rest fails to match after the last `*`.
+    let mut backtrack = None;
+
+    while s < string.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, s));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(p + 1),
+            Some(b'[') => match_class(&pattern[p..], string[s]).map(|length| p + length),
+            Some(b'\\') if p + 1 < pattern.len() => (pattern[p + 1] == string[s]).then_some(p + 2),
+            Some(&byte) => (byte == string[s]).then_some(p + 1),
+            None => None,
+        };
+
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                s += 1;
+            }
+            (None, Some((star, matched))) => {
+                p = star + 1;
+                s = matched + 1;
+                backtrack = Some((star, matched + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
+
+// Matches `byte` against the `[...]` class at the start of `pattern`, returns
+// the length of the class if it matches. An unterminated class runs to the
+// end of the pattern.
+fn match_class(pattern: &[u8], byte: u8) -> Option<usize> {
+    let mut i = 1;
+    let is_negated = pattern.get(i) == Some(&b'^');
+
+    if is_negated {
+        i += 1;
+    }
+
+    let mut is_match = false;
+
+    while i < pattern.len() && pattern[i] != b']' {
+        if pattern[i] == b'\\' && i + 1 < pattern.len() {
+            is_match |= pattern[i + 1] == byte;
+            i += 2;
+        } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']' {
+            let (low, high) = (
+                pattern[i].min(pattern[i + 2]),
+                pattern[i].max(pattern[i + 2]),
+            );
+            is_match |= (low..=high).contains(&byte);
+            i += 3;
+        } else {
+            is_match |= pattern[i] == byte;
+            i += 1;
+        }
+    }
+
+    (is_match != is_negated).then_some((i + 1).min(pattern.len()))
+}